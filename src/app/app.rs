@@ -5,19 +5,29 @@ use {
         cli::TriBool,
         command::{
             Command,
+            MacroRecorder,
             Sequence,
         },
-        conf::Conf,
+        conf::{
+            self,
+            Conf,
+            VerbConf,
+        },
         display::*,
         errors::ProgramError,
         file_sum,
+        follower::Follower,
         git,
         graphics,
+        job::JobsState,
         launchable::Launchable,
+        output::OutputState,
         path::closest_dir,
         pattern::InputPattern,
         preview::PreviewState,
+        session::Session,
         skin::*,
+        stage::Stage,
         syntactic::SyntaxTheme,
         task_sync::{
             Dam,
@@ -50,9 +60,12 @@ use {
 
 /// The GUI
 pub struct App {
-    /// the panels of the application, with their inputs
+    /// the panels of the active tab, with their inputs
     panels: AppPanelsAndInputs,
 
+    /// the tabs of the application (the panels of the active one are in `panels`)
+    tabs: AppTabs,
+
     /// whether the app is in the (uncancellable) process of quitting
     quitting: bool,
 
@@ -70,10 +83,33 @@ pub struct App {
 
     /// a watcher for notify events
     watcher: Watcher,
+
+    /// a poller of the files followed in previews
+    follower: Follower,
+
+    /// the stage of the restored session, to be given to the app state
+    restored_stage: Option<Stage>,
+
+    /// the recorder of the commands executed by the user, and the last macro
+    macros: MacroRecorder,
+
+    /// the commands of a sequence which follow a verb being confirmed,
+    /// to apply if its execution is confirmed
+    suspended_commands: Vec<(String, Command)>,
 }
 
 impl App {
-    pub fn new(con: &AppContext) -> Result<App, ProgramError> {
+    pub fn new(con: &mut AppContext) -> Result<App, ProgramError> {
+        if let Some(session) = Self::session_to_restore(con)? {
+            con.layout_instructions.instructions = session.layout_instructions.clone();
+            if let Some((tabs, panels, stage)) = AppTabs::from_session(&session, con)? {
+                let mut app = Self::with_panels(panels);
+                app.tabs = tabs;
+                app.restored_stage = Some(stage);
+                return Ok(app);
+            }
+            warn!("nothing could be restored from the session");
+        }
         let mut panels = AppPanelsAndInputs::new(con)?;
         if let Some(path) = con.initial_file.as_ref() {
             // open initial_file in preview
@@ -94,17 +130,137 @@ impl App {
                 warn!("could not open preview: {err}");
             }
         }
+        Ok(Self::with_panels(panels))
+    }
+
+    fn with_panels(panels: AppPanelsAndInputs) -> Self {
         let (tx_seqs, rx_seqs) = unbounded::<Sequence>();
         let watcher = Watcher::new(tx_seqs.clone());
-        Ok(Self {
+        let follower = Follower::new(tx_seqs.clone());
+        Self {
             panels,
+            tabs: AppTabs::default(),
             quitting: false,
             launch_at_end: None,
             shared_root: None,
             tx_seqs,
             rx_seqs,
             watcher,
-        })
+            follower,
+            restored_stage: None,
+            macros: MacroRecorder::default(),
+            suspended_commands: Vec::new(),
+        }
+    }
+
+    /// Return the session required by the `--session` launch argument or,
+    /// when `session_auto_restore` is set, the last one launched in the
+    /// same directory
+    fn session_to_restore(con: &AppContext) -> Result<Option<Session>, ProgramError> {
+        if let Some(name) = &con.launch_args.session {
+            let path = Session::named_path(name)?;
+            return Session::load(&path).map(Some);
+        }
+        if !con.session_auto_restore || con.initial_file.is_some() {
+            return Ok(None);
+        }
+        let path = Session::last_path(&con.initial_root);
+        if !path.exists() {
+            return Ok(None);
+        }
+        match Session::load(&path) {
+            Ok(session) if session.launch_dir == con.initial_root => Ok(Some(session)),
+            Ok(_) => Ok(None), // hash collision
+            Err(e) => {
+                warn!("not restoring last session: {e}");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Build what's saved of the application in a session
+    fn session(
+        &self,
+        app_state: &AppState,
+        con: &AppContext,
+    ) -> Session {
+        Session {
+            launch_dir: con.initial_root.clone(),
+            active_tab_idx: self.tabs.active_tab_idx(),
+            layout_instructions: con
+                .layout_instructions
+                .instructions
+                .iter()
+                .filter(|instruction| !matches!(instruction, LayoutInstruction::Clear))
+                .copied()
+                .collect(),
+            tabs: self.tabs.tab_sessions(&self.panels, &app_state.stage),
+        }
+    }
+
+    /// Save the session, to be restored on the next launch in the same
+    /// directory, if `session_auto_restore` is set
+    fn save_last_session(
+        &self,
+        app_state: &AppState,
+        con: &AppContext,
+    ) {
+        if !con.session_auto_restore {
+            return;
+        }
+        let path = Session::last_path(&con.initial_root);
+        if let Err(e) = self.session(app_state, con).save(&path) {
+            warn!("failed to save the session: {e}");
+        }
+    }
+
+    /// Queue a sequence for execution, after the current command
+    fn send_sequence(
+        &self,
+        sequence: Sequence,
+    ) -> Result<(), ProgramError> {
+        self.tx_seqs
+            .send(sequence)
+            .map_err(|e| ProgramError::Internal {
+                details: format!("failed to send sequence: {e}"),
+            })
+    }
+
+    /// Save the last recorded macro as a verb, both in the macros file
+    /// and in the verb store so that it's immediately usable
+    fn save_macro(
+        &self,
+        name: &str,
+        con: &mut AppContext,
+    ) -> Result<(), ProgramError> {
+        let recorded = self.macros.last().ok_or_else(|| ProgramError::Macro {
+            details: "no macro was recorded".to_string(),
+        })?;
+        if name.contains(char::is_whitespace) || name.starts_with(':') {
+            return Err(ProgramError::Macro {
+                details: format!("invalid verb name: {name:?}"),
+            });
+        }
+        let name_taken = con
+            .verb_store
+            .verbs()
+            .iter()
+            .any(|verb| verb.names.iter().any(|n| n == name));
+        if name_taken {
+            return Err(ProgramError::Macro {
+                details: format!("there's already a verb named {name:?}"),
+            });
+        }
+        let separator = recorded.separator();
+        let verb_conf = VerbConf {
+            invocation: Some(name.to_string()),
+            cmd: Some(recorded.raw(&separator)),
+            cmd_separator: Some(separator),
+            ..Default::default()
+        };
+        con.verb_store.add_from_conf(&verb_conf)?;
+        conf::save_macro_verb(verb_conf)?;
+        Ok(())
     }
 
     /// apply a command. Change the states but don't redraw on screen.
@@ -142,6 +298,86 @@ impl App {
                     self.panels.clear_input();
                 }
             }
+            CmdResult::CapturedOutputs {
+                outputs,
+                jobs,
+                refresh,
+                error: exec_error,
+            } => {
+                if is_input_invocation {
+                    self.panels.clear_input_invocation(con);
+                }
+                let mut failed = outputs.iter().filter(|o| !o.success()).count();
+                let mut statuses = Vec::new();
+                match outputs.as_slice() {
+                    [] => {}
+                    [output] => statuses.push(format!(
+                        "`{}` {}",
+                        output.command,
+                        output.status_description()
+                    )),
+                    _ => statuses.push(format!(
+                        "{} commands executed, {failed} failed",
+                        outputs.len()
+                    )),
+                }
+                // the captured commands still running go on as background jobs
+                let mut started = Vec::new();
+                for request in jobs {
+                    let command = request.command.clone();
+                    match app_state.jobs.start(request, self.tx_seqs.clone()) {
+                        Ok(id) => started.push((id, command)),
+                        Err(e) => {
+                            failed += 1;
+                            statuses.push(e.to_string());
+                        }
+                    }
+                }
+                match started.as_slice() {
+                    [] => {}
+                    [(id, command)] => statuses.push(format!(
+                        "`{command}` still running as job #{id} (see `:jobs`)"
+                    )),
+                    _ => statuses.push(format!(
+                        "{} commands still running as jobs {} (see `:jobs`)",
+                        started.len(),
+                        started
+                            .iter()
+                            .map(|(id, _)| format!("#{id}"))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    )),
+                }
+                let status = statuses.join(", ");
+                let has_outputs = !outputs.is_empty();
+                for output in outputs {
+                    app_state.outputs.push(output);
+                }
+                if refresh {
+                    clear_caches();
+                    app_state.stage.refresh();
+                    self.panels.refresh_all_panels(con);
+                }
+                if has_outputs && self.panels.count_of_type(PanelStateType::Output) == 0 {
+                    let state = OutputState::new(app_state, self.panels.state().tree_options(), con);
+                    if let Err(s) = self.panels.new_panel(
+                        Box::new(state),
+                        PanelPurpose::None,
+                        HDir::Right,
+                        false,
+                        con,
+                    ) {
+                        warn!("output panel not opened: {s}");
+                    }
+                }
+                if let Some(exec_error) = exec_error {
+                    error = Some(format!("{status}, then {exec_error}"));
+                } else if failed > 0 {
+                    error = Some(status);
+                } else {
+                    self.panels.mut_panel().set_message(status);
+                }
+            }
             CmdResult::ClosePanel {
                 validate_purpose,
                 panel_ref,
@@ -206,6 +442,15 @@ impl App {
             CmdResult::DisplayError(txt) => {
                 error = Some(txt);
             }
+            CmdResult::Prompt {
+                verb_id,
+                invocation,
+            } => {
+                let cmd = self
+                    .panels
+                    .prompt_before_execution(verb_id, &invocation, app_state, con);
+                self.apply_command(w, &cmd, panel_skin, app_state, con)?;
+            }
             CmdResult::ExecuteSequence { sequence } => {
                 if is_input_invocation {
                     self.panels.clear_input();
@@ -224,6 +469,77 @@ impl App {
                     Internal::focus_staging_area_no_open => {
                         self.panels.focus_by_type(PanelStateType::Stage);
                     }
+                    Internal::open_jobs => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        if !self.panels.focus_by_type(PanelStateType::Jobs) {
+                            let state =
+                                JobsState::new(app_state, self.panels.state().tree_options(), con);
+                            if let Err(s) = self.panels.new_panel(
+                                Box::new(state),
+                                PanelPurpose::None,
+                                HDir::Right,
+                                true,
+                                con,
+                            ) {
+                                error = Some(s);
+                            }
+                        }
+                    }
+                    Internal::refresh_jobs => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        let mut refresh = false;
+                        let mut failed = false;
+                        let mut notifications = Vec::new();
+                        for job in app_state.jobs.take_ended() {
+                            let status = job.status();
+                            refresh |= job.refresh_after;
+                            failed |= !status.is_success();
+                            notifications.push(format!(
+                                "Job #{} `{}` {}",
+                                job.id,
+                                job.command,
+                                status.description(),
+                            ));
+                            if let Some(output) = job.command_output() {
+                                app_state.outputs.push(output);
+                            }
+                        }
+                        if refresh {
+                            clear_caches();
+                            app_state.stage.refresh();
+                            self.panels.refresh_all_panels(con);
+                        }
+                        if !notifications.is_empty() {
+                            let notification = notifications.join(", ");
+                            if failed {
+                                error = Some(notification);
+                            } else {
+                                self.panels.mut_panel().set_message(notification);
+                            }
+                        }
+                    }
+                    Internal::open_output => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        if !self.panels.focus_by_type(PanelStateType::Output) {
+                            let state =
+                                OutputState::new(app_state, self.panels.state().tree_options(), con);
+                            if let Err(s) = self.panels.new_panel(
+                                Box::new(state),
+                                PanelPurpose::None,
+                                HDir::Right,
+                                true,
+                                con,
+                            ) {
+                                error = Some(s);
+                            }
+                        }
+                    }
                     Internal::focus_panel_left => {
                         let len = self.panels.len();
                         new_active_panel_idx =
@@ -276,6 +592,31 @@ impl App {
                             }
                         }
                     }
+                    Internal::set_encoding => {
+                        let arg = cmd
+                            .as_verb_invocation()
+                            .and_then(|vi| vi.args.as_ref())
+                            .map(|arg| arg.trim())
+                            .filter(|arg| !arg.is_empty());
+                        match arg {
+                            Some("auto") => {
+                                con.forced_encoding = None;
+                                self.panels.update_preview(true, con);
+                            }
+                            Some(arg) => match arg.parse() {
+                                Ok(encoding) => {
+                                    con.forced_encoding = Some(encoding);
+                                    self.panels.update_preview(true, con);
+                                }
+                                Err(e) => {
+                                    error = Some(e);
+                                }
+                            },
+                            None => {
+                                error = Some("no encoding provided".to_string());
+                            }
+                        }
+                    }
                     Internal::toggle_second_tree => {
                         let panels_count = self.panels.len();
                         let trees_count = self.panels.count_of_type(PanelStateType::Tree);
@@ -309,6 +650,208 @@ impl App {
                             self.panels.close_rightest_inactive_tree(con);
                         }
                     }
+                    Internal::refresh_changes => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        let changes = self.watcher.take_changes();
+                        if changes.all {
+                            info!("refreshing everything after an event without paths");
+                            clear_caches();
+                            app_state.stage.refresh();
+                            self.panels.refresh_all_panels(con);
+                        } else if !changes.paths.is_empty() {
+                            info!("refreshing {} changed paths", changes.paths.len());
+                            file_sum::invalidate_cache(&changes.paths);
+                            git::invalidate_status_computer_cache(&changes.paths);
+                            crate::verb::clear_file_condition_cache();
+                            app_state.stage.refresh();
+                            self.panels.refresh_changed_in_all_panels(&changes.paths, con);
+                        }
+                    }
+                    Internal::refresh_followed => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        self.panels.refresh_followed_in_all_panels(con);
+                    }
+                    Internal::tab_new => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        let name = cmd
+                            .as_verb_invocation()
+                            .and_then(|vi| vi.args.as_ref())
+                            .map(|name| name.trim().to_string())
+                            .filter(|name| !name.is_empty());
+                        let root = self
+                            .panels
+                            .state()
+                            .selected_path()
+                            .map_or_else(|| app_state.root.clone(), closest_dir);
+                        let tree_options = self.panels.state().tree_options().without_pattern();
+                        match AppPanelsAndInputs::with_root(root, tree_options, None, con) {
+                            Ok(new_panels) => {
+                                self.tabs.open(
+                                    name,
+                                    new_panels,
+                                    &mut self.panels,
+                                    &mut app_state.stage,
+                                    con,
+                                );
+                            }
+                            Err(e) => {
+                                error = Some(e.to_string());
+                            }
+                        }
+                    }
+                    Internal::macro_record => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        self.macros.start();
+                        self.panels
+                            .mut_panel()
+                            .set_message("Recording a macro. Stop with `:macro_stop`");
+                    }
+                    Internal::macro_stop => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        match self.macros.stop() {
+                            Some(0) => {
+                                error = Some("Nothing was recorded".to_string());
+                            }
+                            Some(n) => {
+                                self.panels.mut_panel().set_message(format!(
+                                    "Macro of {n} command(s) recorded. Replay it with `:macro_play`"
+                                ));
+                            }
+                            None => {
+                                error = Some("No macro is being recorded".to_string());
+                            }
+                        }
+                    }
+                    Internal::macro_play | Internal::macro_play_staged => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        if self.macros.is_recording() {
+                            error = Some("A macro can't be played while recording".to_string());
+                        } else if let Some(recorded) = self.macros.last() {
+                            let sequence = if internal == Internal::macro_play {
+                                let count = cmd
+                                    .as_verb_invocation()
+                                    .and_then(|vi| vi.args.as_ref())
+                                    .map(|count| count.trim())
+                                    .filter(|count| !count.is_empty());
+                                match count.map(str::parse) {
+                                    None => recorded.sequence(1),
+                                    Some(Ok(count)) => recorded.sequence(count),
+                                    Some(Err(_)) => Err(ProgramError::Macro {
+                                        details: "the count must be a positive integer"
+                                            .to_string(),
+                                    }),
+                                }
+                            } else if app_state.stage.is_empty() {
+                                Err(ProgramError::Macro {
+                                    details: "the staging area is empty".to_string(),
+                                })
+                            } else {
+                                recorded.sequence_per_path(app_state.stage.paths())
+                            };
+                            if let Err(e) = sequence.and_then(|seq| self.send_sequence(seq)) {
+                                error = Some(e.to_string());
+                            }
+                        } else {
+                            error = Some("No macro was recorded".to_string());
+                        }
+                    }
+                    Internal::macro_save => {
+                        let name = cmd
+                            .as_verb_invocation()
+                            .and_then(|vi| vi.args.as_ref())
+                            .map(|name| name.trim())
+                            .filter(|name| !name.is_empty());
+                        if let Some(name) = name {
+                            match self.save_macro(name, con) {
+                                Ok(()) => {
+                                    if is_input_invocation {
+                                        self.panels.clear_input_invocation(con);
+                                    }
+                                    self.panels.mut_panel().set_message(format!(
+                                        "Macro saved as the *{name}* verb"
+                                    ));
+                                }
+                                Err(e) => {
+                                    error = Some(e.to_string());
+                                }
+                            }
+                        } else {
+                            error = Some("A macro name is needed".to_string());
+                        }
+                    }
+                    Internal::session_save => {
+                        let name = cmd
+                            .as_verb_invocation()
+                            .and_then(|vi| vi.args.as_ref())
+                            .map(|name| name.trim())
+                            .filter(|name| !name.is_empty());
+                        if let Some(name) = name {
+                            let saved = Session::named_path(name)
+                                .and_then(|path| self.session(app_state, con).save(&path));
+                            match saved {
+                                Ok(()) => {
+                                    if is_input_invocation {
+                                        self.panels.clear_input_invocation(con);
+                                    }
+                                    self.panels
+                                        .mut_panel()
+                                        .set_message(format!("Session saved as *{name}*"));
+                                }
+                                Err(e) => {
+                                    error = Some(e.to_string());
+                                }
+                            }
+                        } else {
+                            error = Some("A session name is needed".to_string());
+                        }
+                    }
+                    Internal::tab_close => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        if !self
+                            .tabs
+                            .close_active(&mut self.panels, &mut app_state.stage, con)
+                        {
+                            error = Some("The last tab can't be closed".to_string());
+                        }
+                    }
+                    Internal::tab_next | Internal::tab_previous => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        let len = self.tabs.len();
+                        let idx = if internal == Internal::tab_next {
+                            (self.tabs.active_tab_idx() + 1) % len
+                        } else {
+                            (self.tabs.active_tab_idx() + len - 1) % len
+                        };
+                        self.tabs
+                            .activate(idx, &mut self.panels, &mut app_state.stage, con);
+                    }
+                    Internal::toggle_dry_run => {
+                        if is_input_invocation {
+                            self.panels.clear_input_invocation(con);
+                        }
+                        con.dry_run ^= true;
+                        self.panels.mut_panel().set_message(if con.dry_run {
+                            "Dry run: external commands are displayed, not executed"
+                        } else {
+                            "Dry run ended: external commands are executed"
+                        });
+                    }
                     Internal::toggle_watch => {
                         app_state.watch_tree ^= true;
                         if is_input_invocation {
@@ -338,6 +881,24 @@ impl App {
                 }
                 self.panels.mut_panel().set_message(md);
             }
+            CmdResult::StartJobs(requests) => {
+                if is_input_invocation {
+                    self.panels.clear_input_invocation(con);
+                }
+                let mut started = Vec::new();
+                for request in requests {
+                    match app_state.jobs.start(request, self.tx_seqs.clone()) {
+                        Ok(id) => started.push(format!("#{id}")),
+                        Err(e) => error = Some(e.to_string()),
+                    }
+                }
+                if error.is_none() && !started.is_empty() {
+                    self.panels.mut_panel().set_message(format!(
+                        "Started in background: job {} (see `:jobs`)",
+                        started.join(", "),
+                    ));
+                }
+            }
             CmdResult::Launch(launchable) => {
                 self.launch_at_end = Some(*launchable);
                 self.quitting = true;
@@ -416,6 +977,19 @@ impl App {
             self.panels.refresh_input_status(app_state, panel_skin, con);
         }
 
+        self.sync_app_state(w, app_state, con);
+
+        Ok(())
+    }
+
+    /// update the app state, the tab bar and the preview after a
+    /// change of the active panel or of its state
+    fn sync_app_state(
+        &mut self,
+        w: &mut W,
+        app_state: &mut AppState,
+        con: &AppContext,
+    ) {
         app_state.other_panel_path = self.panels.get_other_panel_path();
         if let Some(path) = self.panels.state().tree_root() {
             app_state.root = path.to_path_buf();
@@ -432,8 +1006,47 @@ impl App {
             }
         }
 
+        let tab_bar = self.tabs.tab_bar(&self.panels);
+        self.panels.set_tab_bar(tab_bar, con);
+
         self.panels.update_preview(false, con);
+    }
 
+    /// apply the commands of a sequence, in order, displaying the panels
+    /// after each one.
+    ///
+    /// When a verb must be confirmed, the application stops there and the
+    /// remaining commands are kept, to be applied if the execution is confirmed.
+    fn apply_sequence_commands(
+        &mut self,
+        w: &mut W,
+        commands: Vec<(String, Command)>,
+        skin: &AppSkin,
+        dam: &mut Dam,
+        app_state: &mut AppState,
+        con: &mut AppContext,
+    ) -> Result<(), ProgramError> {
+        let mut commands = commands.into_iter();
+        while let Some((input, cmd)) = commands.next() {
+            if !matches!(&cmd, Command::Internal { .. }) {
+                self.panels.input().set_content(&input);
+            }
+            self.apply_command(w, &cmd, &skin.focused, app_state, con)?;
+            if self.quitting {
+                return Ok(());
+            }
+            if self.panels.is_prompting() {
+                // the rest waits for the end of the prompt, after the commands
+                // of a sequence which may already be suspended
+                self.suspended_commands.extend(commands);
+                return Ok(());
+            }
+            self.panels.display_panels(w, skin, app_state, con)?;
+            time!(
+                "sequence pending tasks",
+                self.panels.do_pending_tasks(w, skin, dam, app_state, con)?,
+            );
+        }
         Ok(())
     }
 
@@ -475,7 +1088,11 @@ impl App {
         let mut dam = Dam::from(rx_events);
         let skin = AppSkin::new(conf, con.launch_args.color == TriBool::No);
         let mut app_state = AppState::new(&con.initial_root);
-        terminal::update_title(w, &app_state, con);
+        if let Some(stage) = self.restored_stage.take() {
+            app_state.stage = stage;
+        }
+        // updates the title, and the tab bar of a restored session
+        self.sync_app_state(w, &mut app_state, con);
 
         self.panels
             .screen()
@@ -540,6 +1157,7 @@ impl App {
                     app_state.watch_tree = false;
                 }
             }
+            self.follower.follow(self.panels.followed_paths());
             let event = dam.next(&self.rx_seqs);
             if app_state.watch_tree {
                 // we must unwatch before applying the command, as it will probably do many system
@@ -556,13 +1174,30 @@ impl App {
                     }
                     let mut handled = false;
 
-                    // app level handling
-                    if let Some((x, y)) = event.as_click() {
-                        let clicked_idx = self.panels.clicked_panel_index(x, y);
-                        if clicked_idx != self.panels.active_panel_idx() {
-                            // panel activation click
-                            self.panels.activate(clicked_idx);
+                    // app level handling, which doesn't apply while prompting
+                    // because it would change the panel or tab of the prompt
+                    let click = event.as_click().filter(|_| !self.panels.is_prompting());
+                    if let Some((x, y)) = click {
+                        if let Some(tab_bar) = self.panels.tab_bar().filter(|_| y == 0) {
+                            // click in the tab bar
+                            let clicked_tab_idx = tab_bar.clicked_tab_idx(x, self.panels.screen());
+                            if let Some(idx) = clicked_tab_idx {
+                                self.tabs.activate(
+                                    idx,
+                                    &mut self.panels,
+                                    &mut app_state.stage,
+                                    con,
+                                );
+                                self.sync_app_state(w, &mut app_state, con);
+                            }
                             handled = true;
+                        } else {
+                            let clicked_idx = self.panels.clicked_panel_index(x, y);
+                            if clicked_idx != self.panels.active_panel_idx() {
+                                // panel activation click
+                                self.panels.activate(clicked_idx);
+                                handled = true;
+                            }
                         }
                     } else if let Event::Resize(mut width, mut height) = event.event {
                         self.panels.set_terminal_size(width, height, con);
@@ -574,6 +1209,29 @@ impl App {
                         let cmd = self.panels.on_input_event(w, &event, &app_state, con)?;
                         info!("command from panels.on_input_event: {:#?}", cmd);
                         self.apply_command(w, &cmd, &skin.focused, &mut app_state, con)?;
+                        self.macros.record(&cmd, &con.verb_store);
+                        if !self.suspended_commands.is_empty() && !self.panels.is_prompting() {
+                            // the prompting of a verb of a sequence just ended
+                            let commands = std::mem::take(&mut self.suspended_commands);
+                            if matches!(cmd, Command::VerbTrigger { .. }) {
+                                self.apply_sequence_commands(
+                                    w,
+                                    commands,
+                                    &skin,
+                                    &mut dam,
+                                    &mut app_state,
+                                    con,
+                                )?;
+                                if self.quitting {
+                                    self.save_last_session(&app_state, con);
+                                    return Ok(self.launch_at_end.take());
+                                }
+                            } else {
+                                self.panels
+                                    .mut_panel()
+                                    .set_message("The rest of the sequence was cancelled");
+                            }
+                        }
                     }
 
                     // Lazy graphics detection: the first time an image preview is
@@ -593,25 +1251,18 @@ impl App {
                 }
                 Either::Second(Some(sequence)) => {
                     info!("got command sequence: {:?}", sequence);
-                    for (input, arg_cmd) in sequence.parse(con)? {
-                        if !matches!(&arg_cmd, Command::Internal { .. }) {
-                            self.panels.input().set_content(&input);
-                        }
-                        self.apply_command(w, &arg_cmd, &skin.focused, &mut app_state, con)?;
-                        if self.quitting {
-                            return Ok(self.launch_at_end.take());
-                        }
-                        self.panels.display_panels(w, &skin, &app_state, con)?;
-                        time!(
-                            "sequence pending tasks",
-                            self.panels.do_pending_tasks(
-                                w,
-                                &skin,
-                                &mut dam,
-                                &mut app_state,
-                                con
-                            )?,
-                        );
+                    let commands = sequence.parse(con)?;
+                    self.apply_sequence_commands(
+                        w,
+                        commands,
+                        &skin,
+                        &mut dam,
+                        &mut app_state,
+                        con,
+                    )?;
+                    if self.quitting {
+                        self.save_last_session(&app_state, con);
+                        return Ok(self.launch_at_end.take());
                     }
                 }
                 Either::Second(None) => {
@@ -619,6 +1270,7 @@ impl App {
                 }
             }
         }
+        self.save_last_session(&app_state, con);
         terminal::reset_title(w, con);
         if let Ok(mut manager) = graphics::manager().lock() {
             manager.erase_images_before(w, usize::MAX)?;
@@ -635,6 +1287,7 @@ impl App {
 fn clear_caches() {
     file_sum::clear_cache();
     git::clear_status_computer_cache();
+    crate::verb::clear_file_condition_cache();
     #[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
     crate::filesystems::clear_cache();
 }
@@ -8,6 +8,7 @@ use {
         },
         conf::*,
         content_search,
+        content_type::encoding::Encoding,
         display::LayoutInstructions,
         errors::*,
         file_sum,
@@ -19,13 +20,21 @@ use {
         },
         path::SpecialPaths,
         pattern::SearchModeMap,
-        preview::PreviewTransformers,
-        skin::ExtColorMap,
+        preview::{
+            PreviewMode,
+            PreviewTransformers,
+        },
+        skin::{
+            ExtColorMap,
+            FileColorMap,
+        },
         syntactic::SyntaxTheme,
         tree::TreeOptions,
         verb::*,
     },
     crokey::crossterm::tty::IsTty,
+    rustc_hash::FxHashMap,
+    termimad::parse_compound_style,
     std::{
         convert::{
             TryFrom,
@@ -37,6 +46,7 @@ use {
             Path,
             PathBuf,
         },
+        time::Duration,
     },
 };
 
@@ -78,12 +88,17 @@ pub struct AppContext {
     /// whether to show a triangle left to selected lines
     pub show_selection_mark: bool,
 
-    /// mapping from file extension to colors (comes from conf)
-    pub ext_colors: ExtColorMap,
+    /// styles of file names, depending on their extension, on globs
+    /// or on the kind of file (comes from conf and `LS_COLORS`)
+    pub file_colors: FileColorMap,
 
     /// the syntect theme to use for text files previewing
     pub syntax_theme: Option<SyntaxTheme>,
 
+    /// the encoding of text previews, when not detected
+    /// (set with `:set_encoding`)
+    pub forced_encoding: Option<Encoding>,
+
     /// precomputed status to display in standard cases
     /// (ie when no verb is involved)
     pub standard_status: StandardStatus,
@@ -112,9 +127,32 @@ pub struct AppContext {
     /// and there's nothing to cancel
     pub quit_on_last_cancel: bool,
 
+    /// whether the session is saved on quit and restored on the
+    /// next launch in the same directory
+    pub session_auto_restore: bool,
+
+    /// whether external executions are displayed instead of run
+    /// (toggled with `:toggle_dry_run`)
+    pub dry_run: bool,
+
     /// number of threads used by `file_sum` (count, size, date) computation
     pub file_sum_threads_count: usize,
 
+    /// files and directories which aren't counted in `file_sum` computation
+    pub file_sum_excludes: Vec<glob::Pattern>,
+
+    /// whether `file_sum` computation stays on the filesystem of the
+    /// summed directory (like `du -x`)
+    pub file_sum_one_filesystem: bool,
+
+    /// max duration of the computation of a directory sum, after which
+    /// the sum is marked as partial
+    pub file_sum_timeout: Option<Duration>,
+
+    /// whether directory sums are persisted on disk, to be displayed
+    /// in the next sessions while they're computed again
+    pub file_sum_cache: bool,
+
     /// number of files which may be staged in one staging operation
     pub max_staged_count: usize,
 
@@ -161,6 +199,9 @@ pub struct AppContext {
     /// The set of transformers called before previewing a file
     pub preview_transformers: PreviewTransformers,
 
+    /// The preview modes to use by default, per file extension
+    pub preview_modes: FxHashMap<String, PreviewMode>,
+
     /// layout modifiers, like divider moves
     pub layout_instructions: LayoutInstructions,
 
@@ -196,7 +237,7 @@ impl AppContext {
             .map(TryInto::try_into)
             .transpose()?
             .unwrap_or_default();
-        let ext_colors = ExtColorMap::try_from(&config.ext_colors).map_err(ConfError::from)?;
+        let file_colors = build_file_colors(config)?;
         let file_sum_threads_count = config
             .file_sum_threads_count
             .unwrap_or(file_sum::DEFAULT_THREAD_COUNT);
@@ -206,6 +247,17 @@ impl AppContext {
             }
             .into());
         }
+        let file_sum_excludes = config
+            .file_sum_excludes
+            .iter()
+            .map(GlobConf::to_glob)
+            .collect::<Result<Vec<_>, _>>()?;
+        let file_sum_one_filesystem =
+            launch_args.one_filesystem || config.file_sum_one_filesystem.unwrap_or(false);
+        let file_sum_timeout = config
+            .file_sum_timeout
+            .filter(|&secs| secs > 0)
+            .map(Duration::from_secs);
         let max_panels_count = config.max_panels_count.unwrap_or(2).clamp(2, 100);
         let capture_mouse = match (config.capture_mouse, config.disable_mouse_capture) {
             (Some(b), _) => b, // the new "capture_mouse" argument takes precedence
@@ -215,6 +267,7 @@ impl AppContext {
         let max_staged_count = config.max_staged_count.unwrap_or(10_000).clamp(10, 100_000);
         let auto_open_staging_area = config.auto_open_staging_area.unwrap_or(true);
         let (initial_root, initial_file) = initial_root_file(&launch_args)?;
+        let dry_run = launch_args.dry_run;
 
         // tree options are built from the default_flags
         // found in the config file(s) (if any) then overridden
@@ -257,8 +310,9 @@ impl AppContext {
             special_paths,
             search_modes,
             show_selection_mark: config.show_selection_mark.unwrap_or(false),
-            ext_colors,
+            file_colors,
             syntax_theme: config.syntax_theme,
+            forced_encoding: None,
             standard_status,
             true_colors,
             icons,
@@ -267,7 +321,13 @@ impl AppContext {
             capture_mouse,
             max_panels_count,
             quit_on_last_cancel: config.quit_on_last_cancel.unwrap_or(false),
+            session_auto_restore: config.session_auto_restore.unwrap_or(false),
+            dry_run,
             file_sum_threads_count,
+            file_sum_excludes,
+            file_sum_one_filesystem,
+            file_sum_timeout,
+            file_sum_cache: config.file_sum_cache.unwrap_or(false),
             max_staged_count,
             auto_open_staging_area,
             content_search_max_file_size,
@@ -282,6 +342,7 @@ impl AppContext {
             lines_after_match_in_preview: config.lines_after_match_in_preview.unwrap_or(0),
             lines_before_match_in_preview: config.lines_before_match_in_preview.unwrap_or(0),
             preview_transformers,
+            preview_modes: config.preview_modes.clone(),
             layout_instructions,
             server_name,
         })
@@ -319,6 +380,27 @@ impl Default for AppContext {
     }
 }
 
+/// build the map of file name styles, from the `ext_colors`,
+/// `file_colors`, and `kind_colors` of the config, then from
+/// `LS_COLORS` if its import is enabled
+fn build_file_colors(config: &Conf) -> Result<FileColorMap, ConfError> {
+    let ext_colors = ExtColorMap::try_from(&config.ext_colors)?;
+    let mut file_colors = FileColorMap::new(ext_colors);
+    let parse_style = |raw: &str| parse_compound_style(raw).map_err(InvalidSkinError::from);
+    for (glob, raw_style) in &config.file_colors {
+        file_colors.set_glob(glob, parse_style(raw_style)?)?;
+    }
+    for (kind, raw_style) in &config.kind_colors {
+        file_colors.set_kind(*kind, parse_style(raw_style)?);
+    }
+    if config.import_ls_colors == Some(true) {
+        if let Ok(ls_colors) = std::env::var("LS_COLORS") {
+            file_colors.import_ls_colors(&ls_colors);
+        }
+    }
+    Ok(file_colors)
+}
+
 /// try to determine whether the terminal supports true
 /// colors. This doesn't work well, hence the use of an
 /// optional config setting.
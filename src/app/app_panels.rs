@@ -7,7 +7,12 @@ use {
         errors::ProgramError,
         graphics,
         skin::*,
+        pattern::InputPattern,
+        preview::PreviewState,
+        session::*,
+        stage::Stage,
         task_sync::Dam,
+        tree::TreeOptions,
         verb::*,
     },
     crokey::crossterm::{
@@ -36,6 +41,9 @@ pub struct AppPanelsAndInputs {
     /// one input per panel, in the same order. Never empty.
     inputs: Vec<PanelInput>,
 
+    /// the tab bar displayed above the panels, when there are several tabs
+    tab_bar: Option<TabBar>,
+
     /// counter incremented at every draw
     drawing_count: usize,
 }
@@ -61,30 +69,172 @@ impl AppPanelsAndInputs {
     /// the application, starting with a single panel (it can't be empty), based on
     /// the `initial_root`
     pub fn new(con: &AppContext) -> Result<Self, ProgramError> {
-        let screen = Screen::new(con)?;
-        let mut browser_state = Box::new(BrowserState::new(
+        Self::with_root(
             con.initial_root.clone(),
             con.initial_tree_options.clone(),
+            con.initial_file.as_deref(),
+            con,
+        )
+    }
+
+    /// Create a set of panels starting with a single tree panel on `root`,
+    /// with `selected_path` selected if it's in the tree
+    pub fn with_root(
+        root: PathBuf,
+        tree_options: TreeOptions,
+        selected_path: Option<&Path>,
+        con: &AppContext,
+    ) -> Result<Self, ProgramError> {
+        let screen = Screen::new(con)?;
+        let mut browser_state = Box::new(BrowserState::new(
+            root,
+            tree_options,
             screen,
             con,
             &Dam::unlimited(),
         )?);
-        if let Some(path) = con.initial_file.as_ref() {
+        if let Some(path) = selected_path {
             browser_state.tree.try_select_path(path);
         }
+        Ok(Self::with_state(browser_state, screen, con))
+    }
+
+    /// Create a set of panels starting with a single panel on the given state
+    fn with_state(
+        state: Box<dyn PanelState>,
+        screen: Screen,
+        con: &AppContext,
+    ) -> Self {
         let areas = Areas::create(&mut Vec::new(), &con.layout_instructions, 0, screen, false);
-        let input = PanelInput::new(areas.input.clone());
-        let panel = Panel::new(browser_state, areas, con);
+        let mut input = PanelInput::new(areas.input.clone());
+        input.set_content(&state.get_starting_input());
+        let panel = Panel::new(state, areas, con);
         debug!("initial panel areas: {:?}", panel.areas);
-        Ok(Self {
+        Self {
             panels: AppPanels {
                 screen,
                 active_panel_idx: 0,
                 panels: vec![panel],
             },
             inputs: vec![input],
+            tab_bar: None,
             drawing_count: 0,
-        })
+        }
+    }
+
+    /// Rebuild the panels of a tab saved in a session, skipping the
+    /// tree panels whose root doesn't exist anymore.
+    ///
+    /// Return None when no panel could be restored
+    pub fn from_tab_session(
+        tab_session: &TabSession,
+        con: &AppContext,
+    ) -> Result<Option<Self>, ProgramError> {
+        let screen = Screen::new(con)?;
+        let mut restored: Option<Self> = None;
+        let mut active_panel_idx = 0;
+        for (idx, panel_session) in tab_session.panels.iter().enumerate() {
+            if !panel_session.root.is_dir() {
+                info!("not restoring missing root {:?}", panel_session.root);
+                continue;
+            }
+            let mut tree_options = con.initial_tree_options.without_pattern();
+            panel_session.options.apply_to(&mut tree_options);
+            if !panel_session.pattern.is_empty() {
+                // the search is done as a pending task of the new state
+                let parts = CommandParts::from(panel_session.pattern.clone());
+                match InputPattern::new(parts.raw_pattern, &parts.pattern, con) {
+                    Ok(pattern) => {
+                        tree_options.pattern = pattern;
+                    }
+                    Err(e) => {
+                        warn!("not restoring invalid pattern: {e}");
+                    }
+                }
+            }
+            let mut browser_state = Box::new(BrowserState::new(
+                panel_session.root.clone(),
+                tree_options,
+                screen,
+                con,
+                &Dam::unlimited(),
+            )?);
+            if let Some(path) = &panel_session.selection {
+                browser_state.tree.try_select_path(path);
+            }
+            if let Some(panels) = restored.as_mut() {
+                if idx <= tab_session.active_panel_idx {
+                    active_panel_idx = panels.len();
+                }
+                if let Err(e) =
+                    panels.new_panel(browser_state, PanelPurpose::None, HDir::Right, true, con)
+                {
+                    warn!("not restoring panel: {e}");
+                }
+            } else {
+                restored = Some(Self::with_state(browser_state, screen, con));
+            }
+        }
+        let Some(mut panels) = restored else {
+            return Ok(None);
+        };
+        panels.activate(active_panel_idx);
+        if tab_session.preview {
+            if let Some(path) = panels.state().selected_path() {
+                let preview_state = Box::new(PreviewState::new(
+                    path.to_path_buf(),
+                    InputPattern::none(),
+                    None,
+                    panels.state().tree_options(),
+                    con,
+                ));
+                if let Err(e) =
+                    panels.new_panel(preview_state, PanelPurpose::Preview, HDir::Right, false, con)
+                {
+                    warn!("not restoring preview: {e}");
+                }
+            }
+        }
+        Ok(Some(panels))
+    }
+
+    /// Build what's saved in a session of these panels: the tree panels,
+    /// whether there's a preview, and the stage
+    pub fn tab_session(
+        &self,
+        name: Option<String>,
+        stage: &Stage,
+    ) -> TabSession {
+        let mut panels = Vec::new();
+        let mut active_panel_idx = 0;
+        for (idx, panel) in self.panels.panels.iter().enumerate() {
+            let state = panel.state();
+            if state.get_type() != PanelStateType::Tree {
+                continue;
+            }
+            let Some(root) = state.tree_root() else {
+                continue;
+            };
+            if idx <= self.active_panel_idx() {
+                active_panel_idx = panels.len();
+            }
+            let options = state.tree_options();
+            // the pattern is taken from the input, as the search may be pending
+            let pattern = CommandParts::from(self.inputs[idx].get_content()).raw_pattern;
+            panels.push(PanelSession {
+                root: root.to_path_buf(),
+                pattern,
+                selection: state.selected_path().map(Path::to_path_buf),
+                options: SessionTreeOptions::from(&options),
+            });
+        }
+        TabSession {
+            name,
+            active_panel_idx,
+            preview: self.has_preview_panel(),
+            stage: stage.paths().to_vec(),
+            panels,
+        }
     }
 
     // ----------------------------------------------------
@@ -111,6 +261,38 @@ impl AppPanelsAndInputs {
         self.resize_all(con);
     }
 
+    /// give the panels the dimensions of the screen, which may have
+    /// changed while they weren't displayed (eg in another tab)
+    pub fn set_screen(
+        &mut self,
+        screen: Screen,
+        con: &AppContext,
+    ) {
+        if self.screen() != screen {
+            self.panels.screen = screen;
+            self.resize_all(con);
+        }
+    }
+
+    /// set the tab bar displayed above the panels (None when there's a single
+    /// tab), resizing the panels if its height changed
+    pub fn set_tab_bar(
+        &mut self,
+        tab_bar: Option<TabBar>,
+        con: &AppContext,
+    ) {
+        let top = u16::from(tab_bar.is_some());
+        self.tab_bar = tab_bar;
+        if self.panels.screen.top != top {
+            self.panels.screen.top = top;
+            self.resize_all(con);
+        }
+    }
+
+    pub fn tab_bar(&self) -> Option<&TabBar> {
+        self.tab_bar.as_ref()
+    }
+
     pub fn resize_all(
         &mut self,
         con: &AppContext,
@@ -442,6 +624,29 @@ impl AppPanelsAndInputs {
         self.inputs[panel_idx].on_event(w, timed_event, &self.panels, app_state, con)
     }
 
+    /// start asking, in the input of the active panel, the prompts of a
+    /// verb whose execution was requested out of the input
+    pub fn prompt_before_execution(
+        &mut self,
+        verb_id: VerbId,
+        invocation: &VerbInvocation,
+        app_state: &AppState,
+        con: &AppContext,
+    ) -> Command {
+        let verb = con.verb_store.verb(verb_id);
+        let panel_state = self
+            .panels
+            .state_by_ref(verb.impacted_panel)
+            .unwrap_or_else(|| self.panels.state());
+        let sel_info = panel_state.sel_info(app_state);
+        self.inputs[self.panels.active_panel_idx]
+            .prompt_before_execution(verb, invocation, sel_info, app_state, con)
+    }
+    /// tell whether a verb prompt is displayed in any panel
+    pub fn is_prompting(&self) -> bool {
+        self.inputs.iter().any(PanelInput::is_prompting)
+    }
+
     // ----------------------------------------------------
     // command execution
 
@@ -565,6 +770,35 @@ impl AppPanelsAndInputs {
             panel.mut_state().refresh(screen, con);
         }
     }
+    /// update the panels after changes of the given paths, as
+    /// reported by the watcher
+    pub fn refresh_changed_in_all_panels(
+        &mut self,
+        changed: &[PathBuf],
+        con: &AppContext,
+    ) {
+        let screen = self.screen();
+        for panel in &mut self.panels.panels {
+            panel.mut_state().refresh_changed(changed, screen, con);
+        }
+    }
+    /// the files followed in the panels
+    pub fn followed_paths(&self) -> Vec<PathBuf> {
+        self.panels
+            .panels
+            .iter()
+            .filter_map(|panel| panel.state().followed_path())
+            .map(Path::to_path_buf)
+            .collect()
+    }
+    pub fn refresh_followed_in_all_panels(
+        &mut self,
+        con: &AppContext,
+    ) {
+        for panel in &mut self.panels.panels {
+            panel.mut_state().refresh_followed(con);
+        }
+    }
     pub fn refresh_input_status(
         &mut self,
         app_state: &mut AppState,
@@ -732,6 +966,9 @@ impl AppPanelsAndInputs {
         con: &AppContext,
     ) -> Result<(), ProgramError> {
         let screen = self.screen();
+        if let Some(tab_bar) = &self.tab_bar {
+            tab_bar.display(w, screen, &skin.focused)?;
+        }
         let mut cursor_pos = None;
         let active_panel_idx = self.active_panel_idx();
         for (idx, panel) in self.panels.panels.iter_mut().enumerate() {
@@ -1,5 +1,9 @@
 use {
-    crate::stage::Stage,
+    crate::{
+        job::JobList,
+        output::OutputHistory,
+        stage::Stage,
+    },
     std::path::PathBuf,
 };
 
@@ -19,6 +23,12 @@ pub struct AppState {
     /// the selected path in another panel than the currently
     /// active one, if any
     pub other_panel_path: Option<PathBuf>,
+
+    /// the captured outputs of the last external commands
+    pub outputs: OutputHistory,
+
+    /// the external commands launched in the background
+    pub jobs: JobList,
 }
 
 impl AppState {
@@ -28,6 +38,8 @@ impl AppState {
             root: root.into(),
             watch_tree: false,
             other_panel_path: None,
+            outputs: OutputHistory::default(),
+            jobs: JobList::default(),
         }
     }
 }
@@ -0,0 +1,225 @@
+use {
+    super::*,
+    crate::{
+        errors::ProgramError,
+        session::*,
+        stage::Stage,
+    },
+    std::mem,
+};
+
+/// What a tab holds while it's not the active one
+struct ParkedTab {
+    panels: AppPanelsAndInputs,
+    stage: Stage,
+}
+
+/// A named set of panels, with their own roots, patterns and stage
+struct Tab {
+    /// the name given at creation, if any. When there's none, the tab
+    /// is named after the root of its active panel
+    name: Option<String>,
+
+    /// the panels and stage of the tab, None when it's the active tab
+    /// (they're then the ones of the app)
+    parked: Option<ParkedTab>,
+}
+
+/// Stores the tabs of the application.
+///
+/// The panels and stage of the active tab aren't stored here but in the
+/// app, so tab operations swap them with the ones of the other tabs.
+pub struct AppTabs {
+    /// tabs from left to right, never empty
+    tabs: Vec<Tab>,
+
+    active_tab_idx: usize, // guaranteed to be < tabs.len()
+}
+
+impl Default for AppTabs {
+    fn default() -> Self {
+        Self {
+            tabs: vec![Tab {
+                name: None,
+                parked: None,
+            }],
+            active_tab_idx: 0,
+        }
+    }
+}
+
+impl AppTabs {
+    /// Rebuild the tabs saved in a session, skipping the ones which
+    /// can't be restored, and return them with the panels and stage of
+    /// the active one.
+    ///
+    /// Return None when no tab could be restored
+    pub fn from_session(
+        session: &Session,
+        con: &AppContext,
+    ) -> Result<Option<(Self, AppPanelsAndInputs, Stage)>, ProgramError> {
+        let mut tabs = Vec::new();
+        let mut active_tab_idx = 0;
+        for (idx, tab_session) in session.tabs.iter().enumerate() {
+            let Some(panels) = AppPanelsAndInputs::from_tab_session(tab_session, con)? else {
+                continue;
+            };
+            let mut stage = Stage::default();
+            for path in tab_session.stage.iter().filter(|path| path.exists()) {
+                stage.add(path.clone());
+            }
+            if idx <= session.active_tab_idx {
+                active_tab_idx = tabs.len();
+            }
+            tabs.push(Tab {
+                name: tab_session.name.clone(),
+                parked: Some(ParkedTab { panels, stage }),
+            });
+        }
+        let Some(active) = tabs.get_mut(active_tab_idx).and_then(|tab| tab.parked.take()) else {
+            return Ok(None);
+        };
+        Ok(Some((
+            Self {
+                tabs,
+                active_tab_idx,
+            },
+            active.panels,
+            active.stage,
+        )))
+    }
+
+    /// Build what's saved in a session of the tabs, given the panels and
+    /// stage of the active one
+    pub fn tab_sessions(
+        &self,
+        panels: &AppPanelsAndInputs,
+        stage: &Stage,
+    ) -> Vec<TabSession> {
+        self.tabs
+            .iter()
+            .map(|tab| match &tab.parked {
+                Some(parked) => parked.panels.tab_session(tab.name.clone(), &parked.stage),
+                None => panels.tab_session(tab.name.clone(), stage),
+            })
+            .collect()
+    }
+
+    #[allow(clippy::len_without_is_empty)] // there's always at least one tab
+    pub fn len(&self) -> usize {
+        self.tabs.len()
+    }
+    pub fn active_tab_idx(&self) -> usize {
+        self.active_tab_idx
+    }
+
+    /// insert a tab right of the active one, and make it active
+    pub fn open(
+        &mut self,
+        name: Option<String>,
+        mut new_panels: AppPanelsAndInputs,
+        panels: &mut AppPanelsAndInputs,
+        stage: &mut Stage,
+        con: &AppContext,
+    ) {
+        new_panels.set_screen(panels.screen(), con);
+        let parked = ParkedTab {
+            panels: mem::replace(panels, new_panels),
+            stage: mem::take(stage),
+        };
+        self.tabs[self.active_tab_idx].parked = Some(parked);
+        self.active_tab_idx += 1;
+        self.tabs.insert(
+            self.active_tab_idx,
+            Tab {
+                name,
+                parked: None,
+            },
+        );
+    }
+
+    /// make the tab at `idx` the active one, parking the current one
+    ///
+    /// Return false when there's no such tab or it's already active
+    pub fn activate(
+        &mut self,
+        idx: usize,
+        panels: &mut AppPanelsAndInputs,
+        stage: &mut Stage,
+        con: &AppContext,
+    ) -> bool {
+        if idx == self.active_tab_idx {
+            return false;
+        }
+        let Some(mut incoming) = self.tabs.get_mut(idx).and_then(|tab| tab.parked.take()) else {
+            return false;
+        };
+        incoming.panels.set_screen(panels.screen(), con);
+        let parked = ParkedTab {
+            panels: mem::replace(panels, incoming.panels),
+            stage: mem::replace(stage, incoming.stage),
+        };
+        self.tabs[self.active_tab_idx].parked = Some(parked);
+        self.active_tab_idx = idx;
+        true
+    }
+
+    /// close the active tab if it's not the last one, and activate
+    /// its right neighbour (or the left one if it was the rightest)
+    ///
+    /// Return true when the tab has been closed
+    pub fn close_active(
+        &mut self,
+        panels: &mut AppPanelsAndInputs,
+        stage: &mut Stage,
+        con: &AppContext,
+    ) -> bool {
+        if self.tabs.len() < 2 {
+            return false; // we can't remove the last tab
+        }
+        let closed_idx = self.active_tab_idx;
+        let next_idx = if closed_idx + 1 < self.tabs.len() {
+            closed_idx + 1
+        } else {
+            closed_idx - 1
+        };
+        self.activate(next_idx, panels, stage, con);
+        self.tabs.remove(closed_idx);
+        if self.active_tab_idx > closed_idx {
+            self.active_tab_idx -= 1;
+        }
+        true
+    }
+
+    /// build the tab bar to display, if there are several tabs
+    pub fn tab_bar(
+        &self,
+        panels: &AppPanelsAndInputs,
+    ) -> Option<TabBar> {
+        if self.tabs.len() < 2 {
+            return None;
+        }
+        let names = self
+            .tabs
+            .iter()
+            .map(|tab| {
+                if let Some(name) = &tab.name {
+                    return name.clone();
+                }
+                let panels = tab.parked.as_ref().map_or(panels, |parked| &parked.panels);
+                match panels.state().tree_root() {
+                    Some(root) => root
+                        .file_name()
+                        .unwrap_or(root.as_os_str())
+                        .to_string_lossy()
+                        .to_string(),
+                    None => "-".to_string(),
+                }
+            })
+            .collect();
+        Some(TabBar {
+            names,
+            active_idx: self.active_tab_idx,
+        })
+    }
+}
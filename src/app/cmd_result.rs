@@ -5,8 +5,14 @@ use {
         command::Sequence,
         display::LayoutInstruction,
         errors::TreeBuildError,
+        job::JobRequest,
         launchable::Launchable,
-        verb::Internal,
+        output::CommandOutput,
+        verb::{
+            Internal,
+            VerbId,
+            VerbInvocation,
+        },
     },
     std::fmt,
 };
@@ -23,6 +29,16 @@ pub enum CmdResult {
     ApplyOnPanel {
         id: PanelId,
     },
+    /// outputs of external commands, to store in the history and display
+    CapturedOutputs {
+        outputs: Vec<CommandOutput>,
+        /// the captured commands which didn't end soon enough, to go
+        /// on as background jobs
+        jobs: Vec<JobRequest>,
+        refresh: bool,
+        /// the error which stopped the executions, if any
+        error: Option<String>,
+    },
     ClosePanel {
         validate_purpose: bool,
         panel_ref: PanelReference,
@@ -37,6 +53,14 @@ pub enum CmdResult {
     Keep,
     Message(String),
     Launch(Box<Launchable>),
+    /// the verb must be confirmed (and its missing arguments given)
+    /// in the input before its execution
+    Prompt {
+        verb_id: VerbId,
+        invocation: VerbInvocation,
+    },
+    /// external commands to execute as background jobs
+    StartJobs(Vec<JobRequest>),
     NewPanel {
         state: Box<dyn PanelState>,
         purpose: PanelPurpose,
@@ -113,6 +137,18 @@ impl fmt::Debug for CmdResult {
                 .debug_struct("CmdResult::ApplyOnPanel")
                 .field("id", id)
                 .finish(),
+            CmdResult::CapturedOutputs {
+                outputs,
+                jobs,
+                refresh,
+                error,
+            } => f
+                .debug_struct("CmdResult::CapturedOutputs")
+                .field("outputs", outputs)
+                .field("jobs", jobs)
+                .field("refresh", refresh)
+                .field("error", error)
+                .finish(),
             CmdResult::ClosePanel {
                 validate_purpose,
                 panel_ref,
@@ -148,6 +184,14 @@ impl fmt::Debug for CmdResult {
                 .debug_tuple("CmdResult::Launch")
                 .field(launchable)
                 .finish(),
+            CmdResult::Prompt {
+                verb_id,
+                invocation,
+            } => f
+                .debug_struct("CmdResult::Prompt")
+                .field("verb_id", verb_id)
+                .field("invocation", invocation)
+                .finish(),
             CmdResult::NewPanel {
                 state: _,
                 purpose,
@@ -162,6 +206,10 @@ impl fmt::Debug for CmdResult {
                 .debug_struct("CmdResult::NewState")
                 .field("message", message)
                 .finish_non_exhaustive(),
+            CmdResult::StartJobs(requests) => f
+                .debug_tuple("CmdResult::StartJobs")
+                .field(requests)
+                .finish(),
             CmdResult::PopStateAndReapply => write!(f, "CmdResult::PopStateAndReapply"),
             CmdResult::PopState => write!(f, "CmdResult::PopState"),
             CmdResult::Quit => write!(f, "CmdResult::Quit"),
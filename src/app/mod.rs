@@ -2,6 +2,7 @@ mod app;
 mod app_context;
 mod app_panels;
 mod app_state;
+mod app_tabs;
 mod cmd_context;
 mod cmd_result;
 mod display_context;
@@ -16,12 +17,14 @@ mod selection;
 mod standard_status;
 mod state_type;
 mod status;
+mod tab_bar;
 
 pub use {
     app::App,
     app_context::AppContext,
     app_panels::*,
     app_state::*,
+    app_tabs::AppTabs,
     cmd_context::*,
     cmd_result::*,
     display_context::*,
@@ -36,4 +39,5 @@ pub use {
     standard_status::StandardStatus,
     state_type::PanelStateType,
     status::Status,
+    tab_bar::TabBar,
 };
@@ -3,12 +3,14 @@ use {
     crate::{
         command::*,
         display::*,
+        duplicates::DuplicatesState,
         errors::ProgramError,
         flag::Flag,
         help::HelpState,
         pattern::*,
         preview::*,
         print,
+        size_report::SizeReportState,
         stage::*,
         task_sync::Dam,
         tree::*,
@@ -258,6 +260,39 @@ pub trait PanelState {
                     Err(e) => CmdResult::DisplayError(format!("{e}")),
                 }
             }
+            Internal::find_duplicates => {
+                let root = self.tree_root().unwrap_or(&app_state.root).to_path_buf();
+                let state = DuplicatesState::new(root, self.tree_options(), con);
+                if bang && cc.app.preview_panel.is_none() {
+                    CmdResult::NewPanel {
+                        state: Box::new(state),
+                        purpose: PanelPurpose::None,
+                        direction: HDir::Right,
+                    }
+                } else {
+                    CmdResult::new_state(Box::new(state))
+                }
+            }
+            Internal::size_report => {
+                let Some(path) = self.selected_path() else {
+                    return Ok(CmdResult::error("no selection"));
+                };
+                let path = if path.is_dir() {
+                    path
+                } else {
+                    path.parent().unwrap_or(path)
+                };
+                let state = SizeReportState::new(path.to_path_buf(), self.tree_options(), con);
+                if bang && cc.app.preview_panel.is_none() {
+                    CmdResult::NewPanel {
+                        state: Box::new(state),
+                        purpose: PanelPurpose::None,
+                        direction: HDir::Right,
+                    }
+                } else {
+                    CmdResult::new_state(Box::new(state))
+                }
+            }
             Internal::help => {
                 let bang = input_invocation
                     .map(|inv| inv.bang)
@@ -285,6 +320,17 @@ pub trait PanelState {
             Internal::preview_image => self.open_preview(Some(PreviewMode::Image), false, cc),
             Internal::preview_text => self.open_preview(Some(PreviewMode::Text), false, cc),
             Internal::preview_tty => self.open_preview(Some(PreviewMode::Tty), false, cc),
+            Internal::preview_markdown => {
+                self.open_preview(Some(PreviewMode::Markdown), false, cc)
+            }
+            Internal::preview_table => self.open_preview(Some(PreviewMode::Table), false, cc),
+            Internal::preview_info => self.open_preview(Some(PreviewMode::Info), false, cc),
+            Internal::preview_format => {
+                self.open_preview(Some(PreviewMode::Format), false, cc)
+            }
+            Internal::preview_structured => {
+                self.open_preview(Some(PreviewMode::Structured), false, cc)
+            }
             Internal::preview_binary => self.open_preview(Some(PreviewMode::Hex), false, cc),
             Internal::toggle_preview => self.open_preview(None, true, cc),
             Internal::sort_by_count => self.with_new_options(
@@ -587,6 +633,19 @@ pub trait PanelState {
                 bang,
                 con,
             ),
+            Internal::toggle_apparent_sizes => self.with_new_options(
+                screen,
+                &|o| {
+                    o.apparent_sizes ^= true;
+                    if o.apparent_sizes {
+                        "*now displaying apparent sizes*"
+                    } else {
+                        "*now displaying disk usage*"
+                    }
+                },
+                bang,
+                con,
+            ),
             Internal::toggle_trim_root => self.with_new_options(
                 screen,
                 &|o| {
@@ -629,6 +688,33 @@ pub trait PanelState {
             }
             Internal::toggle_second_tree => CmdResult::HandleInApp(Internal::toggle_second_tree),
             Internal::toggle_watch => CmdResult::HandleInApp(Internal::toggle_watch),
+            Internal::refresh_changes => CmdResult::HandleInApp(Internal::refresh_changes),
+            Internal::refresh_followed => CmdResult::HandleInApp(Internal::refresh_followed),
+            Internal::toggle_follow => CmdResult::error("Following needs a preview panel"),
+            Internal::toggle_wrap => CmdResult::error("Wrapping needs a preview panel"),
+            Internal::jump_to_key => CmdResult::error("Jumping to a key needs a structured preview"),
+            Internal::goto_offset => CmdResult::error("Going to an offset needs a hex preview"),
+            Internal::search_bytes => CmdResult::error("Searching bytes needs a hex preview"),
+            Internal::macro_play => CmdResult::HandleInApp(Internal::macro_play),
+            Internal::macro_play_staged => CmdResult::HandleInApp(Internal::macro_play_staged),
+            Internal::macro_record => CmdResult::HandleInApp(Internal::macro_record),
+            Internal::macro_save => CmdResult::HandleInApp(Internal::macro_save),
+            Internal::macro_stop => CmdResult::HandleInApp(Internal::macro_stop),
+            Internal::session_save => CmdResult::HandleInApp(Internal::session_save),
+            Internal::open_output => CmdResult::HandleInApp(Internal::open_output),
+            Internal::open_jobs => CmdResult::HandleInApp(Internal::open_jobs),
+            Internal::refresh_jobs => CmdResult::HandleInApp(Internal::refresh_jobs),
+            Internal::job_kill => {
+                let id = input_invocation
+                    .and_then(|vi| vi.args.as_ref())
+                    .and_then(|arg| arg.trim().parse().ok());
+                crate::job::kill_job(&app_state.jobs, id)
+            }
+            Internal::tab_close => CmdResult::HandleInApp(Internal::tab_close),
+            Internal::toggle_dry_run => CmdResult::HandleInApp(Internal::toggle_dry_run),
+            Internal::tab_new => CmdResult::HandleInApp(Internal::tab_new),
+            Internal::tab_next => CmdResult::HandleInApp(Internal::tab_next),
+            Internal::tab_previous => CmdResult::HandleInApp(Internal::tab_previous),
             Internal::clear_stage => {
                 app_state.stage.clear();
                 if let Some(panel_id) = cc.app.stage_panel {
@@ -682,6 +768,7 @@ pub trait PanelState {
                 }
             }
             Internal::set_syntax_theme => CmdResult::HandleInApp(Internal::set_syntax_theme),
+            Internal::set_encoding => CmdResult::HandleInApp(Internal::set_encoding),
             Internal::print_path => print::print_paths(self.sel_info(app_state), con)?,
             Internal::print_relative_path => {
                 print::print_relative_paths(self.sel_info(app_state), con)?
@@ -976,6 +1063,14 @@ pub trait PanelState {
                     sel_info,
                     Some(self.get_type()),
                 ) {
+                    // the confirmation of a verb typed in the input is asked
+                    // before this point, so we're here in a sequence
+                    PrefixSearchResult::Match(_, verb) if verb.needs_confirmation() => {
+                        Ok(CmdResult::Prompt {
+                            verb_id: verb.id,
+                            invocation: invocation.clone(),
+                        })
+                    }
                     PrefixSearchResult::Match(_, verb) => self.execute_verb(
                         w,
                         verb,
@@ -1041,6 +1136,11 @@ pub trait PanelState {
         vec![]
     }
 
+    /// the file whose growth is followed, if any
+    fn followed_path(&self) -> Option<&Path> {
+        None
+    }
+
     fn selected_path(&self) -> Option<&Path>;
 
     fn selection(&self) -> Option<Selection<'_>>;
@@ -1069,6 +1169,27 @@ pub trait PanelState {
         con: &AppContext,
     ) -> Command; // FIXME this command is never used
 
+    /// Update the state after changes of the given paths, as reported
+    /// by the watcher.
+    ///
+    /// By default, the state is completely refreshed.
+    fn refresh_changed(
+        &mut self,
+        _changed: &[PathBuf],
+        screen: Screen,
+        con: &AppContext,
+    ) {
+        self.refresh(screen, con);
+    }
+
+    /// Update the state after a change of the followed file, as
+    /// reported by the follower
+    fn refresh_followed(
+        &mut self,
+        _con: &AppContext,
+    ) {
+    }
+
     fn tree_options(&self) -> TreeOptions;
 
     /// Build a cmdResult in response to a command being a change of
@@ -233,9 +233,18 @@ impl<'s> StandardStatusBuilder<'s> {
                     parts.add(&ss.no_verb);
                 }
             }
+            PanelStateType::Duplicates => {
+                parts.add(&ss.no_verb);
+            }
             PanelStateType::Fs => {
                 // TODO fs status
             }
+            PanelStateType::Jobs | PanelStateType::Output => {
+                parts.add(&ss.no_verb);
+            }
+            PanelStateType::SizeReport => {
+                parts.add(&ss.no_verb);
+            }
             PanelStateType::Stage => {
                 // TODO stage status
             }
@@ -8,15 +8,27 @@ use serde::{
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PanelStateType {
+    /// groups of identical files
+    Duplicates,
+
     /// filesystems
     Fs,
 
     /// help "screen"
     Help,
 
+    /// background jobs
+    Jobs,
+
+    /// captured output of the commands executed from broot
+    Output,
+
     /// preview panel, never alone on screen
     Preview,
 
+    /// size of a directory, per extension and per child
+    SizeReport,
+
     /// stage panel, never alone on screen
     Stage,
 
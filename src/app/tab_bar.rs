@@ -0,0 +1,108 @@
+use {
+    crate::{
+        display::{
+            Screen,
+            W,
+        },
+        errors::ProgramError,
+        skin::PanelSkin,
+    },
+    std::ops::Range,
+    unicode_width::UnicodeWidthStr,
+};
+
+/// The line displayed above the panels when there are several tabs
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabBar {
+    /// the names of the tabs, from left to right
+    pub names: Vec<String>,
+    pub active_idx: usize,
+}
+
+impl TabBar {
+    fn label(
+        &self,
+        idx: usize,
+    ) -> String {
+        format!(" {}:{} ", idx + 1, self.names[idx])
+    }
+
+    /// compute the x ranges of the labels, stopping at the first one
+    /// which doesn't fit
+    fn label_ranges(
+        &self,
+        screen_width: u16,
+    ) -> Vec<Range<u16>> {
+        let mut ranges = Vec::with_capacity(self.names.len());
+        let mut x = 0;
+        for idx in 0..self.names.len() {
+            let width = self.label(idx).width() as u16;
+            if x + width > screen_width {
+                break;
+            }
+            ranges.push(x..x + width);
+            x += width;
+        }
+        ranges
+    }
+
+    /// return the index of the tab whose label is at x, if any
+    pub fn clicked_tab_idx(
+        &self,
+        x: u16,
+        screen: Screen,
+    ) -> Option<usize> {
+        self.label_ranges(screen.width)
+            .iter()
+            .position(|range| range.contains(&x))
+    }
+
+    pub fn display(
+        &self,
+        w: &mut W,
+        screen: Screen,
+        panel_skin: &PanelSkin,
+    ) -> Result<(), ProgramError> {
+        screen.goto(w, 0, 0)?;
+        let ranges = self.label_ranges(screen.width);
+        for idx in 0..ranges.len() {
+            let style = if idx == self.active_idx {
+                &panel_skin.styles.tab_active
+            } else {
+                &panel_skin.styles.tab
+            };
+            style.queue_str(w, self.label(idx))?;
+        }
+        let end = ranges.last().map_or(0, |range| range.end);
+        if end < screen.width {
+            panel_skin
+                .styles
+                .tab
+                .queue_str(w, " ".repeat((screen.width - end) as usize))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clicked_tab_idx_finds_labels_which_fit() {
+        let tab_bar = TabBar {
+            names: vec!["logs".to_string(), "src".to_string(), "target".to_string()],
+            active_idx: 0,
+        };
+        let screen = Screen {
+            width: 20,
+            height: 10,
+            top: 1,
+        };
+        // labels are " 1:logs " (0..8) and " 2:src " (8..15), the third one doesn't fit
+        assert_eq!(tab_bar.clicked_tab_idx(0, screen), Some(0));
+        assert_eq!(tab_bar.clicked_tab_idx(7, screen), Some(0));
+        assert_eq!(tab_bar.clicked_tab_idx(8, screen), Some(1));
+        assert_eq!(tab_bar.clicked_tab_idx(15, screen), None);
+    }
+}
@@ -110,7 +110,7 @@ impl BrowserState {
     }
 
     pub fn page_height(screen: Screen) -> usize {
-        (screen.height as usize).saturating_sub(2) // br shouldn't be displayed when the screen is smaller
+        (screen.height.saturating_sub(screen.top) as usize).saturating_sub(2) // br shouldn't be displayed when the screen is smaller
     }
 
     /// return a reference to the currently displayed tree, which
@@ -814,7 +814,7 @@ impl PanelState for BrowserState {
             app_state: Some(disc.app_state),
             tree: self.displayed_tree(),
             skin: &disc.panel_skin.styles,
-            ext_colors: &disc.con.ext_colors,
+            file_colors: &disc.con.file_colors,
             area: disc.state_area.clone(),
             in_app: true,
         };
@@ -843,6 +843,23 @@ impl PanelState for BrowserState {
         })
     }
 
+    fn refresh_changed(
+        &mut self,
+        changed: &[PathBuf],
+        screen: Screen,
+        con: &AppContext,
+    ) {
+        let page_height = BrowserState::page_height(screen);
+        if let Err(e) = self.tree.apply_changes(changed, page_height, con) {
+            warn!("updating base tree failed : {e:?}");
+        }
+        if let Some(ref mut tree) = self.filtered_tree {
+            if let Err(e) = tree.apply_changes(changed, page_height, con) {
+                warn!("updating filtered tree failed : {e:?}");
+            }
+        }
+    }
+
     fn get_flags(&self) -> Vec<Flag> {
         let options = &self.displayed_tree().options;
         vec![
@@ -868,13 +885,74 @@ impl PanelState for BrowserState {
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {
+        super::*,
+        crate::{
+            conf::*,
+            skin::*,
+        },
+    };
 
     #[test]
     fn page_height_accounts_for_application_rows() {
-        for (height, expected) in [(0, 0), (1, 0), (2, 0), (10, 8)] {
-            let screen = Screen { width: 80, height };
+        for (height, top, expected) in [(0, 0, 0), (1, 0, 0), (2, 0, 0), (10, 0, 8), (10, 1, 7)] {
+            let screen = Screen {
+                width: 80,
+                height,
+                top,
+            };
             assert_eq!(BrowserState::page_height(screen), expected);
         }
     }
+
+    #[test]
+    fn confirmation_is_asked_in_sequences() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a"), "a").unwrap();
+        let mut conf = Conf::default();
+        conf.verbs.push(VerbConf {
+            invocation: Some("hide".to_string()),
+            internal: Some(":toggle_hidden".to_string()),
+            confirm: Some("Really?".to_string()),
+            ..Default::default()
+        });
+        let verb_store = VerbStore::new(&mut conf).unwrap();
+        let launch_args = parse_default_flags("").unwrap();
+        let con = AppContext::from(launch_args, verb_store, &conf).unwrap();
+        let screen = Screen {
+            width: 80,
+            height: 20,
+            top: 0,
+        };
+        let dam = Dam::unlimited();
+        let mut state =
+            BrowserState::new(dir.path().to_path_buf(), TreeOptions::default(), screen, &con, &dam)
+                .unwrap();
+        let mut app_state = AppState::new(dir.path());
+        let panel_skin = PanelSkin::new(StyleMap::no_term());
+        let app = AppCmdContext {
+            panel_skin: &panel_skin,
+            preview_panel: None,
+            stage_panel: None,
+            screen,
+            con: &con,
+        };
+        let areas = Areas::create(&mut [], &LayoutInstructions::default(), 0, screen, false);
+        // the writer's buffer is big enough to never be flushed to stderr
+        let mut w = std::io::BufWriter::with_capacity(1 << 16, std::io::stderr());
+        let commands = Sequence::new_local(":hide".to_string()).parse(&con).unwrap();
+        assert_eq!(commands.len(), 1);
+        let cc = CmdContext {
+            cmd: &commands[0].1,
+            app: &app,
+            panel: PanelCmdContext {
+                areas: &areas,
+                purpose: PanelPurpose::None,
+            },
+        };
+        let res = state.on_command(&mut w, &mut app_state, &cc).unwrap();
+        assert!(matches!(res, CmdResult::Prompt { .. }));
+        assert!(!state.tree.options.show_hidden);
+        drop(w.into_parts()); // what was written is dropped without being flushed
+    }
 }
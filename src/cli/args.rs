@@ -94,6 +94,18 @@ pub struct Args {
     /// Don't show sizes
     pub no_sizes: bool,
 
+    #[arg(long)]
+    /// Don't cross filesystem boundaries when computing sizes (like `du -x`)
+    pub one_filesystem: bool,
+
+    #[arg(short = 'a', long)]
+    /// Show apparent sizes (as `ls -l`) instead of disk usage
+    pub apparent_sizes: bool,
+
+    #[arg(short = 'A', long)]
+    /// Show disk usage instead of apparent sizes
+    pub no_apparent_sizes: bool,
+
     #[arg(long)]
     /// Sort by count (only show one level of the tree)
     pub sort_by_count: bool,
@@ -154,6 +166,14 @@ pub struct Args {
     #[arg(long, value_name = "verb-output")]
     pub verb_output: Option<PathBuf>,
 
+    /// Name of a session (saved with `:session_save`) to restore
+    #[arg(long, value_name = "name")]
+    pub session: Option<String>,
+
+    /// Display the external commands of verbs instead of executing them
+    #[arg(long)]
+    pub dry_run: bool,
+
     /// Semicolon separated commands to execute
     #[arg(short, long, value_name = "cmd")]
     pub cmd: Option<String>,
@@ -22,6 +22,7 @@ use {
         },
         display,
         errors::ProgramError,
+        file_sum,
         graphics,
         launchable::Launchable,
         shell_install::{
@@ -145,6 +146,7 @@ pub fn run() -> Result<Option<Launchable>, ProgramError> {
         }
         _ => time!(Conf::from_default_location())?,
     };
+    config.read_macros_file()?;
     debug!("config: {:#?}", config);
 
     // verb store is completed from the config file(s)
@@ -177,7 +179,7 @@ pub fn run() -> Result<Option<Launchable>, ProgramError> {
     }
 
     let mut w = display::writer();
-    let app = App::new(&context)?;
+    let app = App::new(&mut context)?;
     w.queue(EnterAlternateScreen)?;
     w.queue(cursor::Hide)?;
     if context.capture_mouse {
@@ -192,6 +194,7 @@ pub fn run() -> Result<Option<Launchable>, ProgramError> {
     w.queue(LeaveAlternateScreen)?;
     w.flush()?;
     clear_resources();
+    file_sum::save_persisted_cache();
     // drain stdin to avoid that some of the input intended for broot
     // is leaked to the terminal
     drain_stdin();
@@ -0,0 +1,179 @@
+use super::{
+    ScrollCommand,
+    move_sel,
+};
+
+/// The scroll and selection in a list of lines which can all be
+/// selected, like the lines of a text preview.
+///
+/// The list itself isn't kept here, so the functions changing the
+/// selection take its length.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ListScroll {
+    /// index of the first displayed line
+    pub scroll: usize,
+    pub page_height: usize,
+    pub selection_idx: Option<usize>,
+}
+
+impl ListScroll {
+    /// number of lines kept, when possible, between the selection
+    /// and the top or bottom of the page
+    pub fn padding(&self) -> usize {
+        (self.page_height / 4).min(4)
+    }
+
+    pub fn is_selected(
+        &self,
+        idx: usize,
+    ) -> bool {
+        self.selection_idx == Some(idx)
+    }
+
+    pub fn ensure_selection_is_visible(
+        &mut self,
+        len: usize,
+    ) {
+        if self.page_height >= len {
+            self.scroll = 0;
+        } else if let Some(idx) = self.selection_idx {
+            let padding = self.padding();
+            if idx < self.scroll + padding || idx + padding > self.scroll + self.page_height {
+                if idx <= padding {
+                    self.scroll = 0;
+                } else if idx + padding > len {
+                    self.scroll = len - self.page_height;
+                } else if idx < self.scroll + self.page_height / 2 {
+                    self.scroll = idx - padding;
+                } else {
+                    self.scroll = idx + padding - self.page_height;
+                }
+            }
+        }
+        // the list may have been shortened
+        self.scroll = self.scroll.min(len.saturating_sub(self.page_height));
+    }
+
+    /// select the line at the given row of the page, if there's one
+    pub fn try_select_row(
+        &mut self,
+        row: usize,
+        len: usize,
+    ) -> bool {
+        let idx = row + self.scroll;
+        if idx < len {
+            self.selection_idx = Some(idx);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn select_first(
+        &mut self,
+        len: usize,
+    ) {
+        if len > 0 {
+            self.selection_idx = Some(0);
+            self.scroll = 0;
+        }
+    }
+
+    pub fn select_last(
+        &mut self,
+        len: usize,
+    ) {
+        if len > 0 {
+            self.selection_idx = Some(len - 1);
+            if self.page_height < len {
+                self.scroll = len - self.page_height;
+            }
+        }
+    }
+
+    /// move the selection, without changing the scroll
+    pub fn move_selection(
+        &mut self,
+        dy: i32,
+        cycle: bool,
+        len: usize,
+    ) {
+        if let Some(idx) = self.selection_idx {
+            self.selection_idx = Some(move_sel(idx, len, dy, cycle));
+        } else if len > 0 {
+            self.selection_idx = Some(0);
+        }
+    }
+
+    pub fn try_scroll(
+        &mut self,
+        cmd: ScrollCommand,
+        len: usize,
+    ) -> bool {
+        self.try_scroll_pages_of(cmd, len, self.page_height)
+    }
+
+    /// scroll, a page being the given number of lines (which may differ
+    /// from the page height when lines are wrapped), and move the
+    /// selection with the page. Return true if the view changed
+    pub fn try_scroll_pages_of(
+        &mut self,
+        cmd: ScrollCommand,
+        len: usize,
+        page_height: usize,
+    ) -> bool {
+        let old_scroll = self.scroll;
+        self.scroll = cmd.apply(self.scroll, len, page_height);
+        if let Some(idx) = self.selection_idx {
+            if self.scroll == old_scroll {
+                let old_selection = self.selection_idx;
+                if cmd.is_up() {
+                    self.selection_idx = Some(0);
+                } else {
+                    self.selection_idx = Some(len.saturating_sub(1));
+                }
+                return self.selection_idx == old_selection;
+            } else if idx >= old_scroll && idx < old_scroll + page_height {
+                if idx + self.scroll < old_scroll {
+                    self.selection_idx = Some(0);
+                } else if idx + self.scroll - old_scroll >= len {
+                    self.selection_idx = Some(len.saturating_sub(1));
+                } else {
+                    self.selection_idx = Some(idx + self.scroll - old_scroll);
+                }
+            }
+        }
+        self.scroll != old_scroll
+    }
+}
+
+#[cfg(test)]
+mod list_scroll_tests {
+    use super::*;
+
+    #[test]
+    fn test_list_scroll() {
+        let len = 100;
+        let mut ls = ListScroll {
+            page_height: 20,
+            ..Default::default()
+        };
+        ls.select_first(len);
+        ls.move_selection(30, false, len);
+        ls.ensure_selection_is_visible(len);
+        assert_eq!(ls.selection_idx, Some(30));
+        assert_eq!(ls.scroll, 30 + ls.padding() - 20);
+        assert!(ls.try_scroll(ScrollCommand::Pages(1), len));
+        assert_eq!(ls.scroll, 34);
+        assert_eq!(ls.selection_idx, Some(50));
+        ls.select_last(len);
+        assert_eq!(ls.scroll, 80);
+        assert!(ls.try_select_row(0, len));
+        assert_eq!(ls.selection_idx, Some(80));
+        assert!(!ls.try_select_row(20, len));
+        ls.move_selection(30, false, len);
+        assert_eq!(ls.selection_idx, Some(99));
+        ls.move_selection(1, true, len);
+        assert_eq!(ls.selection_idx, Some(0));
+    }
+}
@@ -0,0 +1,275 @@
+use {
+    super::{
+        Command,
+        Sequence,
+    },
+    crate::{
+        errors::ProgramError,
+        verb::{
+            Internal,
+            PrefixSearchResult,
+            VerbExecution,
+            VerbStore,
+        },
+    },
+    std::path::Path,
+};
+
+/// separators tried, in order, when building the sequence of a macro,
+/// the first one not found in any step being used
+const SEPARATOR_CANDIDATES: &[&str] = &[";", "¦", "␞", "\u{1e}"];
+
+/// A recorded list of commands, each one as it would be typed
+/// in the input
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Macro {
+    pub steps: Vec<String>,
+}
+
+/// Records the commands executed by the user, so that they can be
+/// replayed as a sequence
+#[derive(Debug, Default)]
+pub struct MacroRecorder {
+    /// the macro being recorded, if any
+    recording: Option<Macro>,
+
+    /// whether the last recorded step is a pattern, which would be
+    /// replaced by the next pattern edit
+    last_step_is_pattern: bool,
+
+    /// the last macro whose recording was stopped
+    last: Option<Macro>,
+}
+
+impl Macro {
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+    /// return a separator which isn't found in any step
+    pub fn separator(&self) -> String {
+        let local = Sequence::local_separator();
+        std::iter::once(local.as_str())
+            .chain(SEPARATOR_CANDIDATES.iter().copied())
+            .find(|sep| !self.steps.iter().any(|step| step.contains(sep)))
+            .unwrap_or("\u{1f}")
+            .to_string()
+    }
+    /// the raw sequence of the steps, as used in the `cmd` of a verb
+    pub fn raw(
+        &self,
+        separator: &str,
+    ) -> String {
+        self.steps.join(separator)
+    }
+    /// build the sequence replaying the macro `times` times
+    ///
+    /// Return an error if the sequence would be empty
+    pub fn sequence(
+        &self,
+        times: usize,
+    ) -> Result<Sequence, ProgramError> {
+        if self.is_empty() {
+            return Err(ProgramError::Macro {
+                details: "the macro has no command".to_string(),
+            });
+        }
+        if times == 0 {
+            return Err(ProgramError::Macro {
+                details: "the macro must be played at least once".to_string(),
+            });
+        }
+        let separator = self.separator();
+        let raw = self.raw(&separator);
+        let raw = vec![raw; times].join(&separator);
+        Ok(Sequence::new(raw, Some(separator)))
+    }
+    /// build the sequence replaying the macro once per path, after
+    /// having selected it
+    ///
+    /// Return an error if the sequence would be empty
+    pub fn sequence_per_path(
+        &self,
+        paths: &[impl AsRef<Path>],
+    ) -> Result<Sequence, ProgramError> {
+        if self.is_empty() {
+            return Err(ProgramError::Macro {
+                details: "the macro has no command".to_string(),
+            });
+        }
+        if paths.is_empty() {
+            return Err(ProgramError::Macro {
+                details: "there's no path to play the macro on".to_string(),
+            });
+        }
+        let mut steps = Vec::with_capacity(paths.len() * (self.steps.len() + 1));
+        for path in paths {
+            steps.push(format!(":show {}", path.as_ref().to_string_lossy()));
+            steps.extend(self.steps.iter().cloned());
+        }
+        let all = Macro { steps };
+        let separator = all.separator();
+        Ok(Sequence::new(all.raw(&separator), Some(separator)))
+    }
+}
+
+impl MacroRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+    /// start recording, dropping the macro being recorded, if any
+    pub fn start(&mut self) {
+        self.recording = Some(Macro::default());
+        self.last_step_is_pattern = false;
+    }
+    /// stop the recording and keep the macro as the last one, unless
+    /// it's empty.
+    ///
+    /// Return the number of recorded steps, None if there was no recording
+    pub fn stop(&mut self) -> Option<usize> {
+        let recorded = self.recording.take()?;
+        let len = recorded.steps.len();
+        if !recorded.is_empty() {
+            self.last = Some(recorded);
+        }
+        Some(len)
+    }
+    pub fn last(&self) -> Option<&Macro> {
+        self.last.as_ref()
+    }
+    /// record a command executed by the user, if a recording is
+    /// in progress.
+    ///
+    /// Only the commands which would be executed in a sequence are
+    /// recorded: unfinished verb invocations and clicks are ignored and
+    /// successive pattern edits are merged.
+    pub fn record(
+        &mut self,
+        cmd: &Command,
+        verb_store: &VerbStore,
+    ) {
+        let Some(recording) = self.recording.as_mut() else {
+            return;
+        };
+        let step = match cmd {
+            Command::PatternEdit { raw, .. } => {
+                if self.last_step_is_pattern {
+                    recording.steps.pop();
+                }
+                self.last_step_is_pattern = !raw.is_empty();
+                if raw.is_empty() {
+                    return;
+                }
+                recording.steps.push(raw.clone());
+                return;
+            }
+            Command::VerbInvocate(invocation) => {
+                if let PrefixSearchResult::Match(_, verb) =
+                    verb_store.search_prefix(&invocation.name, None)
+                {
+                    if verb.get_internal().is_some_and(is_macro_internal) {
+                        return;
+                    }
+                }
+                invocation.to_string()
+            }
+            Command::Internal {
+                internal,
+                input_invocation,
+            } => {
+                if is_macro_internal(*internal) {
+                    return;
+                }
+                let mut step = format!(":{}", internal.name());
+                if let Some(args) = input_invocation.as_ref().and_then(|vi| vi.args.as_ref()) {
+                    step.push(' ');
+                    step.push_str(args);
+                }
+                step
+            }
+            Command::VerbTrigger {
+                verb_id,
+                input_invocation,
+            } => {
+                let verb = verb_store.verb(*verb_id);
+                if verb.get_internal().is_some_and(is_macro_internal) {
+                    return;
+                }
+                let mut step = if let Some(name) = verb.names.first() {
+                    format!(":{name}")
+                } else if let VerbExecution::Internal(ie) = &verb.execution {
+                    let mut step = format!(":{}", ie.internal.name());
+                    if let Some(arg) = &ie.arg {
+                        step.push(' ');
+                        step.push_str(arg);
+                    }
+                    step
+                } else {
+                    warn!("verb {verb_id} has no name and can't be recorded");
+                    return;
+                };
+                if let Some(args) = input_invocation.as_ref().and_then(|vi| vi.args.as_ref()) {
+                    step.push(' ');
+                    step.push_str(args);
+                }
+                step
+            }
+            Command::None | Command::VerbEdit(_) | Command::Click(..) | Command::DoubleClick(..) => {
+                return;
+            }
+        };
+        debug!("recording macro step {step:?}");
+        recording.steps.push(step);
+        self.last_step_is_pattern = false;
+    }
+}
+
+fn is_macro_internal(internal: Internal) -> bool {
+    matches!(
+        internal,
+        Internal::macro_record
+            | Internal::macro_stop
+            | Internal::macro_play
+            | Internal::macro_play_staged
+            | Internal::macro_save
+    )
+}
+
+#[cfg(test)]
+mod macro_tests {
+    use super::*;
+
+    #[test]
+    fn test_macro_sequences() {
+        let m = Macro {
+            steps: vec!["rs$".to_string(), ":line_down".to_string(), ":stage".to_string()],
+        };
+        let sequence = m.sequence(2).unwrap();
+        assert_eq!(sequence.raw.split(&sequence.separator).count(), 6);
+        let m = Macro {
+            steps: vec!["/a;b/".to_string(), ":stage".to_string()],
+        };
+        let sequence = m.sequence(1).unwrap();
+        assert_ne!(sequence.separator, ";");
+        assert_eq!(
+            sequence.raw.split(&sequence.separator).collect::<Vec<_>>(),
+            vec!["/a;b/", ":stage"],
+        );
+        let sequence = m.sequence_per_path(&["/tmp/a", "/tmp/b"]).unwrap();
+        assert_eq!(
+            sequence.raw.split(&sequence.separator).collect::<Vec<_>>(),
+            vec![":show /tmp/a", "/a;b/", ":stage", ":show /tmp/b", "/a;b/", ":stage"],
+        );
+    }
+    #[test]
+    fn test_empty_macro_sequences() {
+        let m = Macro {
+            steps: vec![":line_down".to_string()],
+        };
+        assert!(m.sequence(0).is_err());
+        let no_paths: &[&str] = &[];
+        assert!(m.sequence_per_path(no_paths).is_err());
+        let m = Macro::default();
+        assert!(m.sequence(1).is_err());
+        assert!(m.sequence_per_path(&["/tmp/a"]).is_err());
+    }
+}
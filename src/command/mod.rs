@@ -1,19 +1,36 @@
 mod command;
 mod completion;
+mod list_scroll;
+mod macro_recorder;
 mod panel_input;
 mod parts;
 mod scroll;
+mod sectioned_list;
 mod sel;
 mod sequence;
 mod trigger_type;
+mod verb_prompting;
 
 pub use {
     command::Command,
     completion::Completions,
+    list_scroll::ListScroll,
+    macro_recorder::{
+        Macro,
+        MacroRecorder,
+    },
     panel_input::PanelInput,
     parts::CommandParts,
     scroll::ScrollCommand,
+    sectioned_list::{
+        SectionedLine,
+        SectionedList,
+    },
     sel::move_sel,
     sequence::Sequence,
     trigger_type::TriggerType,
+    verb_prompting::{
+        PromptStep,
+        VerbPrompting,
+    },
 };
@@ -28,6 +28,7 @@ use {
         InputField,
         TimedEvent,
     },
+    unicode_width::UnicodeWidthStr,
 };
 
 /// Wrap the input of a panel, receive events and make commands
@@ -35,6 +36,8 @@ pub struct PanelInput {
     pub input_field: InputField,
     tab_cycle_count: Option<usize>, // last displayed completion index
     input_before_cycle: Option<String>,
+    /// the questions being asked before the execution of a verb
+    prompting: Option<VerbPrompting>,
 }
 
 impl PanelInput {
@@ -43,6 +46,7 @@ impl PanelInput {
             input_field: InputField::new(area),
             tab_cycle_count: None,
             input_before_cycle: None,
+            prompting: None,
         }
     }
 
@@ -67,8 +71,19 @@ impl PanelInput {
     ) -> Result<Option<(u16, u16)>, ProgramError> {
         self.input_field
             .set_normal_style(panel_skin.styles.input);
-        self.input_field.set_focus(active && mode == Mode::Input);
-        if mode == Mode::Command && active {
+        self.input_field
+            .set_focus(active && (mode == Mode::Input || self.prompting.is_some()));
+        if let Some(prompting) = &self.prompting {
+            let label = prompting.label();
+            let width = (label.width() as u16).min(area.width / 2);
+            queue!(w, cursor::MoveTo(area.left, area.top))?;
+            panel_skin
+                .styles
+                .input_prompt
+                .queue_str(w, label.chars().take(width as usize).collect::<String>())?;
+            area.width -= width;
+            area.left += width;
+        } else if mode == Mode::Command && active {
             queue!(w, cursor::MoveTo(area.left, area.top))?;
             panel_skin.styles.mode_command_mark.queue_str(w, "C")?;
             area.width -= 1;
@@ -270,6 +285,7 @@ impl PanelInput {
     }
 
     /// autocomplete a verb (bound to 'tab')
+    #[allow(clippy::too_many_arguments)]
     fn auto_complete_verb(
         &mut self,
         con: &AppContext,
@@ -278,10 +294,11 @@ impl PanelInput {
         parts: &CommandParts,
         panel_state_type: Option<PanelStateType>,
         backwards: bool, // backtab
+        prefix: &str, // what's before the input, when it's only an argument
     ) -> Command {
         let parts_before_cycle;
         let completable_parts = if let Some(s) = &self.input_before_cycle {
-            parts_before_cycle = CommandParts::from(s.clone());
+            parts_before_cycle = CommandParts::from(format!("{prefix}{s}"));
             &parts_before_cycle
         } else {
             parts
@@ -331,12 +348,136 @@ impl PanelInput {
                 .map_or(raw, ToString::to_string);
             raw.push_str(&added);
             self.input_field.set_str(&raw);
-            Command::from_raw(raw, false)
+            Command::from_raw(format!("{prefix}{raw}"), false)
         } else {
             Command::None
         }
     }
 
+    /// start asking the prompts of a verb, in place of the input
+    fn start_prompting(
+        &mut self,
+        verb: &Verb,
+        invocation: Option<&VerbInvocation>,
+        input_before: String,
+        sel_info: SelInfo<'_>,
+        app_state: &AppState,
+        con: &AppContext,
+    ) -> Command {
+        let (prompting, value) =
+            VerbPrompting::new(verb, invocation, input_before, sel_info, app_state, con);
+        self.input_field.set_str(&value);
+        let cmd = prompting.edition_command(verb, &value);
+        self.prompting = Some(prompting);
+        cmd
+    }
+
+    /// start asking the prompts of a verb whose execution was requested
+    /// out of the input, eg in a sequence
+    pub fn prompt_before_execution(
+        &mut self,
+        verb: &Verb,
+        invocation: &VerbInvocation,
+        sel_info: SelInfo<'_>,
+        app_state: &AppState,
+        con: &AppContext,
+    ) -> Command {
+        let parts = CommandParts::from(self.input_field.get_content());
+        self.start_prompting(verb, Some(invocation), parts.raw_pattern, sel_info, app_state, con)
+    }
+
+    pub fn is_prompting(&self) -> bool {
+        self.prompting.is_some()
+    }
+
+    /// stop prompting and put back the input as it was before
+    fn end_prompting(&mut self) {
+        if let Some(prompting) = self.prompting.take() {
+            self.input_field.set_str(&prompting.input_before);
+        }
+        self.tab_cycle_count = None;
+        self.input_before_cycle = None;
+    }
+
+    /// handle a key while a prompt is displayed: edit the answer,
+    /// complete it, validate it, or cancel the execution
+    fn on_prompt_key(
+        &mut self,
+        timed_event: &TimedEvent,
+        key: KeyCombination,
+        panels: &AppPanels,
+        app_state: &AppState,
+        con: &AppContext,
+    ) -> Command {
+        let Some(prompting) = &self.prompting else {
+            return Command::None;
+        };
+        let verb = con.verb_store.verb(prompting.verb_id());
+        let panel_state = panels
+            .state_by_ref(verb.impacted_panel)
+            .unwrap_or_else(|| panels.state());
+        let sel_info = panel_state.sel_info(app_state);
+        if key == key!(esc) {
+            self.end_prompting();
+            return Command::None;
+        }
+        let confirmation = prompting.current().is_confirmation();
+        let validated = if confirmation {
+            match key {
+                key!(y) | key!(shift - y) => true,
+                key!(n) | key!(shift - n) | key!(enter) => {
+                    self.end_prompting();
+                    return Command::None;
+                }
+                _ => false,
+            }
+        } else if key == key!(enter) {
+            true
+        } else if key == key!(tab) || key == key!(backtab) {
+            if !prompting.verb_name().is_empty() {
+                let prefix = format!(":{} ", prompting.verb_name());
+                let raw = self.input_field.get_content();
+                let parts = CommandParts::from(format!("{prefix}{raw}"));
+                let backwards = key == key!(backtab);
+                let panel_state_type = Some(panel_state.get_type());
+                self.auto_complete_verb(
+                    con,
+                    sel_info,
+                    raw,
+                    &parts,
+                    panel_state_type,
+                    backwards,
+                    &prefix,
+                );
+            }
+            false
+        } else {
+            self.tab_cycle_count = None;
+            self.input_before_cycle = None;
+            self.input_field.apply_timed_event(timed_event);
+            false
+        };
+        let value = self.input_field.get_content();
+        let Some(prompting) = self.prompting.as_mut() else {
+            return Command::None;
+        };
+        if !validated {
+            return prompting.edition_command(verb, &value);
+        }
+        self.tab_cycle_count = None;
+        self.input_before_cycle = None;
+        match prompting.answer(value, verb, sel_info, app_state, con) {
+            PromptStep::Ask(value) => {
+                self.input_field.set_str(&value);
+                prompting.edition_command(verb, &value)
+            }
+            PromptStep::Execute(cmd) => {
+                self.end_prompting();
+                cmd
+            }
+        }
+    }
+
     fn find_key_verb<'c>(
         key: KeyCombination,
         panels: &AppPanels,
@@ -368,6 +509,9 @@ impl PanelInput {
             if !sel_info.is_accepted_by(verb.selection_condition) {
                 continue;
             }
+            if !verb.file_condition.accepts_sel_info(*sel_info) {
+                continue;
+            }
             if !verb.accepts_extension(sel_info.extension()) {
                 continue;
             }
@@ -386,6 +530,9 @@ impl PanelInput {
     ) -> Command {
         if self.input_field.apply_timed_event(timed_event) {
             Command::empty()
+        } else if self.is_prompting() {
+            // the selection, which the prompted verb applies to, mustn't change
+            Command::None
         } else {
             match kind {
                 MouseEventKind::Up(MouseButton::Left) => {
@@ -433,6 +580,10 @@ impl PanelInput {
         app_state: &AppState,
         con: &AppContext,
     ) -> Command {
+        if self.prompting.is_some() {
+            return self.on_prompt_key(timed_event, key, panels, app_state, con);
+        }
+
         // value of raw and parts before any key related change
         let raw = self.input_field.get_content();
         let parts = CommandParts::from(raw.clone());
@@ -474,13 +625,13 @@ impl PanelInput {
         // 'tab' completion of a verb or one of its arguments
         if Verb::is_some_internal(verb, Internal::next_match) {
             if parts.verb_invocation.is_some() {
-                return self.auto_complete_verb(con, sel_info, raw, &parts, Some(panel_state_type), false);
+                return self.auto_complete_verb(con, sel_info, raw, &parts, Some(panel_state_type), false, "");
             }
             // if no verb is being edited, the state may handle this internal
             // in a specific way
         } else if Verb::is_some_internal(verb, Internal::previous_match) {
             if parts.verb_invocation.is_some() {
-                return self.auto_complete_verb(con, sel_info, raw, &parts, Some(panel_state_type), true);
+                return self.auto_complete_verb(con, sel_info, raw, &parts, Some(panel_state_type), true, "");
             }
         } else {
             self.tab_cycle_count = None;
@@ -490,6 +641,15 @@ impl PanelInput {
         // 'enter': trigger the verb if any on the input. If none, then may be
         // used as trigger of another verb
         if key == key!(enter) && parts.has_not_empty_verb_invocation() {
+            let typed_verb = parts.verb_invocation.as_ref().and_then(|invocation| {
+                con.verb_store
+                    .search_sel_info_unique(&invocation.name, sel_info, Some(panel_state_type))
+                    .filter(|verb| verb.needs_prompting(Some(invocation)))
+            });
+            if let Some(verb) = typed_verb {
+                let invocation = parts.verb_invocation.as_ref();
+                return self.start_prompting(verb, invocation, parts.raw_pattern.clone(), sel_info, app_state, con);
+            }
             return Command::from_parts(parts, true);
         }
 
@@ -511,6 +671,10 @@ impl PanelInput {
             if mode != Mode::Input && verb.is_internal(Internal::mode_input) {
                 self.enter_input_mode_with_key(key, &parts);
             }
+            if verb.needs_prompting(parts.verb_invocation.as_ref()) {
+                let invocation = parts.verb_invocation.as_ref();
+                return self.start_prompting(verb, invocation, parts.raw_pattern.clone(), sel_info, app_state, con);
+            }
             if verb.auto_exec {
                 return Command::VerbTrigger {
                     verb_id: verb.id,
@@ -0,0 +1,206 @@
+use super::{
+    ScrollCommand,
+    move_sel,
+};
+
+/// A line of a [SectionedList]
+pub trait SectionedLine: Copy + PartialEq {
+    /// whether the line can be selected, which isn't the
+    /// case of section titles
+    fn is_item(self) -> bool;
+}
+
+/// The lines, scroll and selection of a list made of sections,
+/// each one starting with a title which can't be selected
+pub struct SectionedList<L: SectionedLine> {
+    lines: Vec<L>,
+    selection_idx: Option<usize>, // index in lines, never on a title
+    pub scroll: usize,
+    pub page_height: usize,
+}
+
+impl<L: SectionedLine> Default for SectionedList<L> {
+    fn default() -> Self {
+        Self {
+            lines: Vec::new(),
+            selection_idx: None,
+            scroll: 0,
+            page_height: 0,
+        }
+    }
+}
+
+impl<L: SectionedLine> SectionedList<L> {
+    pub fn lines(&self) -> &[L] {
+        &self.lines
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn get(
+        &self,
+        idx: usize,
+    ) -> Option<L> {
+        self.lines.get(idx).copied()
+    }
+
+    pub fn is_selected(
+        &self,
+        idx: usize,
+    ) -> bool {
+        self.selection_idx == Some(idx)
+    }
+
+    pub fn selected_line(&self) -> Option<L> {
+        self.get(self.selection_idx?)
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.selection_idx = None;
+        self.scroll = 0;
+    }
+
+    /// replace the lines, keeping the selected line when it's
+    /// still there, or selecting the first item
+    pub fn set_lines(
+        &mut self,
+        lines: Vec<L>,
+    ) {
+        let old_selection = self.selected_line();
+        self.selection_idx = old_selection
+            .and_then(|sel| lines.iter().position(|&line| line == sel))
+            .or_else(|| lines.iter().position(|line| line.is_item()));
+        self.lines = lines;
+        self.scroll = 0;
+        self.show_selection();
+    }
+
+    pub fn show_selection(&mut self) {
+        if let Some(idx) = self.selection_idx {
+            if idx < self.scroll + 1 {
+                // we keep the section title visible when possible
+                self.scroll = idx.saturating_sub(1);
+            } else if idx >= self.scroll + self.page_height {
+                self.scroll = idx + 1 - self.page_height;
+            }
+        }
+    }
+
+    /// scroll, moving the selection if it would leave the page.
+    /// Return true if the scroll changed
+    pub fn try_scroll(
+        &mut self,
+        cmd: ScrollCommand,
+    ) -> bool {
+        let old_scroll = self.scroll;
+        self.scroll = cmd.apply(self.scroll, self.lines.len(), self.page_height);
+        if let Some(idx) = self.selection_idx {
+            let page = self.scroll..(self.scroll + self.page_height).min(self.lines.len());
+            if !page.contains(&idx) {
+                self.selection_idx = if idx < self.scroll {
+                    page.into_iter().find(|&i| self.lines[i].is_item())
+                } else {
+                    page.into_iter().rev().find(|&i| self.lines[i].is_item())
+                }
+                .or(Some(idx));
+            }
+        }
+        self.scroll != old_scroll
+    }
+
+    /// move the selection by count items, skipping section titles
+    pub fn move_selection(
+        &mut self,
+        count: usize,
+        dir: i32, // -1 for up, 1 for down
+        cycle: bool,
+    ) {
+        let len = self.lines.len();
+        if !self.lines.iter().any(|line| line.is_item()) {
+            return;
+        }
+        let mut idx = self.selection_idx.unwrap_or(0);
+        for _ in 0..count {
+            loop {
+                let next = move_sel(idx, len, dir, cycle);
+                if next == idx {
+                    break;
+                }
+                idx = next;
+                if self.lines[idx].is_item() {
+                    break;
+                }
+            }
+        }
+        if self.lines[idx].is_item() {
+            self.selection_idx = Some(idx);
+        }
+        self.show_selection();
+    }
+
+    /// select the item at the given row, counted from the first
+    /// displayed line. Return true if there's one
+    pub fn try_select_row(
+        &mut self,
+        row: usize,
+    ) -> bool {
+        let idx = row + self.scroll;
+        if self.lines.get(idx).is_some_and(|line| line.is_item()) {
+            self.selection_idx = Some(idx);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod sectioned_list_tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Line {
+        Title,
+        Item(usize),
+    }
+
+    impl SectionedLine for Line {
+        fn is_item(self) -> bool {
+            matches!(self, Self::Item(_))
+        }
+    }
+
+    #[test]
+    fn test_titles_are_skipped() {
+        let mut list = SectionedList {
+            page_height: 3,
+            ..Default::default()
+        };
+        list.set_lines(vec![
+            Line::Title,
+            Line::Item(0),
+            Line::Title,
+            Line::Item(1),
+            Line::Item(2),
+        ]);
+        assert_eq!(list.selected_line(), Some(Line::Item(0)));
+        list.move_selection(1, 1, false);
+        assert_eq!(list.selected_line(), Some(Line::Item(1)));
+        assert_eq!(list.scroll, 1);
+        list.move_selection(1, -1, false);
+        assert_eq!(list.selected_line(), Some(Line::Item(0)));
+        assert_eq!(list.scroll, 0);
+        list.move_selection(1, -1, true);
+        assert_eq!(list.selected_line(), Some(Line::Item(2)));
+        assert!(!list.try_select_row(0));
+        list.set_lines(vec![Line::Title, Line::Item(2)]);
+        assert_eq!(list.selected_line(), Some(Line::Item(2)));
+    }
+}
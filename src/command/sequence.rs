@@ -114,7 +114,10 @@ fn add_commands(
                     });
                 }
                 PrefixSearchResult::Match(_, verb) => {
-                    if let Some(internal) = verb.get_internal() {
+                    // a verb needing a confirmation is kept as an invocation
+                    // so that the confirmation is asked on execution
+                    let internal = verb.get_internal().filter(|_| !verb.needs_confirmation());
+                    if let Some(internal) = internal {
                         command = Command::Internal {
                             internal,
                             input_invocation: Some(invocation.clone()),
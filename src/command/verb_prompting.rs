@@ -0,0 +1,212 @@
+use {
+    super::Command,
+    crate::{
+        app::{
+            AppContext,
+            AppState,
+            SelInfo,
+        },
+        verb::*,
+    },
+    lazy_regex::Captures,
+    rustc_hash::FxHashMap,
+};
+
+/// The state of the questions asked before the execution of a verb
+/// having prompts
+#[derive(Debug)]
+pub struct VerbPrompting {
+    verb_id: VerbId,
+
+    /// the name used to build the invocations (for the status and the
+    /// completions). May be empty for verbs only called by key.
+    verb_name: String,
+
+    prompts: Vec<VerbPrompt>,
+
+    /// index of the prompt being answered
+    idx: usize,
+
+    /// the arguments typed with the invocation, if any (then
+    /// only the confirmations are asked)
+    given_args: Option<String>,
+
+    /// values of the arguments already given
+    answers: FxHashMap<String, String>,
+
+    /// the label of the current prompt, with its groups replaced
+    label: String,
+
+    /// the content of the input before prompting, put back at the end
+    pub input_before: String,
+}
+
+/// What to do after an answer
+pub enum PromptStep {
+    /// ask the next prompt, starting with this value in the input
+    Ask(String),
+    /// all prompts are answered, execute the command
+    Execute(Command),
+}
+
+impl VerbPrompting {
+    /// start prompting for a verb, returning the prompting and the
+    /// initial value of the input.
+    ///
+    /// The verb is assumed to need prompting.
+    pub fn new(
+        verb: &Verb,
+        invocation: Option<&VerbInvocation>,
+        input_before: String,
+        sel_info: SelInfo<'_>,
+        app_state: &AppState,
+        con: &AppContext,
+    ) -> (Self, String) {
+        let given_args = invocation
+            .and_then(|invocation| invocation.args.clone())
+            .filter(|args| !args.trim().is_empty());
+        let prompts = verb
+            .prompts
+            .iter()
+            .filter(|prompt| given_args.is_none() || prompt.is_confirmation())
+            .cloned()
+            .collect();
+        let mut prompting = Self {
+            verb_id: verb.id,
+            verb_name: verb.names.first().cloned().unwrap_or_default(),
+            prompts,
+            idx: 0,
+            given_args,
+            answers: FxHashMap::default(),
+            label: String::new(),
+            input_before,
+        };
+        let value = prompting.prepare_current(verb, sel_info, app_state, con);
+        (prompting, value)
+    }
+
+    pub fn current(&self) -> &VerbPrompt {
+        &self.prompts[self.idx]
+    }
+
+    /// the text to display before the input
+    pub fn label(&self) -> String {
+        if self.current().is_confirmation() {
+            format!("{} [y/n] ", self.label)
+        } else {
+            format!("{}: ", self.label)
+        }
+    }
+
+    /// build the args of the invocation from the answers, the value
+    /// of the current prompt being given (as it's not yet an answer)
+    fn args(
+        &self,
+        verb: &Verb,
+        current_value: Option<&str>,
+    ) -> Option<String> {
+        if let Some(args) = &self.given_args {
+            return Some(args.clone());
+        }
+        let pattern = verb.invocation_parser.as_ref()?.invocation_pattern.args.as_ref()?;
+        let current_name = match (self.current(), current_value) {
+            (VerbPrompt::Arg { name, .. }, Some(_)) => Some(name.as_str()),
+            _ => None,
+        };
+        let args = ARG_DEF_GROUP.replace_all(pattern, |ec: &Captures<'_>| {
+            let name = &ec[1];
+            if Some(name) == current_name {
+                current_value.unwrap_or_default().to_string()
+            } else {
+                self.answers.get(name).cloned().unwrap_or_default()
+            }
+        });
+        Some(args.trim().to_string())
+    }
+
+    /// the invocation as it would be with the current value
+    pub fn invocation(
+        &self,
+        verb: &Verb,
+        current_value: Option<&str>,
+    ) -> VerbInvocation {
+        VerbInvocation {
+            name: self.verb_name.clone(),
+            args: self.args(verb, current_value).filter(|args| !args.is_empty()),
+            bang: false,
+        }
+    }
+
+    /// the command to return while the current value is edited, so that
+    /// the status tells what would be executed
+    pub fn edition_command(
+        &self,
+        verb: &Verb,
+        current_value: &str,
+    ) -> Command {
+        if self.verb_name.is_empty() || self.current().is_confirmation() {
+            Command::None
+        } else {
+            Command::VerbEdit(self.invocation(verb, Some(current_value)))
+        }
+    }
+
+    /// compute the label of the current prompt and return its
+    /// default value
+    fn prepare_current(
+        &mut self,
+        verb: &Verb,
+        sel_info: SelInfo<'_>,
+        app_state: &AppState,
+        con: &AppContext,
+    ) -> String {
+        let args = self.args(verb, None);
+        let builder = ExecutionBuilder::with_invocation(
+            verb.invocation_parser.as_ref(),
+            sel_info,
+            app_state,
+            args.as_ref(),
+        );
+        self.label = builder.string(self.current().label_pattern(), con);
+        match self.current() {
+            VerbPrompt::Arg {
+                default: Some(default),
+                ..
+            } => builder.string(default, con),
+            _ => String::new(),
+        }
+    }
+
+    /// record the answer to the current prompt (the value is ignored
+    /// for confirmations) and tell what to do next
+    pub fn answer(
+        &mut self,
+        value: String,
+        verb: &Verb,
+        sel_info: SelInfo<'_>,
+        app_state: &AppState,
+        con: &AppContext,
+    ) -> PromptStep {
+        if let VerbPrompt::Arg { name, .. } = self.current() {
+            self.answers.insert(name.clone(), value);
+        }
+        self.idx += 1;
+        if self.idx < self.prompts.len() {
+            return PromptStep::Ask(self.prepare_current(verb, sel_info, app_state, con));
+        }
+        self.idx -= 1; // so that current() stays valid
+        let invocation = self.invocation(verb, None);
+        PromptStep::Execute(Command::VerbTrigger {
+            verb_id: self.verb_id,
+            input_invocation: (!invocation.name.is_empty() || invocation.args.is_some())
+                .then_some(invocation),
+        })
+    }
+
+    pub fn verb_id(&self) -> VerbId {
+        self.verb_id
+    }
+    pub fn verb_name(&self) -> &str {
+        &self.verb_name
+    }
+}
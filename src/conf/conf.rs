@@ -14,8 +14,14 @@ use {
         kitty::KittyGraphicsDisplay,
         kitty::TransmissionMedium,
         path::*,
-        preview::PreviewTransformerConf,
-        skin::SkinEntry,
+        preview::{
+            PreviewMode,
+            PreviewTransformerConf,
+        },
+        skin::{
+            FileKind,
+            SkinEntry,
+        },
         syntactic::SyntaxTheme,
         verb::ExecPattern,
     },
@@ -85,8 +91,25 @@ pub struct Conf {
     #[serde(default, alias = "ext-colors")]
     pub ext_colors: FxHashMap<String, String>,
 
+    #[serde(default, alias = "file-colors")]
+    pub file_colors: FxHashMap<String, String>,
+
+    #[serde(alias = "file-sum-cache")]
+    pub file_sum_cache: Option<bool>,
+
+    #[serde(default, alias = "file-sum-excludes")]
+    pub file_sum_excludes: Vec<GlobConf>,
+
+    #[serde(alias = "file-sum-one-filesystem")]
+    pub file_sum_one_filesystem: Option<bool>,
+
     pub file_sum_threads_count: Option<usize>,
 
+    /// max duration, in seconds, of the computation of a directory
+    /// sum, after which the sum is marked as partial
+    #[serde(alias = "file-sum-timeout")]
+    pub file_sum_timeout: Option<u64>,
+
     /// the files used to load this configuration
     #[serde(skip)]
     pub files: Vec<PathBuf>,
@@ -94,6 +117,12 @@ pub struct Conf {
     #[serde(alias = "icon-theme")]
     pub icon_theme: Option<String>,
 
+    /// whether to use the styles defined in the `LS_COLORS`
+    /// environment variable (`file_colors` and `kind_colors` having
+    /// precedence)
+    #[serde(alias = "import-ls-colors")]
+    pub import_ls_colors: Option<bool>,
+
     #[serde(default)]
     pub imports: Vec<Import>,
 
@@ -110,12 +139,18 @@ pub struct Conf {
     #[serde(alias = "graphics-display")]
     pub graphics_display: Option<GraphicsDisplay>,
 
+    #[serde(default, alias = "kind-colors")]
+    pub kind_colors: FxHashMap<FileKind, String>,
+
     #[serde(default, alias = "kept-kitty-temp-files")]
     pub kept_kitty_temp_files: Option<NonZeroUsize>,
 
     #[serde(default, alias = "preview-transformers")]
     pub preview_transformers: Vec<PreviewTransformerConf>,
 
+    #[serde(default, alias = "preview-modes")]
+    pub preview_modes: FxHashMap<String, PreviewMode>,
+
     #[serde(alias = "lines-after-match-in-preview")]
     pub lines_after_match_in_preview: Option<usize>,
 
@@ -138,6 +173,9 @@ pub struct Conf {
     #[serde(alias = "search-modes")]
     pub search_modes: Option<FxHashMap<String, String>>,
 
+    #[serde(alias = "session-auto-restore")]
+    pub session_auto_restore: Option<bool>,
+
     #[serde(alias = "show-matching-characters-on-path-searches")]
     pub show_matching_characters_on_path_searches: Option<bool>,
 
@@ -212,6 +250,16 @@ impl Conf {
         Ok(conf)
     }
 
+    /// read the macros saved with `:macro_save`, if any, whatever the
+    /// configuration file(s) which were read before
+    pub fn read_macros_file(&mut self) -> Result<(), ProgramError> {
+        let macros_path = super::macros_path();
+        if macros_path.exists() {
+            self.read_file(macros_path)?;
+        }
+        Ok(())
+    }
+
     pub fn solve_conf_path(
         &self,
         path: &str,
@@ -252,7 +300,12 @@ impl Conf {
         overwrite!(self, modal, conf);
         overwrite!(self, initial_mode, conf);
         overwrite!(self, quit_on_last_cancel, conf);
+        overwrite!(self, session_auto_restore, conf);
         overwrite!(self, file_sum_threads_count, conf);
+        overwrite!(self, file_sum_one_filesystem, conf);
+        overwrite!(self, file_sum_cache, conf);
+        overwrite!(self, import_ls_colors, conf);
+        overwrite!(self, file_sum_timeout, conf);
         overwrite!(self, max_staged_count, conf);
         overwrite!(self, auto_open_staging_area, conf);
         overwrite!(self, show_matching_characters_on_path_searches, conf);
@@ -273,7 +326,11 @@ impl Conf {
         // config files and they still make sense
         overwrite_map!(self, special_paths, conf);
         overwrite_map!(self, ext_colors, conf);
+        overwrite_map!(self, file_colors, conf);
+        overwrite_map!(self, kind_colors, conf);
+        overwrite_map!(self, preview_modes, conf);
         overwrite_vec!(self, preview_transformers, conf);
+        overwrite_vec!(self, file_sum_excludes, conf);
         self.files.push(path);
         // read the imports
         for import in &conf.imports {
@@ -0,0 +1,48 @@
+use {
+    super::{
+        SerdeFormat,
+        VerbConf,
+    },
+    crate::errors::ProgramError,
+    serde::{
+        Deserialize,
+        Serialize,
+    },
+    std::{
+        fs,
+        path::PathBuf,
+    },
+};
+
+/// The content of the file in which macros are saved as verbs
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct MacrosFile {
+    #[serde(default)]
+    verbs: Vec<VerbConf>,
+}
+
+/// return the path to the file in which `:macro_save` writes the
+/// macros. It's read after the configuration file(s).
+pub fn macros_path() -> PathBuf {
+    super::dir().join("macros.toml")
+}
+
+/// add a verb to the macros file, creating it if needed
+pub fn save_macro_verb(verb: VerbConf) -> Result<PathBuf, ProgramError> {
+    let path = macros_path();
+    let mut file: MacrosFile = if path.exists() {
+        SerdeFormat::read_file(&path)?
+    } else {
+        MacrosFile::default()
+    };
+    file.verbs.push(verb);
+    let content = toml::to_string(&file).map_err(|e| ProgramError::Macro {
+        details: format!("unable to serialize the macros: {e}"),
+    })?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, content)?;
+    info!("macro saved in {:?}", path);
+    Ok(path)
+}
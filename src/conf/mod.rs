@@ -13,6 +13,7 @@ mod default;
 mod default_flags;
 mod format;
 mod import;
+mod macros_file;
 mod special_handling_conf;
 mod verb_conf;
 pub mod file_size;
@@ -23,8 +24,13 @@ pub use {
     default_flags::*,
     format::*,
     import::*,
+    macros_file::*,
     special_handling_conf::*,
-    verb_conf::VerbConf,
+    verb_conf::{
+        FileConditionConf,
+        VerbConf,
+        VerbPromptConf,
+    },
 };
 
 /// return the instance of `ProjectDirs` holding broot's specific paths
@@ -49,6 +49,9 @@ pub struct VerbConf {
     #[serde(default, skip_serializing_if = "FileTypeCondition::is_default")]
     pub apply_to: FileTypeCondition,
 
+    /// Finer conditions on the selected file(s) for the verb to apply
+    pub apply_if: Option<FileConditionConf>,
+
     /// The panel to which the verb applies (even if triggered from
     /// another panel)
     #[serde(default, skip_serializing_if = "PanelReference::is_default")]
@@ -69,5 +72,62 @@ pub struct VerbConf {
     pub panels: Vec<PanelStateType>,
 
     pub refresh_after: Option<bool>,
+
+    /// Whether the output of a command executed without leaving broot
+    /// must be captured and displayed in an output panel (broot waiting
+    /// for the end of the command)
+    pub capture_output: Option<bool>,
+
+    /// Whether a command not leaving broot must run as a background
+    /// job, listed in the jobs panel
+    pub background: Option<bool>,
+
+    /// A question asked for confirmation before any execution (even
+    /// with arguments or in a sequence), eg `"Delete {file}?"`
+    pub confirm: Option<String>,
+
+    /// Prompts asked, in order, before execution when the verb is
+    /// called without arguments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompts: Vec<VerbPromptConf>,
 }
 
+/// A deserializable prompt in a verb definition, either asking the
+/// value of an argument (when `arg` is set) or a yes/no confirmation
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct VerbPromptConf {
+    /// the name of the argument, eg `new_name` for `{new_name}`
+    pub arg: Option<String>,
+
+    /// the text displayed before the input, or the question when
+    /// there's no `arg`
+    pub label: Option<String>,
+
+    /// the initial value of the argument
+    pub default: Option<String>,
+}
+
+
+/// A deserializable set of conditions on the selected file(s), all of
+/// them having to be met for the verb to apply
+#[derive(Default, Debug, Clone, Deserialize, Serialize)]
+pub struct FileConditionConf {
+    /// a glob pattern on the file name, eg `"*.tar.*"`
+    pub name: Option<String>,
+
+    /// the type detected from the first bytes of the file, either a
+    /// name (eg `"elf"`) or a mime type (eg `"image/*"`), as a glob
+    pub content_type: Option<String>,
+
+    /// whether the file must be (or must not be) in a git repository
+    pub in_git_repo: Option<bool>,
+
+    /// whether the file must be (or must not be) an executable file
+    pub executable: Option<bool>,
+
+    /// the minimal size of the file, eg `"10MB"`
+    pub min_size: Option<String>,
+
+    /// the maximal size of the file, eg `"1kiB"`
+    pub max_size: Option<String>,
+}
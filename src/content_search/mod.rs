@@ -5,6 +5,10 @@ mod needle;
 pub use {
     crate::content_type::{
         self,
+        encoding::{
+            Encoding,
+            LineReader,
+        },
         extensions,
         magic_numbers,
     },
@@ -76,12 +80,16 @@ pub fn line_count_at_pos<P: AsRef<Path>>(
     path: P,
     pos: usize,
 ) -> io::Result<usize> {
-    let mut reader = BufReader::new(File::open(path)?);
+    let mut reader = LineReader::open(path)?;
     let mut line = String::new();
     let mut line_count = 1;
     let mut bytes_count = 0;
-    while reader.read_line(&mut line)? > 0 {
-        bytes_count += line.len();
+    loop {
+        let len = reader.read_line(&mut line)?;
+        if len == 0 {
+            break;
+        }
+        bytes_count += len;
         if bytes_count > pos {
             return Ok(line_count);
         }
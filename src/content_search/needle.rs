@@ -3,6 +3,7 @@
 
 use {
     super::*,
+    crate::content_type::encoding::DETECTION_LEN,
     memmap2::Mmap,
     std::{
         convert::TryInto,
@@ -57,10 +58,11 @@ impl Needle {
     // no, it doesn't bring more than a few % in speed
     fn find_naive_1(
         &self,
+        pos: usize,
         hay: &Mmap,
     ) -> Option<usize> {
         let n = self.bytes[0];
-        hay.iter().position(|&b| b == n)
+        hay.get(pos..)?.iter().position(|&b| b == n).map(|p| p + pos)
     }
 
     /// look for matches of the needle when it's length is 2
@@ -203,14 +205,26 @@ impl Needle {
         None
     }
 
+    /// find the first occurrence of the needle from `pos`
+    fn find(
+        &self,
+        pos: usize,
+        hay: &Mmap,
+    ) -> Option<usize> {
+        match self.bytes.len() {
+            1 => self.find_naive_1(pos, hay),
+            2 => self.find_naive_2(pos, hay),
+            3 => self.find_naive_3(pos, hay),
+            4 => self.find_naive_4(pos, hay),
+            6 => self.find_naive_6(pos, hay),
+            _ => self.find_naive(pos, hay),
+        }
+    }
+
     /// search the mem map to find the first occurrence of the needle.
     ///
-    /// Known limit: if the file has an encoding where the needle would
-    /// be represented in a way different than UTF-8, the needle won't
-    /// be found (I noticed the problem with other grepping tools, too,
-    /// which is understandable as detecting the encoding and translating
-    /// the needle would multiply the search time).
-    ///
+    /// Only the UTF-8 representation of the needle is searched here, see
+    /// `find_in_any_encoding` for the other encodings.
     ///
     /// The exact search algorithm used here (I removed Boyer-Moore)
     /// and the optimizations (loop unrolling, etc.) don't really matter
@@ -238,17 +252,42 @@ impl Needle {
             // TODO the Windows equivalent might be PrefetchVirtualMemory
         }
 
-        let pos = match self.bytes.len() {
-            1 => self.find_naive_1(hay),
-            2 => self.find_naive_2(0, hay),
-            3 => self.find_naive_3(0, hay),
-            4 => self.find_naive_4(0, hay),
-            6 => self.find_naive_6(0, hay),
-            _ => self.find_naive(0, hay),
+        self.find(0, hay)
+            .map_or(ContentSearchResult::NotFound, |pos| {
+                ContentSearchResult::Found { pos }
+            })
+    }
+
+    /// Search the needle in UTF-8 then, if it's not found and the start
+    /// of the hay looks like a text in another encoding, in this encoding.
+    ///
+    /// As the needle isn't in most files, the detection runs on most of
+    /// them, but it only reads the first `DETECTION_LEN` bytes, which are
+    /// already in memory after the UTF-8 search.
+    ///
+    /// Return the position of the needle (in bytes) and the encoding.
+    fn find_in_any_encoding(
+        &self,
+        hay: &Mmap,
+    ) -> Option<(usize, Encoding)> {
+        if let ContentSearchResult::Found { pos } = self.search_mmap(hay) {
+            return Some((pos, Encoding::Utf8));
+        }
+        let encoding = Encoding::detect(&hay[..hay.len().min(DETECTION_LEN)])
+            .filter(|&encoding| encoding != Encoding::Utf8)?;
+        let encoded = Self {
+            bytes: encoding.encode(self.as_str())?.into_boxed_slice(),
+            max_file_size: self.max_file_size,
         };
-        pos.map_or(ContentSearchResult::NotFound, |pos| {
-            ContentSearchResult::Found { pos }
-        })
+        let mut pos = 0;
+        loop {
+            let found = encoded.find(pos, hay)?;
+            if !encoding.is_utf16() || found % 2 == 0 {
+                return Some((found, encoding));
+            }
+            // not aligned on a UTF-16 code unit
+            pos = found + 1;
+        }
     }
 
     /// determine whether the file contains the needle
@@ -258,7 +297,10 @@ impl Needle {
     ) -> io::Result<ContentSearchResult> {
         super::get_mmap_if_suitable(hay_path, self.max_file_size).map(|om| {
             om.map_or(ContentSearchResult::NotSuitable, |hay| {
-                self.search_mmap(&hay)
+                match self.find_in_any_encoding(&hay) {
+                    Some((pos, _)) => ContentSearchResult::Found { pos },
+                    None => ContentSearchResult::NotFound,
+                }
             })
         })
     }
@@ -272,11 +314,31 @@ impl Needle {
         let Ok(hay) = get_mmap(hay_path) else {
             return None;
         };
-        match self.search_mmap(&hay) {
-            ContentSearchResult::Found { pos } => {
+        match self.find_in_any_encoding(&hay)? {
+            (pos, Encoding::Utf8) => {
                 Some(ContentMatch::build(&hay, pos, self.as_str(), desired_len))
             }
-            _ => None,
+            (pos, encoding) => {
+                // we decode the line containing the match
+                let mut reader = LineReader::new(&hay[..], encoding);
+                let mut line = String::new();
+                let mut read = 0;
+                while read <= pos {
+                    line.clear();
+                    let len = reader.read_line(&mut line).ok()?;
+                    if len == 0 {
+                        return None;
+                    }
+                    read += len;
+                }
+                let needle_start = line.find(self.as_str())?;
+                Some(ContentMatch::build(
+                    line.as_bytes(),
+                    needle_start,
+                    self.as_str(),
+                    desired_len,
+                ))
+            }
         }
     }
 }
@@ -311,4 +373,25 @@ mod content_search_tests {
         assert!(!res.is_found());
         Ok(())
     }
+
+    #[test]
+    fn test_found_in_other_encodings() -> Result<(), io::Error> {
+        use std::io::Write;
+        let needle = Needle::new("œuvre", 1_000_000);
+        for encoding in [Encoding::Windows1252, Encoding::Utf16Le] {
+            let mut tmp = tempfile::NamedTempFile::new()?;
+            tmp.write_all(encoding.bom())?;
+            tmp.write_all(&encoding.encode("first line\nune œuvre d’art\n").unwrap())?;
+            tmp.flush()?;
+            match needle.search(tmp.path())? {
+                ContentSearchResult::Found { pos } => {
+                    assert_eq!(line_count_at_pos(tmp.path(), pos)?, 2);
+                }
+                _ => panic!("needle not found in {encoding}"),
+            }
+            let content_match = needle.get_match(tmp.path(), 100).unwrap();
+            assert_eq!(content_match.extract, "une œuvre d’art");
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,475 @@
+use {
+    std::{
+        borrow::Cow,
+        fmt,
+        fs::File,
+        io::{
+            self,
+            BufRead,
+            Read,
+        },
+        path::Path,
+        str::FromStr,
+    },
+};
+
+/// number of bytes at the start of a file on which the encoding is guessed
+pub const DETECTION_LEN: usize = 8 * 1024;
+
+/// max number of consecutive non ASCII bytes in a text in Latin-1
+/// or Windows-1252
+const MAX_HIGH_BYTES_RUN: usize = 3;
+
+/// The chars of Windows-1252 for the bytes 0x80 to 0x9F (the other
+/// bytes are the same as in Latin-1). Undefined bytes are `None`.
+#[rustfmt::skip]
+static WINDOWS_1252_HIGH: [Option<char>; 32] = [
+    Some('€'), None, Some('‚'), Some('ƒ'), Some('„'), Some('…'), Some('†'), Some('‡'),
+    Some('ˆ'), Some('‰'), Some('Š'), Some('‹'), Some('Œ'), None, Some('Ž'), None,
+    None, Some('‘'), Some('’'), Some('“'), Some('”'), Some('•'), Some('–'), Some('—'),
+    Some('˜'), Some('™'), Some('š'), Some('›'), Some('œ'), None, Some('ž'), Some('Ÿ'),
+];
+
+/// A text encoding, either detected or forced by the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Windows1252,
+}
+
+impl Encoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+            Self::Latin1 => "latin-1",
+            Self::Windows1252 => "windows-1252",
+        }
+    }
+    pub fn is_utf16(self) -> bool {
+        matches!(self, Self::Utf16Le | Self::Utf16Be)
+    }
+    /// the Byte Order Mark which may start a file in this encoding
+    pub fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => &[0xEF, 0xBB, 0xBF],
+            Self::Utf16Le => &[0xFF, 0xFE],
+            Self::Utf16Be => &[0xFE, 0xFF],
+            Self::Latin1 | Self::Windows1252 => &[],
+        }
+    }
+    /// the length of the BOM at the start of the bytes, if any
+    pub fn bom_len(
+        self,
+        bytes: &[u8],
+    ) -> usize {
+        let bom = self.bom();
+        if !bom.is_empty() && bytes.starts_with(bom) {
+            bom.len()
+        } else {
+            0
+        }
+    }
+    /// the number of bytes of a newline
+    pub fn newline_len(self) -> usize {
+        if self.is_utf16() { 2 } else { 1 }
+    }
+    /// Guess the encoding of a text from its first bytes, with its BOM
+    /// if there's one, or with heuristics.
+    ///
+    /// Return `None` when the bytes don't look like text in any of the
+    /// supported encodings.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        for encoding in [Self::Utf8, Self::Utf16Le, Self::Utf16Be] {
+            if encoding.bom_len(bytes) > 0 {
+                return Some(encoding);
+            }
+        }
+        // checked before UTF-8 as null bytes are valid UTF-8
+        if let Some(encoding) = detect_bomless_utf16(bytes) {
+            return Some(encoding);
+        }
+        match std::str::from_utf8(bytes) {
+            Ok(_) => return Some(Self::Utf8),
+            Err(e) if e.error_len().is_none() => {
+                // just a char cut at the end of the sample
+                return Some(Self::Utf8);
+            }
+            Err(_) => {}
+        }
+        if !looks_like_single_byte_text(bytes) {
+            return None;
+        }
+        let mut in_c1_range = false;
+        for &b in bytes {
+            if (0x80..=0x9F).contains(&b) {
+                // a byte undefined in Windows-1252 means it's not a text
+                WINDOWS_1252_HIGH[(b - 0x80) as usize]?;
+                in_c1_range = true;
+            }
+        }
+        // Latin-1 and Windows-1252 differ only on bytes 0x80 to 0x9F
+        // which are never used in Latin-1 texts
+        Some(if in_c1_range {
+            Self::Windows1252
+        } else {
+            Self::Latin1
+        })
+    }
+    /// Guess the encoding of a file from its first bytes
+    pub fn detect_file<P: AsRef<Path>>(path: P) -> io::Result<Option<Self>> {
+        let mut bytes = Vec::with_capacity(DETECTION_LEN);
+        File::open(path)?
+            .take(DETECTION_LEN as u64)
+            .read_to_end(&mut bytes)?;
+        Ok(Self::detect(&bytes))
+    }
+    /// Decode bytes, not including a BOM, replacing the invalid ones
+    pub fn decode(
+        self,
+        bytes: &[u8],
+    ) -> Cow<'_, str> {
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(bytes),
+            Self::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+            Self::Windows1252 => bytes
+                .iter()
+                .map(|&b| match b {
+                    0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize].unwrap_or('�'),
+                    _ => b as char,
+                })
+                .collect(),
+            Self::Utf16Le | Self::Utf16Be => {
+                let units = bytes.chunks_exact(2).map(|pair| {
+                    let pair = [pair[0], pair[1]];
+                    if self == Self::Utf16Le {
+                        u16::from_le_bytes(pair)
+                    } else {
+                        u16::from_be_bytes(pair)
+                    }
+                });
+                char::decode_utf16(units)
+                    .map(|r| r.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect()
+            }
+        }
+    }
+    /// Encode a string, return `None` if some chars can't be represented
+    pub fn encode(
+        self,
+        s: &str,
+    ) -> Option<Vec<u8>> {
+        match self {
+            Self::Utf8 => Some(s.as_bytes().to_vec()),
+            Self::Latin1 => s.chars().map(|c| u8::try_from(c).ok()).collect(),
+            Self::Windows1252 => s
+                .chars()
+                .map(|c| match u8::try_from(c) {
+                    Ok(b) if !(0x80..=0x9F).contains(&b) => Some(b),
+                    _ => WINDOWS_1252_HIGH
+                        .iter()
+                        .position(|&hc| hc == Some(c))
+                        .map(|idx| 0x80 + idx as u8),
+                })
+                .collect(),
+            Self::Utf16Le => Some(s.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            Self::Utf16Be => Some(s.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+        }
+    }
+    /// Return the position of the first newline in the bytes, which
+    /// are assumed to start at a char boundary
+    pub fn find_newline(
+        self,
+        bytes: &[u8],
+    ) -> Option<usize> {
+        let mut from = 0;
+        loop {
+            let pos = from + memchr::memchr(b'\n', bytes.get(from..)?)?;
+            let found = match self {
+                Self::Utf16Le => pos % 2 == 0 && bytes.get(pos + 1) == Some(&0),
+                Self::Utf16Be => pos % 2 == 1 && bytes[pos - 1] == 0,
+                _ => true,
+            };
+            if found {
+                return Some(if self == Self::Utf16Be { pos - 1 } else { pos });
+            }
+            from = pos + 1;
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace(['-', '_'], "").as_str() {
+            "utf8" => Ok(Self::Utf8),
+            "utf16le" => Ok(Self::Utf16Le),
+            "utf16be" => Ok(Self::Utf16Be),
+            "latin1" | "iso88591" => Ok(Self::Latin1),
+            "windows1252" | "cp1252" => Ok(Self::Windows1252),
+            _ => Err(format!(
+                "Unknown encoding: {s:?}. Supported: utf-8, utf-16le, utf-16be, latin-1, windows-1252"
+            )),
+        }
+    }
+}
+
+/// Tell whether the byte, in a single byte encoding, is a control
+/// char which isn't expected in a text
+fn is_byte_control(b: u8) -> bool {
+    matches!(b, 0..=8 | 0x0E..=0x1A | 0x1C..=0x1F | 0x7F)
+}
+
+/// Tell whether bytes which aren't UTF-8 look like a text in a single
+/// byte encoding: there's no control char, and the non ASCII bytes
+/// (accented letters, typographic signs) are a minority and not long
+/// sequences, as in binary data.
+fn looks_like_single_byte_text(bytes: &[u8]) -> bool {
+    let mut high_count = 0;
+    let mut high_run = 0;
+    for &b in bytes {
+        if is_byte_control(b) {
+            return false;
+        }
+        if b >= 0x80 {
+            high_count += 1;
+            high_run += 1;
+            if high_run > MAX_HIGH_BYTES_RUN {
+                return false;
+            }
+        } else {
+            high_run = 0;
+        }
+    }
+    high_count * 3 <= bytes.len()
+}
+
+/// Recognize UTF-16 without BOM by the proportion of null bytes at odd or
+/// even positions (a typical case being ASCII chars in UTF-16)
+fn detect_bomless_utf16(bytes: &[u8]) -> Option<Encoding> {
+    let units = bytes.len() / 2;
+    if units < 2 {
+        return None;
+    }
+    let mut even_zeros = 0;
+    let mut odd_zeros = 0;
+    for pair in bytes.chunks_exact(2) {
+        if pair[0] == 0 {
+            even_zeros += 1;
+        }
+        if pair[1] == 0 {
+            odd_zeros += 1;
+        }
+    }
+    let encoding = if odd_zeros * 2 > units && even_zeros * 10 < units {
+        Encoding::Utf16Le
+    } else if even_zeros * 2 > units && odd_zeros * 10 < units {
+        Encoding::Utf16Be
+    } else {
+        return None;
+    };
+    // we check the decoded text doesn't contain control chars
+    let text = encoding.decode(&bytes[..units * 2]);
+    if text.chars().any(|c| (c as u32) < 0x80 && is_byte_control(c as u8)) {
+        return None;
+    }
+    Some(encoding)
+}
+
+/// A reader of lines in a given encoding, skipping the BOM at the
+/// start of the file
+pub struct LineReader<R: BufRead> {
+    reader: R,
+    encoding: Encoding,
+    at_start: bool,
+    raw: Vec<u8>,
+}
+
+impl LineReader<io::BufReader<File>> {
+    /// open the file, detecting its encoding (defaulting to UTF-8)
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let encoding = Encoding::detect_file(path)?.unwrap_or(Encoding::Utf8);
+        Ok(Self::new(io::BufReader::new(File::open(path)?), encoding))
+    }
+}
+
+impl<R: BufRead> LineReader<R> {
+    /// Build a line reader. If the encoding has a BOM, the reader
+    /// must be at the start of the file
+    pub fn new(
+        reader: R,
+        encoding: Encoding,
+    ) -> Self {
+        Self {
+            reader,
+            encoding,
+            at_start: true,
+            raw: Vec::new(),
+        }
+    }
+    /// build a line reader for a reader which isn't at the start of the file
+    pub fn new_after_start(
+        reader: R,
+        encoding: Encoding,
+    ) -> Self {
+        Self {
+            at_start: false,
+            ..Self::new(reader, encoding)
+        }
+    }
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+    /// Read a line, decode it and append it to `buf` (with its newline).
+    ///
+    /// Return the number of bytes read (including a BOM), 0 meaning
+    /// the end of the file was reached.
+    ///
+    /// For UTF-8, invalid bytes are an error of kind `InvalidData`,
+    /// like with `BufRead::read_line`.
+    pub fn read_line(
+        &mut self,
+        buf: &mut String,
+    ) -> io::Result<usize> {
+        let mut bom_len = 0;
+        if self.at_start {
+            self.at_start = false;
+            bom_len = self.encoding.bom_len(self.reader.fill_buf()?);
+            self.reader.consume(bom_len);
+        }
+        if self.encoding == Encoding::Utf8 {
+            return Ok(bom_len + self.reader.read_line(buf)?);
+        }
+        self.raw.clear();
+        loop {
+            let n = self.reader.read_until(b'\n', &mut self.raw)?;
+            if n == 0 || self.raw.last() != Some(&b'\n') {
+                break; // end of file
+            }
+            let len = self.raw.len();
+            match self.encoding {
+                Encoding::Utf16Le if len % 2 == 1 => {
+                    // the newline is complete only with its null high byte
+                    let mut high = [0];
+                    if self.reader.read(&mut high)? == 0 {
+                        break;
+                    }
+                    self.raw.push(high[0]);
+                    if high[0] == 0 {
+                        break;
+                    }
+                }
+                Encoding::Utf16Le => {}
+                Encoding::Utf16Be => {
+                    if len % 2 == 0 && self.raw[len - 2] == 0 {
+                        break;
+                    }
+                }
+                _ => break,
+            }
+        }
+        buf.push_str(&self.encoding.decode(&self.raw));
+        Ok(bom_len + self.raw.len())
+    }
+    /// Return an iterator over the lines, without their newline,
+    /// like `BufRead::lines`
+    pub fn lines(self) -> Lines<R> {
+        Lines { reader: self }
+    }
+}
+
+/// An iterator over the decoded lines of a `LineReader`
+pub struct Lines<R: BufRead> {
+    reader: LineReader<R>,
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = io::Result<String>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                Some(Ok(line))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod encoding_tests {
+    use super::*;
+
+    static TEXT: &str = "Déjà vu, l’œuvre “coûte” 5€\nsecond line\n";
+
+    #[test]
+    fn test_detect_and_decode() {
+        for encoding in [
+            Encoding::Utf8,
+            Encoding::Utf16Le,
+            Encoding::Utf16Be,
+            Encoding::Windows1252,
+        ] {
+            let bytes = encoding.encode(TEXT).unwrap();
+            assert_eq!(Encoding::detect(&bytes), Some(encoding));
+            assert_eq!(encoding.decode(&bytes), TEXT);
+        }
+        let latin1 = Encoding::Latin1.encode("Déjà vu").unwrap();
+        assert_eq!(Encoding::detect(&latin1), Some(Encoding::Latin1));
+        assert_eq!(Encoding::Latin1.encode("5€"), None);
+        let mut with_bom = Encoding::Utf16Be.bom().to_vec();
+        with_bom.extend(Encoding::Utf16Be.encode("ȁ").unwrap());
+        assert_eq!(Encoding::detect(&with_bom), Some(Encoding::Utf16Be));
+        assert_eq!(Encoding::detect(b"\x89PNG\r\n\x1a\n"), None);
+        assert_eq!(Encoding::detect(b"ab \xe9\xfa\xf3\xe0 cd"), None);
+        assert_eq!(Encoding::detect(b"\xe9\xe9 \xe9\xe9 \xe9"), None);
+        assert_eq!("CP-1252".parse(), Ok(Encoding::Windows1252));
+    }
+
+    #[test]
+    fn test_line_reader() {
+        for encoding in [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be] {
+            let mut bytes = encoding.bom().to_vec();
+            // '\u{a0a}' contains 0x0A bytes which aren't newlines in UTF-16
+            bytes.extend(encoding.encode("a\u{a0a}\nb\nc").unwrap());
+            let mut reader = LineReader::new(&bytes[..], encoding);
+            let mut lines = Vec::new();
+            let mut read = 0;
+            loop {
+                let mut line = String::new();
+                let n = reader.read_line(&mut line).unwrap();
+                if n == 0 {
+                    break;
+                }
+                read += n;
+                lines.push(line);
+            }
+            assert_eq!(lines, vec!["a\u{a0a}\n", "b\n", "c"]);
+            assert_eq!(read, bytes.len());
+            let start = encoding.bom().len();
+            let nl = encoding.find_newline(&bytes[start..]).unwrap();
+            assert_eq!(encoding.decode(&bytes[start..start + nl]), "a\u{a0a}");
+        }
+    }
+}
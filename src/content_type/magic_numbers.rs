@@ -86,6 +86,10 @@ pub fn is_known_binary(bytes: &[u8]) -> bool {
     if bytes.len() < 4 {
         return false;
     }
+    if bytes.starts_with(&[0xFF, 0xFE]) || bytes.starts_with(&[0xFE, 0xFF]) {
+        // UTF-16 text with a BOM
+        return false;
+    }
     let c = bytes[0];
     if c < 9 || (c > 13 && c < 32) || c >= 254 {
         // c < 9 include several signatures
@@ -128,3 +132,119 @@ pub fn is_file_known_binary<P: AsRef<Path>>(path: P) -> io::Result<bool> {
     let n = file.read(&mut buf)?;
     Ok(is_known_binary(&buf[0..n]))
 }
+
+/// A file type recognized by its signature (the "magic number")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MagicType {
+    /// a short name, eg "png" or "elf"
+    pub name: &'static str,
+    pub mime: &'static str,
+    /// position of the signature in the file
+    offset: usize,
+    signature: &'static [u8],
+}
+
+const fn mt(
+    name: &'static str,
+    mime: &'static str,
+    offset: usize,
+    signature: &'static [u8],
+) -> MagicType {
+    MagicType {
+        name,
+        mime,
+        offset,
+        signature,
+    }
+}
+
+/// The types recognized by `magic_type`. When several ones match,
+/// the first one wins, so the more specific signatures must come first.
+///
+/// Signatures are taken in <https://en.wikipedia.org/wiki/List_of_file_signatures>
+static MAGIC_TYPES: &[MagicType] = &[
+    mt("png", "image/png", 0, b"\x89PNG\r\n\x1A\n"),
+    mt("jpeg", "image/jpeg", 0, b"\xFF\xD8\xFF"),
+    mt("gif", "image/gif", 0, b"GIF8"),
+    mt("webp", "image/webp", 8, b"WEBP"),
+    mt("tiff", "image/tiff", 0, b"II*\x00"),
+    mt("tiff", "image/tiff", 0, b"MM\x00*"),
+    mt("ico", "image/x-icon", 0, b"\x00\x00\x01\x00"),
+    mt("psd", "image/vnd.adobe.photoshop", 0, b"8BPS"),
+    mt("pdf", "application/pdf", 0, b"%PDF-"),
+    mt("ps", "application/postscript", 0, b"%!PS"),
+    mt("rtf", "application/rtf", 0, b"{\\rtf"),
+    mt("sqlite", "application/vnd.sqlite3", 0, b"SQLite format 3\x00"),
+    mt("zip", "application/zip", 0, b"PK\x03\x04"),
+    mt("zip", "application/zip", 0, b"PK\x05\x06"),
+    mt("gzip", "application/gzip", 0, b"\x1F\x8B"),
+    mt("bzip2", "application/x-bzip2", 0, b"BZh"),
+    mt("xz", "application/x-xz", 0, b"\xFD7zXZ\x00"),
+    mt("zstd", "application/zstd", 0, b"\x28\xB5\x2F\xFD"),
+    mt("7z", "application/x-7z-compressed", 0, b"7z\xBC\xAF\x27\x1C"),
+    mt("rar", "application/vnd.rar", 0, b"Rar!\x1A\x07"),
+    mt("tar", "application/x-tar", 257, b"ustar"),
+    mt("deb", "application/vnd.debian.binary-package", 0, b"!<arch>\ndebian"),
+    mt("rpm", "application/x-rpm", 0, b"\xED\xAB\xEE\xDB"),
+    mt("elf", "application/x-executable", 0, b"\x7FELF"),
+    mt("mach-o", "application/x-mach-binary", 0, b"\xCF\xFA\xED\xFE"),
+    mt("mach-o", "application/x-mach-binary", 0, b"\xCE\xFA\xED\xFE"),
+    mt("exe", "application/vnd.microsoft.portable-executable", 0, b"MZ"),
+    mt("wasm", "application/wasm", 0, b"\x00asm"),
+    mt("class", "application/java-vm", 0, b"\xCA\xFE\xBA\xBE"),
+    mt("ogg", "audio/ogg", 0, b"OggS"),
+    mt("flac", "audio/flac", 0, b"fLaC"),
+    mt("mp3", "audio/mpeg", 0, b"ID3"),
+    mt("wav", "audio/wav", 8, b"WAVE"),
+    mt("midi", "audio/midi", 0, b"MThd"),
+    mt("avi", "video/x-msvideo", 8, b"AVI "),
+    mt("mp4", "video/mp4", 4, b"ftyp"),
+    mt("mkv", "video/x-matroska", 0, b"\x1A\x45\xDF\xA3"),
+    mt("woff", "font/woff", 0, b"wOFF"),
+    mt("woff2", "font/woff2", 0, b"wOF2"),
+];
+
+/// number of bytes to read at the start of a file to find its
+/// magic type
+const MAGIC_BYTES_LEN: usize = 262;
+
+impl MagicType {
+    fn matches(
+        self,
+        bytes: &[u8],
+    ) -> bool {
+        bytes
+            .get(self.offset..self.offset + self.signature.len())
+            .is_some_and(|b| b == self.signature)
+    }
+}
+
+/// return the type of the content whose first bytes are given, if
+/// it's one of the recognized ones
+#[must_use]
+pub fn magic_type(bytes: &[u8]) -> Option<MagicType> {
+    MAGIC_TYPES.iter().find(|mt| mt.matches(bytes)).copied()
+}
+
+/// return the type of the file, if it's one of the recognized ones
+pub fn file_magic_type<P: AsRef<Path>>(path: P) -> io::Result<Option<MagicType>> {
+    let mut buf = Vec::with_capacity(MAGIC_BYTES_LEN);
+    File::open(path)?
+        .take(MAGIC_BYTES_LEN as u64)
+        .read_to_end(&mut buf)?;
+    Ok(magic_type(&buf))
+}
+
+#[test]
+fn test_magic_type() {
+    let name = |bytes: &[u8]| magic_type(bytes).map(|mt| mt.name);
+    assert_eq!(name(b"\x7FELF\x02\x01\x01"), Some("elf"));
+    assert_eq!(name(b"%PDF-1.7\n"), Some("pdf"));
+    assert_eq!(name(b"RIFF\x24\x08\x00\x00WAVEfmt "), Some("wav"));
+    assert_eq!(name(b"fn main() {}"), None);
+    assert_eq!(name(b""), None);
+    let mut tar = vec![b'a'; 300];
+    tar[257..262].copy_from_slice(b"ustar");
+    assert_eq!(name(&tar), Some("tar"));
+    assert_eq!(name(&tar[..260]), None);
+}
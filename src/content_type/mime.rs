@@ -0,0 +1,161 @@
+use {
+    super::is_file_binary,
+    crate::path::path_has_ext,
+    std::{
+        fs::File,
+        io::{
+            self,
+            Read,
+        },
+        path::Path,
+    },
+};
+
+/// number of bytes read at the start of the file to recognize its format
+const HEAD_LEN: usize = 512;
+
+/// signatures found at the start of files of common formats
+static SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"II*\0", "image/tiff"),
+    (b"MM\0*", "image/tiff"),
+    (b"\0\0\x01\0", "image/vnd.microsoft.icon"),
+    (b"8BPS", "image/vnd.adobe.photoshop"),
+    (b"%PDF-", "application/pdf"),
+    (b"%!PS", "application/postscript"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"PK\x05\x06", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"BZh", "application/x-bzip2"),
+    (b"\xfd7zXZ\0", "application/x-xz"),
+    (b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (b"Rar!\x1a\x07", "application/vnd.rar"),
+    (b"!<arch>\n", "application/x-archive"),
+    (b"\xed\xab\xee\xdb", "application/x-rpm"),
+    (b"\x7fELF", "application/x-elf"),
+    (b"MZ", "application/vnd.microsoft.portable-executable"),
+    (b"\xcf\xfa\xed\xfe", "application/x-mach-binary"),
+    (b"\xce\xfa\xed\xfe", "application/x-mach-binary"),
+    (b"\xca\xfe\xba\xbe", "application/java-vm"),
+    (b"\0asm", "application/wasm"),
+    (b"SQLite format 3\0", "application/vnd.sqlite3"),
+    (b"OggS", "audio/ogg"),
+    (b"fLaC", "audio/flac"),
+    (b"ID3", "audio/mpeg"),
+    (b"MThd", "audio/midi"),
+    (b"\x1aE\xdf\xa3", "video/x-matroska"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+    (b"\0\x01\0\0\0", "font/ttf"),
+    (b"OTTO", "font/otf"),
+];
+
+/// mime types of some common text formats, by extension
+static TEXT_EXTENSIONS: &[(&str, &str)] = &[
+    ("c", "text/x-c"),
+    ("cpp", "text/x-c++"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("go", "text/x-go"),
+    ("h", "text/x-c"),
+    ("hjson", "application/hjson"),
+    ("htm", "text/html"),
+    ("html", "text/html"),
+    ("java", "text/x-java"),
+    ("js", "text/javascript"),
+    ("json", "application/json"),
+    ("md", "text/markdown"),
+    ("py", "text/x-python"),
+    ("rs", "text/rust"),
+    ("sh", "application/x-sh"),
+    ("svg", "image/svg+xml"),
+    ("toml", "application/toml"),
+    ("ts", "text/typescript"),
+    ("tsv", "text/tab-separated-values"),
+    ("xml", "application/xml"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+];
+
+/// Recognize the format of a file from its first bytes, when they
+/// contain a known signature
+pub fn mime_type_of_head(head: &[u8]) -> Option<&'static str> {
+    if let Some(&(_, mime)) = SIGNATURES.iter().find(|(sig, _)| head.starts_with(sig)) {
+        return Some(mime);
+    }
+    // formats whose signature isn't at the start
+    let at = |offset: usize, sig: &[u8]| head.get(offset..offset + sig.len()) == Some(sig);
+    if at(0, b"RIFF") {
+        if at(8, b"WEBP") {
+            return Some("image/webp");
+        }
+        if at(8, b"WAVE") {
+            return Some("audio/wav");
+        }
+        if at(8, b"AVI ") {
+            return Some("video/x-msvideo");
+        }
+    }
+    if at(4, b"ftyp") {
+        return Some(if at(8, b"qt  ") {
+            "video/quicktime"
+        } else if at(8, b"heic") || at(8, b"mif1") {
+            "image/heic"
+        } else if at(8, b"avif") {
+            "image/avif"
+        } else if at(8, b"M4A ") {
+            "audio/mp4"
+        } else {
+            "video/mp4"
+        });
+    }
+    if at(257, b"ustar") {
+        return Some("application/x-tar");
+    }
+    None
+}
+
+/// Tell the mime type of a file (not a directory), from its content
+/// or, for text files, from its extension
+pub fn mime_type<P: AsRef<Path>>(path: P) -> io::Result<&'static str> {
+    let path = path.as_ref();
+    let mut head = Vec::with_capacity(HEAD_LEN);
+    File::open(path)?
+        .take(HEAD_LEN as u64)
+        .read_to_end(&mut head)?;
+    if head.is_empty() {
+        return Ok("inode/x-empty");
+    }
+    if let Some(mime) = mime_type_of_head(&head) {
+        return Ok(mime);
+    }
+    if is_file_binary(path)? {
+        return Ok("application/octet-stream");
+    }
+    Ok(TEXT_EXTENSIONS
+        .iter()
+        .find(|(ext, _)| path_has_ext(path, ext))
+        .map_or("text/plain", |&(_, mime)| mime))
+}
+
+#[cfg(test)]
+mod mime_tests {
+    use super::*;
+
+    #[test]
+    fn test_mime_type_of_head() {
+        assert_eq!(mime_type_of_head(b"\x7fELF\x02\x01"), Some("application/x-elf"));
+        assert_eq!(mime_type_of_head(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(mime_type_of_head(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(mime_type_of_head(b"\0\0\0\x20ftypisom"), Some("video/mp4"));
+        assert_eq!(mime_type_of_head(b"RIFF"), None);
+        assert_eq!(mime_type_of_head(b"fn main() {}"), None);
+        let mut tar = vec![0; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(mime_type_of_head(&tar), Some("application/x-tar"));
+    }
+}
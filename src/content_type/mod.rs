@@ -1,5 +1,7 @@
+pub mod encoding;
 pub mod extensions;
 pub mod magic_numbers;
+pub mod mime;
 
 use std::{
     io,
@@ -91,7 +91,7 @@ impl Areas {
         screen: Screen,
         with_preview: bool, // slightly larger last panel
     ) {
-        let screen_height = screen.height.max(MINIMAL_PANEL_HEIGHT);
+        let screen_height = screen.height.max(screen.top + MINIMAL_PANEL_HEIGHT);
         let screen_width = screen.width.max(MINIMAL_SCREEN_WIDTH);
         let n = slots.len() as u16;
 
@@ -187,7 +187,7 @@ impl Areas {
                 Slot::New(areas) => areas,
             };
             let y = screen_height - 2;
-            areas.state = Area::new(x, 0, panel_width, y);
+            areas.state = Area::new(x, screen.top, panel_width, y - screen.top);
             areas.status = if WIDE_STATUS {
                 Area::new(0, y, screen_width, 1)
             } else {
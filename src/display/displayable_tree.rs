@@ -5,6 +5,7 @@ use {
         CropWriter,
         GitStatusDisplay,
         MatchedString,
+        PARTIAL_SUM_MARK,
         SPACE_FILLING,
         num_format::format_count,
     },
@@ -15,7 +16,7 @@ use {
         file_sum::FileSum,
         pattern::PatternObject,
         skin::{
-            ExtColorMap,
+            FileColorMap,
             StyleMap,
         },
         task_sync::ComputationResult,
@@ -62,7 +63,7 @@ pub struct DisplayableTree<'a, 's, 't> {
     pub skin: &'s StyleMap,
     pub area: termimad::Area,
     pub in_app: bool, // if true we show the selection and scrollbar
-    pub ext_colors: &'s ExtColorMap,
+    pub file_colors: &'s FileColorMap,
 }
 
 impl<'a, 's, 't> DisplayableTree<'a, 's, 't> {
@@ -70,7 +71,7 @@ impl<'a, 's, 't> DisplayableTree<'a, 's, 't> {
     pub fn out_of_app(
         tree: &'t Tree,
         skin: &'s StyleMap,
-        ext_colors: &'s ExtColorMap,
+        file_colors: &'s FileColorMap,
         width: u16,
         height: u16,
     ) -> DisplayableTree<'a, 's, 't> {
@@ -78,7 +79,7 @@ impl<'a, 's, 't> DisplayableTree<'a, 's, 't> {
             app_state: None,
             tree,
             skin,
-            ext_colors,
+            file_colors,
             area: termimad::Area {
                 left: 0,
                 top: 0,
@@ -109,9 +110,7 @@ impl<'a, 's, 't> DisplayableTree<'a, 's, 't> {
             TreeLineType::BrokenSymLink(_) | TreeLineType::SymLink { .. } => self.skin.link,
             TreeLineType::Pruning => self.skin.pruning,
         };
-        if let Some(ext_color) = line.extension().and_then(|ext| self.ext_colors.get(ext)) {
-            style.set_fg(ext_color);
-        }
+        self.file_colors.apply(line, &mut style);
         if selected {
             if let Some(c) = self.skin.selected_line.get_bg() {
                 style.set_bg(c);
@@ -187,14 +186,21 @@ impl<'a, 's, 't> DisplayableTree<'a, 's, 't> {
     }
 
     fn write_line_size<W: Write>(
+        &self,
         cw: &mut CropWriter<W>,
         line: &TreeLine,
         style: &CompoundStyle,
         _selected: bool,
     ) -> Result<usize, termimad::Error> {
         Ok(if let Some(s) = line.sum {
-            cw.queue_g_string(style, format!("{:>4}", file_size::fit_4(s.to_size())))?;
-            1
+            let size = s.size(self.tree.options.apparent_sizes);
+            cw.queue_g_string(style, format!("{:>4}", file_size::fit_4(size)))?;
+            if s.is_partial() {
+                cw.queue_char(style, PARTIAL_SUM_MARK)?;
+                0
+            } else {
+                1
+            }
         } else {
             5
         })
@@ -211,17 +217,25 @@ impl<'a, 's, 't> DisplayableTree<'a, 's, 't> {
     ) -> Result<usize, termimad::Error> {
         Ok(if let Some(s) = line.sum {
             let label_style = self.label_style(line, selected, false);
-            let pb = ProgressBar::new(s.part_of_size(total_size), 10);
+            let apparent = self.tree.options.apparent_sizes;
+            let pb = ProgressBar::new(s.part_of_size(total_size, apparent), 10);
             cond_bg!(sparse_style, self, selected, self.skin.sparse);
-            cw.queue_g_string(&label_style, format!("{:>4}", file_size::fit_4(s.to_size())))?;
-            cw.queue_char(
-                sparse_style,
-                if s.is_sparse() && line.is_file() {
-                    's'
-                } else {
-                    ' '
-                },
+            cw.queue_g_string(
+                &label_style,
+                format!("{:>4}", file_size::fit_4(s.size(apparent))),
             )?;
+            if s.is_partial() {
+                cw.queue_char(&label_style, PARTIAL_SUM_MARK)?;
+            } else {
+                cw.queue_char(
+                    sparse_style,
+                    if s.is_sparse() && line.is_file() {
+                        's'
+                    } else {
+                        ' '
+                    },
+                )?;
+            }
             cw.queue_g_string(&label_style, format!("{pb:<10}"))?;
             1
         } else {
@@ -425,7 +439,13 @@ impl<'a, 's, 't> DisplayableTree<'a, 's, 't> {
         let line = &self.tree.lines[0];
         if self.tree.options.show_sizes {
             if let Some(s) = line.sum {
-                cw.queue_g_string(style, format!("{:>4} ", file_size::fit_4(s.to_size())))?;
+                let size = s.size(self.tree.options.apparent_sizes);
+                let mark = if s.is_partial() {
+                    PARTIAL_SUM_MARK
+                } else {
+                    ' '
+                };
+                cw.queue_g_string(style, format!("{:>4}{mark}", file_size::fit_4(size)))?;
             }
         }
         let title = line.path.to_string_lossy();
@@ -625,7 +645,7 @@ impl<'a, 's, 't> DisplayableTree<'a, 's, 't> {
                                     selected,
                                 )?
                             } else {
-                                Self::write_line_size(cw, line, &label_style, selected)?
+                                self.write_line_size(cw, line, &label_style, selected)?
                             }
                         }
 
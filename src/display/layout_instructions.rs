@@ -1,16 +1,19 @@
 use {
     lazy_regex::*,
-    serde::Deserialize,
+    serde::{
+        Deserialize,
+        Serialize,
+    },
     std::str::FromStr,
 };
 
-#[derive(Debug, Clone, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(transparent)]
 pub struct LayoutInstructions {
     pub instructions: Vec<LayoutInstruction>,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum LayoutInstruction {
     Clear, // clear all instructions
@@ -73,6 +73,7 @@ pub use {
     layout_instructions::*,
     luma::LumaCondition,
     matched_string::MatchedString,
+    num_format::format_count,
     screen::Screen,
 };
 use {
@@ -89,6 +90,10 @@ pub static BRANCH_FILLING: Lazy<Filling> = Lazy::new(|| Filling::from_char('─'
 /// of the terminal (over the other panels)
 pub const WIDE_STATUS: bool = true;
 
+/// the char displayed after a size whose computation was stopped
+/// by the timeout before the end
+pub const PARTIAL_SUM_MARK: char = '+';
+
 /// the type used by all GUI writing functions
 pub type W = std::io::BufWriter<std::io::Stderr>;
 
@@ -17,10 +17,12 @@ use {
 };
 
 /// The dimensions of the screen
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct Screen {
     pub width: u16,
     pub height: u16,
+    /// number of rows reserved above the panels (eg for the tab bar)
+    pub top: u16,
 }
 
 impl Screen {
@@ -28,6 +30,7 @@ impl Screen {
         let mut screen = Screen {
             width: 0,
             height: 0,
+            top: 0,
         };
         screen.read_size(con)?;
         Ok(screen)
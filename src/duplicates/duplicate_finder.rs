@@ -0,0 +1,471 @@
+use {
+    crate::{
+        app::AppContext,
+        path::Directive,
+        task_sync::Dam,
+    },
+    rayon::prelude::*,
+    rustc_hash::FxHashMap,
+    std::{
+        fs,
+        hash::{
+            DefaultHasher,
+            Hasher,
+        },
+        io::{
+            self,
+            Read,
+        },
+        path::{
+            Path,
+            PathBuf,
+        },
+        time::{
+            Duration,
+            Instant,
+        },
+    },
+};
+
+#[cfg(unix)]
+use {
+    rustc_hash::FxHashSet,
+    std::os::unix::fs::MetadataExt,
+};
+
+/// number of bytes hashed at the start of files in the partial hash phase
+const PARTIAL_HASH_LEN: u64 = 4096;
+
+/// number of files hashed in parallel between two checks of the dam
+const HASH_BATCH_SIZE: usize = 64;
+
+/// number of sets of files with the same hash compared in parallel
+/// between two checks of the dam
+const COMPARE_BATCH_SIZE: usize = 16;
+
+/// size of the chunks read when comparing files
+const COMPARE_CHUNK_LEN: usize = 64 * 1024;
+
+/// max duration of a step, so that the progress can be displayed
+const STEP_DURATION: Duration = Duration::from_millis(150);
+
+/// pseudo and volatile file systems, not searched unless the search
+/// starts inside them
+const PSEUDO_FS_PATHS: &[&str] = &["/proc", "/sys", "/dev", "/run"];
+
+/// directories of pseudo file systems where removable drives are mounted
+const MOUNT_PATHS: &[&str] = &["/run/media"];
+
+/// tell whether the path is in a pseudo file system, out of the root
+/// of the search and out of the directories where drives are mounted
+fn is_in_pseudo_fs(
+    path: &Path,
+    root: &Path,
+) -> bool {
+    PSEUDO_FS_PATHS
+        .iter()
+        .any(|p| path.starts_with(p) && !root.starts_with(p))
+        && !MOUNT_PATHS
+            .iter()
+            .any(|m| path.starts_with(m) || Path::new(m).starts_with(path))
+}
+
+/// A set of files having the same content
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    /// size of each file
+    pub size: u64,
+    /// paths of the identical files, the first one being the
+    /// one which is kept when staging the others
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    /// the space which would be freed by keeping only one file
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    Listing,
+    PartialHashing,
+    FullHashing,
+    Comparing,
+    Done,
+}
+
+/// A resumable search of duplicate files below a root.
+///
+/// Files are first bucketed by size, then by a hash of their
+/// start, then by a hash of their whole content. Files with the
+/// same hash are finally compared byte per byte. Each call to
+/// `step` advances the search until it's done, until an event
+/// comes in the dam, or until it's time to display the progress.
+pub struct DuplicateFinder {
+    root: PathBuf,
+    phase: Phase,
+    dirs: Vec<PathBuf>,
+    by_size: FxHashMap<u64, Vec<PathBuf>>,
+    #[cfg(unix)]
+    nodes: FxHashSet<(u64, u64)>,
+    listed_count: usize,
+    /// files waiting to be hashed in the current phase
+    to_hash: Vec<(u64, PathBuf)>,
+    to_hash_count: usize,
+    hashed: Vec<(u64, u64, PathBuf)>,
+    /// sets of files with the same size and hash, to compare
+    to_compare: Vec<(u64, Vec<PathBuf>)>,
+    to_compare_count: usize,
+    groups: Vec<DuplicateGroup>,
+}
+
+impl DuplicateFinder {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            phase: Phase::Listing,
+            dirs: vec![root.clone()],
+            root,
+            by_size: FxHashMap::default(),
+            #[cfg(unix)]
+            nodes: FxHashSet::default(),
+            listed_count: 0,
+            to_hash: Vec::new(),
+            to_hash_count: 0,
+            hashed: Vec::new(),
+            to_compare: Vec::new(),
+            to_compare_count: 0,
+            groups: Vec::new(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.phase == Phase::Done
+    }
+
+    /// the groups of identical files, sorted by decreasing wasted
+    /// space (only complete when the search is done)
+    pub fn groups(&self) -> &[DuplicateGroup] {
+        &self.groups
+    }
+
+    /// a short description of the search progress
+    pub fn progress(&self) -> String {
+        match self.phase {
+            Phase::Listing => format!("listing files: {}", self.listed_count),
+            Phase::PartialHashing | Phase::FullHashing => format!(
+                "comparing files: {}/{}",
+                self.to_hash_count - self.to_hash.len(),
+                self.to_hash_count,
+            ),
+            Phase::Comparing => format!(
+                "checking duplicates: {}/{}",
+                self.to_compare_count - self.to_compare.len(),
+                self.to_compare_count,
+            ),
+            Phase::Done => format!("{} groups of duplicate files", self.groups.len()),
+        }
+    }
+
+    /// advance the search
+    pub fn step(
+        &mut self,
+        dam: &Dam,
+        con: &AppContext,
+    ) {
+        let start = Instant::now();
+        while !self.is_done() && !dam.has_event() && start.elapsed() < STEP_DURATION {
+            match self.phase {
+                Phase::Listing => {
+                    if let Some(dir) = self.dirs.pop() {
+                        self.read_dir(&dir, con);
+                    } else {
+                        self.start_partial_hashing();
+                    }
+                }
+                Phase::PartialHashing | Phase::FullHashing => {
+                    if self.to_hash.is_empty() {
+                        self.end_hashing_phase();
+                    } else {
+                        self.hash_batch();
+                    }
+                }
+                Phase::Comparing => {
+                    if self.to_compare.is_empty() {
+                        self.end_comparing_phase();
+                    } else {
+                        self.compare_batch();
+                    }
+                }
+                Phase::Done => {}
+            }
+        }
+    }
+
+    fn read_dir(
+        &mut self,
+        dir: &Path,
+        con: &AppContext,
+    ) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for e in entries.flatten() {
+            // we don't follow symlinks
+            let Ok(md) = e.metadata() else {
+                continue;
+            };
+            let path = e.path();
+            if is_in_pseudo_fs(&path, &self.root) {
+                continue;
+            }
+            if md.is_dir() {
+                if con.special_paths.sum(&path) == Directive::Never {
+                    continue;
+                }
+                self.dirs.push(path);
+            } else if md.is_file() && md.len() > 0 {
+                // hard links to the same node aren't duplicates
+                #[cfg(unix)]
+                if md.nlink() > 1 && !self.nodes.insert((md.dev(), md.ino())) {
+                    continue;
+                }
+                self.listed_count += 1;
+                self.by_size.entry(md.len()).or_default().push(path);
+            }
+        }
+    }
+
+    fn start_partial_hashing(&mut self) {
+        self.to_hash = std::mem::take(&mut self.by_size)
+            .into_iter()
+            .filter(|(_, paths)| paths.len() > 1)
+            .flat_map(|(size, paths)| paths.into_iter().map(move |path| (size, path)))
+            .collect();
+        self.to_hash_count = self.to_hash.len();
+        self.phase = Phase::PartialHashing;
+    }
+
+    fn hash_batch(&mut self) {
+        let len = self.to_hash.len().min(HASH_BATCH_SIZE);
+        let batch: Vec<(u64, PathBuf)> = self.to_hash.drain(..len).collect();
+        let hash_len = if self.phase == Phase::PartialHashing {
+            Some(PARTIAL_HASH_LEN)
+        } else {
+            None
+        };
+        let hashed: Vec<(u64, u64, PathBuf)> = batch
+            .into_par_iter()
+            .filter_map(|(size, path)| match hash_file(&path, hash_len) {
+                Ok(hash) => Some((size, hash, path)),
+                Err(e) => {
+                    debug!("can't hash {path:?}: {e}");
+                    None
+                }
+            })
+            .collect();
+        self.hashed.extend(hashed);
+    }
+
+    fn end_hashing_phase(&mut self) {
+        let mut buckets: FxHashMap<(u64, u64), Vec<PathBuf>> = FxHashMap::default();
+        for (size, hash, path) in self.hashed.drain(..) {
+            buckets.entry((size, hash)).or_default().push(path);
+        }
+        let buckets = buckets.into_iter().filter(|(_, paths)| paths.len() > 1);
+        if self.phase == Phase::PartialHashing {
+            for ((size, _), paths) in buckets {
+                if size <= PARTIAL_HASH_LEN {
+                    // the partial hash was a full one
+                    self.to_compare.push((size, paths));
+                } else {
+                    self.to_hash
+                        .extend(paths.into_iter().map(|path| (size, path)));
+                }
+            }
+            self.to_hash_count = self.to_hash.len();
+            self.phase = Phase::FullHashing;
+        } else {
+            self.to_compare
+                .extend(buckets.map(|((size, _), paths)| (size, paths)));
+            self.to_compare_count = self.to_compare.len();
+            self.phase = Phase::Comparing;
+        }
+    }
+
+    fn compare_batch(&mut self) {
+        let len = self.to_compare.len().min(COMPARE_BATCH_SIZE);
+        let batch: Vec<(u64, Vec<PathBuf>)> = self.to_compare.drain(..len).collect();
+        let groups: Vec<DuplicateGroup> = batch
+            .into_par_iter()
+            .flat_map_iter(|(size, paths)| {
+                split_identical(paths)
+                    .into_iter()
+                    .filter(|paths| paths.len() > 1)
+                    .map(move |paths| DuplicateGroup { size, paths })
+            })
+            .collect();
+        self.groups.extend(groups);
+    }
+
+    fn end_comparing_phase(&mut self) {
+        for group in &mut self.groups {
+            // the shallowest file is the one we keep
+            group
+                .paths
+                .sort_by(|a, b| (a.components().count(), a).cmp(&(b.components().count(), b)));
+        }
+        self.groups
+            .sort_by(|a, b| b.wasted().cmp(&a.wasted()).then(a.paths.cmp(&b.paths)));
+        self.phase = Phase::Done;
+    }
+}
+
+/// hash the content of a file, or only its first bytes when a
+/// length is given
+fn hash_file(
+    path: &Path,
+    len: Option<u64>,
+) -> io::Result<u64> {
+    let file = fs::File::open(path)?;
+    let mut reader: Box<dyn Read> = match len {
+        Some(len) => Box::new(file.take(len)),
+        None => Box::new(file),
+    };
+    let mut hasher = DefaultHasher::new();
+    let mut buf = [0; 16 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.write(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+/// split files (supposedly of the same size) into sets of files
+/// with identical contents
+fn split_identical(paths: Vec<PathBuf>) -> Vec<Vec<PathBuf>> {
+    let mut sets: Vec<Vec<PathBuf>> = Vec::new();
+    for path in paths {
+        let set_idx = sets.iter().position(|set| {
+            same_content(&set[0], &path).unwrap_or_else(|e| {
+                debug!("can't compare {path:?}: {e}");
+                false
+            })
+        });
+        match set_idx {
+            Some(idx) => sets[idx].push(path),
+            None => sets.push(vec![path]),
+        }
+    }
+    sets
+}
+
+/// compare the contents of two files, byte per byte
+fn same_content(
+    a: &Path,
+    b: &Path,
+) -> io::Result<bool> {
+    let mut file_a = fs::File::open(a)?;
+    let mut file_b = fs::File::open(b)?;
+    let mut buf_a = vec![0; COMPARE_CHUNK_LEN];
+    let mut buf_b = vec![0; COMPARE_CHUNK_LEN];
+    loop {
+        let len_a = read_chunk(&mut file_a, &mut buf_a)?;
+        let len_b = read_chunk(&mut file_b, &mut buf_b)?;
+        if buf_a[..len_a] != buf_b[..len_b] {
+            return Ok(false);
+        }
+        if len_a == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// fill the buffer, unless the end of the file is reached first
+fn read_chunk(
+    file: &mut fs::File,
+    buf: &mut [u8],
+) -> io::Result<usize> {
+    let mut len = 0;
+    while len < buf.len() {
+        match file.read(&mut buf[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    Ok(len)
+}
+
+#[cfg(test)]
+mod duplicate_finder_tests {
+    use super::*;
+
+    #[test]
+    fn test_find_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let big_a: Vec<u8> = (0..10_000).map(|i| (i % 251) as u8).collect();
+        let mut big_b = big_a.clone();
+        big_b[9_000] = 0; // same size and same start, different content
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), "same content").unwrap();
+        fs::write(root.join("sub/a-copy.txt"), "same content").unwrap();
+        fs::write(root.join("other.txt"), "same length!").unwrap();
+        fs::write(root.join("big"), &big_a).unwrap();
+        fs::write(root.join("sub/big-copy"), &big_a).unwrap();
+        fs::write(root.join("sub/big-other"), &big_b).unwrap();
+        let con = AppContext::default();
+        let mut finder = DuplicateFinder::new(root.to_path_buf());
+        while !finder.is_done() {
+            finder.step(&Dam::unlimited(), &con);
+        }
+        let groups = finder.groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].size, 10_000);
+        assert_eq!(
+            groups[0].paths,
+            vec![root.join("big"), root.join("sub/big-copy")]
+        );
+        assert_eq!(groups[0].wasted(), 10_000);
+        assert_eq!(
+            groups[1].paths,
+            vec![root.join("a.txt"), root.join("sub/a-copy.txt")]
+        );
+    }
+
+    #[test]
+    fn test_is_in_pseudo_fs() {
+        let root = Path::new("/");
+        assert!(is_in_pseudo_fs(Path::new("/proc/1"), root));
+        assert!(is_in_pseudo_fs(Path::new("/sys"), root));
+        assert!(is_in_pseudo_fs(Path::new("/run/user/1000"), root));
+        assert!(!is_in_pseudo_fs(Path::new("/home/me"), root));
+        assert!(!is_in_pseudo_fs(Path::new("/running"), root));
+        // removable drives are searched
+        assert!(!is_in_pseudo_fs(Path::new("/run"), root));
+        assert!(!is_in_pseudo_fs(Path::new("/run/media"), root));
+        assert!(!is_in_pseudo_fs(Path::new("/run/media/me/usb/a.txt"), root));
+        // the search may be explicitly started in a pseudo file system
+        assert!(!is_in_pseudo_fs(Path::new("/run/user/1000"), Path::new("/run/user")));
+    }
+
+    #[test]
+    fn test_split_identical() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut long_a = vec![7; COMPARE_CHUNK_LEN + 10];
+        fs::write(root.join("a1"), &long_a).unwrap();
+        fs::write(root.join("a2"), &long_a).unwrap();
+        long_a[COMPARE_CHUNK_LEN + 5] = 8; // differs only in the second chunk
+        fs::write(root.join("b"), &long_a).unwrap();
+        let paths = vec![root.join("a1"), root.join("b"), root.join("a2")];
+        assert_eq!(
+            split_identical(paths),
+            vec![vec![root.join("a1"), root.join("a2")], vec![root.join("b")]],
+        );
+    }
+}
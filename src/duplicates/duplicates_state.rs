@@ -0,0 +1,419 @@
+use {
+    super::*,
+    crate::{
+        app::*,
+        command::*,
+        display::*,
+        errors::ProgramError,
+        pattern::*,
+        stage::StageState,
+        task_sync::Dam,
+        tree::TreeOptions,
+        verb::*,
+    },
+    crokey::crossterm::{
+        QueueableCommand,
+        cursor,
+        style::Color,
+    },
+    std::path::{
+        Path,
+        PathBuf,
+    },
+    termimad::*,
+};
+
+/// A line of the displayed list
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DuplicatesLine {
+    /// a group title, with the index of the group
+    Group(usize),
+    /// a file, with the indexes of its group and in the group
+    File(usize, usize),
+}
+
+impl SectionedLine for DuplicatesLine {
+    fn is_item(self) -> bool {
+        matches!(self, Self::File(..))
+    }
+}
+
+/// an application state listing the groups of identical files
+/// found below a root
+pub struct DuplicatesState {
+    root: PathBuf,
+    finder: DuplicateFinder,
+    list: SectionedList<DuplicatesLine>,
+    pattern: Pattern,
+    tree_options: TreeOptions,
+    mode: Mode,
+}
+
+impl DuplicatesState {
+    /// create a state whose search is done as a pending task
+    pub fn new(
+        root: PathBuf,
+        tree_options: TreeOptions,
+        con: &AppContext,
+    ) -> Self {
+        Self {
+            finder: DuplicateFinder::new(root.clone()),
+            root,
+            list: SectionedList::default(),
+            pattern: Pattern::None,
+            tree_options,
+            mode: con.initial_mode(),
+        }
+    }
+
+    fn path_of(
+        &self,
+        group_idx: usize,
+        path_idx: usize,
+    ) -> &Path {
+        &self.finder.groups()[group_idx].paths[path_idx]
+    }
+
+    /// the path as displayed, relative to the root
+    fn displayed_path(
+        &self,
+        group_idx: usize,
+        path_idx: usize,
+    ) -> String {
+        let path = self.path_of(group_idx, path_idx);
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// rebuild the lines, keeping only the files matching
+    /// the pattern (and the titles of their groups)
+    fn update_lines(&mut self) {
+        let mut lines = Vec::new();
+        for (group_idx, group) in self.finder.groups().iter().enumerate() {
+            let files: Vec<DuplicatesLine> = (0..group.paths.len())
+                .filter(|&path_idx| {
+                    self.pattern
+                        .score_of_string(&self.displayed_path(group_idx, path_idx))
+                        .is_some()
+                })
+                .map(|path_idx| DuplicatesLine::File(group_idx, path_idx))
+                .collect();
+            if !files.is_empty() {
+                lines.push(DuplicatesLine::Group(group_idx));
+                lines.extend(files);
+            }
+        }
+        self.list.set_lines(lines);
+    }
+
+    fn move_line(
+        &mut self,
+        internal_exec: &InternalExecution,
+        input_invocation: Option<&VerbInvocation>,
+        dir: i32, // -1 for up, 1 for down
+        cycle: bool,
+    ) -> CmdResult {
+        let count = get_arg(input_invocation, internal_exec, 1);
+        self.list.move_selection(count, dir, cycle);
+        CmdResult::Keep
+    }
+
+    /// stage all the files of the displayed groups but the first one
+    /// of each group
+    fn stage_duplicates(
+        &self,
+        app_state: &mut AppState,
+        cc: &CmdContext,
+    ) -> CmdResult {
+        let mut count = 0;
+        for &line in self.list.lines() {
+            if let DuplicatesLine::File(group_idx, path_idx) = line {
+                if path_idx > 0 {
+                    let path = self.path_of(group_idx, path_idx).to_path_buf();
+                    if app_state.stage.add(path) {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        if count > 0 && cc.app.stage_panel.is_none() {
+            CmdResult::NewPanel {
+                state: Box::new(StageState::new(
+                    app_state,
+                    self.tree_options.without_pattern(),
+                    cc.app.con,
+                )),
+                purpose: PanelPurpose::None,
+                direction: HDir::Right,
+            }
+        } else {
+            CmdResult::Message(format!("{count} duplicate files staged"))
+        }
+    }
+}
+
+impl PanelState for DuplicatesState {
+    fn get_type(&self) -> PanelStateType {
+        PanelStateType::Duplicates
+    }
+
+    fn set_mode(
+        &mut self,
+        mode: Mode,
+    ) {
+        self.mode = mode;
+    }
+
+    fn get_mode(&self) -> Mode {
+        self.mode
+    }
+
+    fn selected_path(&self) -> Option<&Path> {
+        match self.list.selected_line()? {
+            DuplicatesLine::File(group_idx, path_idx) => Some(self.path_of(group_idx, path_idx)),
+            DuplicatesLine::Group(_) => None,
+        }
+    }
+
+    fn selection(&self) -> Option<Selection<'_>> {
+        self.selected_path().map(|path| Selection {
+            path,
+            stype: SelectionType::File,
+            is_exe: false,
+            line: 0,
+        })
+    }
+
+    fn tree_options(&self) -> TreeOptions {
+        self.tree_options.clone()
+    }
+
+    fn with_new_options(
+        &mut self,
+        _screen: Screen,
+        change_options: &dyn Fn(&mut TreeOptions) -> &'static str,
+        _in_new_panel: bool,
+        _con: &AppContext,
+    ) -> CmdResult {
+        change_options(&mut self.tree_options);
+        CmdResult::Keep
+    }
+
+    fn refresh(
+        &mut self,
+        _screen: Screen,
+        _con: &AppContext,
+    ) -> Command {
+        self.finder = DuplicateFinder::new(self.root.clone());
+        self.list.clear();
+        Command::empty()
+    }
+
+    fn do_pending_task(
+        &mut self,
+        _app_state: &mut AppState,
+        _screen: Screen,
+        con: &AppContext,
+        dam: &mut Dam,
+    ) -> Result<(), ProgramError> {
+        self.finder.step(dam, con);
+        if self.finder.is_done() {
+            self.update_lines();
+        }
+        Ok(())
+    }
+
+    fn get_pending_task(&self) -> Option<&'static str> {
+        if self.finder.is_done() {
+            None
+        } else {
+            Some("searching duplicates")
+        }
+    }
+
+    fn on_pattern(
+        &mut self,
+        pattern: InputPattern,
+        _app_state: &AppState,
+        _con: &AppContext,
+    ) -> Result<CmdResult, ProgramError> {
+        self.pattern = pattern.pattern;
+        self.update_lines();
+        Ok(CmdResult::Keep)
+    }
+
+    fn display(
+        &mut self,
+        w: &mut W,
+        disc: &DisplayContext,
+    ) -> Result<(), ProgramError> {
+        let area = &disc.state_area;
+        let con = &disc.con;
+        let stage = &disc.app_state.stage;
+        self.list.page_height = area.height as usize - 1;
+        let width = area.width as usize;
+        let scrollbar = area.scrollbar(self.list.scroll, self.list.len());
+        let styles = &disc.panel_skin.styles;
+        let selection_bg = styles
+            .selected_line
+            .get_bg()
+            .unwrap_or(Color::AnsiValue(240));
+        let mut selected_match_style = styles.char_match;
+        selected_match_style.set_bg(selection_bg);
+        let mut selected_file_style = styles.file;
+        selected_file_style.set_bg(selection_bg);
+        //- title line
+        w.queue(cursor::MoveTo(area.left, area.top))?;
+        let mut cw = CropWriter::new(w, width);
+        let title_style = &styles.staging_area_title;
+        cw.queue_g_string(title_style, self.root.to_string_lossy().to_string())?;
+        let summary = if self.finder.is_done() {
+            let wasted: u64 = self
+                .finder
+                .groups()
+                .iter()
+                .map(DuplicateGroup::wasted)
+                .sum();
+            format!(
+                "  {} groups, {} wasted",
+                self.finder.groups().len(),
+                file_size::fit_4(wasted),
+            )
+        } else {
+            format!("  {}", self.finder.progress())
+        };
+        if summary.len() < cw.allowed {
+            cw.repeat(title_style, &SPACE_FILLING, cw.allowed - summary.len())?;
+            cw.queue_g_string(title_style, summary)?;
+        }
+        cw.fill(title_style, &SPACE_FILLING)?;
+        //- content
+        for y in 1..area.height {
+            w.queue(cursor::MoveTo(area.left, y + area.top))?;
+            let idx = self.list.scroll + y as usize - 1;
+            let selected = self.list.is_selected(idx);
+            let mut cw = CropWriter::new(w, width - 1); // -1 for scrollbar
+            let txt_style = if selected {
+                &styles.selected_line
+            } else {
+                &styles.default
+            };
+            match self.list.get(idx) {
+                Some(DuplicatesLine::Group(group_idx)) => {
+                    let group = &self.finder.groups()[group_idx];
+                    cw.queue_g_string(
+                        &styles.help_headers,
+                        format!(
+                            "{} files of {} - {} wasted",
+                            group.paths.len(),
+                            file_size::fit_4(group.size),
+                            file_size::fit_4(group.wasted()),
+                        ),
+                    )?;
+                }
+                Some(DuplicatesLine::File(group_idx, path_idx)) => {
+                    if con.show_selection_mark {
+                        cw.queue_char(txt_style, if selected { '▶' } else { ' ' })?;
+                    }
+                    let path = self.path_of(group_idx, path_idx);
+                    cw.queue_char(txt_style, if stage.contains(path) { '+' } else { ' ' })?;
+                    cw.queue_char(txt_style, ' ')?;
+                    let label = self.displayed_path(group_idx, path_idx);
+                    let (file_style, match_style) = if selected {
+                        (&selected_file_style, &selected_match_style)
+                    } else {
+                        (&styles.file, &styles.char_match)
+                    };
+                    let matched_string = MatchedString::new(
+                        self.pattern.search_string(&label),
+                        &label,
+                        file_style,
+                        match_style,
+                    );
+                    matched_string.queue_on(&mut cw)?;
+                }
+                None => {}
+            }
+            cw.fill(txt_style, &SPACE_FILLING)?;
+            let scrollbar_style = if ScrollCommand::is_thumb(y + area.top, scrollbar) {
+                &styles.scrollbar_thumb
+            } else {
+                &styles.scrollbar_track
+            };
+            scrollbar_style.queue_str(w, "▐")?;
+        }
+        Ok(())
+    }
+
+    fn on_internal(
+        &mut self,
+        w: &mut W,
+        invocation_parser: Option<&InvocationParser>,
+        internal_exec: &InternalExecution,
+        input_invocation: Option<&VerbInvocation>,
+        trigger_type: TriggerType,
+        app_state: &mut AppState,
+        cc: &CmdContext,
+    ) -> Result<CmdResult, ProgramError> {
+        let con = &cc.app.con;
+        Ok(match internal_exec.internal {
+            Internal::back => {
+                if self.pattern.is_some() {
+                    self.pattern = Pattern::None;
+                    self.update_lines();
+                    CmdResult::Keep
+                } else {
+                    CmdResult::PopState
+                }
+            }
+            Internal::line_down => self.move_line(internal_exec, input_invocation, 1, true),
+            Internal::line_up => self.move_line(internal_exec, input_invocation, -1, true),
+            Internal::line_down_no_cycle => {
+                self.move_line(internal_exec, input_invocation, 1, false)
+            }
+            Internal::line_up_no_cycle => {
+                self.move_line(internal_exec, input_invocation, -1, false)
+            }
+            Internal::page_down => {
+                self.list.try_scroll(ScrollCommand::Pages(1));
+                CmdResult::Keep
+            }
+            Internal::page_up => {
+                self.list.try_scroll(ScrollCommand::Pages(-1));
+                CmdResult::Keep
+            }
+            Internal::open_stay => match self.selection() {
+                Some(selection) => selection.to_opener(con)?,
+                None => CmdResult::error("no selected file"),
+            },
+            Internal::stage_all_files => self.stage_duplicates(app_state, cc),
+            Internal::panel_left_no_open => CmdResult::HandleInApp(Internal::panel_left_no_open),
+            Internal::panel_right_no_open => CmdResult::HandleInApp(Internal::panel_right_no_open),
+            Internal::open_leave => CmdResult::PopStateAndReapply,
+            _ => self.on_internal_generic(
+                w,
+                invocation_parser,
+                internal_exec,
+                input_invocation,
+                trigger_type,
+                app_state,
+                cc,
+            )?,
+        })
+    }
+
+    fn on_click(
+        &mut self,
+        _x: u16,
+        y: u16,
+        _screen: Screen,
+        _con: &AppContext,
+    ) -> Result<CmdResult, ProgramError> {
+        if y >= 1 {
+            self.list.try_select_row(y as usize - 1);
+        }
+        Ok(CmdResult::Keep)
+    }
+}
@@ -0,0 +1,9 @@
+//! Search of identical files below a directory
+
+mod duplicate_finder;
+mod duplicates_state;
+
+pub use {
+    duplicate_finder::*,
+    duplicates_state::DuplicatesState,
+};
@@ -16,18 +16,23 @@ custom_error! {pub ProgramError
     Io {source: io::Error} = "IO Error : {source}",
     LaunchError {program: String, source: io::Error} = "Unable to launch {program}: {source}",
     Lfs {details: String} = "Failed to fetch mounts: {details}",
+    Macro {details: String} = "Macro error: {details}",
     NetError {source: NetError} = "{source}",
     OpenError { source: opener::OpenError } = "Open error: {source}",
+    Session {details: String} = "Session error: {details}",
     ShelInstall { source: ShellInstallError } = "{source}",
     Svg {source: SvgError} = "SVG error: {source}",
     SyntectCrashed { details: String } = "Syntect crashed on {details:?}",
     Termimad {source: termimad::Error} = "Termimad Error : {source}",
+    TooBigFile = "File is too big for this view",
     Trash {message: String} = "Trash error: {message}",
     TreeBuild {source: TreeBuildError} = "{source}",
+    UnknownFileFormat = "File format not recognized",
     UnknownShell {shell: String} = "Unknown shell: {shell}",
     UnknownVerb {name: String} = "No verb matches {name:?}",
     UnmappableFile = "File can't be mapped",
     UnmatchingVerbArgs {name: String} = "No matching argument found for verb {name:?}",
+    UnparsableFile {details: String} = "File can't be parsed: {details}",
     UnprintableFile = "File can't be printed", // has characters that can't be printed without escaping
     Unrecognized {token: String} = "Unrecognized: {token}",
     ZeroLenFile = "File seems empty",
@@ -0,0 +1,541 @@
+//! Stream properties and tags of WAV, FLAC, MP3 and Ogg files
+
+use {
+    super::{
+        format_duration,
+        format_sample_rate,
+        u16_le,
+        u32_be,
+        u32_le,
+        u64_be,
+        u64_le,
+    },
+    crate::file_info::InfoSection,
+    memchr::memmem,
+};
+
+/// tag values longer than this are cropped
+const MAX_TAG_LEN: usize = 100;
+
+/// the number of bytes read from the end of an Ogg file to find the
+/// last page
+const OGG_TAIL_LEN: usize = 65_536;
+
+fn crop(value: &str) -> String {
+    if value.chars().count() > MAX_TAG_LEN {
+        let mut cropped: String = value.chars().take(MAX_TAG_LEN).collect();
+        cropped.push('…');
+        cropped
+    } else {
+        value.to_string()
+    }
+}
+
+fn channels_description(channels: u32) -> String {
+    match channels {
+        1 => "1 (mono)".to_string(),
+        2 => "2 (stereo)".to_string(),
+        n => n.to_string(),
+    }
+}
+
+pub fn wav_sections(data: &[u8]) -> Option<Vec<InfoSection>> {
+    let mut audio = InfoSection::new("Audio");
+    let mut tags = InfoSection::new("Tags");
+    let mut byte_rate = 0;
+    let mut pos = 12;
+    while let (Some(id), Some(size)) = (data.get(pos..pos + 4), u32_le(data, pos + 4)) {
+        let start = pos + 8;
+        let size = size as usize;
+        let chunk = &data[start..(start + size).min(data.len())];
+        match id {
+            b"fmt " => {
+                let format = match u16_le(chunk, 0)? {
+                    1 => "PCM".to_string(),
+                    3 => "IEEE float".to_string(),
+                    6 => "A-law".to_string(),
+                    7 => "µ-law".to_string(),
+                    0x55 => "MP3".to_string(),
+                    0xfffe => "extensible".to_string(),
+                    tag => format!("{tag:#x}"),
+                };
+                audio.add("format", format);
+                audio.add("channels", channels_description(u16_le(chunk, 2)?.into()));
+                audio.add("sample rate", format_sample_rate(u32_le(chunk, 4)?));
+                byte_rate = u32_le(chunk, 8)?;
+                audio.add("bits per sample", u16_le(chunk, 14)?.to_string());
+            }
+            b"data" if byte_rate > 0 => {
+                let seconds = size as f64 / f64::from(byte_rate);
+                audio.add("duration", format_duration(seconds));
+                audio.add("bitrate", format!("{} kb/s", u64::from(byte_rate) * 8 / 1000));
+            }
+            b"LIST" if chunk.starts_with(b"INFO") => {
+                let mut pos = 4;
+                while let (Some(id), Some(size)) = (chunk.get(pos..pos + 4), u32_le(chunk, pos + 4))
+                {
+                    let start = pos + 8;
+                    let end = (start + size as usize).min(chunk.len());
+                    let value = String::from_utf8_lossy(&chunk[start..end]);
+                    let value = value.trim_end_matches('\0');
+                    let name = match id {
+                        b"INAM" => "title",
+                        b"IART" => "artist",
+                        b"IPRD" => "album",
+                        b"ICRD" => "date",
+                        b"IGNR" => "genre",
+                        b"ICMT" => "comment",
+                        b"ISFT" => "software",
+                        _ => "",
+                    };
+                    if !name.is_empty() && !value.is_empty() {
+                        tags.add(name, crop(value));
+                    }
+                    pos = end + end % 2;
+                }
+            }
+            _ => {}
+        }
+        // chunks are padded to an even size
+        pos = start + size + size % 2;
+    }
+    let mut sections = Vec::new();
+    audio.push_to(&mut sections);
+    tags.push_to(&mut sections);
+    Some(sections)
+}
+
+/// parse a Vorbis comment block (used by FLAC, Ogg Vorbis and Opus)
+fn vorbis_comments(
+    data: &[u8],
+    tags: &mut InfoSection,
+) -> Option<()> {
+    let vendor_len = u32_le(data, 0)? as usize;
+    let vendor = data.get(4..4 + vendor_len)?;
+    if !vendor.is_empty() {
+        tags.add("encoder", crop(&String::from_utf8_lossy(vendor)));
+    }
+    let mut pos = 4 + vendor_len;
+    let count = u32_le(data, pos)?;
+    pos += 4;
+    for _ in 0..count {
+        let len = u32_le(data, pos)? as usize;
+        let comment = data.get(pos + 4..pos + 4 + len)?;
+        pos += 4 + len;
+        let comment = String::from_utf8_lossy(comment);
+        let Some((key, value)) = comment.split_once('=') else {
+            continue;
+        };
+        // cover pictures are big base64 blobs
+        if key.eq_ignore_ascii_case("METADATA_BLOCK_PICTURE") {
+            tags.add("picture", "yes");
+            continue;
+        }
+        tags.add(key.to_lowercase(), crop(value));
+    }
+    Some(())
+}
+
+pub fn flac_sections(data: &[u8]) -> Option<Vec<InfoSection>> {
+    let mut audio = InfoSection::new("Audio");
+    let mut tags = InfoSection::new("Tags");
+    audio.add("format", "FLAC");
+    let mut pos = 4;
+    loop {
+        let header = *data.get(pos)?;
+        let len = (u32_be(data, pos)? & 0xff_ffff) as usize;
+        let block = data.get(pos + 4..pos + 4 + len)?;
+        match header & 0x7f {
+            0 => {
+                // STREAMINFO
+                let bits = u64_be(block, 10)?;
+                let sample_rate = (bits >> 44) as u32;
+                let channels = ((bits >> 41) & 0x7) as u32 + 1;
+                let bits_per_sample = ((bits >> 36) & 0x1f) + 1;
+                let samples = bits & 0xf_ffff_ffff;
+                audio.add("channels", channels_description(channels));
+                audio.add("sample rate", format_sample_rate(sample_rate));
+                audio.add("bits per sample", bits_per_sample.to_string());
+                if sample_rate > 0 && samples > 0 {
+                    let seconds = samples as f64 / f64::from(sample_rate);
+                    audio.add("duration", format_duration(seconds));
+                }
+            }
+            4 => {
+                vorbis_comments(block, &mut tags);
+            }
+            6 => {
+                tags.add("picture", "yes");
+            }
+            _ => {}
+        }
+        if header & 0x80 != 0 {
+            break; // last metadata block
+        }
+        pos += 4 + len;
+    }
+    let mut sections = Vec::new();
+    audio.push_to(&mut sections);
+    tags.push_to(&mut sections);
+    Some(sections)
+}
+
+/// decode the text of an ID3v2 text frame
+fn id3_text(frame: &[u8]) -> String {
+    let (&encoding, bytes) = match frame.split_first() {
+        Some(split) => split,
+        None => return String::new(),
+    };
+    let utf16 = |bytes: &[u8], big_endian: bool| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|c| {
+                if big_endian {
+                    u16::from_be_bytes([c[0], c[1]])
+                } else {
+                    u16::from_le_bytes([c[0], c[1]])
+                }
+            })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    let text = match encoding {
+        0 => bytes.iter().map(|&b| b as char).collect(),
+        1 => match bytes {
+            [0xfe, 0xff, rest @ ..] => utf16(rest, true),
+            [0xff, 0xfe, rest @ ..] => utf16(rest, false),
+            _ => utf16(bytes, false),
+        },
+        2 => utf16(bytes, true),
+        _ => String::from_utf8_lossy(bytes).to_string(),
+    };
+    // multiple values are separated with null chars
+    text.trim_end_matches('\0').replace('\0', ", ")
+}
+
+/// parse the ID3v2 tag at the start of the file, return the size of the tag
+fn id3v2_tags(
+    data: &[u8],
+    tags: &mut InfoSection,
+) -> Option<usize> {
+    let major = *data.get(3)?;
+    let flags = *data.get(5)?;
+    let syncsafe = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .fold(0usize, |size, &b| (size << 7) | usize::from(b & 0x7f))
+    };
+    let size = syncsafe(data.get(6..10)?);
+    let footer = if flags & 0x10 != 0 { 10 } else { 0 };
+    let tag_len = 10 + size + footer;
+    let tag = data.get(10..(10 + size).min(data.len()))?;
+    tags.add("ID3 version", format!("2.{major}"));
+    if major < 3 {
+        return Some(tag_len); // old frame layout, not supported
+    }
+    let mut pos = 0;
+    if flags & 0x40 != 0 {
+        // extended header
+        let ext_size = if major == 4 {
+            syncsafe(tag.get(0..4)?)
+        } else {
+            u32_be(tag, 0)? as usize + 4
+        };
+        pos = ext_size;
+    }
+    while let Some(id) = tag.get(pos..pos + 4) {
+        if id[0] == 0 {
+            break; // padding
+        }
+        let frame_size = if major == 4 {
+            syncsafe(tag.get(pos + 4..pos + 8)?)
+        } else {
+            u32_be(tag, pos + 4)? as usize
+        };
+        let start = pos + 10;
+        let frame = tag.get(start..(start + frame_size).min(tag.len()))?;
+        let name = match id {
+            b"TIT2" => "title",
+            b"TPE1" => "artist",
+            b"TPE2" => "album artist",
+            b"TALB" => "album",
+            b"TCOM" => "composer",
+            b"TCON" => "genre",
+            b"TRCK" => "track",
+            b"TPOS" => "disc",
+            b"TYER" | b"TDRC" => "date",
+            b"TENC" => "encoded by",
+            b"TSSE" => "encoder",
+            b"APIC" => {
+                tags.add("picture", "yes");
+                ""
+            }
+            _ => "",
+        };
+        if !name.is_empty() {
+            let text = id3_text(frame);
+            if !text.is_empty() {
+                tags.add(name, crop(&text));
+            }
+        }
+        pos = start + frame_size;
+    }
+    Some(tag_len)
+}
+
+const MPEG1_BITRATES: [[u32; 15]; 3] = [
+    [0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448],
+    [0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384],
+    [0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320],
+];
+const MPEG2_BITRATES: [[u32; 15]; 2] = [
+    [0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256],
+    [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160],
+];
+
+pub fn mp3_sections(data: &[u8]) -> Option<Vec<InfoSection>> {
+    let mut audio = InfoSection::new("Audio");
+    let mut tags = InfoSection::new("Tags");
+    let audio_start = if data.starts_with(b"ID3") {
+        id3v2_tags(data, &mut tags)?
+    } else {
+        0
+    };
+    // the first frame header, which starts with 11 set bits
+    let frame = (audio_start..data.len().saturating_sub(4))
+        .take(65_536)
+        .find(|&i| data[i] == 0xff && data[i + 1] & 0xe0 == 0xe0 && data[i + 1] & 0x06 != 0)?;
+    let header = data.get(frame..frame + 4)?;
+    let version = (header[1] >> 3) & 0x3; // 3: MPEG 1, 2: MPEG 2, 0: MPEG 2.5
+    let layer = 4 - ((header[1] >> 1) & 0x3); // 1, 2 or 3
+    let bitrate_idx = (header[2] >> 4) as usize;
+    let sample_rate_idx = ((header[2] >> 2) & 0x3) as usize;
+    let mono = header[3] >> 6 == 3;
+    let base_rate = *[44_100, 48_000, 32_000].get(sample_rate_idx)?;
+    let (version_name, sample_rate, bitrate) = match version {
+        3 => ("MPEG 1", base_rate, MPEG1_BITRATES[layer as usize - 1].get(bitrate_idx)),
+        2 => ("MPEG 2", base_rate / 2, MPEG2_BITRATES[(layer > 1) as usize].get(bitrate_idx)),
+        0 => ("MPEG 2.5", base_rate / 4, MPEG2_BITRATES[(layer > 1) as usize].get(bitrate_idx)),
+        _ => return None,
+    };
+    let bitrate = *bitrate?;
+    audio.add("format", format!("{version_name} layer {layer}"));
+    audio.add("channels", channels_description(if mono { 1 } else { 2 }));
+    audio.add("sample rate", format_sample_rate(sample_rate));
+    let samples_per_frame = match (layer, version) {
+        (1, _) => 384,
+        (3, 2 | 0) => 576,
+        _ => 1152,
+    };
+    // a VBR file starts with a Xing (or Info) frame giving the frame count
+    let side_info_len = match (version == 3, mono) {
+        (true, false) => 32,
+        (true, true) | (false, false) => 17,
+        (false, true) => 9,
+    };
+    let xing = frame + 4 + side_info_len;
+    let frame_count = match data.get(xing..xing + 4) {
+        Some(b"Xing" | b"Info") if u32_be(data, xing + 4)? & 1 != 0 => u32_be(data, xing + 8),
+        _ => None,
+    };
+    let seconds = match frame_count {
+        Some(frames) => {
+            audio.add("bitrate", "variable");
+            f64::from(frames) * f64::from(samples_per_frame) / f64::from(sample_rate)
+        }
+        None if bitrate > 0 => {
+            audio.add("bitrate", format!("{bitrate} kb/s"));
+            (data.len() - frame) as f64 * 8.0 / (f64::from(bitrate) * 1000.0)
+        }
+        None => 0.0,
+    };
+    if seconds > 0.0 {
+        audio.add("duration", format!("{} (approx.)", format_duration(seconds)));
+    }
+    let mut sections = Vec::new();
+    audio.push_to(&mut sections);
+    tags.push_to(&mut sections);
+    Some(sections)
+}
+
+/// read the first packets of an Ogg stream, concatenating the
+/// segments of the pages
+fn ogg_packets(
+    data: &[u8],
+    max_packets: usize,
+) -> Vec<Vec<u8>> {
+    let mut packets = Vec::new();
+    let mut packet = Vec::new();
+    let mut pos = 0;
+    while packets.len() < max_packets && data.get(pos..pos + 4) == Some(b"OggS") {
+        let Some(&segment_count) = data.get(pos + 26) else {
+            break;
+        };
+        let table_start = pos + 27;
+        let Some(table) = data.get(table_start..table_start + segment_count as usize) else {
+            break;
+        };
+        let mut segment_start = table_start + table.len();
+        for &len in table {
+            let len = len as usize;
+            let Some(segment) = data.get(segment_start..segment_start + len) else {
+                return packets;
+            };
+            packet.extend_from_slice(segment);
+            segment_start += len;
+            // a segment shorter than 255 bytes ends the packet
+            if len < 255 {
+                packets.push(std::mem::take(&mut packet));
+                if packets.len() == max_packets {
+                    break;
+                }
+            }
+        }
+        pos = segment_start;
+    }
+    packets
+}
+
+pub fn ogg_sections(data: &[u8]) -> Option<Vec<InfoSection>> {
+    let mut audio = InfoSection::new("Audio");
+    let mut tags = InfoSection::new("Tags");
+    let packets = ogg_packets(data, 2);
+    let identification = packets.first()?;
+    let (granule_rate, pre_skip) = if identification.starts_with(b"\x01vorbis") {
+        audio.add("format", "Ogg Vorbis");
+        audio.add("channels", channels_description((*identification.get(11)?).into()));
+        let rate = u32_le(identification, 12)?;
+        audio.add("sample rate", format_sample_rate(rate));
+        let nominal_bitrate = u32_le(identification, 20)?;
+        if nominal_bitrate > 0 && nominal_bitrate < i32::MAX as u32 {
+            audio.add("bitrate", format!("{} kb/s", nominal_bitrate / 1000));
+        }
+        if let Some(comments) = packets.get(1).and_then(|p| p.strip_prefix(b"\x03vorbis")) {
+            vorbis_comments(comments, &mut tags);
+        }
+        (rate, 0)
+    } else if identification.starts_with(b"OpusHead") {
+        audio.add("format", "Ogg Opus");
+        audio.add("channels", channels_description((*identification.get(9)?).into()));
+        let input_rate = u32_le(identification, 12)?;
+        if input_rate > 0 {
+            audio.add("input sample rate", format_sample_rate(input_rate));
+        }
+        if let Some(comments) = packets.get(1).and_then(|p| p.strip_prefix(b"OpusTags")) {
+            vorbis_comments(comments, &mut tags);
+        }
+        // Opus granule positions are always at 48 kHz
+        (48_000, u16_le(identification, 10)?.into())
+    } else {
+        let format = if identification.starts_with(b"\x7fFLAC") {
+            "Ogg FLAC"
+        } else if identification.starts_with(b"\x80theora") {
+            "Ogg Theora (video)"
+        } else if identification.starts_with(b"Speex") {
+            "Ogg Speex"
+        } else {
+            "Ogg (unknown codec)"
+        };
+        audio.add("format", format);
+        (0, 0)
+    };
+    // the granule position of the last page gives the duration
+    let tail_start = data.len().saturating_sub(OGG_TAIL_LEN);
+    if granule_rate > 0 {
+        if let Some(last_page) = memmem::rfind(&data[tail_start..], b"OggS") {
+            let granule = u64_le(data, tail_start + last_page + 6)?;
+            let samples = granule.saturating_sub(pre_skip);
+            let seconds = samples as f64 / f64::from(granule_rate);
+            audio.add("duration", format_duration(seconds));
+        }
+    }
+    let mut sections = Vec::new();
+    audio.push_to(&mut sections);
+    tags.push_to(&mut sections);
+    Some(sections)
+}
+
+#[cfg(test)]
+mod audio_tests {
+    use {
+        super::*,
+        crate::file_format::value,
+    };
+
+    /// one second of 16 bits stereo at 8 kHz, with the given byte rate
+    fn tiny_wav(byte_rate: u32) -> Vec<u8> {
+        let mut wav = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&8_000u32.to_le_bytes());
+        wav.extend_from_slice(&byte_rate.to_le_bytes());
+        wav.extend_from_slice(&4u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&32_000u32.to_le_bytes());
+        wav.resize(wav.len() + 32_000, 0);
+        wav
+    }
+
+    #[test]
+    fn test_wav_sections() {
+        let sections = wav_sections(&tiny_wav(32_000)).unwrap();
+        assert_eq!(value(&sections, "Audio", "format"), Some("PCM"));
+        assert_eq!(value(&sections, "Audio", "channels"), Some("2 (stereo)"));
+        assert_eq!(value(&sections, "Audio", "sample rate"), Some("8 kHz"));
+        assert_eq!(value(&sections, "Audio", "duration"), Some("0:01.0"));
+        assert_eq!(value(&sections, "Audio", "bitrate"), Some("256 kb/s"));
+    }
+
+    #[test]
+    fn test_malformed_wav() {
+        let data = tiny_wav(32_000);
+        for len in 0..100 {
+            wav_sections(&data[..len]);
+        }
+        // a byte rate whose bitrate doesn't fit in 32 bits
+        let sections = wav_sections(&tiny_wav(u32::MAX)).unwrap();
+        assert_eq!(value(&sections, "Audio", "bitrate"), Some("34359738 kb/s"));
+    }
+
+    #[test]
+    fn test_mp3_sections() {
+        let mut mp3 = b"ID3\x03\x00\x00".to_vec();
+        let mut frame = b"TIT2".to_vec();
+        frame.extend_from_slice(&6u32.to_be_bytes());
+        frame.extend_from_slice(b"\0\0\0Song!");
+        mp3.extend_from_slice(&[0, 0, 0, frame.len() as u8]);
+        mp3.extend_from_slice(&frame);
+        // MPEG 1 layer 3, 128 kb/s, 44.1 kHz, joint stereo
+        for _ in 0..100 {
+            mp3.extend_from_slice(&[0xff, 0xfb, 0x90, 0x40]);
+            mp3.resize(mp3.len() + 413, 0);
+        }
+        let sections = mp3_sections(&mp3).unwrap();
+        assert_eq!(value(&sections, "Tags", "title"), Some("Song!"));
+        assert_eq!(value(&sections, "Audio", "format"), Some("MPEG 1 layer 3"));
+        assert_eq!(value(&sections, "Audio", "sample rate"), Some("44.1 kHz"));
+        assert_eq!(value(&sections, "Audio", "bitrate"), Some("128 kb/s"));
+        assert_eq!(value(&sections, "Audio", "duration"), Some("0:02.6 (approx.)"));
+    }
+
+    #[test]
+    fn test_vorbis_comments() {
+        let mut block = Vec::new();
+        block.extend_from_slice(&4u32.to_le_bytes());
+        block.extend_from_slice(b"test");
+        block.extend_from_slice(&1u32.to_le_bytes());
+        block.extend_from_slice(&12u32.to_le_bytes());
+        block.extend_from_slice(b"ARTIST=Someo");
+        let mut tags = InfoSection::new("Tags");
+        vorbis_comments(&block, &mut tags).unwrap();
+        assert_eq!(
+            tags.entries,
+            [
+                ("encoder".to_string(), "test".to_string()),
+                ("artist".to_string(), "Someo".to_string()),
+            ]
+        );
+    }
+}
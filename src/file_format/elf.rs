@@ -0,0 +1,458 @@
+//! Headers, segments and sections of ELF executables, shared
+//! libraries and object files
+
+use {
+    super::{
+        u16_be,
+        u16_le,
+        u32_be,
+        u32_le,
+        u64_be,
+        u64_le,
+    },
+    crate::file_info::InfoSection,
+};
+
+const PT_INTERP: u32 = 3;
+const SHT_DYNAMIC: u32 = 6;
+const DT_NEEDED: u64 = 1;
+const DT_RPATH: u64 = 15;
+const DT_SONAME: u64 = 14;
+const DT_RUNPATH: u64 = 29;
+
+/// more sections or segments than this are considered a parse error
+const MAX_HEADERS: usize = 10_000;
+
+/// A reader of the integers of an ELF file, whose width and byte
+/// order depend on the file
+struct Reader<'d> {
+    data: &'d [u8],
+    is_64: bool,
+    big_endian: bool,
+}
+
+impl Reader<'_> {
+    fn u16(
+        &self,
+        at: usize,
+    ) -> Option<u16> {
+        if self.big_endian {
+            u16_be(self.data, at)
+        } else {
+            u16_le(self.data, at)
+        }
+    }
+    fn u32(
+        &self,
+        at: usize,
+    ) -> Option<u32> {
+        if self.big_endian {
+            u32_be(self.data, at)
+        } else {
+            u32_le(self.data, at)
+        }
+    }
+    fn u64(
+        &self,
+        at: usize,
+    ) -> Option<u64> {
+        if self.big_endian {
+            u64_be(self.data, at)
+        } else {
+            u64_le(self.data, at)
+        }
+    }
+    /// read an address or offset: 4 bytes in 32 bits files, 8 in 64 bits ones
+    fn word(
+        &self,
+        at: usize,
+    ) -> Option<u64> {
+        if self.is_64 {
+            self.u64(at)
+        } else {
+            self.u32(at).map(u64::from)
+        }
+    }
+    /// return the position of the entry of a table, if the whole
+    /// entry is in the file
+    fn entry(
+        &self,
+        table_offset: u64,
+        idx: usize,
+        entry_size: usize,
+    ) -> Option<usize> {
+        let at = usize::try_from(table_offset)
+            .ok()?
+            .checked_add(idx.checked_mul(entry_size)?)?;
+        (at.checked_add(entry_size)? <= self.data.len()).then_some(at)
+    }
+    /// read a null terminated string
+    fn str(
+        &self,
+        at: usize,
+    ) -> Option<String> {
+        let bytes = self.data.get(at..)?;
+        let end = bytes.iter().position(|&b| b == 0)?;
+        Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+    }
+}
+
+struct SectionHeader {
+    name_offset: u32,
+    kind: u32,
+    flags: u64,
+    addr: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+}
+
+pub fn sections(data: &[u8]) -> Option<Vec<InfoSection>> {
+    let r = Reader {
+        data,
+        is_64: match data.get(4)? {
+            1 => false,
+            2 => true,
+            _ => return None,
+        },
+        big_endian: match data.get(5)? {
+            1 => false,
+            2 => true,
+            _ => return None,
+        },
+    };
+    let mut sections = Vec::new();
+    let mut header = InfoSection::new("ELF");
+    header.add("class", if r.is_64 { "64 bits" } else { "32 bits" });
+    header.add(
+        "byte order",
+        if r.big_endian {
+            "big endian"
+        } else {
+            "little endian"
+        },
+    );
+    header.add("OS ABI", os_abi_name(*data.get(7)?));
+    header.add("type", type_name(r.u16(16)?));
+    header.add("machine", machine_name(r.u16(18)?));
+    let (phoff, shoff, flags_at, sizes_at) = if r.is_64 {
+        (r.u64(32)?, r.u64(40)?, 48, 54)
+    } else {
+        (u64::from(r.u32(28)?), u64::from(r.u32(32)?), 36, 42)
+    };
+    header.add("entry point", format!("{:#x}", r.word(24)?));
+    header.add("flags", format!("{:#x}", r.u32(flags_at)?));
+    let phentsize = r.u16(sizes_at)? as usize;
+    let phnum = r.u16(sizes_at + 2)? as usize;
+    let shentsize = r.u16(sizes_at + 4)? as usize;
+    let shnum = r.u16(sizes_at + 6)? as usize;
+    let shstrndx = r.u16(sizes_at + 8)? as usize;
+    if phnum > MAX_HEADERS || shnum > MAX_HEADERS {
+        return None;
+    }
+
+    // program headers (segments)
+    let mut segments = InfoSection::new("Segments");
+    for i in 0..phnum {
+        let at = r.entry(phoff, i, phentsize)?;
+        let kind = r.u32(at)?;
+        let (offset, vaddr, filesz, memsz, flags) = if r.is_64 {
+            (
+                r.u64(at + 8)?,
+                r.u64(at + 16)?,
+                r.u64(at + 32)?,
+                r.u64(at + 40)?,
+                r.u32(at + 4)?,
+            )
+        } else {
+            (
+                u64::from(r.u32(at + 4)?),
+                u64::from(r.u32(at + 8)?),
+                u64::from(r.u32(at + 16)?),
+                u64::from(r.u32(at + 20)?),
+                r.u32(at + 24)?,
+            )
+        };
+        if kind == PT_INTERP {
+            let start = usize::try_from(offset).ok()?;
+            let end = start.checked_add(usize::try_from(filesz).ok()?)?;
+            let interpreter = data.get(start..end)?;
+            let interpreter = interpreter.strip_suffix(&[0]).unwrap_or(interpreter);
+            header.add("interpreter", String::from_utf8_lossy(interpreter));
+        }
+        let perms: String = [(4, 'R'), (2, 'W'), (1, 'X')]
+            .iter()
+            .map(|&(bit, c)| if flags & bit != 0 { c } else { '-' })
+            .collect();
+        segments.add(
+            segment_type_name(kind),
+            format!("{perms}  {vaddr:#x}  {filesz} bytes, {memsz} in memory"),
+        );
+    }
+
+    // section headers
+    let mut headers = Vec::with_capacity(shnum);
+    for i in 0..shnum {
+        let at = r.entry(shoff, i, shentsize)?;
+        let (flags, addr, offset, size, link) = if r.is_64 {
+            (
+                r.u64(at + 8)?,
+                r.u64(at + 16)?,
+                r.u64(at + 24)?,
+                r.u64(at + 32)?,
+                r.u32(at + 40)?,
+            )
+        } else {
+            (
+                u64::from(r.u32(at + 8)?),
+                u64::from(r.u32(at + 12)?),
+                u64::from(r.u32(at + 16)?),
+                u64::from(r.u32(at + 20)?),
+                r.u32(at + 24)?,
+            )
+        };
+        headers.push(SectionHeader {
+            name_offset: r.u32(at)?,
+            kind: r.u32(at + 4)?,
+            flags,
+            addr,
+            offset,
+            size,
+            link,
+        });
+    }
+    let names_offset = headers
+        .get(shstrndx)
+        .and_then(|h| usize::try_from(h.offset).ok());
+    let section_name = |h: &SectionHeader| {
+        names_offset
+            .and_then(|offset| offset.checked_add(h.name_offset as usize))
+            .and_then(|at| r.str(at))
+            .unwrap_or_default()
+    };
+    let mut dynamic = InfoSection::new("Dynamic linking");
+    for h in headers.iter().filter(|h| h.kind == SHT_DYNAMIC) {
+        let Some(strtab) = headers.get(h.link as usize) else {
+            continue;
+        };
+        let entry_size = if r.is_64 { 16 } else { 8 };
+        for i in 0..(h.size as usize / entry_size).min(MAX_HEADERS) {
+            let Some(at) = r.entry(h.offset, i, entry_size) else {
+                break;
+            };
+            let (Some(tag), Some(value)) = (r.word(at), r.word(at + entry_size / 2)) else {
+                break;
+            };
+            let name = match tag {
+                DT_NEEDED => "needed",
+                DT_SONAME => "soname",
+                DT_RPATH => "rpath",
+                DT_RUNPATH => "runpath",
+                0 => break, // DT_NULL
+                _ => continue,
+            };
+            let value = usize::try_from(strtab.offset)
+                .ok()
+                .zip(usize::try_from(value).ok())
+                .and_then(|(offset, value)| offset.checked_add(value))
+                .and_then(|at| r.str(at));
+            if let Some(value) = value {
+                dynamic.add(name, value);
+            }
+        }
+    }
+    let mut elf_sections = InfoSection::new("Sections");
+    for h in headers.iter().skip(1) {
+        elf_sections.add(
+            section_name(h),
+            format!(
+                "{:<12} {:>4}  {:#x}  {} bytes",
+                section_type_name(h.kind),
+                section_flags(h.flags),
+                h.addr,
+                h.size,
+            ),
+        );
+    }
+    header.push_to(&mut sections);
+    dynamic.push_to(&mut sections);
+    segments.push_to(&mut sections);
+    elf_sections.push_to(&mut sections);
+    Some(sections)
+}
+
+fn os_abi_name(abi: u8) -> String {
+    match abi {
+        0 => "System V".to_string(),
+        1 => "HP-UX".to_string(),
+        2 => "NetBSD".to_string(),
+        3 => "Linux".to_string(),
+        6 => "Solaris".to_string(),
+        9 => "FreeBSD".to_string(),
+        12 => "OpenBSD".to_string(),
+        97 => "ARM".to_string(),
+        255 => "standalone".to_string(),
+        _ => format!("{abi}"),
+    }
+}
+
+fn type_name(kind: u16) -> String {
+    match kind {
+        1 => "relocatable (object file)".to_string(),
+        2 => "executable".to_string(),
+        3 => "shared object (library or PIE executable)".to_string(),
+        4 => "core dump".to_string(),
+        _ => format!("{kind:#x}"),
+    }
+}
+
+fn machine_name(machine: u16) -> String {
+    match machine {
+        2 => "SPARC".to_string(),
+        3 => "x86".to_string(),
+        8 => "MIPS".to_string(),
+        20 => "PowerPC".to_string(),
+        21 => "PowerPC 64".to_string(),
+        22 => "S/390".to_string(),
+        40 => "ARM".to_string(),
+        43 => "SPARC V9".to_string(),
+        62 => "x86-64".to_string(),
+        183 => "AArch64".to_string(),
+        243 => "RISC-V".to_string(),
+        247 => "BPF".to_string(),
+        258 => "LoongArch".to_string(),
+        _ => format!("{machine}"),
+    }
+}
+
+fn segment_type_name(kind: u32) -> String {
+    match kind {
+        0 => "NULL".to_string(),
+        1 => "LOAD".to_string(),
+        2 => "DYNAMIC".to_string(),
+        3 => "INTERP".to_string(),
+        4 => "NOTE".to_string(),
+        5 => "SHLIB".to_string(),
+        6 => "PHDR".to_string(),
+        7 => "TLS".to_string(),
+        0x6474_e550 => "GNU_EH_FRAME".to_string(),
+        0x6474_e551 => "GNU_STACK".to_string(),
+        0x6474_e552 => "GNU_RELRO".to_string(),
+        0x6474_e553 => "GNU_PROPERTY".to_string(),
+        _ => format!("{kind:#x}"),
+    }
+}
+
+fn section_type_name(kind: u32) -> String {
+    match kind {
+        0 => "NULL".to_string(),
+        1 => "PROGBITS".to_string(),
+        2 => "SYMTAB".to_string(),
+        3 => "STRTAB".to_string(),
+        4 => "RELA".to_string(),
+        5 => "HASH".to_string(),
+        6 => "DYNAMIC".to_string(),
+        7 => "NOTE".to_string(),
+        8 => "NOBITS".to_string(),
+        9 => "REL".to_string(),
+        11 => "DYNSYM".to_string(),
+        14 => "INIT_ARRAY".to_string(),
+        15 => "FINI_ARRAY".to_string(),
+        16 => "PREINIT_ARRAY".to_string(),
+        17 => "GROUP".to_string(),
+        0x6fff_fff6 => "GNU_HASH".to_string(),
+        0x6fff_fffe => "VERNEED".to_string(),
+        0x6fff_ffff => "VERSYM".to_string(),
+        _ => format!("{kind:#x}"),
+    }
+}
+
+/// the flags of a section, with the letters used by readelf
+fn section_flags(flags: u64) -> String {
+    [
+        (0x1, 'W'),
+        (0x2, 'A'),
+        (0x4, 'X'),
+        (0x10, 'M'),
+        (0x20, 'S'),
+        (0x40, 'I'),
+        (0x200, 'G'),
+        (0x400, 'T'),
+    ]
+    .iter()
+    .filter(|&&(bit, _)| flags & bit != 0)
+    .map(|&(_, c)| c)
+    .collect()
+}
+
+#[cfg(test)]
+mod elf_tests {
+    use {
+        super::*,
+        crate::file_format::value,
+    };
+
+    /// build a minimal 64 bits little endian executable with a null
+    /// section, a .text section and the section names
+    fn tiny_elf() -> Vec<u8> {
+        let names = b"\0.text\0.shstrtab\0";
+        let mut data = vec![0u8; 64];
+        data[..8].copy_from_slice(b"\x7fELF\x02\x01\x01\x03");
+        data[16..18].copy_from_slice(&2u16.to_le_bytes()); // executable
+        data[18..20].copy_from_slice(&62u16.to_le_bytes()); // x86-64
+        data[24..32].copy_from_slice(&0x401000u64.to_le_bytes()); // entry
+        let names_offset = data.len() as u64;
+        data.extend_from_slice(names);
+        let shoff = data.len() as u64;
+        data[40..48].copy_from_slice(&shoff.to_le_bytes());
+        data[58..60].copy_from_slice(&64u16.to_le_bytes()); // shentsize
+        data[60..62].copy_from_slice(&3u16.to_le_bytes()); // shnum
+        data[62..64].copy_from_slice(&2u16.to_le_bytes()); // shstrndx
+        let mut section = |name: u32, kind: u32, flags: u64, offset: u64, size: u64| {
+            let mut h = vec![0u8; 64];
+            h[0..4].copy_from_slice(&name.to_le_bytes());
+            h[4..8].copy_from_slice(&kind.to_le_bytes());
+            h[8..16].copy_from_slice(&flags.to_le_bytes());
+            h[24..32].copy_from_slice(&offset.to_le_bytes());
+            h[32..40].copy_from_slice(&size.to_le_bytes());
+            data.extend_from_slice(&h);
+        };
+        section(0, 0, 0, 0, 0);
+        section(1, 1, 0x6, 0x1000, 42);
+        section(7, 3, 0, names_offset, names.len() as u64);
+        data
+    }
+
+    #[test]
+    fn test_elf_sections() {
+        let sections = sections(&tiny_elf()).unwrap();
+        assert_eq!(value(&sections, "ELF", "class"), Some("64 bits"));
+        assert_eq!(value(&sections, "ELF", "OS ABI"), Some("Linux"));
+        assert_eq!(value(&sections, "ELF", "type"), Some("executable"));
+        assert_eq!(value(&sections, "ELF", "machine"), Some("x86-64"));
+        assert_eq!(value(&sections, "ELF", "entry point"), Some("0x401000"));
+        assert_eq!(
+            value(&sections, "Sections", ".text"),
+            Some("PROGBITS       AX  0x0  42 bytes")
+        );
+        assert!(value(&sections, "Sections", ".shstrtab").is_some());
+        // a truncated file isn't described
+        assert!(super::sections(&tiny_elf()[..40]).is_none());
+    }
+
+    #[test]
+    fn test_malformed_elf() {
+        let data = tiny_elf();
+        for len in 0..data.len() {
+            super::sections(&data[..len]);
+        }
+        // section headers starting at the end of the address space
+        let mut data = tiny_elf();
+        data[40..48].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(super::sections(&data).is_none());
+        // section headers too big to fit in the file
+        let mut data = tiny_elf();
+        data[58..60].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert!(super::sections(&data).is_none());
+    }
+}
@@ -0,0 +1,177 @@
+//! Readable summaries of some common binary formats (executables,
+//! databases, documents, audio and video files), displayed in the
+//! preview instead of a hex dump.
+
+mod audio;
+mod elf;
+mod pdf;
+mod sqlite;
+mod video;
+
+use {
+    crate::{
+        content_type::mime,
+        file_info::InfoSection,
+    },
+    memmap2::Mmap,
+    std::{
+        fs::File,
+        path::Path,
+    },
+};
+
+type Parser = fn(&[u8]) -> Option<Vec<InfoSection>>;
+
+/// Return the parser of the format with the given mime type, if it's
+/// one of the supported formats
+fn parser(mime: &str) -> Option<Parser> {
+    let parser: Parser = match mime {
+        "application/x-elf" => elf::sections,
+        "application/vnd.sqlite3" => sqlite::sections,
+        "application/pdf" => pdf::sections,
+        "audio/wav" => audio::wav_sections,
+        "audio/flac" => audio::flac_sections,
+        "audio/mpeg" => audio::mp3_sections,
+        "audio/ogg" => audio::ogg_sections,
+        "video/mp4" | "video/quicktime" | "audio/mp4" => video::mp4_sections,
+        "video/x-matroska" => video::matroska_sections,
+        _ => return None,
+    };
+    Some(parser)
+}
+
+/// Tell whether the file starts with the signature of one of the
+/// supported formats. Only the first bytes of the file are read.
+pub fn is_supported(path: &Path) -> bool {
+    mime::mime_type(path).is_ok_and(|mime| parser(mime).is_some())
+}
+
+/// Describe the content of a file, if its format is one of the
+/// supported ones and it can be parsed.
+///
+/// The file is mapped, not read: the parsers only access the parts they
+/// need (headers, indexes, trailer) and those which must scan the content,
+/// like the PDF one, don't go further than their limits.
+pub fn read_format_sections(path: &Path) -> Option<Vec<InfoSection>> {
+    let file = File::open(path).ok()?;
+    let mmap = unsafe { Mmap::map(&file).ok()? };
+    format_sections(&mmap)
+}
+
+/// Describe the content of a file given as bytes, if its format is one
+/// of the supported ones and it can be parsed
+pub fn format_sections(data: &[u8]) -> Option<Vec<InfoSection>> {
+    let sections = parser(mime::mime_type_of_head(data)?)?(data)?;
+    (!sections.is_empty()).then_some(sections)
+}
+
+fn u16_be(
+    data: &[u8],
+    at: usize,
+) -> Option<u16> {
+    data.get(at..)?.first_chunk().map(|&b| u16::from_be_bytes(b))
+}
+fn u32_be(
+    data: &[u8],
+    at: usize,
+) -> Option<u32> {
+    data.get(at..)?.first_chunk().map(|&b| u32::from_be_bytes(b))
+}
+fn u64_be(
+    data: &[u8],
+    at: usize,
+) -> Option<u64> {
+    data.get(at..)?.first_chunk().map(|&b| u64::from_be_bytes(b))
+}
+fn u16_le(
+    data: &[u8],
+    at: usize,
+) -> Option<u16> {
+    data.get(at..)?.first_chunk().map(|&b| u16::from_le_bytes(b))
+}
+fn u32_le(
+    data: &[u8],
+    at: usize,
+) -> Option<u32> {
+    data.get(at..)?.first_chunk().map(|&b| u32::from_le_bytes(b))
+}
+fn u64_le(
+    data: &[u8],
+    at: usize,
+) -> Option<u64> {
+    data.get(at..)?.first_chunk().map(|&b| u64::from_le_bytes(b))
+}
+
+/// format a duration like `1:02:03` or `3:05.2`
+fn format_duration(seconds: f64) -> String {
+    let tenths = (seconds * 10.0).round() as u64;
+    let (h, m, s, t) = (
+        tenths / 36_000,
+        tenths / 600 % 60,
+        tenths / 10 % 60,
+        tenths % 10,
+    );
+    if h > 0 {
+        format!("{h}:{m:02}:{s:02}")
+    } else {
+        format!("{m}:{s:02}.{t}")
+    }
+}
+
+/// format a sample rate, eg `44.1 kHz`
+fn format_sample_rate(rate: u32) -> String {
+    let khz = format!("{:.3}", f64::from(rate) / 1000.0);
+    format!("{} kHz", khz.trim_end_matches('0').trim_end_matches('.'))
+}
+
+/// return the value of an entry of the sections built by a parser
+#[cfg(test)]
+fn value<'s>(
+    sections: &'s [InfoSection],
+    title: &str,
+    name: &str,
+) -> Option<&'s str> {
+    sections
+        .iter()
+        .find(|s| s.title == title)?
+        .entries
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.as_str())
+}
+
+#[cfg(test)]
+mod file_format_tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0.0), "0:00.0");
+        assert_eq!(format_duration(185.24), "3:05.2");
+        assert_eq!(format_duration(3723.0), "1:02:03");
+        assert_eq!(format_sample_rate(44_100), "44.1 kHz");
+        assert_eq!(format_sample_rate(48_000), "48 kHz");
+        assert_eq!(format_sample_rate(11_025), "11.025 kHz");
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        assert!(format_sections(b"just some text").is_none());
+        assert!(format_sections(b"").is_none());
+    }
+
+    #[test]
+    fn test_is_supported() {
+        let dir = tempfile::tempdir().unwrap();
+        let pdf = dir.path().join("doc.pdf");
+        std::fs::write(&pdf, b"%PDF-1.7\n").unwrap();
+        assert!(is_supported(&pdf));
+        // known, but not described
+        let png = dir.path().join("img.png");
+        std::fs::write(&png, b"\x89PNG\r\n\x1a\n").unwrap();
+        assert!(!is_supported(&png));
+        let random = dir.path().join("random.bin");
+        std::fs::write(&random, [0xfe, 0x00, 0x12, 0xab]).unwrap();
+        assert!(!is_supported(&random));
+    }
+}
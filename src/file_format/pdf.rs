@@ -0,0 +1,565 @@
+//! Version, page count, document information and (when it's
+//! simply encoded) text of PDF documents
+
+use {
+    crate::file_info::InfoSection,
+    flate2::read::ZlibDecoder,
+    lazy_regex::bytes_regex,
+    memchr::memmem,
+    std::{
+        borrow::Cow,
+        io::Read,
+    },
+};
+
+/// the decoded size of a stream is limited to this
+const MAX_STREAM_LEN: u64 = 4_000_000;
+
+/// the decoded size of all streams is limited to this
+const MAX_DECODED_LEN: u64 = 32_000_000;
+
+/// the number of read streams is limited to this
+const MAX_STREAMS: usize = 2_000;
+
+/// only the start of bigger files is scanned for streams, and their
+/// pages aren't counted
+const MAX_SCANNED_LEN: usize = 64_000_000;
+
+/// in files not completely scanned, the references to the document
+/// information and encryption dictionaries are looked for in this
+/// many bytes at the end, where's the trailer
+const TRAILER_LEN: usize = 1_000_000;
+
+/// the extracted text is cropped after this number of lines
+const MAX_TEXT_LINES: usize = 500;
+
+/// lines of text are cropped after this number of chars
+const MAX_LINE_LEN: usize = 300;
+
+const INFO_KEYS: &[(&str, &str)] = &[
+    ("Title", "title"),
+    ("Author", "author"),
+    ("Subject", "subject"),
+    ("Keywords", "keywords"),
+    ("Creator", "creator"),
+    ("Producer", "producer"),
+    ("CreationDate", "created"),
+    ("ModDate", "modified"),
+];
+
+/// A stream of the document, with its dictionary and its raw content
+struct Stream<'d> {
+    dict: &'d [u8],
+    raw: &'d [u8],
+}
+
+impl<'d> Stream<'d> {
+    /// return the content, decoded when it's deflated (other filters
+    /// aren't supported), the decoded size being taken from the budget
+    fn content(
+        &self,
+        budget: &mut u64,
+    ) -> Option<Cow<'d, [u8]>> {
+        if memmem::find(self.dict, b"/Filter").is_none() {
+            return Some(Cow::Borrowed(self.raw));
+        }
+        if memmem::find(self.dict, b"/FlateDecode").is_none() || *budget == 0 {
+            return None;
+        }
+        let mut content = Vec::new();
+        let decoder = ZlibDecoder::new(self.raw);
+        // a stream may be truncated or padded: what's read is kept
+        let _ = decoder
+            .take(MAX_STREAM_LEN.min(*budget))
+            .read_to_end(&mut content);
+        *budget -= content.len() as u64;
+        Some(Cow::Owned(content))
+    }
+}
+
+pub fn sections(data: &[u8]) -> Option<Vec<InfoSection>> {
+    let mut sections = Vec::new();
+    let mut document = InfoSection::new("PDF document");
+    let version = data.get(5..)?.split(|b| b.is_ascii_whitespace()).next()?;
+    document.add("version", String::from_utf8_lossy(version));
+    let scanned = &data[..data.len().min(MAX_SCANNED_LEN)];
+    let complete = scanned.len() == data.len();
+    let trailer = if complete {
+        data
+    } else {
+        &data[data.len() - TRAILER_LEN..]
+    };
+    let mut budget = MAX_DECODED_LEN;
+    if complete {
+        // object streams hold objects, among them pages (the uncompressed
+        // ones were already counted)
+        let page_count = page_count(data)
+            + streams(data)
+                .filter(|s| bytes_regex!(r"/Type\s*/ObjStm").is_match(s.dict))
+                .filter(|s| memmem::find(s.dict, b"/Filter").is_some())
+                .filter_map(|s| s.content(&mut budget))
+                .map(|content| page_count(&content))
+                .sum::<usize>();
+        if page_count > 0 {
+            document.add("pages", page_count.to_string());
+        }
+    }
+    if memmem::find(trailer, b"/Encrypt").is_some() {
+        document.add("encrypted", "yes");
+    }
+    if let Some(info) = info_dict(data, trailer) {
+        for (key, name) in INFO_KEYS {
+            if let Some(value) = info_value(info, key) {
+                if !value.is_empty() {
+                    document.add(*name, value);
+                }
+            }
+        }
+    }
+    document.push_to(&mut sections);
+
+    let mut text = InfoSection::new("Text");
+    let mut line_count = 0;
+    'streams: for stream in streams(scanned) {
+        if is_not_content(stream.dict) {
+            continue;
+        }
+        let Some(content) = stream.content(&mut budget) else {
+            continue;
+        };
+        for line in extract_text(&content).lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line_count == MAX_TEXT_LINES {
+                text.add_text("…");
+                break 'streams;
+            }
+            let mut line = line.to_string();
+            if line.chars().count() > MAX_LINE_LEN {
+                line = line.chars().take(MAX_LINE_LEN).collect();
+                line.push('…');
+            }
+            text.add_text(line);
+            line_count += 1;
+        }
+    }
+    text.push_to(&mut sections);
+    Some(sections)
+}
+
+/// count the page objects (and not the `/Pages` tree nodes)
+fn page_count(data: &[u8]) -> usize {
+    bytes_regex!(r"/Type\s*/Page\b").find_iter(data).count()
+}
+
+/// tell whether a stream is known not to be a page content stream
+fn is_not_content(dict: &[u8]) -> bool {
+    bytes_regex!(r"/Type\s*/(ObjStm|XRef|Metadata|EmbeddedFile)|/Subtype|/Length1|/FunctionType")
+        .is_match(dict)
+}
+
+/// iterate over the streams of the document, without decoding them
+fn streams(data: &[u8]) -> impl Iterator<Item = Stream<'_>> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        while let Some(idx) = memmem::find(&data[pos..], b"stream") {
+            let keyword = pos + idx;
+            pos = keyword + 6;
+            // "endstream" also contains "stream"
+            if data[..keyword].ends_with(b"end") {
+                continue;
+            }
+            let start = match data.get(pos..pos + 2) {
+                Some(b"\r\n") => pos + 2,
+                Some([b'\n', _]) => pos + 1,
+                _ => continue,
+            };
+            let len = memmem::find(&data[start..], b"endstream")?;
+            let end = start + len;
+            pos = end + 9;
+            let dict_start = memmem::rfind(&data[..keyword], b"obj").unwrap_or(0);
+            return Some(Stream {
+                dict: &data[dict_start..keyword],
+                raw: &data[start..end],
+            });
+        }
+        None
+    })
+    .take(MAX_STREAMS)
+}
+
+/// find the document information dictionary, whose reference is
+/// looked for in the given trailer part of the document
+fn info_dict<'d>(
+    data: &'d [u8],
+    trailer: &[u8],
+) -> Option<&'d [u8]> {
+    let captures = bytes_regex!(r"/Info\s+(\d+)\s+(\d+)\s+R")
+        .captures_iter(trailer)
+        .last()?;
+    let mut header = captures[1].to_vec();
+    header.push(b' ');
+    header.extend_from_slice(&captures[2]);
+    header.extend_from_slice(b" obj");
+    // "5 0 obj" must not be found in "15 0 obj"
+    let mut end = data.len();
+    let start = loop {
+        let idx = memmem::rfind(&data[..end], &header)?;
+        if idx == 0 || !data[idx - 1].is_ascii_digit() {
+            break idx + header.len();
+        }
+        end = idx;
+    };
+    let len = memmem::find(&data[start..], b"endobj")?;
+    Some(&data[start..start + len])
+}
+
+/// read an entry of the document information dictionary
+fn info_value(
+    dict: &[u8],
+    key: &str,
+) -> Option<String> {
+    let mut key_pattern = b"/".to_vec();
+    key_pattern.extend_from_slice(key.as_bytes());
+    let mut at = 0;
+    // the key must not be the prefix of another one
+    let value_start = loop {
+        let idx = at + memmem::find(&dict[at..], &key_pattern)? + key_pattern.len();
+        if !dict.get(idx)?.is_ascii_alphanumeric() {
+            break idx;
+        }
+        at = idx;
+    };
+    let value = dict[value_start..].trim_ascii_start();
+    let bytes = match value.first()? {
+        b'(' => parse_literal_string(value).0,
+        b'<' => parse_hex_string(value).0,
+        _ => return None,
+    };
+    let value = decode_text_string(&bytes);
+    Some(if key.ends_with("Date") {
+        format_date(&value)
+    } else {
+        value
+    })
+}
+
+/// decode a "text string", either UTF-16 with a BOM or PDFDocEncoding
+/// (approximated as latin-1)
+fn decode_text_string(bytes: &[u8]) -> String {
+    if let Some(utf16) = bytes.strip_prefix(b"\xfe\xff") {
+        let units: Vec<u16> = utf16
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else if let Ok(s) = std::str::from_utf8(bytes) {
+        s.to_string()
+    } else {
+        bytes.iter().map(|&b| b as char).collect()
+    }
+}
+
+/// format a date like `D:20230415103000+02'00'` as `2023-04-15 10:30:00 +02:00`
+fn format_date(date: &str) -> String {
+    let Some(digits) = date.strip_prefix("D:") else {
+        return date.to_string();
+    };
+    let part = |range: std::ops::Range<usize>| digits.get(range).filter(|s| !s.is_empty());
+    let (Some(y), Some(mo), Some(d)) = (part(0..4), part(4..6), part(6..8)) else {
+        return date.to_string();
+    };
+    let mut formatted = format!("{y}-{mo}-{d}");
+    if let (Some(h), Some(mi), Some(s)) = (part(8..10), part(10..12), part(12..14)) {
+        formatted.push_str(&format!(" {h}:{mi}:{s}"));
+        match digits.get(14..) {
+            Some("Z" | "Z00'00'" | "Z00'00") => formatted.push_str(" UTC"),
+            Some(tz) => {
+                // like +02'00
+                if let (Some(h), Some(m)) = (tz.get(..3), tz.get(4..6)) {
+                    formatted.push_str(&format!(" {h}:{m}"));
+                }
+            }
+            None => {}
+        }
+    }
+    formatted
+}
+
+/// parse a literal string starting at `(`, return its bytes and the
+/// length of the literal
+fn parse_literal_string(s: &[u8]) -> (Vec<u8>, usize) {
+    let mut bytes = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < s.len() {
+        let c = s[i];
+        i += 1;
+        match c {
+            b'(' => {
+                depth += 1;
+                if depth == 1 {
+                    continue;
+                }
+            }
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            b'\\' => {
+                let Some(&escaped) = s.get(i) else {
+                    break;
+                };
+                i += 1;
+                match escaped {
+                    b'n' => bytes.push(b'\n'),
+                    b'r' => bytes.push(b'\r'),
+                    b't' => bytes.push(b'\t'),
+                    b'b' => bytes.push(8),
+                    b'f' => bytes.push(12),
+                    b'0'..=b'7' => {
+                        let mut value = u32::from(escaped - b'0');
+                        for _ in 0..2 {
+                            match s.get(i) {
+                                Some(&d @ b'0'..=b'7') => {
+                                    value = value * 8 + u32::from(d - b'0');
+                                    i += 1;
+                                }
+                                _ => break,
+                            }
+                        }
+                        bytes.push(value as u8);
+                    }
+                    b'\r' => {
+                        // line continuation
+                        if s.get(i) == Some(&b'\n') {
+                            i += 1;
+                        }
+                    }
+                    b'\n' => {}
+                    _ => bytes.push(escaped),
+                }
+                continue;
+            }
+            _ => {}
+        }
+        bytes.push(c);
+    }
+    (bytes, i)
+}
+
+/// parse a hex string starting at `<`, return its bytes and the
+/// length of the literal
+fn parse_hex_string(s: &[u8]) -> (Vec<u8>, usize) {
+    let end = s.iter().position(|&b| b == b'>').map_or(s.len(), |p| p + 1);
+    let digits: Vec<u8> = s[..end]
+        .iter()
+        .filter_map(|&b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect();
+    let bytes = digits
+        .chunks(2)
+        .map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0))
+        .collect();
+    (bytes, end)
+}
+
+/// tell whether the bytes of a shown string look like text, rather
+/// than like glyph ids of a font with a custom encoding
+fn looks_like_text(bytes: &[u8]) -> bool {
+    let printable = bytes
+        .iter()
+        .filter(|&&b| b >= 0x20 && b != 0x7f || b == b'\t')
+        .count();
+    printable * 10 >= bytes.len() * 9
+}
+
+/// extract the text shown by the operators of a content stream
+fn extract_text(content: &[u8]) -> String {
+    let mut text = Vec::new();
+    // the strings and numbers given to the next operator
+    let mut operands: Vec<Result<Vec<u8>, f64>> = Vec::new();
+    let mut i = 0;
+    while i < content.len() {
+        let c = content[i];
+        match c {
+            b'(' => {
+                let (bytes, len) = parse_literal_string(&content[i..]);
+                operands.push(Ok(bytes));
+                i += len;
+            }
+            b'<' if content.get(i + 1) == Some(&b'<') => {
+                i += 2;
+            }
+            b'<' => {
+                let (bytes, len) = parse_hex_string(&content[i..]);
+                operands.push(Ok(bytes));
+                i += len;
+            }
+            b'%' => {
+                i += content[i..]
+                    .iter()
+                    .position(|&b| b == b'\n' || b == b'\r')
+                    .unwrap_or(content.len() - i);
+            }
+            b'/' | b'[' | b']' | b'>' | b'{' | b'}' => {
+                i += 1;
+                if c == b'/' {
+                    // a name
+                    while i < content.len() && is_regular(content[i]) {
+                        i += 1;
+                    }
+                }
+            }
+            _ if c.is_ascii_whitespace() => {
+                i += 1;
+            }
+            _ => {
+                let start = i;
+                while i < content.len() && is_regular(content[i]) {
+                    i += 1;
+                }
+                if i == start {
+                    i += 1; // some unexpected char
+                    continue;
+                }
+                let token = &content[start..i];
+                if let Some(n) = std::str::from_utf8(token).ok().and_then(|t| t.parse().ok()) {
+                    operands.push(Err(n));
+                    continue;
+                }
+                match token {
+                    b"Tj" | b"TJ" | b"'" | b"\"" => {
+                        if token == b"'" || token == b"\"" {
+                            text.push(b'\n');
+                        }
+                        for operand in &operands {
+                            match operand {
+                                Ok(bytes) if looks_like_text(bytes) => {
+                                    text.extend_from_slice(bytes);
+                                }
+                                // a big negative kerning in a TJ array is a space
+                                Err(n) if *n < -200.0 => text.push(b' '),
+                                _ => {}
+                            }
+                        }
+                    }
+                    b"Td" | b"TD" => {
+                        let dy = match operands.last() {
+                            Some(Err(dy)) => *dy,
+                            _ => 1.0,
+                        };
+                        text.push(if dy == 0.0 { b' ' } else { b'\n' });
+                    }
+                    b"T*" | b"Tm" | b"ET" => text.push(b'\n'),
+                    b"BI" => {
+                        // an inline image, whose data must be skipped
+                        match memmem::find(&content[i..], b"EI") {
+                            Some(len) => i += len + 2,
+                            None => break,
+                        }
+                    }
+                    _ => {}
+                }
+                operands.clear();
+            }
+        }
+    }
+    decode_text_string(&text)
+}
+
+fn is_regular(b: u8) -> bool {
+    !b.is_ascii_whitespace() && !b"()<>[]{}/%".contains(&b)
+}
+
+#[cfg(test)]
+mod pdf_tests {
+    use {
+        super::*,
+        crate::file_format::value,
+        flate2::{
+            Compression,
+            write::ZlibEncoder,
+        },
+        std::io::Write,
+    };
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn tiny_pdf() -> Vec<u8> {
+        let mut pdf = b"%PDF-1.7\n".to_vec();
+        pdf.extend_from_slice(b"1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n");
+        pdf.extend_from_slice(b"2 0 obj\n<< /Type /Pages /Kids [3 0 R] /Count 1 >>\nendobj\n");
+        pdf.extend_from_slice(b"3 0 obj\n<< /Type /Page /Contents 4 0 R >>\nendobj\n");
+        let content = deflate(
+            b"BT /F1 12 Tf 72 700 Td (Hello \\(PDF\\)) Tj 0 -14 Td \
+            [(Wor) 20 (ld) -300 <21>] TJ ET",
+        );
+        pdf.extend_from_slice(b"4 0 obj\n<< /Filter /FlateDecode >>\nstream\n");
+        pdf.extend_from_slice(&content);
+        pdf.extend_from_slice(b"\nendstream\nendobj\n");
+        pdf.extend_from_slice(
+            b"5 0 obj\n<< /Title (Greetings) /Author <FEFF00C9006D0069006C0065> \
+            /CreationDate (D:20230415103000+02'00') >>\nendobj\n",
+        );
+        pdf.extend_from_slice(b"trailer\n<< /Root 1 0 R /Info 5 0 R >>\n%%EOF\n");
+        pdf
+    }
+
+    #[test]
+    fn test_pdf_sections() {
+        let sections = sections(&tiny_pdf()).unwrap();
+        assert_eq!(value(&sections, "PDF document", "version"), Some("1.7"));
+        assert_eq!(value(&sections, "PDF document", "pages"), Some("1"));
+        assert_eq!(value(&sections, "PDF document", "title"), Some("Greetings"));
+        assert_eq!(value(&sections, "PDF document", "author"), Some("Émile"));
+        assert_eq!(
+            value(&sections, "PDF document", "created"),
+            Some("2023-04-15 10:30:00 +02:00")
+        );
+        let text = &sections.iter().find(|s| s.title == "Text").unwrap().entries;
+        let text: Vec<&str> = text.iter().map(|(_, line)| line.as_str()).collect();
+        assert_eq!(text, ["Hello (PDF)", "World !"]);
+    }
+
+    #[test]
+    fn test_text_is_cropped() {
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        for i in 0..MAX_TEXT_LINES + 100 {
+            let content = deflate(format!("BT (line {i}) Tj ET").as_bytes());
+            let header = format!("{i} 0 obj\n<< /Filter /FlateDecode >>\nstream\n");
+            pdf.extend_from_slice(header.as_bytes());
+            pdf.extend_from_slice(&content);
+            pdf.extend_from_slice(b"\nendstream\nendobj\n");
+        }
+        let sections = sections(&pdf).unwrap();
+        let text = &sections.iter().find(|s| s.title == "Text").unwrap().entries;
+        assert_eq!(text.len(), MAX_TEXT_LINES + 1);
+        assert_eq!(text[0].1, "line 0");
+        assert_eq!(text[MAX_TEXT_LINES].1, "…");
+    }
+
+    #[test]
+    fn test_parse_strings() {
+        assert_eq!(parse_literal_string(b"(a(b)c\\051\\n) Tj").0, b"a(b)c)\n");
+        assert_eq!(parse_hex_string(b"<48 65 6C6C 6F> Tj"), (b"Hello".to_vec(), 15));
+        assert_eq!(parse_hex_string(b"<7>").0, [0x70]);
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date("D:20230415103000Z"), "2023-04-15 10:30:00 UTC");
+        assert_eq!(format_date("D:20230415103000-05'00'"), "2023-04-15 10:30:00 -05:00");
+        assert_eq!(format_date("D:20230415"), "2023-04-15");
+        assert_eq!(format_date("D:2023"), "D:2023");
+        // the time zone isn't cut in the middle of a char
+        assert_eq!(format_date("D:20230415103000+0é00'"), "2023-04-15 10:30:00");
+    }
+}
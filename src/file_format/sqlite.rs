@@ -0,0 +1,629 @@
+//! Tables, indexes and schema of SQLite databases, read directly
+//! from the b-trees of the file
+
+use {
+    super::{
+        u16_be,
+        u32_be,
+    },
+    crate::file_info::InfoSection,
+};
+
+/// size of the database header, at the start of the first page
+const HEADER_LEN: usize = 100;
+
+/// the maximal number of pages read to count the rows of all tables
+const MAX_COUNTED_PAGES: usize = 20_000;
+
+/// the maximal number of pages read for a single payload
+const MAX_OVERFLOW_PAGES: usize = 1_000;
+
+const INDEX_INTERIOR: u8 = 2;
+const TABLE_INTERIOR: u8 = 5;
+const INDEX_LEAF: u8 = 10;
+const TABLE_LEAF: u8 = 13;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(usize),
+}
+
+impl Value {
+    fn as_str(&self) -> &str {
+        match self {
+            Self::Text(s) => s,
+            _ => "",
+        }
+    }
+    fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TextEncoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// An entry of the `sqlite_schema` table
+#[derive(Debug)]
+struct SchemaEntry {
+    kind: String,
+    name: String,
+    table_name: String,
+    root_page: Option<usize>,
+    sql: String,
+}
+
+struct Database<'d> {
+    data: &'d [u8],
+    page_size: usize,
+    usable_size: usize,
+    encoding: TextEncoding,
+}
+
+impl<'d> Database<'d> {
+    fn new(data: &'d [u8]) -> Option<Self> {
+        let page_size = match u16_be(data, 16)? {
+            1 => 65_536,
+            n if n >= 512 && n.is_power_of_two() => n as usize,
+            _ => return None,
+        };
+        let reserved = *data.get(20)? as usize;
+        let encoding = match u32_be(data, 56)? {
+            2 => TextEncoding::Utf16Le,
+            3 => TextEncoding::Utf16Be,
+            _ => TextEncoding::Utf8,
+        };
+        Some(Self {
+            data,
+            page_size,
+            usable_size: page_size.checked_sub(reserved)?,
+            encoding,
+        })
+    }
+    /// return the bytes of a page, whose numbers start at 1
+    fn page(
+        &self,
+        number: usize,
+    ) -> Option<&'d [u8]> {
+        let start = number.checked_sub(1)?.checked_mul(self.page_size)?;
+        self.data.get(start..start.checked_add(self.page_size)?)
+    }
+    /// return the page, the offset of its b-tree header (which is after
+    /// the database header on the first page), and the page type
+    fn btree_page(
+        &self,
+        number: usize,
+    ) -> Option<(&'d [u8], usize, u8)> {
+        let page = self.page(number)?;
+        let header_start = if number == 1 { HEADER_LEN } else { 0 };
+        let kind = *page.get(header_start)?;
+        match kind {
+            INDEX_INTERIOR | TABLE_INTERIOR | INDEX_LEAF | TABLE_LEAF => {
+                Some((page, header_start, kind))
+            }
+            _ => None,
+        }
+    }
+    /// return the offsets of the cells of a b-tree page
+    fn cell_offsets(
+        page: &[u8],
+        header_start: usize,
+        kind: u8,
+    ) -> Option<Vec<usize>> {
+        let count = u16_be(page, header_start + 3)? as usize;
+        let header_len = if kind == TABLE_LEAF || kind == INDEX_LEAF {
+            8
+        } else {
+            12
+        };
+        (0..count)
+            .map(|i| u16_be(page, header_start + header_len + 2 * i).map(usize::from))
+            .collect()
+    }
+    /// return the numbers of the child pages of an interior page
+    fn children(
+        page: &[u8],
+        header_start: usize,
+        kind: u8,
+    ) -> Option<Vec<usize>> {
+        let mut children = Vec::new();
+        for offset in Self::cell_offsets(page, header_start, kind)? {
+            children.push(u32_be(page, offset)? as usize);
+        }
+        children.push(u32_be(page, header_start + 8)? as usize);
+        Some(children)
+    }
+    /// read the whole payload of a cell, following the overflow pages
+    fn payload(
+        &self,
+        page: &'d [u8],
+        start: usize,
+        len: usize,
+        is_table: bool,
+    ) -> Option<Vec<u8>> {
+        let u = self.usable_size;
+        let max_local = if is_table {
+            u.checked_sub(35)?
+        } else {
+            ((u.checked_sub(12)?) * 64 / 255).checked_sub(23)?
+        };
+        let min_local = ((u.checked_sub(12)?) * 32 / 255).checked_sub(23)?;
+        if len <= max_local {
+            return page.get(start..start.checked_add(len)?).map(<[u8]>::to_vec);
+        }
+        let k = min_local + (len - min_local) % (u - 4);
+        let local = if k <= max_local { k } else { min_local };
+        let local_end = start.checked_add(local)?;
+        let mut payload = page.get(start..local_end)?.to_vec();
+        let mut next = u32_be(page, local_end)? as usize;
+        for _ in 0..MAX_OVERFLOW_PAGES {
+            if payload.len() >= len || next == 0 {
+                break;
+            }
+            let overflow = self.page(next)?;
+            let wanted = (len - payload.len()).min(u - 4);
+            payload.extend_from_slice(overflow.get(4..4 + wanted)?);
+            next = u32_be(overflow, 0)? as usize;
+        }
+        Some(payload)
+    }
+    /// read all the records of a table b-tree
+    fn table_records(
+        &self,
+        root: usize,
+    ) -> Option<Vec<Vec<Value>>> {
+        let mut records = Vec::new();
+        let mut stack = vec![root];
+        let mut budget = MAX_COUNTED_PAGES;
+        while let Some(number) = stack.pop() {
+            budget = budget.checked_sub(1)?;
+            let (page, header_start, kind) = self.btree_page(number)?;
+            match kind {
+                TABLE_INTERIOR => {
+                    let mut children = Self::children(page, header_start, kind)?;
+                    children.reverse();
+                    stack.extend(children);
+                }
+                TABLE_LEAF => {
+                    for offset in Self::cell_offsets(page, header_start, kind)? {
+                        let (len, n) = varint(page.get(offset..)?)?;
+                        let (_rowid, m) = varint(page.get(offset.checked_add(n)?..)?)?;
+                        let start = offset.checked_add(n + m)?;
+                        let len = usize::try_from(len).ok()?;
+                        let payload = self.payload(page, start, len, true)?;
+                        records.push(self.record(&payload)?);
+                    }
+                }
+                _ => return None,
+            }
+        }
+        Some(records)
+    }
+    /// count the entries of a table or index b-tree, reading at most
+    /// `budget` pages. Return `None` when the budget isn't enough.
+    fn count_entries(
+        &self,
+        root: usize,
+        budget: &mut usize,
+    ) -> Option<usize> {
+        let mut count = 0;
+        let mut stack = vec![root];
+        while let Some(number) = stack.pop() {
+            *budget = budget.checked_sub(1)?;
+            let (page, header_start, kind) = self.btree_page(number)?;
+            let cells = u16_be(page, header_start + 3)? as usize;
+            match kind {
+                TABLE_LEAF | INDEX_LEAF => {
+                    count += cells;
+                }
+                TABLE_INTERIOR | INDEX_INTERIOR => {
+                    // unlike the ones of tables, the interior cells
+                    // of indexes hold entries
+                    if kind == INDEX_INTERIOR {
+                        count += cells;
+                    }
+                    stack.extend(Self::children(page, header_start, kind)?);
+                }
+                _ => return None,
+            }
+        }
+        Some(count)
+    }
+    /// decode a record
+    fn record(
+        &self,
+        payload: &[u8],
+    ) -> Option<Vec<Value>> {
+        let (header_len, mut pos) = varint(payload)?;
+        let header_len = header_len as usize;
+        let mut serial_types = Vec::new();
+        while pos < header_len {
+            let (serial_type, n) = varint(payload.get(pos..)?)?;
+            serial_types.push(serial_type);
+            pos += n;
+        }
+        let mut pos = header_len;
+        let mut values = Vec::with_capacity(serial_types.len());
+        for serial_type in serial_types {
+            let int = |len: usize| -> Option<i64> {
+                let bytes = payload.get(pos..pos.checked_add(len)?)?;
+                // sign extension from the first byte
+                let mut value: i64 = if bytes.first()? & 0x80 != 0 { -1 } else { 0 };
+                for &b in bytes {
+                    value = (value << 8) | i64::from(b);
+                }
+                Some(value)
+            };
+            let (value, len) = match serial_type {
+                0 => (Value::Null, 0),
+                1 => (Value::Integer(int(1)?), 1),
+                2 => (Value::Integer(int(2)?), 2),
+                3 => (Value::Integer(int(3)?), 3),
+                4 => (Value::Integer(int(4)?), 4),
+                5 => (Value::Integer(int(6)?), 6),
+                6 => (Value::Integer(int(8)?), 8),
+                7 => (Value::Real(f64::from_bits(int(8)? as u64)), 8),
+                8 => (Value::Integer(0), 0),
+                9 => (Value::Integer(1), 0),
+                n if n >= 12 && n % 2 == 0 => {
+                    let len = (n as usize - 12) / 2;
+                    (Value::Blob(len), len)
+                }
+                n if n >= 13 => {
+                    let len = (n as usize - 13) / 2;
+                    let bytes = payload.get(pos..pos.checked_add(len)?)?;
+                    (Value::Text(self.decode_text(bytes)), len)
+                }
+                _ => return None,
+            };
+            values.push(value);
+            pos = pos.checked_add(len)?;
+        }
+        Some(values)
+    }
+    fn decode_text(
+        &self,
+        bytes: &[u8],
+    ) -> String {
+        let utf16 = |to_u16: fn([u8; 2]) -> u16| {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|c| to_u16([c[0], c[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        };
+        match self.encoding {
+            TextEncoding::Utf8 => String::from_utf8_lossy(bytes).to_string(),
+            TextEncoding::Utf16Le => utf16(u16::from_le_bytes),
+            TextEncoding::Utf16Be => utf16(u16::from_be_bytes),
+        }
+    }
+    fn schema(&self) -> Option<Vec<SchemaEntry>> {
+        let records = self.table_records(1)?;
+        Some(
+            records
+                .into_iter()
+                .filter(|r| r.len() >= 5)
+                .map(|r| SchemaEntry {
+                    kind: r[0].as_str().to_string(),
+                    name: r[1].as_str().to_string(),
+                    table_name: r[2].as_str().to_string(),
+                    root_page: r[3].as_int().and_then(|p| usize::try_from(p).ok()),
+                    sql: r[4].as_str().to_string(),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// read a SQLite varint, return it with the number of bytes it takes
+fn varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let b = *bytes.get(i)?;
+        if i == 8 {
+            return Some(((value << 8) | u64::from(b), 9));
+        }
+        value = (value << 7) | u64::from(b & 0x7f);
+        if b & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+fn plural(
+    n: usize,
+    word: &str,
+) -> String {
+    if n == 1 {
+        format!("1 {word}")
+    } else {
+        format!("{n} {word}s")
+    }
+}
+
+pub fn sections(data: &[u8]) -> Option<Vec<InfoSection>> {
+    let db = Database::new(data)?;
+    let mut sections = Vec::new();
+
+    let mut header = InfoSection::new("SQLite database");
+    header.add("page size", db.page_size.to_string());
+    let page_count = match u32_be(data, 28)? as usize {
+        0 => data.len() / db.page_size,
+        n => n,
+    };
+    header.add("pages", page_count.to_string());
+    header.add(
+        "text encoding",
+        match db.encoding {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Utf16Le => "UTF-16le",
+            TextEncoding::Utf16Be => "UTF-16be",
+        },
+    );
+    header.add(
+        "journal mode",
+        if *data.get(18)? == 2 { "WAL" } else { "rollback" },
+    );
+    let free_pages = u32_be(data, 36)?;
+    if free_pages > 0 {
+        header.add("free pages", free_pages.to_string());
+    }
+    let user_version = u32_be(data, 60)?;
+    if user_version != 0 {
+        header.add("user version", user_version.to_string());
+    }
+    let application_id = u32_be(data, 68)?;
+    if application_id != 0 {
+        header.add("application id", format!("{application_id:#x}"));
+    }
+    let version = u32_be(data, 96)?;
+    header.add(
+        "written by",
+        format!(
+            "SQLite {}.{}.{}",
+            version / 1_000_000,
+            version / 1000 % 1000,
+            version % 1000
+        ),
+    );
+    header.push_to(&mut sections);
+
+    let Some(schema) = db.schema() else {
+        let mut error = InfoSection::new("Schema");
+        error.add("error", "the schema can't be read");
+        error.push_to(&mut sections);
+        return Some(sections);
+    };
+    let mut tables = InfoSection::new("Tables");
+    let mut indexes = InfoSection::new("Indexes");
+    let mut views = InfoSection::new("Views");
+    let mut triggers = InfoSection::new("Triggers");
+    let mut budget = MAX_COUNTED_PAGES;
+    for entry in &schema {
+        match entry.kind.as_str() {
+            "table" => {
+                let is_virtual = entry.root_page.is_none_or(|p| p == 0);
+                let rows = if is_virtual {
+                    "virtual table".to_string()
+                } else {
+                    entry
+                        .root_page
+                        .and_then(|root| db.count_entries(root, &mut budget))
+                        .map_or_else(|| "many rows".to_string(), |n| plural(n, "row"))
+                };
+                tables.add(&entry.name, rows);
+            }
+            "index" => indexes.add(&entry.name, format!("on {}", entry.table_name)),
+            "view" => views.add_text(&entry.name),
+            "trigger" => triggers.add(&entry.name, format!("on {}", entry.table_name)),
+            _ => {}
+        }
+    }
+    tables.push_to(&mut sections);
+    indexes.push_to(&mut sections);
+    views.push_to(&mut sections);
+    triggers.push_to(&mut sections);
+
+    let mut sql = InfoSection::new("Schema");
+    for entry in schema.iter().filter(|e| !e.sql.is_empty()) {
+        for line in entry.sql.lines() {
+            sql.add_text(line);
+        }
+        if let Some((_, last)) = sql.entries.last_mut() {
+            last.push(';');
+        }
+    }
+    sql.push_to(&mut sections);
+    Some(sections)
+}
+
+#[cfg(test)]
+mod sqlite_tests {
+    use {
+        super::*,
+        crate::file_format::value,
+    };
+
+    const PAGE_SIZE: usize = 512;
+
+    fn encode_varint(value: u64) -> Vec<u8> {
+        // only the values needed by the tests
+        assert!(value < 1 << 14);
+        if value < 0x80 {
+            vec![value as u8]
+        } else {
+            vec![0x80 | (value >> 7) as u8, (value & 0x7f) as u8]
+        }
+    }
+
+    /// encode a record (blobs being made of zeros)
+    fn encode_record(values: &[Value]) -> Vec<u8> {
+        let mut types = Vec::new();
+        let mut body = Vec::new();
+        for value in values {
+            match value {
+                Value::Null => {
+                    types.extend(encode_varint(0));
+                }
+                Value::Integer(i) => match i8::try_from(*i) {
+                    Ok(small) => {
+                        types.extend(encode_varint(1));
+                        body.extend_from_slice(&small.to_be_bytes());
+                    }
+                    Err(_) => {
+                        types.extend(encode_varint(6));
+                        body.extend_from_slice(&i.to_be_bytes());
+                    }
+                },
+                Value::Real(r) => {
+                    types.extend(encode_varint(7));
+                    body.extend_from_slice(&r.to_bits().to_be_bytes());
+                }
+                Value::Text(s) => {
+                    types.extend(encode_varint(13 + 2 * s.len() as u64));
+                    body.extend_from_slice(s.as_bytes());
+                }
+                Value::Blob(len) => {
+                    types.extend(encode_varint(12 + 2 * *len as u64));
+                    body.resize(body.len() + len, 0);
+                }
+            }
+        }
+        let mut record = encode_varint(types.len() as u64 + 1);
+        record.extend(types);
+        record.extend(body);
+        record
+    }
+
+    /// write a table leaf page with the given records
+    fn write_leaf(
+        page: &mut [u8],
+        header_start: usize,
+        records: &[Vec<u8>],
+    ) {
+        page[header_start] = TABLE_LEAF;
+        page[header_start + 3..header_start + 5]
+            .copy_from_slice(&(records.len() as u16).to_be_bytes());
+        let mut content_start = page.len();
+        for (i, record) in records.iter().enumerate() {
+            let mut cell = encode_varint(record.len() as u64);
+            cell.extend(encode_varint(i as u64 + 1)); // rowid
+            cell.extend(record);
+            content_start -= cell.len();
+            page[content_start..content_start + cell.len()].copy_from_slice(&cell);
+            let pointer = header_start + 8 + 2 * i;
+            page[pointer..pointer + 2].copy_from_slice(&(content_start as u16).to_be_bytes());
+        }
+    }
+
+    /// build a database with a table of 3 rows and an index
+    fn tiny_db() -> Vec<u8> {
+        let mut data = vec![0u8; 2 * PAGE_SIZE];
+        data[..16].copy_from_slice(b"SQLite format 3\0");
+        data[16..18].copy_from_slice(&(PAGE_SIZE as u16).to_be_bytes());
+        data[18] = 1;
+        data[28..32].copy_from_slice(&2u32.to_be_bytes());
+        data[56..60].copy_from_slice(&1u32.to_be_bytes());
+        data[96..100].copy_from_slice(&3_045_001u32.to_be_bytes());
+        let text = |s: &str| Value::Text(s.to_string());
+        let schema = [
+            encode_record(&[
+                text("table"),
+                text("fruits"),
+                text("fruits"),
+                Value::Integer(2),
+                text("CREATE TABLE fruits(name TEXT)"),
+            ]),
+            encode_record(&[
+                text("view"),
+                text("red_fruits"),
+                text("red_fruits"),
+                Value::Integer(0),
+                text("CREATE VIEW red_fruits AS\nSELECT * FROM fruits"),
+            ]),
+        ];
+        write_leaf(&mut data[..PAGE_SIZE], HEADER_LEN, &schema);
+        let rows: Vec<_> = ["apple", "cherry", "kiwi"]
+            .iter()
+            .map(|name| encode_record(&[text(name)]))
+            .collect();
+        write_leaf(&mut data[PAGE_SIZE..], 0, &rows);
+        data
+    }
+
+    #[test]
+    fn test_varint() {
+        assert_eq!(varint(&[0x05]), Some((5, 1)));
+        assert_eq!(varint(&[0x81, 0x00]), Some((128, 2)));
+        assert_eq!(varint(&[0xff; 9]), Some((u64::MAX, 9)));
+        assert_eq!(varint(&[0x81]), None);
+    }
+
+    #[test]
+    fn test_record() {
+        let data = tiny_db();
+        let db = Database::new(&data).unwrap();
+        let values = vec![
+            Value::Null,
+            Value::Integer(-3),
+            Value::Integer(300_000),
+            Value::Real(1.5),
+            Value::Text("kiwi".to_string()),
+            Value::Blob(3),
+        ];
+        assert_eq!(db.record(&encode_record(&values)), Some(values));
+        // a text as long as the biggest varint
+        let mut record = vec![10];
+        record.extend([0xff; 9]);
+        assert_eq!(db.record(&record), None);
+    }
+
+    #[test]
+    fn test_malformed_db() {
+        let data = tiny_db();
+        for len in 0..data.len() {
+            sections(&data[..len]);
+        }
+        assert!(sections(&data[..HEADER_LEN - 1]).is_none());
+        // a cell pointing after the end of its page
+        let mut data = tiny_db();
+        data[PAGE_SIZE + 8..PAGE_SIZE + 10].copy_from_slice(&u16::MAX.to_be_bytes());
+        sections(&data);
+    }
+
+    #[test]
+    fn test_sqlite_sections() {
+        let sections = sections(&tiny_db()).unwrap();
+        assert_eq!(value(&sections, "SQLite database", "page size"), Some("512"));
+        assert_eq!(value(&sections, "SQLite database", "pages"), Some("2"));
+        assert_eq!(
+            value(&sections, "SQLite database", "written by"),
+            Some("SQLite 3.45.1")
+        );
+        assert_eq!(value(&sections, "Tables", "fruits"), Some("3 rows"));
+        assert_eq!(value(&sections, "Views", ""), Some("red_fruits"));
+        let schema = &sections.iter().find(|s| s.title == "Schema").unwrap().entries;
+        let schema: Vec<&str> = schema.iter().map(|(_, line)| line.as_str()).collect();
+        assert_eq!(
+            schema,
+            [
+                "CREATE TABLE fruits(name TEXT);",
+                "CREATE VIEW red_fruits AS",
+                "SELECT * FROM fruits;",
+            ]
+        );
+    }
+}
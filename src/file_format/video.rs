@@ -0,0 +1,419 @@
+//! Duration and tracks of MP4, QuickTime, Matroska and WebM files
+
+use {
+    super::{
+        format_duration,
+        format_sample_rate,
+        u16_be,
+        u32_be,
+        u64_be,
+    },
+    crate::file_info::InfoSection,
+};
+
+/// An ISO base media (MP4) box
+struct Mp4Box<'d> {
+    kind: &'d [u8],
+    content: &'d [u8],
+}
+
+/// iterate over the boxes contained in some data
+fn mp4_boxes(data: &[u8]) -> impl Iterator<Item = Mp4Box<'_>> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let size = u32_be(data, pos)? as usize;
+        let kind = data.get(pos + 4..pos + 8)?;
+        let (header_len, size) = match size {
+            0 => (8, data.len() - pos), // up to the end
+            1 => (16, usize::try_from(u64_be(data, pos + 8)?).ok()?),
+            _ => (8, size),
+        };
+        if size < header_len {
+            return None;
+        }
+        let end = pos.checked_add(size)?.min(data.len());
+        let content = data.get(pos + header_len..end)?;
+        pos = end;
+        Some(Mp4Box { kind, content })
+    })
+}
+
+/// find the content of a box by its path, eg `["mdia", "hdlr"]`
+fn find_box<'d>(
+    data: &'d [u8],
+    path: &[&str],
+) -> Option<&'d [u8]> {
+    let (first, rest) = path.split_first()?;
+    let b = mp4_boxes(data).find(|b| b.kind == first.as_bytes())?;
+    if rest.is_empty() {
+        Some(b.content)
+    } else {
+        find_box(b.content, rest)
+    }
+}
+
+/// read the timescale and duration of a `mvhd` or `mdhd` box
+fn mp4_duration(header: &[u8]) -> Option<f64> {
+    let (timescale, duration) = if header.first()? == &1 {
+        (u32_be(header, 20)?, u64_be(header, 24)?)
+    } else {
+        (u32_be(header, 12)?, u64::from(u32_be(header, 16)?))
+    };
+    (timescale > 0 && duration > 0 && duration != u64::from(u32::MAX))
+        .then(|| duration as f64 / f64::from(timescale))
+}
+
+fn fourcc(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).trim_end().to_string()
+}
+
+/// describe a `trak` box
+fn mp4_track(trak: &[u8]) -> Option<String> {
+    let handler = find_box(trak, &["mdia", "hdlr"])?;
+    let handler = handler.get(8..12)?;
+    let mut description = match handler {
+        b"vide" => "video",
+        b"soun" => "audio",
+        b"subt" | b"sbtl" | b"text" => "subtitles",
+        b"hint" => "hint",
+        b"meta" => "metadata",
+        _ => "other",
+    }
+    .to_string();
+    // the first sample description tells the codec
+    let stsd = find_box(trak, &["mdia", "minf", "stbl", "stsd"]);
+    if let Some(entry) = stsd.and_then(|d| d.get(8..)) {
+        if let Some(codec) = entry.get(4..8) {
+            description.push_str(&format!("  {}", fourcc(codec)));
+        }
+        match handler {
+            b"vide" => {
+                if let (Some(w), Some(h)) = (u16_be(entry, 32), u16_be(entry, 34)) {
+                    description.push_str(&format!("  {w} × {h}"));
+                }
+            }
+            b"soun" => {
+                if let Some(channels) = u16_be(entry, 24) {
+                    description.push_str(&format!("  {channels} ch"));
+                }
+                if let Some(rate) = u32_be(entry, 32) {
+                    description.push_str(&format!("  {}", format_sample_rate(rate >> 16)));
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(seconds) = find_box(trak, &["mdia", "mdhd"]).and_then(mp4_duration) {
+        description.push_str(&format!("  {}", format_duration(seconds)));
+    }
+    Some(description)
+}
+
+pub fn mp4_sections(data: &[u8]) -> Option<Vec<InfoSection>> {
+    let mut container = InfoSection::new("Container");
+    let mut tracks = InfoSection::new("Tracks");
+    let ftyp = find_box(data, &["ftyp"])?;
+    container.add("brand", fourcc(ftyp.get(0..4)?));
+    let compatible: Vec<String> = ftyp.get(8..)?.chunks_exact(4).map(fourcc).collect();
+    if !compatible.is_empty() {
+        container.add("compatible brands", compatible.join(", "));
+    }
+    if let Some(moov) = find_box(data, &["moov"]) {
+        if let Some(seconds) = find_box(moov, &["mvhd"]).and_then(mp4_duration) {
+            container.add("duration", format_duration(seconds));
+        }
+        for (i, trak) in mp4_boxes(moov).filter(|b| b.kind == b"trak").enumerate() {
+            if let Some(description) = mp4_track(trak.content) {
+                tracks.add(format!("track {}", i + 1), description);
+            }
+        }
+    }
+    let mut sections = Vec::new();
+    container.push_to(&mut sections);
+    tracks.push_to(&mut sections);
+    Some(sections)
+}
+
+const EBML_HEADER: u32 = 0x1A45_DFA3;
+const DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMECODE_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const TITLE: u32 = 0x7BA9;
+const MUXING_APP: u32 = 0x4D80;
+const WRITING_APP: u32 = 0x5741;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const LANGUAGE: u32 = 0x22_B59C;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const CHANNELS: u32 = 0x9F;
+const CLUSTER: u32 = 0x1F43_B675;
+
+/// read an EBML variable size integer, return it with its length.
+/// The marker bit is kept for ids and removed for sizes.
+fn ebml_vint(
+    data: &[u8],
+    keep_marker: bool,
+) -> Option<(u64, usize)> {
+    let first = *data.first()?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 {
+        return None;
+    }
+    let mut value = u64::from(if keep_marker {
+        first
+    } else {
+        first & (0xff_u16 >> len) as u8
+    });
+    for &b in data.get(1..len)? {
+        value = (value << 8) | u64::from(b);
+    }
+    Some((value, len))
+}
+
+/// iterate over the EBML elements contained in some data. An element
+/// of unknown size extends to the end of the data.
+fn ebml_elements(data: &[u8]) -> impl Iterator<Item = (u32, &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let (id, id_len) = ebml_vint(data.get(pos..)?, true)?;
+        let (size, size_len) = ebml_vint(data.get(pos + id_len..)?, false)?;
+        let start = pos + id_len + size_len;
+        let unknown_size = size == (1 << (7 * size_len)) - 1;
+        let end = if unknown_size {
+            data.len()
+        } else {
+            start.checked_add(usize::try_from(size).ok()?)?.min(data.len())
+        };
+        let content = data.get(start..end)?;
+        pos = end;
+        Some((id as u32, content))
+    })
+}
+
+fn ebml_uint(content: &[u8]) -> u64 {
+    content
+        .iter()
+        .take(8)
+        .fold(0, |value, &b| (value << 8) | u64::from(b))
+}
+
+fn ebml_float(content: &[u8]) -> Option<f64> {
+    match content.len() {
+        4 => Some(f64::from(f32::from_bits(ebml_uint(content) as u32))),
+        8 => Some(f64::from_bits(ebml_uint(content))),
+        _ => None,
+    }
+}
+
+fn ebml_string(content: &[u8]) -> String {
+    String::from_utf8_lossy(content)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+/// describe a Matroska track entry
+fn matroska_track(entry: &[u8]) -> String {
+    let mut kind = "other";
+    let mut codec = String::new();
+    let mut language = String::new();
+    let mut details = String::new();
+    for (id, content) in ebml_elements(entry) {
+        match id {
+            TRACK_TYPE => {
+                kind = match ebml_uint(content) {
+                    1 => "video",
+                    2 => "audio",
+                    17 => "subtitles",
+                    _ => "other",
+                };
+            }
+            CODEC_ID => codec = ebml_string(content),
+            LANGUAGE => language = ebml_string(content),
+            VIDEO => {
+                let (mut width, mut height) = (0, 0);
+                for (id, content) in ebml_elements(content) {
+                    match id {
+                        PIXEL_WIDTH => width = ebml_uint(content),
+                        PIXEL_HEIGHT => height = ebml_uint(content),
+                        _ => {}
+                    }
+                }
+                details = format!("{width} × {height}");
+            }
+            AUDIO => {
+                let (mut rate, mut channels) = (8000.0, 1);
+                for (id, content) in ebml_elements(content) {
+                    match id {
+                        SAMPLING_FREQUENCY => rate = ebml_float(content).unwrap_or(rate),
+                        CHANNELS => channels = ebml_uint(content),
+                        _ => {}
+                    }
+                }
+                details = format!("{channels} ch  {}", format_sample_rate(rate as u32));
+            }
+            _ => {}
+        }
+    }
+    let mut description = kind.to_string();
+    for part in [codec, details, language] {
+        if !part.is_empty() {
+            description.push_str("  ");
+            description.push_str(&part);
+        }
+    }
+    description
+}
+
+pub fn matroska_sections(data: &[u8]) -> Option<Vec<InfoSection>> {
+    let mut container = InfoSection::new("Container");
+    let mut tracks = InfoSection::new("Tracks");
+    for (id, content) in ebml_elements(data) {
+        match id {
+            EBML_HEADER => {
+                if let Some((_, doc_type)) = ebml_elements(content).find(|(id, _)| *id == DOC_TYPE)
+                {
+                    container.add("document type", ebml_string(doc_type));
+                }
+            }
+            SEGMENT => {
+                for (id, content) in ebml_elements(content) {
+                    match id {
+                        INFO => matroska_info(content, &mut container),
+                        TRACKS => {
+                            let entries = ebml_elements(content)
+                                .filter(|(id, _)| *id == TRACK_ENTRY)
+                                .map(|(_, entry)| entry);
+                            for (i, entry) in entries.enumerate() {
+                                tracks.add(format!("track {}", i + 1), matroska_track(entry));
+                            }
+                        }
+                        // the media data, usually after the metadata
+                        CLUSTER => break,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut sections = Vec::new();
+    container.push_to(&mut sections);
+    tracks.push_to(&mut sections);
+    Some(sections)
+}
+
+fn matroska_info(
+    info: &[u8],
+    container: &mut InfoSection,
+) {
+    let mut timecode_scale = 1_000_000; // in nanoseconds
+    let mut duration = None;
+    for (id, content) in ebml_elements(info) {
+        match id {
+            TIMECODE_SCALE => timecode_scale = ebml_uint(content),
+            DURATION => duration = ebml_float(content),
+            TITLE => container.add("title", ebml_string(content)),
+            MUXING_APP => container.add("muxing app", ebml_string(content)),
+            WRITING_APP => container.add("writing app", ebml_string(content)),
+            _ => {}
+        }
+    }
+    if let Some(duration) = duration {
+        let seconds = duration * timecode_scale as f64 / 1e9;
+        container.add("duration", format_duration(seconds));
+    }
+}
+
+#[cfg(test)]
+mod video_tests {
+    use {
+        super::*,
+        crate::file_format::value,
+    };
+
+    fn mp4_box(
+        kind: &[u8],
+        content: &[u8],
+    ) -> Vec<u8> {
+        let mut b = ((content.len() + 8) as u32).to_be_bytes().to_vec();
+        b.extend_from_slice(kind);
+        b.extend_from_slice(content);
+        b
+    }
+
+    fn ebml(
+        id: &[u8],
+        content: &[u8],
+    ) -> Vec<u8> {
+        assert!(content.len() < 127);
+        let mut e = id.to_vec();
+        e.push(0x80 | content.len() as u8);
+        e.extend_from_slice(content);
+        e
+    }
+
+    #[test]
+    fn test_mp4_sections() {
+        let mut mvhd = vec![0u8; 100];
+        mvhd[12..16].copy_from_slice(&1000u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&65_500u32.to_be_bytes());
+        let mut hdlr = vec![0u8; 24];
+        hdlr[8..12].copy_from_slice(b"vide");
+        let mut entry = vec![0u8; 86];
+        entry[4..8].copy_from_slice(b"avc1");
+        entry[32..34].copy_from_slice(&1280u16.to_be_bytes());
+        entry[34..36].copy_from_slice(&720u16.to_be_bytes());
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsd.extend_from_slice(&entry);
+        let stbl = mp4_box(b"stbl", &mp4_box(b"stsd", &stsd));
+        let minf = mp4_box(b"minf", &stbl);
+        let mut mdia = mp4_box(b"hdlr", &hdlr);
+        mdia.extend(minf);
+        let trak = mp4_box(b"trak", &mp4_box(b"mdia", &mdia));
+        let mut moov = mp4_box(b"mvhd", &mvhd);
+        moov.extend(trak);
+        let mut data = mp4_box(b"ftyp", b"isom\0\0\x02\0isomavc1");
+        data.extend(mp4_box(b"moov", &moov));
+        data.extend(mp4_box(b"mdat", &[0; 64]));
+        let sections = mp4_sections(&data).unwrap();
+        assert_eq!(value(&sections, "Container", "brand"), Some("isom"));
+        assert_eq!(value(&sections, "Container", "duration"), Some("1:05.5"));
+        assert_eq!(
+            value(&sections, "Tracks", "track 1"),
+            Some("video  avc1  1280 × 720")
+        );
+    }
+
+    #[test]
+    fn test_matroska_sections() {
+        let mut data = ebml(&[0x1A, 0x45, 0xDF, 0xA3], &ebml(&[0x42, 0x82], b"webm"));
+        let mut info = ebml(&[0x2A, 0xD7, 0xB1], &[0x0F, 0x42, 0x40]);
+        info.extend(ebml(&[0x44, 0x89], &10_000f32.to_bits().to_be_bytes()));
+        let mut video = ebml(&[0xB0], &[0x02, 0x80]);
+        video.extend(ebml(&[0xBA], &[0x01, 0xE0]));
+        let mut track = ebml(&[0x83], &[1]);
+        track.extend(ebml(&[0x86], b"V_VP9"));
+        track.extend(ebml(&[0xE0], &video));
+        let mut segment = ebml(&[0x15, 0x49, 0xA9, 0x66], &info);
+        segment.extend(ebml(&[0x16, 0x54, 0xAE, 0x6B], &ebml(&[0xAE], &track)));
+        // a segment of unknown size
+        data.extend_from_slice(&[0x18, 0x53, 0x80, 0x67, 0x01]);
+        data.extend_from_slice(&[0xff; 7]);
+        data.extend(segment);
+        let sections = matroska_sections(&data).unwrap();
+        assert_eq!(value(&sections, "Container", "document type"), Some("webm"));
+        assert_eq!(value(&sections, "Container", "duration"), Some("0:10.0"));
+        assert_eq!(
+            value(&sections, "Tracks", "track 1"),
+            Some("video  V_VP9  640 × 480")
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use {
+    super::xattr,
+    crate::{
+        content_type::{
+            encoding::Encoding,
+            is_file_binary,
+            mime,
+        },
+        syntactic::SYNTAXER,
+    },
+    chrono::{
+        DateTime,
+        Local,
+    },
+    std::{
+        fs::{
+            self,
+            File,
+            FileType,
+        },
+        io::{
+            self,
+            BufRead,
+            BufReader,
+        },
+        path::{
+            Path,
+            PathBuf,
+        },
+        time::SystemTime,
+    },
+};
+
+/// a symlink chain longer than this is considered a loop
+const MAX_LINK_HOPS: usize = 40;
+
+/// EXIF values longer than this are cropped
+const MAX_EXIF_VALUE_LEN: usize = 100;
+
+/// the entries of a directory are counted up to this number, as
+/// listing a huge directory would block the display
+const MAX_COUNTED_ENTRIES: usize = 10_000;
+
+/// Count the entries of a directory, up to `MAX_COUNTED_ENTRIES`
+fn entries_count_description(entries: fs::ReadDir) -> String {
+    let count = entries.take(MAX_COUNTED_ENTRIES + 1).count();
+    if count > MAX_COUNTED_ENTRIES {
+        format!("more than {MAX_COUNTED_ENTRIES}")
+    } else {
+        count.to_string()
+    }
+}
+
+/// A titled group of properties of a file.
+///
+/// An entry with an empty name is displayed as a line of free text.
+#[derive(Debug, Clone)]
+pub struct InfoSection {
+    pub title: &'static str,
+    pub entries: Vec<(String, String)>,
+}
+
+impl InfoSection {
+    pub fn new(title: &'static str) -> Self {
+        Self {
+            title,
+            entries: Vec::new(),
+        }
+    }
+    pub fn add<N: Into<String>, V: Into<String>>(
+        &mut self,
+        name: N,
+        value: V,
+    ) {
+        self.entries.push((name.into(), value.into()));
+    }
+    /// add a line of text, displayed without name
+    pub fn add_text<V: Into<String>>(
+        &mut self,
+        text: V,
+    ) {
+        self.entries.push((String::new(), text.into()));
+    }
+    /// add the section to the list, unless it's empty
+    pub fn push_to(
+        self,
+        sections: &mut Vec<InfoSection>,
+    ) {
+        if !self.entries.is_empty() {
+            sections.push(self);
+        }
+    }
+}
+
+/// Gather the metadata of a file (or directory), like `stat` and
+/// `file` would.
+///
+/// Only the initial `lstat` may fail: the properties which can't be
+/// read are just omitted.
+pub fn read_sections(path: &Path) -> io::Result<Vec<InfoSection>> {
+    let link_metadata = fs::symlink_metadata(path)?;
+    let metadata = fs::metadata(path).unwrap_or_else(|_| link_metadata.clone());
+    let mut sections = Vec::new();
+
+    let mut file = InfoSection::new("File");
+    file.add("path", path.to_string_lossy());
+    file.add("type", file_type_name(link_metadata.file_type()));
+    if metadata.is_file() {
+        file.add("size", size_description(metadata.len()));
+        if let Ok(mime) = mime::mime_type(path) {
+            file.add("mime type", mime);
+        }
+    } else if metadata.is_dir() {
+        file.add("mime type", "inode/directory");
+        if let Ok(entries) = fs::read_dir(path) {
+            file.add("entries", entries_count_description(entries));
+        }
+    }
+    file.push_to(&mut sections);
+
+    if link_metadata.file_type().is_symlink() {
+        symlink_section(path).push_to(&mut sections);
+    }
+
+    #[cfg(not(any(target_family = "windows", target_os = "android")))]
+    {
+        unix_sections(&link_metadata, &mut sections);
+    }
+
+    let mut times = InfoSection::new("Times");
+    if let Ok(time) = link_metadata.modified() {
+        times.add("modified", format_time(time));
+    }
+    if let Ok(time) = link_metadata.accessed() {
+        times.add("accessed", format_time(time));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        if let Some(time) = system_time(link_metadata.ctime(), link_metadata.ctime_nsec()) {
+            times.add("changed", format_time(time));
+        }
+    }
+    if let Ok(time) = link_metadata.created() {
+        times.add("created", format_time(time));
+    }
+    times.push_to(&mut sections);
+
+    let mut xattrs = InfoSection::new("Extended attributes");
+    match xattr::xattrs(path) {
+        Ok(attributes) => xattrs.entries = attributes,
+        Err(e) => xattrs.add("error", e.to_string()),
+    }
+    xattrs.push_to(&mut sections);
+
+    if metadata.is_file() {
+        image_section(path).push_to(&mut sections);
+        text_section(path).push_to(&mut sections);
+    }
+    Ok(sections)
+}
+
+fn file_type_name(ft: FileType) -> &'static str {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if ft.is_fifo() {
+            return "fifo";
+        }
+        if ft.is_socket() {
+            return "socket";
+        }
+        if ft.is_block_device() {
+            return "block device";
+        }
+        if ft.is_char_device() {
+            return "character device";
+        }
+    }
+    if ft.is_symlink() {
+        "symbolic link"
+    } else if ft.is_dir() {
+        "directory"
+    } else if ft.is_file() {
+        "regular file"
+    } else {
+        "unknown"
+    }
+}
+
+fn size_description(len: u64) -> String {
+    if len < 10_000 {
+        format!("{len} bytes")
+    } else {
+        format!("{len} bytes ({})", file_size::fit_4(len))
+    }
+}
+
+fn format_time(time: SystemTime) -> String {
+    let time: DateTime<Local> = time.into();
+    time.format("%Y-%m-%d %H:%M:%S%.3f %:z").to_string()
+}
+
+#[cfg(unix)]
+fn system_time(
+    secs: i64,
+    nsecs: i64,
+) -> Option<SystemTime> {
+    DateTime::from_timestamp(secs, nsecs as u32).map(SystemTime::from)
+}
+
+/// The chain of the targets of a symbolic link, up to the final file
+fn symlink_section(path: &Path) -> InfoSection {
+    let mut section = InfoSection::new("Symbolic link");
+    let mut current = path.to_path_buf();
+    for hop in 1..=MAX_LINK_HOPS {
+        let target = match fs::read_link(&current) {
+            Ok(target) => target,
+            Err(e) => {
+                section.add("error", e.to_string());
+                return section;
+            }
+        };
+        section.add(format!("target {hop}"), target.to_string_lossy());
+        // a relative target is relative to the directory of the link
+        let next: PathBuf = match current.parent() {
+            Some(parent) if target.is_relative() => parent.join(&target),
+            _ => target,
+        };
+        match fs::symlink_metadata(&next) {
+            Ok(md) if md.file_type().is_symlink() => {
+                current = next;
+            }
+            Ok(_) => {
+                let resolved = fs::canonicalize(&next).unwrap_or(next);
+                section.add("resolved", resolved.to_string_lossy());
+                return section;
+            }
+            Err(_) => {
+                section.add("broken", "the target doesn't exist");
+                return section;
+            }
+        }
+    }
+    section.add("loop", format!("more than {MAX_LINK_HOPS} links"));
+    section
+}
+
+#[cfg(not(any(target_family = "windows", target_os = "android")))]
+fn unix_sections(
+    md: &fs::Metadata,
+    sections: &mut Vec<InfoSection>,
+) {
+    use {
+        crate::permissions,
+        std::os::unix::fs::MetadataExt,
+    };
+    let mut access = InfoSection::new("Access");
+    let mode = md.mode();
+    access.add(
+        "mode",
+        format!("{:04o} {}", mode & 0o7777, umask::Mode::from(mode)),
+    );
+    let special: Vec<&str> = [(0o4000, "setuid"), (0o2000, "setgid"), (0o1000, "sticky")]
+        .into_iter()
+        .filter(|(bit, _)| mode & bit != 0)
+        .map(|(_, name)| name)
+        .collect();
+    if !special.is_empty() {
+        access.add("special bits", special.join(", "));
+    }
+    access.add(
+        "owner",
+        format!("{} ({})", permissions::user_name(md.uid()), md.uid()),
+    );
+    access.add(
+        "group",
+        format!("{} ({})", permissions::group_name(md.gid()), md.gid()),
+    );
+    access.push_to(sections);
+
+    let mut inode = InfoSection::new("Inode");
+    inode.add("inode", md.ino().to_string());
+    inode.add("device", format!("{:#x}", md.dev()));
+    inode.add("links", md.nlink().to_string());
+    inode.add("blocks", format!("{} × 512 bytes", md.blocks()));
+    inode.add("block size", md.blksize().to_string());
+    if md.rdev() != 0 {
+        inode.add("device id", format!("{:#x}", md.rdev()));
+    }
+    inode.push_to(sections);
+}
+
+/// The dimensions of an image, and its EXIF data
+fn image_section(path: &Path) -> InfoSection {
+    let mut section = InfoSection::new("Image");
+    let Ok((width, height)) = image::image_dimensions(path) else {
+        return section;
+    };
+    section.add("dimensions", format!("{width} × {height}"));
+    let Ok(file) = File::open(path) else {
+        return section;
+    };
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(file)) else {
+        return section;
+    };
+    for field in exif.fields() {
+        if field.ifd_num != exif::In::PRIMARY || field.tag == exif::Tag::MakerNote {
+            continue;
+        }
+        let mut value = field.display_value().with_unit(&exif).to_string();
+        if value.chars().count() > MAX_EXIF_VALUE_LEN {
+            value = value.chars().take(MAX_EXIF_VALUE_LEN).collect();
+            value.push('…');
+        }
+        section.add(field.tag.to_string(), value);
+    }
+    section
+}
+
+/// The encoding and syntax of a text file
+fn text_section(path: &Path) -> InfoSection {
+    let mut section = InfoSection::new("Text");
+    if is_file_binary(path).unwrap_or(true) {
+        return section;
+    }
+    let Ok(Some(encoding)) = Encoding::detect_file(path) else {
+        return section;
+    };
+    section.add("encoding", encoding.name());
+    let syntax_set = &SYNTAXER.syntax_set;
+    let syntax = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(|ext| syntax_set.find_syntax_by_extension(ext))
+        .or_else(|| {
+            // the first line may tell the syntax, eg with a shebang
+            let file = File::open(path).ok()?;
+            let mut first_line = String::new();
+            BufReader::new(file).read_line(&mut first_line).ok()?;
+            syntax_set.find_syntax_by_first_line(&first_line).ok().flatten()
+        });
+    if let Some(syntax) = syntax {
+        section.add("syntax", &syntax.name);
+    }
+    section
+}
@@ -0,0 +1,255 @@
+use {
+    super::{
+        InfoSection,
+        read_sections,
+    },
+    crate::{
+        app::AppContext,
+        command::{
+            ListScroll,
+            ScrollCommand,
+        },
+        display::{
+            Screen,
+            W,
+        },
+        errors::ProgramError,
+        skin::PanelSkin,
+    },
+    crokey::crossterm::{
+        QueueableCommand,
+        cursor,
+        style::{
+            Color,
+            Print,
+            SetBackgroundColor,
+            SetForegroundColor,
+        },
+    },
+    std::path::Path,
+    termimad::{
+        Area,
+        CropWriter,
+        SPACE_FILLING,
+    },
+    unicode_width::UnicodeWidthStr,
+};
+
+/// names longer than this don't widen the column of names
+const MAX_NAME_WIDTH: usize = 28;
+
+enum InfoLine {
+    Title(&'static str),
+    Entry { name: String, value: String },
+}
+
+/// A preview of the metadata of a file: inode, owner, times,
+/// extended attributes, mime type, EXIF data, etc.
+///
+/// It's also used to display the summary of a binary file of
+/// a known format.
+pub struct InfoView {
+    lines: Vec<InfoLine>,
+    name_width: usize,
+    list_scroll: ListScroll,
+}
+
+impl InfoView {
+    pub fn new(path: &Path) -> Result<Self, ProgramError> {
+        let sections = read_sections(path)?;
+        Ok(Self::from_sections(sections))
+    }
+    pub fn from_sections(sections: Vec<InfoSection>) -> Self {
+        let mut lines = Vec::new();
+        let mut name_width = 0;
+        for section in sections {
+            lines.push(InfoLine::Title(section.title));
+            for (name, value) in section.entries {
+                name_width = name_width.max(name.width().min(MAX_NAME_WIDTH));
+                lines.push(InfoLine::Entry { name, value });
+            }
+        }
+        Self {
+            lines,
+            name_width,
+            list_scroll: ListScroll::default(),
+        }
+    }
+    /// return the value of the selected entry, or the title of
+    /// the selected section
+    pub fn get_selected_line(&self) -> Option<String> {
+        self.list_scroll
+            .selection_idx
+            .and_then(|idx| self.lines.get(idx))
+            .map(|line| match line {
+                InfoLine::Title(title) => (*title).to_string(),
+                InfoLine::Entry { value, .. } => value.clone(),
+            })
+    }
+    fn ensure_selection_is_visible(&mut self) {
+        self.list_scroll.ensure_selection_is_visible(self.lines.len());
+    }
+    pub fn unselect(&mut self) {
+        self.list_scroll.selection_idx = None;
+    }
+    pub fn try_select_y(
+        &mut self,
+        y: u16,
+    ) -> bool {
+        self.list_scroll.try_select_row(y as usize, self.lines.len())
+    }
+    pub fn select_first(&mut self) {
+        self.list_scroll.select_first(self.lines.len());
+    }
+    pub fn select_last(&mut self) {
+        self.list_scroll.select_last(self.lines.len());
+    }
+    pub fn move_selection(
+        &mut self,
+        dy: i32,
+        cycle: bool,
+    ) {
+        self.list_scroll.move_selection(dy, cycle, self.lines.len());
+        self.ensure_selection_is_visible();
+    }
+    pub fn try_scroll(
+        &mut self,
+        cmd: ScrollCommand,
+    ) -> bool {
+        self.list_scroll.try_scroll(cmd, self.lines.len())
+    }
+    pub fn display(
+        &mut self,
+        w: &mut W,
+        _screen: Screen,
+        panel_skin: &PanelSkin,
+        area: &Area,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        if area.height as usize != self.list_scroll.page_height {
+            self.list_scroll.page_height = area.height as usize;
+            self.ensure_selection_is_visible();
+        }
+        let styles = &panel_skin.styles;
+        let normal_bg = styles
+            .preview
+            .get_bg()
+            .or_else(|| styles.default.get_bg())
+            .unwrap_or(Color::Reset);
+        let selection_bg = styles
+            .selected_line
+            .get_bg()
+            .unwrap_or(Color::AnsiValue(240));
+        let content_width = area.width as usize - 1; // 1 char left for scrollbar
+        let scrollbar = area.scrollbar(self.list_scroll.scroll, self.lines.len());
+        let scrollbar_fg = styles
+            .scrollbar_thumb
+            .get_fg()
+            .or_else(|| styles.preview.get_fg())
+            .unwrap_or(Color::White);
+        for y in 0..area.height as usize {
+            w.queue(cursor::MoveTo(area.left, area.top + y as u16))?;
+            let mut cw = CropWriter::new(w, content_width);
+            let line_idx = self.list_scroll.scroll + y;
+            let selected = self.list_scroll.is_selected(line_idx);
+            let bg = if selected { selection_bg } else { normal_bg };
+            let mut style = styles.preview;
+            style.set_bg(bg);
+            let mark = if selected && con.show_selection_mark {
+                '▶'
+            } else {
+                ' '
+            };
+            match self.lines.get(line_idx) {
+                Some(InfoLine::Title(title)) => {
+                    let mut title_style = styles.preview_title;
+                    if selected {
+                        title_style.set_bg(bg);
+                    }
+                    cw.queue_char(&title_style, mark)?;
+                    cw.queue_str(&title_style, title)?;
+                    style = title_style;
+                }
+                Some(InfoLine::Entry { name, value }) if name.is_empty() => {
+                    cw.queue_char(&style, mark)?;
+                    cw.queue_str(&style, " ")?;
+                    cw.queue_str(&style, value)?;
+                }
+                Some(InfoLine::Entry { name, value }) => {
+                    let mut name_style = styles.info_name;
+                    name_style.set_bg(bg);
+                    cw.queue_char(&style, mark)?;
+                    cw.queue_str(&style, " ")?;
+                    cw.queue_str(&name_style, name)?;
+                    let padding = self.name_width.saturating_sub(name.width()) + 2;
+                    cw.repeat(&style, &SPACE_FILLING, padding)?;
+                    cw.queue_str(&style, value)?;
+                }
+                None => {}
+            }
+            cw.fill(&style, &SPACE_FILLING)?;
+            w.queue(SetBackgroundColor(bg))?;
+            if ScrollCommand::is_thumb(area.top + y as u16, scrollbar) {
+                w.queue(SetForegroundColor(scrollbar_fg))?;
+                w.queue(Print('▐'))?;
+            } else {
+                w.queue(Print(' '))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod info_view_tests {
+    use {
+        super::*,
+        std::fs,
+    };
+
+    fn entry<'v>(
+        view: &'v InfoView,
+        wanted: &str,
+    ) -> Option<&'v str> {
+        view.lines.iter().find_map(|line| match line {
+            InfoLine::Entry { name, value } if name == wanted => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn test_file_info() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        fs::write(&path, "# Title\n\nSome text\n").unwrap();
+        let view = InfoView::new(&path).unwrap();
+        assert_eq!(entry(&view, "type"), Some("regular file"));
+        assert_eq!(entry(&view, "size"), Some("19 bytes"));
+        assert_eq!(entry(&view, "mime type"), Some("text/markdown"));
+        assert_eq!(entry(&view, "encoding"), Some("utf-8"));
+        assert_eq!(entry(&view, "syntax"), Some("Markdown"));
+        assert!(entry(&view, "modified").is_some());
+        assert!(InfoView::new(&dir.path().join("missing")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target.txt");
+        fs::write(&target, "hello").unwrap();
+        std::os::unix::fs::symlink("target.txt", dir.path().join("a")).unwrap();
+        std::os::unix::fs::symlink("a", dir.path().join("b")).unwrap();
+        std::os::unix::fs::symlink("nowhere", dir.path().join("broken")).unwrap();
+        let view = InfoView::new(&dir.path().join("b")).unwrap();
+        assert_eq!(entry(&view, "type"), Some("symbolic link"));
+        assert_eq!(entry(&view, "target 1"), Some("a"));
+        assert_eq!(entry(&view, "target 2"), Some("target.txt"));
+        let resolved = fs::canonicalize(&target).unwrap();
+        assert_eq!(entry(&view, "resolved"), Some(resolved.to_str().unwrap()));
+        // the size is the one of the final target
+        assert_eq!(entry(&view, "size"), Some("5 bytes"));
+        let view = InfoView::new(&dir.path().join("broken")).unwrap();
+        assert!(entry(&view, "broken").is_some());
+    }
+}
@@ -0,0 +1,8 @@
+mod info_section;
+mod info_view;
+mod xattr;
+
+pub use {
+    info_section::*,
+    info_view::InfoView,
+};
@@ -0,0 +1,126 @@
+//! Reading of the extended attributes of a file, on linux and macOS.
+//!
+//! Symbolic links aren't followed.
+
+use std::{
+    io,
+    path::Path,
+};
+
+/// values longer than this are cropped
+const MAX_VALUE_LEN: usize = 200;
+
+/// Return the names and values of the extended attributes of a file,
+/// the values which aren't printable text being written in hexadecimal
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub fn xattrs(path: &Path) -> io::Result<Vec<(String, String)>> {
+    use std::{
+        ffi::CString,
+        os::unix::ffi::OsStrExt,
+    };
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    let names = read_buffer(|buf, len| unsafe { list(&c_path, buf, len) })?;
+    let mut attributes = Vec::new();
+    for name in names.split(|&b| b == 0).filter(|name| !name.is_empty()) {
+        let c_name = CString::new(name)?;
+        let value = read_buffer(|buf, len| unsafe { get(&c_path, &c_name, buf, len) })
+            .map_or_else(|e| format!("({e})"), |value| display_value(&value));
+        attributes.push((String::from_utf8_lossy(name).to_string(), value));
+    }
+    Ok(attributes)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn xattrs(_path: &Path) -> io::Result<Vec<(String, String)>> {
+    Ok(Vec::new())
+}
+
+/// Call a function of the xattr API twice: once to get the needed size,
+/// then to fill the buffer
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn read_buffer<F>(f: F) -> io::Result<Vec<u8>>
+where
+    F: Fn(*mut libc::c_void, usize) -> isize,
+{
+    let len = f(std::ptr::null_mut(), 0);
+    if len < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut buf = vec![0u8; len as usize];
+    let len = f(buf.as_mut_ptr().cast(), buf.len());
+    if len < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    buf.truncate(len as usize);
+    Ok(buf)
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn list(
+    path: &std::ffi::CStr,
+    buf: *mut libc::c_void,
+    len: usize,
+) -> isize {
+    unsafe { libc::llistxattr(path.as_ptr(), buf.cast(), len) }
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn get(
+    path: &std::ffi::CStr,
+    name: &std::ffi::CStr,
+    buf: *mut libc::c_void,
+    len: usize,
+) -> isize {
+    unsafe { libc::lgetxattr(path.as_ptr(), name.as_ptr(), buf, len) }
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn list(
+    path: &std::ffi::CStr,
+    buf: *mut libc::c_void,
+    len: usize,
+) -> isize {
+    unsafe { libc::listxattr(path.as_ptr(), buf.cast(), len, libc::XATTR_NOFOLLOW) }
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn get(
+    path: &std::ffi::CStr,
+    name: &std::ffi::CStr,
+    buf: *mut libc::c_void,
+    len: usize,
+) -> isize {
+    unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), buf, len, 0, libc::XATTR_NOFOLLOW) }
+}
+
+/// Make a displayable string of an attribute value: the text if it's
+/// printable, hexadecimal otherwise
+#[cfg_attr(not(any(target_os = "linux", target_os = "macos")), allow(dead_code))]
+fn display_value(value: &[u8]) -> String {
+    let value = value.strip_suffix(&[0]).unwrap_or(value);
+    let mut s = match std::str::from_utf8(value) {
+        Ok(text) if !text.chars().any(char::is_control) => text.to_string(),
+        _ => value.iter().map(|b| format!("{b:02x}")).collect(),
+    };
+    if s.len() > MAX_VALUE_LEN {
+        let mut end = MAX_VALUE_LEN;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+        s.push('…');
+    }
+    s
+}
+
+#[cfg(test)]
+mod xattr_tests {
+    use super::*;
+
+    #[test]
+    fn test_display_value() {
+        assert_eq!(display_value(b"text/plain\0"), "text/plain");
+        assert_eq!(display_value(&[0x01, 0xab]), "01ab");
+        assert_eq!(display_value(&[b'a'; 300]).chars().count(), MAX_VALUE_LEN + 1);
+    }
+}
@@ -1,8 +1,14 @@
 /// compute consolidated data for directories: modified date, size, and count.
 /// A cache is used to avoid recomputing the same directories again and again.
 /// On unix, hard links are checked to avoid counting twice an inode.
+/// Sums may also be persisted on disk, to be displayed in the next sessions
+/// while they're computed again.
+mod persisted_cache;
+mod size_report;
 mod sum_computation;
 
+pub use size_report::SizeReport;
+
 use {
     crate::{
         app::*,
@@ -30,32 +36,56 @@ pub fn clear_cache() {
     SUM_CACHE.lock().unwrap().clear();
 }
 
+/// remove from the cache the sums of the changed paths, of their
+/// ancestors, and of their descendants
+pub fn invalidate_cache(changed: &[PathBuf]) {
+    #[allow(clippy::missing_panics_doc)] // panics if the mutex is poisoned (in which case it's better)
+    SUM_CACHE.lock().unwrap().retain(|path, _| {
+        !changed
+            .iter()
+            .any(|c| c.starts_with(path) || path.starts_with(c))
+    });
+}
+
+/// write on disk the sums which must be persisted (if the
+/// persisted cache is enabled and was modified)
+pub fn save_persisted_cache() {
+    persisted_cache::save();
+}
+
 /// Reduction of counts, dates and sizes on a file or directory
 #[derive(Debug, Copy, Clone)]
 pub struct FileSum {
-    real_size: u64, // bytes, the space it takes on disk
-    count: usize,   // number of files
-    modified: u32,  // seconds from Epoch to last modification, or 0 if there was an error
-    sparse: bool,   // only for non directories: tells whether the file is sparse
+    real_size: u64,     // bytes, the space it takes on disk
+    apparent_size: u64, // bytes, the logical size, as displayed by `ls -l`
+    count: usize,       // number of files
+    modified: u32,      // seconds from Epoch to last modification, or 0 if there was an error
+    sparse: bool,       // only for non directories: tells whether the file is sparse
+    partial: bool,      // whether the computation was stopped before the end
+    stale: bool,        // whether it comes from a previous session and should be recomputed
 }
 
 impl FileSum {
     pub fn new(
         real_size: u64,
+        apparent_size: u64,
         sparse: bool,
         count: usize,
         modified: u32,
     ) -> Self {
         Self {
             real_size,
+            apparent_size,
             count,
             modified,
             sparse,
+            partial: false,
+            stale: false,
         }
     }
 
     pub fn zero() -> Self {
-        Self::new(0, false, 0, 0)
+        Self::new(0, 0, false, 0, 0)
     }
 
     pub fn incr(&mut self) {
@@ -86,22 +116,46 @@ impl FileSum {
                     path,
                     sum_computation::compute_dir_sum(path, &mut sum_cache, dam, con),
                 );
-                if let Some(sum) = sum {
+                // a partial sum isn't cached, so that it's computed
+                // again when needed
+                if let Some(sum) = sum.filter(|sum| !sum.partial) {
                     sum_cache.insert(PathBuf::from(path), sum);
+                    if con.file_sum_cache {
+                        persisted_cache::insert(path, sum);
+                    }
                 }
                 sum
             }
         }
     }
 
+    /// Return the sum of the directory computed in a previous session, if
+    /// the persisted cache is enabled and the directory wasn't modified
+    /// since. This sum is stale: it should be displayed only until
+    /// it's computed again.
+    pub fn from_persisted_cache(
+        path: &Path,
+        con: &AppContext,
+    ) -> Option<Self> {
+        if con.file_sum_cache {
+            persisted_cache::get(path)
+        } else {
+            None
+        }
+    }
+
+    /// return the part of the total size, either in disk usage
+    /// or in apparent size
     pub fn part_of_size(
         self,
         total: Self,
+        apparent: bool,
     ) -> f32 {
-        if total.real_size == 0 {
+        let total_size = total.size(apparent);
+        if total_size == 0 {
             0.0
         } else {
-            self.real_size as f32 / total.real_size as f32
+            self.size(apparent) as f32 / total_size as f32
         }
     }
     /// return the number of files (normally at least 1)
@@ -113,10 +167,26 @@ impl FileSum {
     pub fn to_seconds(self) -> u32 {
         self.modified
     }
-    /// return the size in bytes
+    /// return the size in bytes, that is the space taken on disk
     pub fn to_size(self) -> u64 {
         self.real_size
     }
+    /// return the apparent size in bytes, which may be greater than the
+    /// disk usage for sparse files, or smaller for small files
+    pub fn to_apparent_size(self) -> u64 {
+        self.apparent_size
+    }
+    /// return either the apparent size or the disk usage, in bytes
+    pub fn size(
+        self,
+        apparent: bool,
+    ) -> u64 {
+        if apparent {
+            self.apparent_size
+        } else {
+            self.real_size
+        }
+    }
     pub fn to_valid_seconds(self) -> Option<i64> {
         if self.modified != 0 {
             Some(i64::from(self.modified))
@@ -130,6 +200,16 @@ impl FileSum {
     pub fn is_sparse(self) -> bool {
         self.sparse
     }
+    /// tell whether the computation was stopped by the timeout before
+    /// the end, in which case the real values may be greater
+    pub fn is_partial(self) -> bool {
+        self.partial
+    }
+    /// tell whether the sum comes from a previous session, in which case
+    /// it may be different from the current one
+    pub fn is_stale(self) -> bool {
+        self.stale
+    }
 }
 
 impl AddAssign for FileSum {
@@ -138,11 +218,14 @@ impl AddAssign for FileSum {
         &mut self,
         other: Self,
     ) {
-        *self = Self::new(
-            self.real_size + other.real_size,
-            self.sparse | other.sparse,
-            self.count + other.count,
-            self.modified.max(other.modified),
-        );
+        *self = Self {
+            real_size: self.real_size + other.real_size,
+            apparent_size: self.apparent_size + other.apparent_size,
+            count: self.count + other.count,
+            modified: self.modified.max(other.modified),
+            sparse: self.sparse | other.sparse,
+            partial: self.partial | other.partial,
+            stale: self.stale | other.stale,
+        };
     }
 }
@@ -0,0 +1,286 @@
+use {
+    super::FileSum,
+    crate::conf::app_dirs,
+    once_cell::sync::Lazy,
+    rustc_hash::FxHashMap,
+    std::{
+        fs,
+        io::{
+            self,
+            BufRead,
+            BufReader,
+            BufWriter,
+            Write,
+        },
+        path::{
+            Path,
+            PathBuf,
+        },
+        sync::Mutex,
+        time::{
+            SystemTime,
+            UNIX_EPOCH,
+        },
+    },
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+
+/// first line of the file, to be changed when the format changes
+const HEADER: &str = "broot file sums v1";
+
+/// max number of persisted sums, the least recently computed
+/// ones being dropped
+const MAX_ENTRIES: usize = 50_000;
+
+static PERSISTED_CACHE: Lazy<Mutex<Option<PersistedCache>>> = Lazy::new(|| Mutex::new(None));
+
+/// What must not have changed for a persisted sum to be used
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DirStamp {
+    inode: u64,
+    /// nanoseconds from Epoch to last modification of the directory
+    mtime: u128,
+}
+
+impl DirStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let md = fs::metadata(path).ok()?;
+        #[cfg(unix)]
+        let inode = md.ino();
+        #[cfg(not(unix))]
+        let inode = 0;
+        let mtime = md
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+        Some(Self { inode, mtime })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PersistedSum {
+    stamp: DirStamp,
+    /// seconds from Epoch to the computation of the sum
+    computed: u64,
+    sum: FileSum,
+}
+
+/// The directory sums computed in previous sessions
+#[derive(Debug, Default)]
+struct PersistedCache {
+    entries: FxHashMap<PathBuf, PersistedSum>,
+    dirty: bool,
+}
+
+impl PersistedCache {
+    fn file_path() -> PathBuf {
+        app_dirs().cache_dir().join("file-sums.txt")
+    }
+    fn load() -> Self {
+        let path = Self::file_path();
+        match fs::File::open(&path) {
+            Ok(file) => match Self::read(BufReader::new(file)) {
+                Ok(cache) => {
+                    info!("{} sums read in {:?}", cache.entries.len(), path);
+                    cache
+                }
+                Err(e) => {
+                    warn!("error while reading {path:?}: {e}");
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(), // the file isn't created yet
+        }
+    }
+    fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut cache = Self::default();
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(HEADER) {
+            info!("obsolete file sums cache, ignored");
+            return Ok(cache);
+        }
+        for line in lines {
+            let line = line?;
+            if let Some((path, persisted)) = parse_line(&line) {
+                cache.entries.insert(path, persisted);
+            } else {
+                warn!("invalid line in file sums cache: {line:?}");
+            }
+        }
+        Ok(cache)
+    }
+    fn write<W: Write>(
+        &self,
+        mut w: W,
+    ) -> io::Result<()> {
+        let mut entries: Vec<(&PathBuf, &PersistedSum)> = self.entries.iter().collect();
+        entries.sort_by_key(|(_, persisted)| std::cmp::Reverse(persisted.computed));
+        writeln!(w, "{HEADER}")?;
+        for (path, p) in entries.into_iter().take(MAX_ENTRIES) {
+            // paths which can't be written on one line aren't persisted
+            let Some(path) = path.to_str().filter(|s| !s.contains('\n')) else {
+                continue;
+            };
+            writeln!(
+                w,
+                "{} {} {} {} {} {} {} {} {}",
+                p.stamp.inode,
+                p.stamp.mtime,
+                p.computed,
+                p.sum.real_size,
+                p.sum.apparent_size,
+                p.sum.count,
+                p.sum.modified,
+                u8::from(p.sum.sparse),
+                path,
+            )?;
+        }
+        w.flush()
+    }
+    fn save(&self) -> io::Result<()> {
+        let path = Self::file_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // we write in a temporary file then rename it, so that a broot
+        // instance never reads a partially written file
+        let tmp_path = path.with_extension("tmp");
+        self.write(BufWriter::new(fs::File::create(&tmp_path)?))?;
+        fs::rename(&tmp_path, &path)?;
+        info!("{} sums written in {:?}", self.entries.len(), path);
+        Ok(())
+    }
+}
+
+fn parse_line(line: &str) -> Option<(PathBuf, PersistedSum)> {
+    let mut tokens = line.splitn(9, ' ');
+    let mut next = || tokens.next();
+    let inode = next()?.parse().ok()?;
+    let mtime = next()?.parse().ok()?;
+    let computed = next()?.parse().ok()?;
+    let real_size = next()?.parse().ok()?;
+    let apparent_size = next()?.parse().ok()?;
+    let count = next()?.parse().ok()?;
+    let modified = next()?.parse().ok()?;
+    let sparse = next()? == "1";
+    let path = PathBuf::from(next()?);
+    let mut sum = FileSum::new(real_size, apparent_size, sparse, count, modified);
+    sum.stale = true;
+    Some((
+        path,
+        PersistedSum {
+            stamp: DirStamp { inode, mtime },
+            computed,
+            sum,
+        },
+    ))
+}
+
+/// Run a function on the persisted cache, reading it from disk if needed
+fn with_cache<R>(f: impl FnOnce(&mut PersistedCache) -> R) -> R {
+    #[allow(clippy::missing_panics_doc)] // panics on mutex poisoning (good)
+    let mut cache = PERSISTED_CACHE.lock().unwrap();
+    f(cache.get_or_insert_with(PersistedCache::load))
+}
+
+/// Return the sum of the directory persisted in a previous session,
+/// if the directory wasn't modified since
+pub fn get(path: &Path) -> Option<FileSum> {
+    let stamp = DirStamp::of(path)?;
+    with_cache(|cache| {
+        cache
+            .entries
+            .get(path)
+            .filter(|persisted| persisted.stamp == stamp)
+            .map(|persisted| persisted.sum)
+    })
+}
+
+/// Remember the sum of a directory, so that it can be used in
+/// the next sessions
+pub fn insert(
+    path: &Path,
+    mut sum: FileSum,
+) {
+    let Some(stamp) = DirStamp::of(path) else {
+        return;
+    };
+    let computed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    sum.stale = true;
+    with_cache(|cache| {
+        cache.entries.insert(
+            path.to_path_buf(),
+            PersistedSum {
+                stamp,
+                computed,
+                sum,
+            },
+        );
+        cache.dirty = true;
+    });
+}
+
+/// Write the persisted cache on disk, if it was modified
+pub fn save() {
+    #[allow(clippy::missing_panics_doc)] // panics on mutex poisoning (good)
+    let mut cache = PERSISTED_CACHE.lock().unwrap();
+    if let Some(cache) = cache.as_mut().filter(|cache| cache.dirty) {
+        match cache.save() {
+            Ok(()) => {
+                cache.dirty = false;
+            }
+            Err(e) => {
+                warn!("error while saving the file sums cache: {e}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod persisted_cache_tests {
+    use super::*;
+
+    #[test]
+    fn test_write_read() {
+        let mut cache = PersistedCache::default();
+        let path = PathBuf::from("/some dir/with spaces");
+        let stamp = DirStamp {
+            inode: 1234,
+            mtime: 1_700_000_000_123_456_789,
+        };
+        let sum = FileSum::new(4096, 3000, true, 12, 1_700_000_000);
+        cache.entries.insert(
+            path.clone(),
+            PersistedSum {
+                stamp,
+                computed: 1_700_000_100,
+                sum,
+            },
+        );
+        let mut bytes = Vec::new();
+        cache.write(&mut bytes).unwrap();
+        let read = PersistedCache::read(bytes.as_slice()).unwrap();
+        assert_eq!(read.entries.len(), 1);
+        let persisted = read.entries.get(&path).unwrap();
+        assert_eq!(persisted.stamp, stamp);
+        assert_eq!(persisted.sum.to_size(), 4096);
+        assert_eq!(persisted.sum.to_apparent_size(), 3000);
+        assert_eq!(persisted.sum.to_count(), 12);
+        assert_eq!(persisted.sum.to_seconds(), 1_700_000_000);
+        assert!(persisted.sum.is_sparse());
+        assert!(persisted.sum.is_stale());
+    }
+
+    #[test]
+    fn test_obsolete_file_is_ignored() {
+        let content = "broot file sums v0\n1 2 3 4 5 6 7 0 /a/path\n";
+        let read = PersistedCache::read(content.as_bytes()).unwrap();
+        assert!(read.entries.is_empty());
+    }
+}
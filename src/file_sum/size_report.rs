@@ -0,0 +1,139 @@
+use {
+    super::*,
+    std::cmp::Reverse,
+};
+
+/// Breakdown of the size of a directory, per file extension
+/// and per direct child of the directory.
+#[derive(Debug, Clone)]
+pub struct SizeReport {
+    /// the directory
+    pub root: PathBuf,
+    /// the sum of the whole directory
+    pub total: FileSum,
+    /// sums of the files (not the directories) grouped by lowercase
+    /// extension, with an empty key for files without extension.
+    /// Sorted by decreasing size (disk usage unless sorted again)
+    pub extensions: Vec<(String, FileSum)>,
+    /// sums of the direct children of the root, sorted by decreasing
+    /// size (disk usage unless sorted again)
+    pub children: Vec<(PathBuf, FileSum)>,
+}
+
+impl SizeReport {
+    pub fn new(root: PathBuf) -> Self {
+        let total = sum_computation::compute_file_sum(&root);
+        Self {
+            root,
+            total,
+            extensions: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// compute the report of a directory, using the same threads and the
+    /// same hard link deduplication than for directory sums.
+    ///
+    /// Return None if the computation was interrupted.
+    pub fn compute(
+        path: &Path,
+        dam: &Dam,
+        con: &AppContext,
+    ) -> Option<Self> {
+        time!(
+            "size report computation",
+            path,
+            sum_computation::compute_dir_report(path, dam, con),
+        )
+    }
+
+    pub(super) fn set_content(
+        &mut self,
+        children: Vec<(PathBuf, FileSum)>,
+        extensions: FxHashMap<String, FileSum>,
+    ) {
+        for (_, sum) in &children {
+            self.total += *sum;
+        }
+        self.children = children;
+        self.extensions = extensions.into_iter().collect();
+        self.sort_by_size(false);
+    }
+
+    /// sort children and extensions by decreasing apparent
+    /// size or disk usage
+    pub fn sort_by_size(
+        &mut self,
+        apparent: bool,
+    ) {
+        self.children
+            .sort_by_key(|(_, sum)| Reverse(sum.size(apparent)));
+        self.extensions
+            .sort_by_key(|(_, sum)| Reverse(sum.size(apparent)));
+    }
+}
+
+#[cfg(test)]
+mod size_report_tests {
+    use {
+        super::*,
+        std::fs,
+    };
+
+    #[test]
+    fn test_size_report_groups() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/deep")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("src/deep/lib.RS"), "// lib").unwrap();
+        fs::write(root.join("README.md"), "# readme").unwrap();
+        fs::write(root.join("LICENSE"), "MIT").unwrap();
+        let con = AppContext::default();
+        let report = SizeReport::compute(root, &Dam::unlimited(), &con).unwrap();
+        assert_eq!(report.children.len(), 3);
+        let src = report
+            .children
+            .iter()
+            .find(|(path, _)| path.ends_with("src"))
+            .unwrap();
+        // src, src/deep, and the two files
+        assert_eq!(src.1.to_count(), 4);
+        let count_of = |ext: &str| {
+            report
+                .extensions
+                .iter()
+                .find(|(e, _)| e == ext)
+                .map(|(_, sum)| sum.to_count())
+        };
+        assert_eq!(count_of("rs"), Some(2));
+        let rs_sum = report.extensions.iter().find(|(e, _)| e == "rs").unwrap().1;
+        assert_eq!(rs_sum.to_apparent_size(), 18);
+        assert_eq!(count_of("md"), Some(1));
+        assert_eq!(count_of(""), Some(1));
+        assert_eq!(report.total.to_count(), 7);
+    }
+
+    #[test]
+    fn test_size_report_excludes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/target")).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("src/target/main.o"), "binary").unwrap();
+        fs::write(root.join("src/main.o"), "binary").unwrap();
+        let con = AppContext {
+            file_sum_excludes: vec![
+                glob::Pattern::new("**/target").unwrap(),
+                glob::Pattern::new("**/*.o").unwrap(),
+            ],
+            ..AppContext::default()
+        };
+        let report = SizeReport::compute(root, &Dam::unlimited(), &con).unwrap();
+        assert_eq!(report.extensions.len(), 1);
+        assert_eq!(report.extensions[0].0, "rs");
+        // src and src/main.rs
+        assert_eq!(report.children[0].1.to_count(), 2);
+        assert!(!report.total.is_partial());
+    }
+}
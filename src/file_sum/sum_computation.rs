@@ -1,5 +1,8 @@
 use {
-    super::FileSum,
+    super::{
+        FileSum,
+        SizeReport,
+    },
     crate::{
         app::*,
         path::*,
@@ -9,7 +12,10 @@ use {
         ThreadPool,
         ThreadPoolBuilder,
     },
-    rustc_hash::FxHashMap,
+    rustc_hash::{
+        FxHashMap,
+        FxHashSet,
+    },
     std::{
         convert::TryInto,
         fs,
@@ -20,11 +26,13 @@ use {
         sync::{
             Arc,
             Mutex,
+            MutexGuard,
             atomic::{
                 AtomicIsize,
                 Ordering,
             },
         },
+        time::Instant,
     },
     termimad::crossbeam::channel,
 };
@@ -37,6 +45,68 @@ struct DirSummer {
     thread_pool: ThreadPool,
 }
 
+/// The rules telling what must be skipped during the computation
+/// of the sums below a directory, and when to stop it
+#[derive(Clone)]
+struct SumFilter {
+    special_paths: SpecialPaths,
+    excludes: Arc<Vec<glob::Pattern>>,
+    /// the device of the root, when we must stay on its filesystem
+    #[cfg(unix)]
+    dev: Option<u64>,
+    deadline: Option<Instant>,
+}
+
+impl SumFilter {
+    fn new(
+        root: &Path,
+        con: &AppContext,
+    ) -> Self {
+        #[cfg(unix)]
+        let dev = if con.file_sum_one_filesystem {
+            fs::metadata(root).ok().map(|md| md.dev())
+        } else {
+            None
+        };
+        Self {
+            special_paths: con.special_paths.reduce(root),
+            excludes: Arc::new(con.file_sum_excludes.clone()),
+            #[cfg(unix)]
+            dev,
+            deadline: con.file_sum_timeout.map(|timeout| Instant::now() + timeout),
+        }
+    }
+    /// tell whether the entry (file or directory) must be counted
+    fn accepts(
+        &self,
+        path: &Path,
+        md: &fs::Metadata,
+    ) -> bool {
+        if self.excludes.iter().any(|glob| glob.matches_path(path)) {
+            debug!("not summing excluded path {path:?}");
+            return false;
+        }
+        if md.is_dir() {
+            if self.special_paths.sum(path) == Directive::Never {
+                debug!("not summing (deep) special path {path:?}");
+                return false;
+            }
+            #[cfg(unix)]
+            if self.dev.is_some_and(|dev| dev != md.dev()) {
+                debug!("not summing {path:?} on another filesystem");
+                return false;
+            }
+        }
+        true
+    }
+    /// tell whether the computation must be stopped, the sums
+    /// being then partial
+    fn is_late(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() > deadline)
+    }
+}
+
 /// a node id, taking the device into account to be sure to discriminate
 /// nodes with the same inode but on different devices
 #[cfg(unix)]
@@ -72,23 +142,13 @@ impl DirSummer {
     ) -> Option<FileSum> {
         let threads_count = self.thread_count;
 
-        if con.special_paths.sum(path) == Directive::Never {
-            return Some(FileSum::zero());
-        }
-
-        // there are problems in /proc - See issue #637
-        if path.starts_with("/proc") {
-            debug!("not summing in /proc");
-            return Some(FileSum::zero());
-        }
-        if path.starts_with("/run") && !path.starts_with("/run/media") {
-            debug!("not summing in /run");
+        if is_never_summed(path, con) {
             return Some(FileSum::zero());
         }
 
         // to avoid counting twice a node, we store their id in a set
         #[cfg(unix)]
-        let nodes = Arc::new(Mutex::new(rustc_hash::FxHashSet::<NodeId>::default()));
+        let nodes = Arc::new(Mutex::new(FxHashSet::<NodeId>::default()));
 
         // busy is the number of directories which are either being processed or queued
         // We use this count to determine when threads can stop waiting for tasks
@@ -99,7 +159,7 @@ impl DirSummer {
         // A None means there's nothing left and the thread may send its result and stop
         let (dirs_sender, dirs_receiver) = channel::unbounded();
 
-        let special_paths = con.special_paths.reduce(path);
+        let filter = SumFilter::new(path, con);
 
         // the first level is managed a little differently: we look at the cache
         // before adding. This enables faster computations in two cases:
@@ -108,9 +168,11 @@ impl DirSummer {
         if let Ok(entries) = fs::read_dir(path) {
             for e in entries.flatten() {
                 if let Ok(md) = e.metadata() {
+                    let entry_path = e.path();
+                    if !filter.accepts(&entry_path, &md) {
+                        continue;
+                    }
                     if md.is_dir() {
-                        let entry_path = e.path();
-
                         if con.special_paths.sum(&entry_path) == Directive::Never {
                             debug!("not summing special path {entry_path:?}");
                             continue;
@@ -128,16 +190,8 @@ impl DirSummer {
                         dirs_sender.send(Some(entry_path)).unwrap();
                     } else {
                         #[cfg(unix)]
-                        if md.nlink() > 1 {
-                            let mut nodes = nodes.lock().unwrap();
-                            let node_id = NodeId {
-                                inode: md.ino(),
-                                dev: md.dev(),
-                            };
-                            if !nodes.insert(node_id) {
-                                // it was already in the set
-                                continue;
-                            }
+                        if !is_first_link(&nodes, &md) {
+                            continue;
                         }
                     }
                     sum += md_sum(&md);
@@ -164,7 +218,7 @@ impl DirSummer {
             #[cfg(unix)]
             let nodes = nodes.clone();
 
-            let special_paths = special_paths.clone();
+            let filter = filter.clone();
 
             let observer = dam.observer();
             let thread_sum_sender = thread_sum_sender.clone();
@@ -176,30 +230,19 @@ impl DirSummer {
                         if let Ok(entries) = fs::read_dir(open_dir) {
                             for e in entries.flatten() {
                                 if let Ok(md) = e.metadata() {
+                                    let path = e.path();
+                                    if !filter.accepts(&path, &md) {
+                                        continue;
+                                    }
                                     if md.is_dir() {
-                                        let path = e.path();
-
-                                        if special_paths.sum(&path) == Directive::Never {
-                                            debug!("not summing (deep) special path {path:?}");
-                                            continue;
-                                        }
-
                                         // we add the directory to the channel of dirs needing
                                         // processing
                                         busy.fetch_add(1, Ordering::Relaxed);
                                         dirs_sender.send(Some(path)).unwrap();
                                     } else {
                                         #[cfg(unix)]
-                                        if md.nlink() > 1 {
-                                            let mut nodes = nodes.lock().unwrap();
-                                            let node_id = NodeId {
-                                                inode: md.ino(),
-                                                dev: md.dev(),
-                                            };
-                                            if !nodes.insert(node_id) {
-                                                // it was already in the set
-                                                continue;
-                                            }
+                                        if !is_first_link(&nodes, &md) {
+                                            continue;
                                         }
                                     }
                                     thread_sum += md_sum(&md);
@@ -215,10 +258,16 @@ impl DirSummer {
                         dirs_sender.send(None).unwrap(); // to unlock the next waiting thread
                         break;
                     }
+                    // completion is checked first, so that a complete sum isn't marked partial
                     if busy.load(Ordering::Relaxed) < 1 {
                         dirs_sender.send(None).unwrap(); // to unlock the next waiting thread
                         break;
                     }
+                    if filter.is_late() {
+                        thread_sum.partial = true;
+                        dirs_sender.send(None).unwrap(); // to unlock the next waiting thread
+                        break;
+                    }
                 }
                 thread_sum_sender.send(thread_sum).unwrap();
             });
@@ -239,6 +288,162 @@ impl DirSummer {
         }
         Some(sum)
     }
+    /// compute the sums of a directory's content, grouped by extension
+    /// and by direct child.
+    ///
+    /// Contrary to `compute_dir_sum`, the cache isn't used because the
+    /// content of the cached directories must be seen for the extensions.
+    pub fn compute_dir_report(
+        &mut self,
+        path: &Path,
+        dam: &Dam,
+        con: &AppContext,
+    ) -> Option<SizeReport> {
+        let threads_count = self.thread_count;
+        let mut report = SizeReport::new(path.to_path_buf());
+        if is_never_summed(path, con) {
+            return Some(report);
+        }
+
+        #[cfg(unix)]
+        let nodes = Arc::new(Mutex::new(FxHashSet::<NodeId>::default()));
+
+        let mut busy = 0;
+        let mut children: Vec<(PathBuf, FileSum)> = Vec::new();
+        let mut extensions: FxHashMap<String, FileSum> = FxHashMap::default();
+
+        // the channel contains the directories to handle, with the index
+        // of the direct child of the root they're in
+        let (dirs_sender, dirs_receiver) = channel::unbounded();
+
+        let filter = SumFilter::new(path, con);
+
+        if let Ok(entries) = fs::read_dir(path) {
+            for e in entries.flatten() {
+                let Ok(md) = e.metadata() else {
+                    continue;
+                };
+                let entry_path = e.path();
+                if !filter.accepts(&entry_path, &md) {
+                    continue;
+                }
+                if md.is_dir() {
+                    if is_never_summed(&entry_path, con) {
+                        debug!("not summing special path {entry_path:?}");
+                        continue;
+                    }
+                    busy += 1;
+                    dirs_sender
+                        .send(Some((children.len(), entry_path.clone())))
+                        .unwrap();
+                } else {
+                    #[cfg(unix)]
+                    if !is_first_link(&nodes, &md) {
+                        continue;
+                    }
+                    *extensions
+                        .entry(extension_key(&entry_path))
+                        .or_insert_with(FileSum::zero) += md_sum(&md);
+                }
+                children.push((entry_path, md_sum(&md)));
+            }
+        }
+
+        if busy > 0 {
+            let busy = Arc::new(AtomicIsize::new(busy));
+            let (thread_sum_sender, thread_sum_receiver) = channel::bounded(threads_count);
+            let children_count = children.len();
+            for _ in 0..threads_count {
+                let busy = Arc::clone(&busy);
+                let (dirs_sender, dirs_receiver) = (dirs_sender.clone(), dirs_receiver.clone());
+
+                #[cfg(unix)]
+                let nodes = nodes.clone();
+
+                let filter = filter.clone();
+
+                let observer = dam.observer();
+                let thread_sum_sender = thread_sum_sender.clone();
+                self.thread_pool.spawn(move || {
+                    let mut children_sums = vec![FileSum::zero(); children_count];
+                    let mut ext_sums: FxHashMap<String, FileSum> = FxHashMap::default();
+                    loop {
+                        let o = dirs_receiver.recv();
+                        if let Ok(Some((child_idx, open_dir))) = o {
+                            if let Ok(entries) = fs::read_dir(open_dir) {
+                                for e in entries.flatten() {
+                                    let Ok(md) = e.metadata() else {
+                                        children_sums[child_idx].incr();
+                                        continue;
+                                    };
+                                    let path = e.path();
+                                    if !filter.accepts(&path, &md) {
+                                        continue;
+                                    }
+                                    if md.is_dir() {
+                                        busy.fetch_add(1, Ordering::Relaxed);
+                                        dirs_sender.send(Some((child_idx, path))).unwrap();
+                                    } else {
+                                        #[cfg(unix)]
+                                        if !is_first_link(&nodes, &md) {
+                                            continue;
+                                        }
+                                        *ext_sums
+                                            .entry(extension_key(&path))
+                                            .or_insert_with(FileSum::zero) += md_sum(&md);
+                                    }
+                                    children_sums[child_idx] += md_sum(&md);
+                                }
+                            }
+                            busy.fetch_sub(1, Ordering::Relaxed);
+                        }
+                        if observer.has_event() || busy.load(Ordering::Relaxed) < 1 {
+                            dirs_sender.send(None).unwrap(); // to unlock the next waiting thread
+                            break;
+                        }
+                        if filter.is_late() {
+                            // we don't know which children were completed, so all sums are partial
+                            for sum in &mut children_sums {
+                                sum.partial = true;
+                            }
+                            dirs_sender.send(None).unwrap(); // to unlock the next waiting thread
+                            break;
+                        }
+                    }
+                    thread_sum_sender.send((children_sums, ext_sums)).unwrap();
+                });
+            }
+            for _ in 0..threads_count {
+                match thread_sum_receiver.recv() {
+                    Ok((children_sums, ext_sums)) => {
+                        for (child, sum) in children.iter_mut().zip(children_sums) {
+                            child.1 += sum;
+                        }
+                        for (ext, sum) in ext_sums {
+                            *extensions.entry(ext).or_insert_with(FileSum::zero) += sum;
+                        }
+                    }
+                    Err(e) => {
+                        warn!("Error while recv summing thread result : {e:?}");
+                    }
+                }
+            }
+            if dam.has_event() {
+                return None;
+            }
+        }
+        report.set_content(children, extensions);
+        Some(report)
+    }
+}
+
+fn dir_summer(con: &AppContext) -> MutexGuard<'static, DirSummer> {
+    use once_cell::sync::OnceCell;
+    static DIR_SUMMER: OnceCell<Mutex<DirSummer>> = OnceCell::new();
+    DIR_SUMMER
+        .get_or_init(|| Mutex::new(DirSummer::new(con.file_sum_threads_count)))
+        .lock()
+        .unwrap()
 }
 
 /// compute the consolidated numbers for a directory, with implementation
@@ -251,13 +456,63 @@ pub fn compute_dir_sum(
     dam: &Dam,
     con: &AppContext,
 ) -> Option<FileSum> {
-    use once_cell::sync::OnceCell;
-    static DIR_SUMMER: OnceCell<Mutex<DirSummer>> = OnceCell::new();
-    DIR_SUMMER
-        .get_or_init(|| Mutex::new(DirSummer::new(con.file_sum_threads_count)))
-        .lock()
-        .unwrap()
-        .compute_dir_sum(path, cache, dam, con)
+    dir_summer(con).compute_dir_sum(path, cache, dam, con)
+}
+
+/// compute the breakdown of the size of a directory, per extension
+/// and per direct child
+pub fn compute_dir_report(
+    path: &Path,
+    dam: &Dam,
+    con: &AppContext,
+) -> Option<SizeReport> {
+    dir_summer(con).compute_dir_report(path, dam, con)
+}
+
+/// tell whether the directory must not be entered when summing
+fn is_never_summed(
+    path: &Path,
+    con: &AppContext,
+) -> bool {
+    if con.special_paths.sum(path) == Directive::Never {
+        return true;
+    }
+    // there are problems in /proc - See issue #637
+    if path.starts_with("/proc") {
+        debug!("not summing in /proc");
+        return true;
+    }
+    if path.starts_with("/run") && !path.starts_with("/run/media") {
+        debug!("not summing in /run");
+        return true;
+    }
+    false
+}
+
+/// tell whether the node wasn't already seen through another hard link,
+/// and remember it
+#[cfg(unix)]
+fn is_first_link(
+    nodes: &Mutex<FxHashSet<NodeId>>,
+    md: &fs::Metadata,
+) -> bool {
+    if md.nlink() < 2 {
+        return true;
+    }
+    let node_id = NodeId {
+        inode: md.ino(),
+        dev: md.dev(),
+    };
+    nodes.lock().unwrap().insert(node_id)
+}
+
+/// the lowercase extension used as key in size reports, empty
+/// when there's no extension
+fn extension_key(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .unwrap_or_default()
 }
 
 /// compute the sum for a regular file (not a folder)
@@ -272,6 +527,7 @@ pub fn compute_file_sum(path: &Path) -> FileSum {
                 let block_size = md.blocks() * 512;
                 FileSum::new(
                     block_size.min(nominal_size),
+                    nominal_size,
                     block_size < nominal_size,
                     1,
                     seconds,
@@ -279,9 +535,9 @@ pub fn compute_file_sum(path: &Path) -> FileSum {
             }
 
             #[cfg(not(unix))]
-            FileSum::new(md.len(), false, 1, seconds)
+            FileSum::new(md.len(), md.len(), false, 1, seconds)
         }
-        Err(_) => FileSum::new(0, false, 1, 0),
+        Err(_) => FileSum::new(0, 0, false, 1, 0),
     }
 }
 
@@ -310,5 +566,5 @@ fn md_sum(md: &fs::Metadata) -> FileSum {
     let size = md.len();
 
     let seconds = extract_seconds(md);
-    FileSum::new(size, false, 1, seconds)
+    FileSum::new(size, md.len(), false, 1, seconds)
 }
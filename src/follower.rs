@@ -0,0 +1,109 @@
+use {
+    crate::command::Sequence,
+    std::{
+        fs,
+        path::{
+            Path,
+            PathBuf,
+        },
+        sync::{
+            Arc,
+            Mutex,
+        },
+        thread,
+        time::{
+            Duration,
+            SystemTime,
+        },
+    },
+    termimad::crossbeam::channel,
+};
+
+/// delay between two checks of the followed files
+const POLL_PERIOD: Duration = Duration::from_millis(300);
+
+/// What's compared to detect a change of a followed file
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let md = fs::metadata(path).ok()?;
+        Some(Self {
+            len: md.len(),
+            modified: md.modified().ok(),
+        })
+    }
+}
+
+/// the followed paths, with their last seen stamps
+type Followed = Arc<Mutex<Vec<(PathBuf, Option<FileStamp>)>>>;
+
+/// Poll the files followed by previews (like `tail -f`), and send a
+/// `:refresh_followed` sequence when one of them changes.
+///
+/// Contrary to the watcher, which ignores data changes as they're very
+/// noisy with log files, the follower is only interested in such changes.
+/// Polling makes it work for all file systems, and for files replaced
+/// by a rotation.
+pub struct Follower {
+    followed: Followed,
+}
+
+impl Follower {
+    pub fn new(tx_seqs: channel::Sender<Sequence>) -> Self {
+        let followed: Followed = Default::default();
+        let polled = Arc::clone(&followed);
+        thread::spawn(move || {
+            loop {
+                thread::sleep(POLL_PERIOD);
+                if !tx_seqs.is_empty() {
+                    // we don't accumulate refreshes when the app is busy,
+                    // changes will be seen at a next check
+                    continue;
+                }
+                let mut changed = false;
+                for (path, stamp) in polled.lock().unwrap().iter_mut() {
+                    let new_stamp = FileStamp::of(path);
+                    if new_stamp != *stamp {
+                        *stamp = new_stamp;
+                        changed = true;
+                    }
+                }
+                if !changed {
+                    continue;
+                }
+                let sequence = Sequence::new_single(":refresh_followed");
+                if tx_seqs.send(sequence).is_err() {
+                    info!("sequence receiver disconnected, stopping follower thread");
+                    break;
+                }
+            }
+        });
+        Self { followed }
+    }
+    /// set the paths to follow, keeping the state of the ones which
+    /// were already followed
+    #[allow(clippy::missing_panics_doc)] // panics on mutex poisoning (good)
+    pub fn follow(
+        &self,
+        paths: Vec<PathBuf>,
+    ) {
+        let mut followed = self.followed.lock().unwrap();
+        if followed.len() == paths.len()
+            && followed.iter().zip(&paths).all(|((a, _), b)| a == b)
+        {
+            return;
+        }
+        *followed = paths
+            .into_iter()
+            .map(|path| {
+                let stamp = FileStamp::of(&path);
+                (path, stamp)
+            })
+            .collect();
+    }
+}
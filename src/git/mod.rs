@@ -15,6 +15,7 @@ pub use {
     status_computer::{
         clear_status_computer_cache,
         get_tree_status,
+        invalidate_status_computer_cache,
     },
 };
 
@@ -10,7 +10,10 @@ use {
     },
     git2::Repository,
     once_cell::sync::Lazy,
-    rustc_hash::FxHashMap,
+    rustc_hash::{
+        FxHashMap,
+        FxHashSet,
+    },
     std::{
         path::{
             Path,
@@ -113,3 +116,18 @@ pub fn clear_status_computer_cache() {
     let mut ts_cache = TS_CACHE_MX.lock().unwrap();
     ts_cache.clear();
 }
+
+/// clear the computations of the repositories containing the
+/// changed paths, keeping the other ones
+#[allow(clippy::missing_panics_doc)] // panics if the mutex is poisoned (in which case it's better)
+pub fn invalidate_status_computer_cache(changed: &[PathBuf]) {
+    let repo_paths: FxHashSet<PathBuf> = changed
+        .iter()
+        .filter_map(|path| git::closest_repo_dir(path))
+        .collect();
+    if repo_paths.is_empty() {
+        return;
+    }
+    let mut ts_cache = TS_CACHE_MX.lock().unwrap();
+    ts_cache.retain(|repo_path, _| !repo_paths.contains(repo_path));
+}
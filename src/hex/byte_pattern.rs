@@ -0,0 +1,115 @@
+/// A sequence of bytes searched in a hex preview
+#[derive(Debug, Clone, PartialEq)]
+pub struct BytePattern {
+    pub raw: String,
+    pub bytes: Vec<u8>,
+}
+
+impl BytePattern {
+    /// Parse a pattern given either as hexadecimal bytes (eg `ca fe`,
+    /// `0xcafe`) or as text. A text made of hex digits may be quoted
+    /// (eg `"cafe"`) to be searched as text.
+    ///
+    /// Return `None` for an empty pattern.
+    pub fn parse(raw: &str) -> Option<Self> {
+        if raw.is_empty() {
+            return None;
+        }
+        let bytes = if let Some(text) = raw
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .filter(|s| !s.is_empty())
+        {
+            text.as_bytes().to_vec()
+        } else if let Some(bytes) = parse_hex_bytes(raw) {
+            bytes
+        } else {
+            raw.as_bytes().to_vec()
+        };
+        Some(Self {
+            raw: raw.to_string(),
+            bytes,
+        })
+    }
+}
+
+/// parse bytes written in hexadecimal, with an optional `0x` prefix
+/// and optional spaces between bytes
+fn parse_hex_bytes(s: &str) -> Option<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    let digits: Vec<u8> = s
+        .split_whitespace()
+        .flat_map(|part| part.bytes())
+        .collect();
+    if digits.is_empty()
+        || !digits.iter().all(u8::is_ascii_hexdigit)
+        || s.split_whitespace().any(|part| part.len() % 2 != 0)
+    {
+        return None;
+    }
+    digits
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+/// Parse an offset in a file, either decimal or hexadecimal with a `0x`
+/// prefix. An offset starting with `+` or `-` is relative to `current`.
+pub fn parse_offset(
+    s: &str,
+    current: usize,
+) -> Option<usize> {
+    let s = s.trim();
+    let (sign, s) = if let Some(s) = s.strip_prefix('+') {
+        (1, s)
+    } else if let Some(s) = s.strip_prefix('-') {
+        (-1, s)
+    } else {
+        (0, s)
+    };
+    let n = if let Some(hex) = s.strip_prefix("0x") {
+        usize::from_str_radix(hex, 16).ok()?
+    } else {
+        s.parse().ok()?
+    };
+    match sign {
+        1 => current.checked_add(n),
+        -1 => current.checked_sub(n),
+        _ => Some(n),
+    }
+}
+
+#[cfg(test)]
+mod byte_pattern_tests {
+    use super::*;
+
+    fn bytes(raw: &str) -> Vec<u8> {
+        BytePattern::parse(raw).unwrap().bytes
+    }
+
+    #[test]
+    fn test_parse_pattern() {
+        assert_eq!(bytes("cafe"), vec![0xca, 0xfe]);
+        assert_eq!(bytes("0xCAFE"), vec![0xca, 0xfe]);
+        assert_eq!(bytes("7f 45 4c 46"), vec![0x7f, b'E', b'L', b'F']);
+        assert_eq!(bytes("\"cafe\""), b"cafe".to_vec());
+        assert_eq!(bytes("ELF"), b"ELF".to_vec());
+        // not bytes, as one part has an odd number of digits
+        assert_eq!(bytes("a bc"), b"a bc".to_vec());
+        assert_eq!(bytes("\""), b"\"".to_vec());
+        assert_eq!(BytePattern::parse(""), None);
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("1024", 5), Some(1024));
+        assert_eq!(parse_offset("0x1f", 5), Some(31));
+        assert_eq!(parse_offset("+0x10", 5), Some(21));
+        assert_eq!(parse_offset("-3", 5), Some(2));
+        assert_eq!(parse_offset("-6", 5), None);
+        assert_eq!(parse_offset("12k", 5), None);
+    }
+}
@@ -1,5 +1,10 @@
 use {
-    super::byte::Byte,
+    super::{
+        BytePattern,
+        byte::Byte,
+        inspector,
+        parse_offset,
+    },
     crate::{
         command::ScrollCommand,
         display::{
@@ -18,6 +23,7 @@ use {
             SetForegroundColor,
         },
     },
+    memchr::memmem,
     memmap2::Mmap,
     std::{
         fs::File,
@@ -26,21 +32,36 @@ use {
     },
     termimad::{
         Area,
+        CompoundStyle,
         CropWriter,
         SPACE_FILLING,
     },
 };
 
+/// number of lines at the bottom of the view showing the values
+/// decoded at the cursor, when there's a cursor
+const INSPECTOR_HEIGHT: usize = 2;
+
 pub struct HexLine {
     pub bytes: Vec<u8>, // from 1 to 16 bytes
 }
 
+/// a byte pattern searched in the file, with its number of occurrences
+struct ByteSearch {
+    pattern: BytePattern,
+    count: usize,
+}
+
 /// a preview showing the content of a file in hexa
 pub struct HexView {
     path: PathBuf,
     len: usize,
     scroll: usize,
-    page_height: usize,
+    /// height of the view, including the inspector
+    height: usize,
+    /// offset of the selected byte
+    cursor: Option<usize>,
+    search: Option<ByteSearch>,
 }
 
 impl HexView {
@@ -50,47 +71,224 @@ impl HexView {
             path,
             len,
             scroll: 0,
-            page_height: 0,
+            height: 0,
+            cursor: None,
+            search: None,
         })
     }
     pub fn line_count(&self) -> usize {
         self.len / 16 + usize::from(self.len % 16 != 0)
     }
+    /// tell whether the inspector is displayed below the bytes
+    fn has_inspector(&self) -> bool {
+        self.cursor.is_some() && self.height > INSPECTOR_HEIGHT + 2
+    }
+    /// number of lines of bytes displayed
+    fn page_height(&self) -> usize {
+        if self.has_inspector() {
+            self.height - INSPECTOR_HEIGHT
+        } else {
+            self.height
+        }
+    }
+    /// map the file in memory, or return None if it's empty
+    fn map(&mut self) -> io::Result<Option<Mmap>> {
+        let file = File::open(&self.path)?;
+        if self.len == 0 {
+            return Ok(None);
+        }
+        let mmap = unsafe { Mmap::map(&file)? };
+        let new_len = mmap.len();
+        if new_len != self.len {
+            warn!(
+                "previewed file len changed from {} to {}",
+                self.len, new_len
+            );
+            self.len = new_len;
+            self.cursor = self
+                .cursor
+                .filter(|_| new_len > 0)
+                .map(|cursor| cursor.min(new_len - 1));
+            if new_len == 0 {
+                return Ok(None);
+            }
+        }
+        Ok(Some(mmap))
+    }
     pub fn try_scroll(
         &mut self,
         cmd: ScrollCommand,
     ) -> bool {
         let old_scroll = self.scroll;
-        self.scroll = cmd.apply(self.scroll, self.line_count(), self.page_height);
+        self.scroll = cmd.apply(self.scroll, self.line_count(), self.page_height());
+        if let Some(cursor) = self.cursor {
+            // the cursor follows the content
+            let cursor = (cursor + 16 * self.scroll).saturating_sub(16 * old_scroll);
+            self.cursor = Some(cursor.min(self.len - 1));
+        }
         self.scroll != old_scroll
     }
     pub fn select_first(&mut self) {
         self.scroll = 0;
+        if self.cursor.is_some() {
+            self.cursor = Some(0);
+        }
     }
     pub fn select_last(&mut self) {
-        if self.page_height < self.line_count() {
-            self.scroll = self.line_count() - self.page_height;
+        if self.cursor.is_some() {
+            self.cursor = Some(self.len - 1);
+        }
+        if self.page_height() < self.line_count() {
+            self.scroll = self.line_count() - self.page_height();
+        }
+    }
+    pub fn unselect(&mut self) {
+        self.cursor = None;
+    }
+    /// Move the cursor by `delta` bytes, or put it on the first
+    /// visible byte if there was none.
+    pub fn move_cursor(
+        &mut self,
+        delta: i64,
+    ) -> bool {
+        if self.len == 0 {
+            return false;
+        }
+        let old_cursor = self.cursor;
+        let cursor = match self.cursor {
+            Some(cursor) => cursor
+                .saturating_add_signed(delta as isize)
+                .min(self.len - 1),
+            None => (16 * self.scroll).min(self.len - 1),
+        };
+        self.select_offset(cursor);
+        self.cursor != old_cursor
+    }
+    pub fn try_select_y(
+        &mut self,
+        y: u16,
+    ) -> bool {
+        let line = self.scroll + y as usize;
+        if y as usize >= self.page_height() || line >= self.line_count() {
+            return false;
+        }
+        let column = self.cursor.map_or(0, |cursor| cursor % 16);
+        self.cursor = Some((16 * line + column).min(self.len - 1));
+        true
+    }
+    /// Put the cursor on the byte at the given offset, scrolling
+    /// if necessary to make it visible
+    fn select_offset(
+        &mut self,
+        offset: usize,
+    ) {
+        self.cursor = Some(offset);
+        let line = offset / 16;
+        let page_height = self.page_height().max(1);
+        if line < self.scroll {
+            self.scroll = line;
+        } else if line >= self.scroll + page_height {
+            self.scroll = line + 1 - page_height;
+        }
+    }
+    /// Move the cursor to the offset described by `s`, either absolute
+    /// or relative to the cursor (eg `0x1a0`, `+32`).
+    /// Return false if the offset is invalid or out of the file.
+    pub fn go_to_offset(
+        &mut self,
+        s: &str,
+    ) -> bool {
+        match parse_offset(s, self.cursor.unwrap_or(0)) {
+            Some(offset) if offset < self.len => {
+                self.select_offset(offset);
+                true
+            }
+            _ => false,
+        }
+    }
+    /// Search a byte pattern, moving the cursor to the first occurrence
+    /// after it. Return the number of occurrences in the file.
+    ///
+    /// An empty pattern removes the current search.
+    pub fn search(
+        &mut self,
+        raw: &str,
+    ) -> Result<usize, ProgramError> {
+        let Some(pattern) = BytePattern::parse(raw) else {
+            self.search = None;
+            return Ok(0);
+        };
+        let Some(mmap) = self.map()? else {
+            return Ok(0);
+        };
+        let count = memmem::find_iter(&mmap, &pattern.bytes).count();
+        let from = self.cursor.unwrap_or(16 * self.scroll);
+        self.select_next_occurrence(&mmap, &pattern.bytes, from);
+        self.search = Some(ByteSearch { pattern, count });
+        Ok(count)
+    }
+    /// Select the first occurrence of the needle starting at or after
+    /// `from`, or the first one of the file if there's none
+    fn select_next_occurrence(
+        &mut self,
+        hay: &[u8],
+        needle: &[u8],
+        from: usize,
+    ) {
+        let from = from.min(hay.len());
+        let finder = memmem::Finder::new(needle);
+        let offset = finder
+            .find(&hay[from..])
+            .map(|idx| from + idx)
+            .or_else(|| finder.find(hay));
+        if let Some(offset) = offset {
+            self.select_offset(offset);
+        }
+    }
+    pub fn has_search(&self) -> bool {
+        self.search.is_some()
+    }
+    /// Move the cursor to the next occurrence of the searched pattern,
+    /// cycling to the start of the file when there's none after it
+    pub fn next_match(&mut self) -> Result<(), ProgramError> {
+        let Some(needle) = self.search.as_ref().map(|s| s.pattern.bytes.clone()) else {
+            return Ok(());
+        };
+        let Some(mmap) = self.map()? else {
+            return Ok(());
+        };
+        let from = self.cursor.map_or(0, |cursor| cursor + 1);
+        self.select_next_occurrence(&mmap, &needle, from);
+        Ok(())
+    }
+    /// Move the cursor to the previous occurrence of the searched
+    /// pattern, cycling to the end of the file when there's none before it
+    pub fn previous_match(&mut self) -> Result<(), ProgramError> {
+        let Some(needle) = self.search.as_ref().map(|s| s.pattern.bytes.clone()) else {
+            return Ok(());
+        };
+        let Some(mmap) = self.map()? else {
+            return Ok(());
+        };
+        let cursor = self.cursor.unwrap_or(0);
+        // the occurrence must start before the cursor
+        let end = (cursor + needle.len()).saturating_sub(1).min(self.len);
+        let finder = memmem::FinderRev::new(&needle);
+        let offset = finder.rfind(&mmap[..end]).or_else(|| finder.rfind(&mmap));
+        if let Some(offset) = offset {
+            self.select_offset(offset);
         }
+        Ok(())
     }
     pub fn get_page(
         &mut self,
         start_line_idx: usize,
         line_count: usize,
     ) -> io::Result<Vec<HexLine>> {
-        let file = File::open(&self.path)?;
         let mut lines = Vec::new();
-        if self.len == 0 {
+        let Some(mmap) = self.map()? else {
             return Ok(lines);
-        }
-        let mmap = unsafe { Mmap::map(&file)? };
-        let new_len = mmap.len();
-        if new_len != self.len {
-            warn!(
-                "previewed file len changed from {} to {}",
-                self.len, new_len
-            );
-            self.len = new_len;
-        }
+        };
         let mut start_idx = 16 * start_line_idx;
         while start_idx < self.len {
             let line_len = 16.min(self.len - start_idx);
@@ -111,9 +309,11 @@ impl HexView {
         panel_skin: &PanelSkin,
         area: &Area,
     ) -> Result<(), ProgramError> {
-        let line_count = area.height as usize;
-        self.page_height = area.height as usize;
+        self.height = area.height as usize;
+        let line_count = self.page_height();
         let page = self.get_page(self.scroll, line_count)?;
+        let page_start = 16 * self.scroll;
+        let matched = self.page_matches(page_start, 16 * line_count)?;
         let addresses_len = if self.len < 0xffff {
             4
         } else if self.len < 0xff_ffff {
@@ -161,7 +361,8 @@ impl HexView {
             margin_around_addresses = true;
             //rem -= 2;
         }
-        let scrollbar = area.scrollbar(self.scroll, self.line_count());
+        let bytes_area = Area::new(area.left, area.top, area.width, line_count as u16);
+        let scrollbar = bytes_area.scrollbar(self.scroll, self.line_count());
         let scrollbar_fg = styles
             .scrollbar_thumb
             .get_fg()
@@ -190,16 +391,21 @@ impl HexView {
                     cw.queue_char(&styles.default, ' ')?;
                 }
                 let line = &page[y];
+                let line_start = 16 * (self.scroll + y);
+                let byte_style = |x: usize, byte: Byte| {
+                    let offset = line_start + x;
+                    let matched = matched.get(offset - page_start).copied().unwrap_or(false);
+                    self.byte_style(offset, byte, matched, panel_skin)
+                };
                 for x in 0..16 {
                     if x == 8 && hex_middle_space {
                         cw.queue_char(&styles.default, ' ')?;
                     }
                     if let Some(b) = line.bytes.get(x) {
-                        let byte = Byte::from(*b);
+                        let style = byte_style(x, Byte::from(*b));
+                        cw.queue_g_string(&style, format!("{b:02x}"))?;
                         if inter_hex {
-                            cw.queue_g_string(byte.style(styles), format!("{b:02x} "))?;
-                        } else {
-                            cw.queue_g_string(byte.style(styles), format!("{b:02x}"))?;
+                            cw.queue_char(&styles.default, ' ')?;
                         }
                     } else {
                         cw.queue_str(&styles.default, if inter_hex { "   " } else { "  " })?;
@@ -213,7 +419,7 @@ impl HexView {
                         }
                         if let Some(b) = line.bytes.get(x) {
                             let byte = Byte::from(*b);
-                            cw.queue_char(byte.style(styles), byte.as_char())?;
+                            cw.queue_char(&byte_style(x, byte), byte.as_char())?;
                         }
                     }
                 }
@@ -226,6 +432,95 @@ impl HexView {
                 w.queue(Print(' '))?;
             }
         }
+        if self.has_inspector() {
+            let inspector_area = Area::new(
+                area.left,
+                area.top + line_count as u16,
+                area.width,
+                INSPECTOR_HEIGHT as u16,
+            );
+            self.display_inspector(w, panel_skin, &inspector_area)?;
+        }
+        Ok(())
+    }
+
+    /// the style of a byte, depending on its category and on whether
+    /// it's under the cursor or part of a match
+    fn byte_style(
+        &self,
+        offset: usize,
+        byte: Byte,
+        matched: bool,
+        panel_skin: &PanelSkin,
+    ) -> CompoundStyle {
+        let styles = &panel_skin.styles;
+        let mut style = *byte.style(styles);
+        let bg = if self.cursor == Some(offset) {
+            styles.hex_cursor.get_bg()
+        } else if matched {
+            styles.preview_match.get_bg()
+        } else {
+            None
+        };
+        if let Some(bg) = bg {
+            style.set_bg(bg);
+        }
+        style
+    }
+
+    /// tell, for each byte of the given range, whether it's part of an
+    /// occurrence of the searched pattern
+    fn page_matches(
+        &mut self,
+        start: usize,
+        len: usize,
+    ) -> io::Result<Vec<bool>> {
+        let mut matched = vec![false; len];
+        let Some(needle) = self.search.as_ref().map(|s| s.pattern.bytes.clone()) else {
+            return Ok(matched);
+        };
+        let Some(mmap) = self.map()? else {
+            return Ok(matched);
+        };
+        // occurrences starting before the page may end in it
+        let hay_start = (start + 1).saturating_sub(needle.len()).min(mmap.len());
+        let hay_end = (start + len + needle.len()).min(mmap.len());
+        for idx in memmem::find_iter(&mmap[hay_start..hay_end], &needle) {
+            let match_start = hay_start + idx;
+            for offset in match_start..match_start + needle.len() {
+                if let Some(m) = offset.checked_sub(start).and_then(|i| matched.get_mut(i)) {
+                    *m = true;
+                }
+            }
+        }
+        Ok(matched)
+    }
+
+    /// display the values decoded from the bytes at the cursor, one
+    /// line per byte order
+    fn display_inspector(
+        &mut self,
+        w: &mut W,
+        panel_skin: &PanelSkin,
+        area: &Area,
+    ) -> Result<(), ProgramError> {
+        let styles = &panel_skin.styles;
+        let mut bytes = Vec::new();
+        if let (Some(cursor), Some(mmap)) = (self.cursor, self.map()?) {
+            if cursor < mmap.len() {
+                bytes.extend_from_slice(&mmap[cursor..mmap.len().min(cursor + 4)]);
+            }
+        }
+        for (y, (label, big_endian)) in [(" le", false), (" be", true)].into_iter().enumerate() {
+            w.queue(cursor::MoveTo(area.left, area.top + y as u16))?;
+            let mut cw = CropWriter::new(w, area.width as usize);
+            cw.queue_str(&styles.preview_line_number, label)?;
+            for (name, value) in inspector::decode(&bytes, big_endian) {
+                cw.queue_g_string(&styles.preview_line_number, format!("  {name} "))?;
+                cw.queue_g_string(&styles.preview, value.unwrap_or_else(|| "-".to_string()))?;
+            }
+            cw.fill(&styles.default, &SPACE_FILLING)?;
+        }
         Ok(())
     }
 
@@ -246,6 +541,18 @@ impl HexView {
         } else if s.len() + 1 < width {
             s = format!("{s}b");
         }
+        if let Some(cursor) = self.cursor {
+            let position = format!("@0x{cursor:x} ({cursor})  ");
+            if position.len() + s.len() < width {
+                s = format!("{position}{s}");
+            }
+        }
+        if let Some(search) = &self.search {
+            let matches = format!("matches: {}  ", search.count);
+            if matches.len() + s.len() < width {
+                s = format!("{matches}{s}");
+            }
+        }
         w.queue(cursor::MoveTo(
             area.left + area.width - s.len() as u16,
             area.top,
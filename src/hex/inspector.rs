@@ -0,0 +1,71 @@
+/// Decode the bytes at the start of `bytes` as the numeric types
+/// shown by the inspector of the hex view.
+///
+/// A value is `None` when there aren't enough bytes.
+pub fn decode(
+    bytes: &[u8],
+    big_endian: bool,
+) -> [(&'static str, Option<String>); 3] {
+    let u16 = bytes
+        .first_chunk::<2>()
+        .map(|&b| if big_endian { u16::from_be_bytes(b) } else { u16::from_le_bytes(b) });
+    let u32 = bytes
+        .first_chunk::<4>()
+        .map(|&b| if big_endian { u32::from_be_bytes(b) } else { u32::from_le_bytes(b) });
+    let f32 = u32.map(f32::from_bits);
+    [
+        ("u16", u16.map(|v| v.to_string())),
+        ("u32", u32.map(|v| v.to_string())),
+        ("f32", f32.map(format_f32)),
+    ]
+}
+
+/// format a float, with an exponent when it would be long otherwise
+fn format_f32(v: f32) -> String {
+    let abs = v.abs();
+    if abs != 0.0 && !(1e-4..1e9).contains(&abs) {
+        format!("{v:e}")
+    } else {
+        v.to_string()
+    }
+}
+
+#[cfg(test)]
+mod inspector_tests {
+    use super::*;
+
+    fn values(
+        bytes: &[u8],
+        big_endian: bool,
+    ) -> Vec<Option<String>> {
+        decode(bytes, big_endian)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    #[test]
+    fn test_decode() {
+        let bytes = [0x00, 0x00, 0xc0, 0x3f, 0xff];
+        assert_eq!(
+            values(&bytes, false),
+            vec![
+                Some("0".to_string()),
+                Some("1069547520".to_string()),
+                Some("1.5".to_string()),
+            ]
+        );
+        assert_eq!(
+            values(&bytes, true),
+            vec![
+                Some("0".to_string()),
+                Some("49215".to_string()),
+                Some("6.8965e-41".to_string()),
+            ]
+        );
+        assert_eq!(
+            values(&bytes[3..], false),
+            vec![Some("65343".to_string()), None, None]
+        );
+    }
+}
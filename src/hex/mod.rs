@@ -1,4 +1,12 @@
 mod byte;
+mod byte_pattern;
 mod hex_view;
+mod inspector;
 
-pub use hex_view::HexView;
+pub use {
+    byte_pattern::{
+        BytePattern,
+        parse_offset,
+    },
+    hex_view::HexView,
+};
@@ -0,0 +1,446 @@
+use {
+    crate::{
+        command::Sequence,
+        errors::ProgramError,
+        launchable::Launchable,
+        output::CommandOutput,
+    },
+    std::{
+        collections::VecDeque,
+        io::{
+            self,
+            Read,
+        },
+        path::PathBuf,
+        process::{
+            Child,
+            ExitStatus,
+            Output,
+        },
+        sync::{
+            Arc,
+            Mutex,
+        },
+        thread,
+        time::{
+            Duration,
+            Instant,
+        },
+    },
+    termimad::crossbeam::channel::Sender,
+};
+
+/// delay between two checks of the end of a job's process
+const WAIT_PERIOD: Duration = Duration::from_millis(100);
+
+/// delay between two checks of the end of a captured process
+const CAPTURE_WAIT_PERIOD: Duration = Duration::from_millis(10);
+
+/// max time broot waits for the end of a captured command, before
+/// letting it run as a background job
+pub const CAPTURE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// max number of checks of the end of the output readers, after the end
+/// of the process (the pipes may be kept open by its own children)
+const READERS_WAIT_CHECKS: usize = 5;
+
+/// max number of lines of output kept for a job, the oldest ones being dropped
+const MAX_OUTPUT_LINES: usize = 2000;
+
+/// max number of bytes kept for a line of output
+const MAX_LINE_LEN: usize = 4096;
+
+/// An external command to execute as a background job
+#[derive(Debug)]
+pub struct JobRequest {
+    /// the command line, as displayed to the user
+    pub command: String,
+    pub launchable: Launchable,
+    /// whether the panels must be refreshed when the job ends
+    pub refresh_after: bool,
+    /// the process, when it was already started (for a captured
+    /// command which didn't end soon enough)
+    pub process: Option<RunningProcess>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobStatus {
+    Running,
+    Ended(ExitStatus),
+    Killed,
+    /// the process couldn't be waited for
+    Lost,
+}
+
+/// The last lines of the output of a job
+#[derive(Debug, Default)]
+struct OutputLines {
+    lines: VecDeque<String>,
+    /// the start of a line whose end wasn't read yet
+    current: Vec<u8>,
+}
+
+/// A process started without the terminal, whose output is collected
+#[derive(Debug)]
+pub struct RunningProcess {
+    child: Child,
+    working_dir: PathBuf,
+    output: Arc<Mutex<OutputLines>>,
+    readers: Vec<thread::JoinHandle<()>>,
+}
+
+/// What's updated by the thread watching the process
+#[derive(Debug)]
+struct JobProgress {
+    status: JobStatus,
+    ended: Option<Instant>,
+    killed: bool,
+    /// whether the process was reaped, its pid being then free for reuse.
+    /// Only changed while the child is locked
+    exited: bool,
+}
+
+/// An external process running (or having run) in the background,
+/// whose output is collected
+#[derive(Debug)]
+pub struct Job {
+    pub id: usize,
+    pub command: String,
+    pub working_dir: PathBuf,
+    pub refresh_after: bool,
+    started: Instant,
+    child: Arc<Mutex<Child>>,
+    output: Arc<Mutex<OutputLines>>,
+    progress: Arc<Mutex<JobProgress>>,
+    /// whether the end of the job was notified to the user
+    pub(super) notified: bool,
+}
+
+impl JobStatus {
+    pub fn is_running(self) -> bool {
+        self == Self::Running
+    }
+    pub fn is_success(self) -> bool {
+        matches!(self, Self::Ended(status) if status.success())
+    }
+    /// a short description, eg "running" or "exited with code 0"
+    pub fn description(self) -> String {
+        match self {
+            Self::Running => "running".to_string(),
+            Self::Ended(status) => match status.code() {
+                Some(code) => format!("exited with code {code}"),
+                None => "was terminated by a signal".to_string(),
+            },
+            Self::Killed => "killed".to_string(),
+            Self::Lost => "lost".to_string(),
+        }
+    }
+}
+
+impl OutputLines {
+    fn push_bytes(
+        &mut self,
+        bytes: &[u8],
+    ) {
+        for part in bytes.split_inclusive(|&b| b == b'\n') {
+            let (content, ended) = match part.strip_suffix(b"\n") {
+                Some(content) => (content, true),
+                None => (part, false),
+            };
+            let room = MAX_LINE_LEN.saturating_sub(self.current.len());
+            self.current
+                .extend_from_slice(&content[..content.len().min(room)]);
+            if ended {
+                self.end_line();
+            }
+        }
+    }
+    fn end_line(&mut self) {
+        let mut line = String::from_utf8_lossy(&self.current).to_string();
+        if line.ends_with('\r') {
+            line.pop();
+        }
+        self.current.clear();
+        if self.lines.len() == MAX_OUTPUT_LINES {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+    /// the last lines, including the one not ended yet
+    fn tail(
+        &self,
+        max_lines: usize,
+    ) -> Vec<String> {
+        let current = (!self.current.is_empty())
+            .then(|| String::from_utf8_lossy(&self.current).to_string());
+        let ended_count = max_lines.saturating_sub(usize::from(current.is_some()));
+        let start = self.lines.len().saturating_sub(ended_count);
+        self.lines
+            .range(start..)
+            .cloned()
+            .chain(current)
+            .take(max_lines)
+            .collect()
+    }
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for line in &self.lines {
+            bytes.extend_from_slice(line.as_bytes());
+            bytes.push(b'\n');
+        }
+        bytes.extend_from_slice(&self.current);
+        bytes
+    }
+    /// build the output to keep in the history (only the last lines
+    /// of the output are kept)
+    fn command_output(
+        &self,
+        command: String,
+        working_dir: PathBuf,
+        status: ExitStatus,
+    ) -> CommandOutput {
+        let output = Output {
+            status,
+            stdout: self.to_bytes(),
+            stderr: Vec::new(),
+        };
+        CommandOutput::new(command, working_dir, output)
+    }
+}
+
+impl RunningProcess {
+    /// Start the launchable, which must be a program, and the threads
+    /// collecting its output
+    pub fn start(launchable: &Launchable) -> Result<Self, ProgramError> {
+        let (mut child, working_dir) = launchable.spawn_piped()?;
+        let output = Arc::new(Mutex::new(OutputLines::default()));
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(spawn_reader(stdout, Arc::clone(&output)));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(spawn_reader(stderr, Arc::clone(&output)));
+        }
+        Ok(Self {
+            child,
+            working_dir,
+            output,
+            readers,
+        })
+    }
+    /// Wait for the end of the process, at most `timeout`, and return
+    /// its exit status if it ended
+    pub fn wait_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> io::Result<Option<ExitStatus>> {
+        let start = Instant::now();
+        loop {
+            if let Some(status) = self.child.try_wait()? {
+                wait_readers(&self.readers);
+                return Ok(Some(status));
+            }
+            if start.elapsed() >= timeout {
+                return Ok(None);
+            }
+            thread::sleep(CAPTURE_WAIT_PERIOD);
+        }
+    }
+    /// build the output to keep in the history, once the process ended
+    pub fn command_output(
+        &self,
+        command: String,
+        status: ExitStatus,
+    ) -> CommandOutput {
+        self.output
+            .lock()
+            .unwrap()
+            .command_output(command, self.working_dir.clone(), status)
+    }
+}
+
+impl Job {
+    /// Start the process of the job and the threads collecting its
+    /// output and waiting for its end.
+    ///
+    /// When the process ends, a `:refresh_jobs` sequence is sent so
+    /// that the application can notify the user.
+    pub fn start(
+        id: usize,
+        request: JobRequest,
+        tx_seqs: Sender<Sequence>,
+    ) -> Result<Self, ProgramError> {
+        let process = match request.process {
+            Some(process) => process,
+            None => RunningProcess::start(&request.launchable)?,
+        };
+        let RunningProcess {
+            child,
+            working_dir,
+            output,
+            readers,
+        } = process;
+        let progress = Arc::new(Mutex::new(JobProgress {
+            status: JobStatus::Running,
+            ended: None,
+            killed: false,
+            exited: false,
+        }));
+        let child = Arc::new(Mutex::new(child));
+        {
+            let child = Arc::clone(&child);
+            let progress = Arc::clone(&progress);
+            thread::spawn(move || {
+                let status = loop {
+                    let mut locked_child = child.lock().unwrap();
+                    let status = match locked_child.try_wait() {
+                        Ok(Some(status)) => JobStatus::Ended(status),
+                        Ok(None) => {
+                            drop(locked_child);
+                            thread::sleep(WAIT_PERIOD);
+                            continue;
+                        }
+                        Err(e) => {
+                            warn!("error while waiting for job {id}: {e}");
+                            JobStatus::Lost
+                        }
+                    };
+                    // recorded before the child is unlocked, so that kill doesn't
+                    // signal a pid which may have been reused
+                    progress.lock().unwrap().exited = true;
+                    break status;
+                };
+                let ended = Instant::now();
+                // we wait a little for the readers so that the output is complete
+                // on notification
+                wait_readers(&readers);
+                {
+                    let mut progress = progress.lock().unwrap();
+                    progress.status = if progress.killed {
+                        JobStatus::Killed
+                    } else {
+                        status
+                    };
+                    progress.ended = Some(ended);
+                }
+                if let Err(e) = tx_seqs.send(Sequence::new_single(":refresh_jobs")) {
+                    warn!("error when sending end of job {id}: {e}");
+                }
+            });
+        }
+        Ok(Self {
+            id,
+            command: request.command,
+            working_dir,
+            refresh_after: request.refresh_after,
+            started: Instant::now(),
+            child,
+            output,
+            progress,
+            notified: false,
+        })
+    }
+    pub fn status(&self) -> JobStatus {
+        self.progress.lock().unwrap().status
+    }
+    /// the time since start, or the duration of the job if it ended
+    pub fn elapsed(&self) -> Duration {
+        match self.progress.lock().unwrap().ended {
+            Some(ended) => ended - self.started,
+            None => self.started.elapsed(),
+        }
+    }
+    /// kill the process of the job, if it's still running
+    pub fn kill(&self) -> Result<(), ProgramError> {
+        // the child stays locked until the signal is sent, so that the
+        // process can't be reaped in between
+        let mut child = self.child.lock().unwrap();
+        {
+            let mut progress = self.progress.lock().unwrap();
+            if progress.exited {
+                return Ok(());
+            }
+            progress.killed = true;
+        }
+        #[cfg(unix)]
+        {
+            // the process is the leader of its group, we kill the whole group
+            let pgid = child.id() as libc::pid_t;
+            if unsafe { libc::kill(-pgid, libc::SIGKILL) } == 0 {
+                return Ok(());
+            }
+        }
+        child.kill()?;
+        Ok(())
+    }
+    /// the last lines of the output (stdout and stderr interleaved)
+    pub fn output_tail(
+        &self,
+        max_lines: usize,
+    ) -> Vec<String> {
+        self.output.lock().unwrap().tail(max_lines)
+    }
+    /// build the output to keep in the history, once the job ended
+    /// (only the last lines of the output are kept)
+    pub fn command_output(&self) -> Option<CommandOutput> {
+        let JobStatus::Ended(status) = self.status() else {
+            return None;
+        };
+        Some(self.output.lock().unwrap().command_output(
+            self.command.clone(),
+            self.working_dir.clone(),
+            status,
+        ))
+    }
+}
+
+/// spawn a thread appending what's read to the output of a process
+fn spawn_reader<R: Read + Send + 'static>(
+    mut reader: R,
+    output: Arc<Mutex<OutputLines>>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut buf = [0; 4096];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => output.lock().unwrap().push_bytes(&buf[..n]),
+            }
+        }
+    })
+}
+
+/// wait a little for the end of the output readers (the pipes may be
+/// kept open by the children of the process)
+fn wait_readers(readers: &[thread::JoinHandle<()>]) {
+    for _ in 0..READERS_WAIT_CHECKS {
+        if readers.iter().all(|reader| reader.is_finished()) {
+            break;
+        }
+        thread::sleep(WAIT_PERIOD);
+    }
+}
+
+#[cfg(test)]
+mod background_job_tests {
+    use super::*;
+
+    #[test]
+    fn test_output_lines() {
+        let mut output = OutputLines::default();
+        output.push_bytes(b"a\r\nb");
+        output.push_bytes(b"c\nd");
+        assert_eq!(output.tail(2), vec!["bc".to_string(), "d".to_string()]);
+        assert_eq!(output.tail(5), vec!["a", "bc", "d"]);
+        assert!(output.tail(0).is_empty());
+        assert_eq!(output.to_bytes(), b"a\nbc\nd");
+        output.push_bytes(&vec![b'x'; MAX_LINE_LEN + 10]);
+        output.push_bytes(b"\n");
+        assert_eq!(output.tail(1)[0].len(), MAX_LINE_LEN);
+        for _ in 0..MAX_OUTPUT_LINES {
+            output.push_bytes(b"line\n");
+        }
+        assert_eq!(output.lines.len(), MAX_OUTPUT_LINES);
+        assert_eq!(output.tail(1), vec!["line"]);
+    }
+}
@@ -0,0 +1,171 @@
+use {
+    super::*,
+    crate::{
+        command::Sequence,
+        errors::ProgramError,
+    },
+    termimad::crossbeam::channel::Sender,
+};
+
+/// max number of ended (and notified) jobs kept in the list
+const MAX_ENDED_JOBS: usize = 30;
+
+/// The background jobs, running or ended, oldest first
+#[derive(Debug, Default)]
+pub struct JobList {
+    jobs: Vec<Job>,
+    next_id: usize,
+}
+
+impl JobList {
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+    pub fn count_running(&self) -> usize {
+        self.jobs.iter().filter(|job| job.status().is_running()).count()
+    }
+    pub fn get(
+        &self,
+        id: usize,
+    ) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+    /// start a job and return its id (starting at 1)
+    pub fn start(
+        &mut self,
+        request: JobRequest,
+        tx_seqs: Sender<Sequence>,
+    ) -> Result<usize, ProgramError> {
+        self.next_id += 1;
+        let job = Job::start(self.next_id, request, tx_seqs)?;
+        self.jobs.push(job);
+        // only the jobs whose end was notified may be removed
+        let is_evictable = |job: &Job| job.notified && !job.status().is_running();
+        if self.jobs.iter().filter(|job| is_evictable(job)).count() > MAX_ENDED_JOBS {
+            if let Some(idx) = self.jobs.iter().position(is_evictable) {
+                self.jobs.remove(idx);
+            }
+        }
+        Ok(self.next_id)
+    }
+    /// kill the job with the given id, or the only running one
+    /// when no id is given
+    pub fn kill(
+        &self,
+        id: Option<usize>,
+    ) -> Result<&Job, String> {
+        let job = match id {
+            Some(id) => self.get(id).ok_or_else(|| format!("No job #{id}"))?,
+            None => {
+                let mut running = self.jobs.iter().filter(|job| job.status().is_running());
+                match (running.next(), running.next()) {
+                    (Some(job), None) => job,
+                    (None, _) => return Err("No running job".to_string()),
+                    _ => return Err("Several jobs are running, give the id of the job".to_string()),
+                }
+            }
+        };
+        if !job.status().is_running() {
+            return Err(format!("Job #{} isn't running", job.id));
+        }
+        job.kill().map_err(|e| e.to_string())?;
+        Ok(job)
+    }
+    /// return the jobs which ended since the last call, marking
+    /// them as notified
+    pub fn take_ended(&mut self) -> Vec<&Job> {
+        let mut ended = Vec::new();
+        for job in &mut self.jobs {
+            if !job.notified && !job.status().is_running() {
+                job.notified = true;
+                ended.push(&*job);
+            }
+        }
+        ended
+    }
+}
+
+#[cfg(all(test, unix))]
+mod job_list_tests {
+    use {
+        super::*,
+        crate::launchable::Launchable,
+        std::time::Duration,
+        termimad::crossbeam::channel::unbounded,
+    };
+
+    fn request(command: &str) -> JobRequest {
+        JobRequest {
+            command: command.to_string(),
+            launchable: Launchable::Program {
+                exe: "sh".to_string(),
+                args: vec!["-c".to_string(), command.to_string()],
+                working_dir: None,
+                switch_terminal: false,
+                capture_mouse: false,
+                keyboard_enhanced: false,
+            },
+            refresh_after: false,
+            process: None,
+        }
+    }
+
+    #[test]
+    fn test_job_list() {
+        let (tx, rx) = unbounded();
+        let mut jobs = JobList::default();
+        let a = jobs.start(request("echo a; echo b >&2"), tx.clone()).unwrap();
+        let b = jobs.start(request("sleep 10"), tx).unwrap();
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let ended = jobs.take_ended();
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].id, a);
+        assert!(ended[0].status().is_success());
+        // stdout and stderr are read by different threads, so their
+        // order isn't guaranteed
+        let mut output = ended[0].output_tail(2);
+        output.sort();
+        assert_eq!(output, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(jobs.count_running(), 1);
+        assert_eq!(jobs.kill(None).unwrap().id, b);
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let ended = jobs.take_ended();
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].status(), JobStatus::Killed);
+        assert!(jobs.kill(Some(b)).is_err());
+    }
+
+    #[test]
+    fn test_captured_process() {
+        // a chatty command: only the last lines of its output are kept
+        let command = "seq 100000; exit 2";
+        let mut process = RunningProcess::start(&request(command).launchable).unwrap();
+        let status = process
+            .wait_timeout(Duration::from_secs(10))
+            .unwrap()
+            .unwrap();
+        let output = process.command_output(command.to_string(), status);
+        assert_eq!(output.status_description(), "exited with code 2");
+        assert_eq!(output.text.lines().count(), 2000);
+        assert_eq!(output.text.lines().last(), Some("100000"));
+        // a command which doesn't end soon enough goes on as a job
+        let (tx, rx) = unbounded();
+        let mut request = request("sleep 0.5; echo done");
+        let mut process = RunningProcess::start(&request.launchable).unwrap();
+        assert!(process.wait_timeout(Duration::from_millis(50)).unwrap().is_none());
+        request.process = Some(process);
+        let mut jobs = JobList::default();
+        let id = jobs.start(request, tx).unwrap();
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        let ended = jobs.take_ended();
+        assert_eq!(ended[0].id, id);
+        assert!(ended[0].status().is_success());
+        assert_eq!(ended[0].output_tail(1), vec!["done".to_string()]);
+    }
+}
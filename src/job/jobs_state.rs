@@ -0,0 +1,359 @@
+use {
+    super::*,
+    crate::{
+        app::*,
+        command::{
+            Command,
+            TriggerType,
+        },
+        display::{
+            Screen,
+            W,
+        },
+        errors::ProgramError,
+        tree::TreeOptions,
+        tty::TLine,
+        verb::*,
+    },
+    crokey::crossterm::{
+        QueueableCommand,
+        cursor,
+        style::{
+            Print,
+            SetBackgroundColor,
+        },
+    },
+    std::path::{
+        Path,
+        PathBuf,
+    },
+    termimad::{
+        Area,
+        CropWriter,
+        SPACE_FILLING,
+    },
+};
+
+static TITLE: &str = "Jobs";
+
+/// an application state listing the background jobs, with the tail
+/// of the output of the selected one
+pub struct JobsState {
+    /// id of the selected job, the most recent one when None
+    selected_id: Option<usize>,
+    /// index of a clicked line, to be selected on next sync
+    clicked_idx: Option<usize>,
+    scroll: usize,
+    /// screen row of the first line of the list
+    list_top: u16,
+    page_height: usize,
+    /// working dir of the selected job
+    working_dir: PathBuf,
+    tree_options: TreeOptions,
+    mode: Mode,
+}
+
+impl JobsState {
+    pub fn new(
+        app_state: &AppState,
+        tree_options: TreeOptions,
+        con: &AppContext,
+    ) -> JobsState {
+        let mut state = JobsState {
+            selected_id: None,
+            clicked_idx: None,
+            scroll: 0,
+            list_top: 0,
+            page_height: 0,
+            working_dir: app_state.root.clone(),
+            tree_options,
+            mode: con.initial_mode(),
+        };
+        state.sync(&app_state.jobs);
+        state
+    }
+    /// the index in the list of the selected job
+    fn selected_idx(
+        &self,
+        jobs: &JobList,
+    ) -> Option<usize> {
+        match self.selected_id {
+            Some(id) => jobs.jobs().iter().position(|job| job.id == id),
+            None => jobs.len().checked_sub(1),
+        }
+    }
+    /// make sure the selection and the working dir are consistent
+    /// with the current list of jobs
+    fn sync(
+        &mut self,
+        jobs: &JobList,
+    ) {
+        if let Some(job) = self.clicked_idx.take().and_then(|idx| jobs.jobs().get(idx)) {
+            self.selected_id = Some(job.id);
+        }
+        match self.selected_idx(jobs) {
+            Some(idx) => self.working_dir.clone_from(&jobs.jobs()[idx].working_dir),
+            None => self.selected_id = None,
+        }
+    }
+    fn move_selection(
+        &mut self,
+        dy: i32,
+        cycle: bool,
+        jobs: &JobList,
+    ) {
+        let Some(idx) = self.selected_idx(jobs) else {
+            return;
+        };
+        let idx = crate::command::move_sel(idx, jobs.len(), dy, cycle);
+        self.selected_id = Some(jobs.jobs()[idx].id);
+        self.fix_scroll(idx);
+        self.sync(jobs);
+    }
+    fn fix_scroll(
+        &mut self,
+        idx: usize,
+    ) {
+        if idx < self.scroll {
+            self.scroll = idx;
+        } else if self.page_height > 0 && idx >= self.scroll + self.page_height {
+            self.scroll = idx + 1 - self.page_height;
+        }
+    }
+    fn no_opt_selection(&self) -> Selection<'_> {
+        Selection {
+            path: &self.working_dir,
+            stype: SelectionType::Directory,
+            is_exe: false,
+            line: 0,
+        }
+    }
+}
+
+impl PanelState for JobsState {
+    fn get_type(&self) -> PanelStateType {
+        PanelStateType::Jobs
+    }
+
+    fn set_mode(
+        &mut self,
+        mode: Mode,
+    ) {
+        self.mode = mode;
+    }
+
+    fn get_mode(&self) -> Mode {
+        self.mode
+    }
+
+    fn selected_path(&self) -> Option<&Path> {
+        Some(&self.working_dir)
+    }
+
+    fn selection(&self) -> Option<Selection<'_>> {
+        Some(self.no_opt_selection())
+    }
+
+    fn tree_options(&self) -> TreeOptions {
+        self.tree_options.clone()
+    }
+
+    fn with_new_options(
+        &mut self,
+        _screen: Screen,
+        change_options: &dyn Fn(&mut TreeOptions) -> &'static str,
+        _in_new_panel: bool,
+        _con: &AppContext,
+    ) -> CmdResult {
+        change_options(&mut self.tree_options);
+        CmdResult::Keep
+    }
+
+    fn refresh(
+        &mut self,
+        _screen: Screen,
+        _con: &AppContext,
+    ) -> Command {
+        Command::empty()
+    }
+
+    fn on_click(
+        &mut self,
+        _x: u16,
+        y: u16,
+        _screen: Screen,
+        _con: &AppContext,
+    ) -> Result<CmdResult, ProgramError> {
+        if y >= self.list_top && ((y - self.list_top) as usize) < self.page_height {
+            self.clicked_idx = Some((y - self.list_top) as usize + self.scroll);
+        }
+        Ok(CmdResult::Keep)
+    }
+
+    fn display(
+        &mut self,
+        w: &mut W,
+        disc: &DisplayContext,
+    ) -> Result<(), ProgramError> {
+        let jobs = &disc.app_state.jobs;
+        self.sync(jobs);
+        let area = &disc.state_area;
+        let styles = &disc.panel_skin.styles;
+        let width = area.width as usize;
+        w.queue(cursor::MoveTo(area.left, area.top))?;
+        let mut cw = CropWriter::new(w, width);
+        cw.queue_str(&styles.staging_area_title, TITLE)?;
+        let running = jobs.count_running();
+        if running > 0 {
+            cw.queue_str(&styles.staging_area_title, &format!(" - running: {running}"))?;
+        }
+        cw.fill(&styles.staging_area_title, &SPACE_FILLING)?;
+        let height = area.height.saturating_sub(1) as usize;
+        self.list_top = area.top + 1;
+        // the list takes at most half the panel, the rest is for the output
+        self.page_height = jobs.len().clamp(1, (height / 2).max(1));
+        let selected_idx = self.selected_idx(jobs);
+        if let Some(idx) = selected_idx {
+            self.fix_scroll(idx);
+        }
+        for y in 0..self.page_height {
+            w.queue(cursor::MoveTo(area.left, area.top + 1 + y as u16))?;
+            let mut cw = CropWriter::new(w, width);
+            let job_idx = y + self.scroll;
+            let Some(job) = jobs.jobs().get(job_idx) else {
+                if y == 0 {
+                    cw.queue_str(&styles.default, "no job")?;
+                }
+                cw.fill(&styles.default, &SPACE_FILLING)?;
+                continue;
+            };
+            let selected = Some(job_idx) == selected_idx;
+            let status = job.status();
+            let mut status_style = if status.is_running() {
+                styles.job_running
+            } else if status.is_success() {
+                styles.job_success
+            } else {
+                styles.job_failure
+            };
+            let mut style = styles.default;
+            if selected {
+                if let Some(c) = styles.selected_line.get_bg() {
+                    style.set_bg(c);
+                    status_style.set_bg(c);
+                }
+            }
+            cw.queue_str(&style, &format!("#{:<3}", job.id))?;
+            cw.queue_str(&status_style, &format!("{:<22}", status.description()))?;
+            cw.queue_str(&style, &format!("{:>7} ", format_elapsed(job.elapsed())))?;
+            cw.queue_str(&style, &job.command)?;
+            cw.fill(&style, &SPACE_FILLING)?;
+        }
+        let tail_top = area.top + 1 + self.page_height as u16;
+        let tail_height = height.saturating_sub(self.page_height + 1);
+        w.queue(cursor::MoveTo(area.left, tail_top))?;
+        let mut cw = CropWriter::new(w, width);
+        let selected_job = selected_idx.map(|idx| &jobs.jobs()[idx]);
+        if let Some(job) = selected_job {
+            cw.queue_str(&styles.preview_title, &format!("output of #{}", job.id))?;
+        }
+        cw.fill(&styles.preview_title, &SPACE_FILLING)?;
+        let tail = selected_job
+            .map(|job| job.output_tail(tail_height))
+            .unwrap_or_default();
+        let tail_area = Area::new(area.left, tail_top + 1, area.width, tail_height as u16);
+        let bg = styles.preview.get_bg().or_else(|| styles.default.get_bg());
+        for y in 0..tail_height {
+            w.queue(cursor::MoveTo(tail_area.left, tail_area.top + y as u16))?;
+            if let Some(bg) = bg {
+                w.queue(SetBackgroundColor(bg))?;
+            }
+            let mut allowed = width;
+            if let Some(line) = tail.get(y) {
+                allowed -= TLine::from_tty(line).draw_in(w, allowed)?;
+            }
+            if let Some(bg) = bg {
+                w.queue(SetBackgroundColor(bg))?;
+            }
+            for _ in 0..allowed {
+                w.queue(Print(' '))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn no_verb_status(
+        &self,
+        has_previous_state: bool,
+        con: &AppContext,
+        width: usize, // available width
+    ) -> Status {
+        let mut ssb = con
+            .standard_status
+            .builder(PanelStateType::Jobs, self.no_opt_selection(), width);
+        ssb.has_previous_state = has_previous_state;
+        ssb.status()
+    }
+
+    fn on_internal(
+        &mut self,
+        w: &mut W,
+        invocation_parser: Option<&InvocationParser>,
+        internal_exec: &InternalExecution,
+        input_invocation: Option<&VerbInvocation>,
+        trigger_type: TriggerType,
+        app_state: &mut AppState,
+        cc: &CmdContext,
+    ) -> Result<CmdResult, ProgramError> {
+        let jobs = &app_state.jobs;
+        self.sync(jobs);
+        let count = get_arg(input_invocation, internal_exec, 1);
+        let res = match internal_exec.internal {
+            Internal::line_down | Internal::line_down_no_cycle => {
+                let cycle = internal_exec.internal == Internal::line_down;
+                self.move_selection(count, cycle, jobs);
+                CmdResult::Keep
+            }
+            Internal::line_up | Internal::line_up_no_cycle => {
+                let cycle = internal_exec.internal == Internal::line_up;
+                self.move_selection(-count, cycle, jobs);
+                CmdResult::Keep
+            }
+            Internal::select_first => {
+                self.selected_id = jobs.jobs().first().map(|job| job.id);
+                CmdResult::Keep
+            }
+            Internal::select_last => {
+                self.selected_id = None;
+                CmdResult::Keep
+            }
+            Internal::job_kill if input_invocation.and_then(|vi| vi.args.as_ref()).is_none() => {
+                match self.selected_idx(jobs).map(|idx| jobs.jobs()[idx].id) {
+                    Some(id) => kill_job(jobs, Some(id)),
+                    None => CmdResult::error("No selected job"),
+                }
+            }
+            _ => self.on_internal_generic(
+                w,
+                invocation_parser,
+                internal_exec,
+                input_invocation,
+                trigger_type,
+                app_state,
+                cc,
+            )?,
+        };
+        Ok(res)
+    }
+}
+
+/// kill the job with the given id, or the only running one
+pub fn kill_job(
+    jobs: &JobList,
+    id: Option<usize>,
+) -> CmdResult {
+    match jobs.kill(id) {
+        Ok(job) => CmdResult::Message(format!("Job #{} killed", job.id)),
+        Err(e) => CmdResult::DisplayError(e),
+    }
+}
@@ -0,0 +1,35 @@
+mod background_job;
+mod job_list;
+mod jobs_state;
+
+pub use {
+    background_job::*,
+    job_list::*,
+    jobs_state::*,
+};
+
+use std::time::Duration;
+
+/// format a duration for the jobs panel, eg "8s", "3m05s", "2h07m"
+pub fn format_elapsed(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_elapsed() {
+        assert_eq!(format_elapsed(Duration::from_millis(8_500)), "8s");
+        assert_eq!(format_elapsed(Duration::from_secs(185)), "3m05s");
+        assert_eq!(format_elapsed(Duration::from_secs(2 * 3600 + 7 * 60 + 3)), "2h07m");
+    }
+}
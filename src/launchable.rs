@@ -8,7 +8,7 @@ use {
         },
         errors::ProgramError,
         skin::{
-            ExtColorMap,
+            FileColorMap,
             StyleMap,
         },
         tree::Tree,
@@ -35,7 +35,11 @@ use {
         },
         path::PathBuf,
         path::Path,
-        process::Command,
+        process::{
+            Child,
+            Command,
+            Stdio,
+        },
     },
     which::which,
 };
@@ -57,7 +61,7 @@ pub enum Launchable {
     TreePrinter {
         tree: Box<Tree>,
         skin: Box<StyleMap>,
-        ext_colors: ExtColorMap,
+        file_colors: FileColorMap,
         width: u16,
         height: u16,
     },
@@ -117,12 +121,12 @@ impl Launchable {
         tree: &Tree,
         screen: Screen,
         style_map: StyleMap,
-        ext_colors: ExtColorMap,
+        file_colors: FileColorMap,
     ) -> Launchable {
         Launchable::TreePrinter {
             tree: Box::new(tree.clone()),
             skin: Box::new(style_map),
-            ext_colors,
+            file_colors,
             width: screen.width,
             height: tree_print_height(tree.lines.len(), screen.height),
         }
@@ -189,11 +193,11 @@ impl Launchable {
             Launchable::TreePrinter {
                 tree,
                 skin,
-                ext_colors,
+                file_colors,
                 width,
                 height,
             } => {
-                let dp = DisplayableTree::out_of_app(tree, skin, ext_colors, *width, *height);
+                let dp = DisplayableTree::out_of_app(tree, skin, file_colors, *width, *height);
                 dp.write_on(&mut std::io::stdout())
             }
             Launchable::Program {
@@ -267,6 +271,63 @@ impl Launchable {
             }
         }
     }
+
+    /// Build the command of a launchable which must be a program, to
+    /// be executed without the terminal, and return it with its working dir
+    fn detached_command(&self) -> Result<(Command, PathBuf), ProgramError> {
+        let Launchable::Program {
+            exe,
+            args,
+            working_dir,
+            ..
+        } = self
+        else {
+            return Err(ProgramError::Internal {
+                details: "only programs can be executed without the terminal".to_string(),
+            });
+        };
+        let working_dir = working_dir
+            .clone()
+            .or_else(|| env::current_dir().ok())
+            .unwrap_or_default();
+        let mut command = Command::new(exe);
+        command
+            .args(args.iter())
+            .current_dir(&working_dir)
+            .stdin(Stdio::null());
+        Ok((command, working_dir))
+    }
+
+    fn launch_error(
+        &self,
+        source: io::Error,
+    ) -> ProgramError {
+        let program = match self {
+            Launchable::Program { exe, .. } => exe.clone(),
+            _ => String::new(),
+        };
+        ProgramError::LaunchError { program, source }
+    }
+
+    /// Start the launchable, which must be a program, without waiting for
+    /// its end, with its stdout and stderr piped.
+    ///
+    /// On unix, the process is the leader of a new process group.
+    ///
+    /// Return the child process and its working dir.
+    pub fn spawn_piped(&self) -> Result<(Child, PathBuf), ProgramError> {
+        let (mut process_command, working_dir) = self.detached_command()?;
+        // the process gets its own group, so that it doesn't receive the signals
+        // sent to broot and that it can be killed with its children
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut process_command, 0);
+        let child = process_command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|source| self.launch_error(source))?;
+        Ok((child, working_dir))
+    }
 }
 
 fn tree_print_height(
@@ -9,29 +9,40 @@ pub mod conf;
 pub mod content_search;
 pub mod content_type;
 pub mod display;
+pub mod duplicates;
 pub mod errors;
+pub mod file_format;
+pub mod file_info;
 pub mod file_sum;
 pub mod flag;
+pub mod follower;
 pub mod git;
 pub mod graphics;
 pub mod help;
 pub mod hex;
 pub mod icon;
 pub mod image;
+pub mod job;
 pub mod keys;
 pub mod kitty;
 pub mod launchable;
+pub mod markdown;
+pub mod output;
 pub mod path;
 pub mod pattern;
 pub mod permissions;
 pub mod preview;
 pub mod print;
+pub mod session;
 pub mod shell_install;
+pub mod size_report;
 #[cfg(feature = "sixel")]
 pub mod sixel;
 pub mod skin;
 pub mod stage;
+pub mod structured;
 pub mod syntactic;
+pub mod table;
 pub mod task_sync;
 pub mod terminal;
 pub mod tree;
@@ -0,0 +1,392 @@
+use {
+    crate::{
+        app::AppContext,
+        command::ScrollCommand,
+        display::{
+            Screen,
+            W,
+        },
+        errors::ProgramError,
+        skin::PanelSkin,
+        syntactic::{
+            Region,
+            SYNTAXER,
+            WINDOWED_MIN_FILE_SIZE,
+        },
+        tty::TLine,
+    },
+    crokey::crossterm::{
+        QueueableCommand,
+        cursor,
+        style::{
+            Color,
+            Print,
+            SetBackgroundColor,
+            SetForegroundColor,
+        },
+    },
+    std::{
+        fmt::Write,
+        fs,
+        io,
+        path::{
+            Path,
+            PathBuf,
+        },
+    },
+    termimad::{
+        Area,
+        DisplayableLine,
+        FmtText,
+        MadSkin,
+    },
+    unicode_width::UnicodeWidthChar,
+};
+
+/// max number of renderings, for widths other than the current one, kept
+/// so that going back to a width (eg when closing a panel) is immediate
+const MAX_CACHED_RENDERS: usize = 3;
+
+/// A preview of a markdown file, rendered with termimad, the code
+/// blocks being highlighted with syntect.
+///
+/// The file is rendered again when the width of the panel changes,
+/// unless it was recently rendered for this width.
+pub struct MarkdownView {
+    pub path: PathBuf,
+    source: String,
+    /// the lines, as rendered for `width`
+    lines: Vec<TLine>,
+    width: usize,
+    /// previous renderings, with their width, the most recent last
+    cached_renders: Vec<(usize, Vec<TLine>)>,
+    scroll: usize,
+    page_height: usize,
+}
+
+/// A part of the markdown source: either some text that termimad
+/// renders, or a fenced code block
+#[derive(Debug, PartialEq)]
+enum Block<'s> {
+    Text(String),
+    Code {
+        lang: &'s str,
+        lines: Vec<&'s str>,
+    },
+}
+
+impl MarkdownView {
+    pub fn new(path: &Path) -> Result<Self, ProgramError> {
+        if fs::metadata(path)?.len() >= WINDOWED_MIN_FILE_SIZE {
+            return Err(ProgramError::TooBigFile);
+        }
+        let source = fs::read_to_string(path).map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidData => ProgramError::UnprintableFile,
+            _ => e.into(),
+        })?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            source,
+            lines: Vec::new(),
+            width: 0,
+            cached_renders: Vec::new(),
+            scroll: 0,
+            page_height: 0,
+        })
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.page_height)
+    }
+
+    pub fn try_scroll(
+        &mut self,
+        cmd: ScrollCommand,
+    ) -> bool {
+        let old_scroll = self.scroll;
+        self.scroll = cmd
+            .apply(self.scroll, self.lines.len(), self.page_height)
+            .min(self.max_scroll());
+        self.scroll != old_scroll
+    }
+
+    pub fn select_first(&mut self) {
+        self.scroll = 0;
+    }
+    pub fn select_last(&mut self) {
+        self.scroll = self.max_scroll();
+    }
+
+    /// set the lines to the rendering for the width, taking it
+    /// from the cache when possible
+    fn set_width(
+        &mut self,
+        width: usize,
+        skin: &MadSkin,
+        con: &AppContext,
+    ) {
+        let cached = self.cached_renders.iter().position(|(w, _)| *w == width);
+        let lines = match cached {
+            Some(idx) => self.cached_renders.remove(idx).1,
+            None => render(&self.source, skin, width, con),
+        };
+        let old_lines = std::mem::replace(&mut self.lines, lines);
+        if self.width > 0 {
+            self.cached_renders.push((self.width, old_lines));
+            if self.cached_renders.len() > MAX_CACHED_RENDERS {
+                self.cached_renders.remove(0);
+            }
+        }
+        self.width = width;
+    }
+
+    pub fn display(
+        &mut self,
+        w: &mut W,
+        _screen: Screen,
+        panel_skin: &PanelSkin,
+        area: &Area,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let content_width = area.width as usize - 1; // 1 char left for scrollbar
+        if content_width != self.width {
+            let old_count = self.lines.len();
+            self.set_width(content_width, &panel_skin.help_skin, con);
+            // we try to keep the same part of the document in view
+            self.scroll = (self.scroll * self.lines.len())
+                .checked_div(old_count)
+                .unwrap_or(0);
+        }
+        self.page_height = area.height as usize;
+        self.scroll = self.scroll.min(self.max_scroll());
+        let styles = &panel_skin.styles;
+        let bg = styles
+            .preview
+            .get_bg()
+            .or_else(|| styles.default.get_bg())
+            .unwrap_or(Color::Reset);
+        let scrollbar = area.scrollbar(self.scroll, self.lines.len());
+        let scrollbar_fg = styles
+            .scrollbar_thumb
+            .get_fg()
+            .or_else(|| styles.preview.get_fg())
+            .unwrap_or(Color::White);
+        for y in 0..area.height {
+            w.queue(cursor::MoveTo(area.left, area.top + y))?;
+            let mut allowed = content_width;
+            if let Some(tline) = self.lines.get(self.scroll + y as usize) {
+                allowed -= tline.draw_in(w, allowed)?;
+            }
+            w.queue(SetBackgroundColor(bg))?;
+            for _ in 0..allowed {
+                w.queue(Print(' '))?;
+            }
+            if ScrollCommand::is_thumb(area.top + y, scrollbar) {
+                w.queue(SetForegroundColor(scrollbar_fg))?;
+                w.queue(Print('▐'))?;
+            } else {
+                w.queue(Print(' '))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split the source into text parts and fenced code blocks
+fn split_blocks(source: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut text = String::new();
+    let mut lines = source.lines();
+    while let Some(line) = lines.next() {
+        let Some((fence, lang)) = opening_fence(line) else {
+            text.push_str(line);
+            text.push('\n');
+            continue;
+        };
+        if !text.is_empty() {
+            blocks.push(Block::Text(std::mem::take(&mut text)));
+        }
+        let fence_char = fence.chars().next().unwrap();
+        let mut code = Vec::new();
+        for line in lines.by_ref() {
+            let trimmed = line.trim();
+            if trimmed.starts_with(fence) && trimmed.chars().all(|c| c == fence_char) {
+                break;
+            }
+            code.push(line);
+        }
+        blocks.push(Block::Code { lang, lines: code });
+    }
+    if !text.is_empty() {
+        blocks.push(Block::Text(text));
+    }
+    blocks
+}
+
+/// If the line opens a fenced code block, return the fence
+/// and the language
+fn opening_fence(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    if line.len() - trimmed.len() > 3 {
+        return None; // it's an indented code block
+    }
+    let fence_char = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let fence_len = trimmed.chars().take_while(|&c| c == fence_char).count();
+    if fence_len < 3 {
+        return None;
+    }
+    let info = trimmed[fence_len..].trim();
+    if fence_char == '`' && info.contains('`') {
+        return None;
+    }
+    let lang = info
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+        .next()
+        .unwrap_or("");
+    Some((&trimmed[..fence_len], lang))
+}
+
+/// Render the markdown source for the given width
+fn render(
+    source: &str,
+    skin: &MadSkin,
+    width: usize,
+    con: &AppContext,
+) -> Vec<TLine> {
+    let mut lines = Vec::new();
+    for block in split_blocks(source) {
+        match block {
+            Block::Text(text) => {
+                let fmt_text = FmtText::from(skin, &text, Some(width));
+                for line in &fmt_text.lines {
+                    let dl = DisplayableLine::new(skin, line, Some(width));
+                    lines.push(TLine::from_tty(&dl.to_string()));
+                }
+            }
+            Block::Code { lang, lines: code } => {
+                render_code(&code, lang, skin, width, con, &mut lines);
+            }
+        }
+    }
+    lines
+}
+
+/// A row of a code block, made of parts with their foreground color
+#[derive(Default)]
+struct CodeRow {
+    parts: Vec<(Option<Color>, String)>,
+    width: usize,
+}
+
+/// Render a code block, highlighted if the language is known, the
+/// long lines being wrapped
+fn render_code(
+    code: &[&str],
+    lang: &str,
+    skin: &MadSkin,
+    width: usize,
+    con: &AppContext,
+    lines: &mut Vec<TLine>,
+) {
+    let style = &skin.code_block.compound_style;
+    let mut highlighter = if lang.is_empty() {
+        None
+    } else {
+        SYNTAXER.highlighter_for_token(lang, con)
+    };
+    let text_width = width.saturating_sub(2).max(1); // a space on each side
+    let mut rows = Vec::new();
+    for line in code {
+        // syntect needs the end of line for some syntaxes
+        let line = format!("{}\n", line.replace('\t', "    "));
+        let regions: Vec<(Option<Color>, String)> = highlighter
+            .as_mut()
+            .and_then(|h| h.highlight_line(&line, &SYNTAXER.syntax_set).ok())
+            .map(|ranges| {
+                ranges
+                    .iter()
+                    .map(Region::from_syntect)
+                    .map(|region| (Some(region.fg), region.string))
+                    .collect()
+            })
+            .unwrap_or_else(|| vec![(style.get_fg(), line.clone())]);
+        let mut row = CodeRow::default();
+        for (fg, string) in regions {
+            for c in string.chars().filter(|&c| c != '\n' && c != '\r') {
+                let cw = c.width().unwrap_or(0);
+                if row.width + cw > text_width && row.width > 0 {
+                    rows.push(std::mem::take(&mut row));
+                }
+                match row.parts.last_mut() {
+                    Some((last_fg, s)) if *last_fg == fg => s.push(c),
+                    _ => row.parts.push((fg, c.to_string())),
+                }
+                row.width += cw;
+            }
+        }
+        rows.push(row);
+    }
+    // like termimad, we give all rows of the block the same width
+    let block_width = rows.iter().map(|row| row.width).max().unwrap_or(0);
+    let bg = style
+        .get_bg()
+        .map(|bg| SetBackgroundColor(bg).to_string())
+        .unwrap_or_default();
+    for row in rows {
+        let mut s = format!("{bg} ");
+        for (fg, part) in &row.parts {
+            if let Some(fg) = fg {
+                let _ = write!(s, "{}", SetForegroundColor(*fg));
+            }
+            s.push_str(part);
+        }
+        let _ = write!(s, "{:1$}", "", block_width - row.width + 1);
+        lines.push(TLine::from_tty(&s));
+    }
+}
+
+#[cfg(test)]
+mod markdown_view_tests {
+    use super::*;
+
+    #[test]
+    fn test_split_blocks() {
+        let source = "# Title\n\nSome text\n```rust\nfn main() {\n```\n  ~~~~\ncode\n~~~\n~~~~\nend\n";
+        assert_eq!(
+            split_blocks(source),
+            vec![
+                Block::Text("# Title\n\nSome text\n".to_string()),
+                Block::Code {
+                    lang: "rust",
+                    lines: vec!["fn main() {"],
+                },
+                Block::Code {
+                    lang: "",
+                    lines: vec!["code", "~~~"],
+                },
+                Block::Text("end\n".to_string()),
+            ]
+        );
+        // an unclosed block goes to the end of the document
+        assert_eq!(
+            split_blocks("text\n``` js\na\n"),
+            vec![
+                Block::Text("text\n".to_string()),
+                Block::Code {
+                    lang: "js",
+                    lines: vec!["a"],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_opening_fence() {
+        assert_eq!(opening_fence("```"), Some(("```", "")));
+        assert_eq!(opening_fence("````python title=x"), Some(("````", "python")));
+        assert_eq!(opening_fence("   ~~~ sh"), Some(("~~~", "sh")));
+        assert_eq!(opening_fence("    ```"), None);
+        assert_eq!(opening_fence("``"), None);
+        assert_eq!(opening_fence("```a`b"), None);
+    }
+}
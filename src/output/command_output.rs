@@ -0,0 +1,149 @@
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    process::{
+        ExitStatus,
+        Output,
+    },
+};
+
+/// max number of command outputs kept in the history
+pub const MAX_OUTPUTS: usize = 20;
+
+/// The captured output of an external command executed without
+/// leaving broot
+#[derive(Debug, Clone)]
+pub struct CommandOutput {
+    /// identifier given on insertion in the history
+    pub id: usize,
+    /// the executed command line, as displayed to the user
+    pub command: String,
+    pub working_dir: PathBuf,
+    pub status: ExitStatus,
+    /// the standard output followed by the standard error
+    pub text: String,
+}
+
+/// The most recent command outputs, oldest first
+#[derive(Debug, Default)]
+pub struct OutputHistory {
+    outputs: VecDeque<CommandOutput>,
+    next_id: usize,
+}
+
+impl CommandOutput {
+    pub fn new(
+        command: String,
+        working_dir: PathBuf,
+        output: Output,
+    ) -> Self {
+        let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+        if !output.stderr.is_empty() {
+            if !text.is_empty() && !text.ends_with('\n') {
+                text.push('\n');
+            }
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+        }
+        Self {
+            id: 0,
+            command,
+            working_dir,
+            status: output.status,
+            text,
+        }
+    }
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+    /// a short description of the exit status, eg "exited with code 0"
+    pub fn status_description(&self) -> String {
+        match self.status.code() {
+            Some(code) => format!("exited with code {code}"),
+            None => "was terminated by a signal".to_string(),
+        }
+    }
+}
+
+impl OutputHistory {
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+    /// add an output, dropping the oldest one if the history is full,
+    /// and return the id given to the output
+    pub fn push(
+        &mut self,
+        mut output: CommandOutput,
+    ) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        output.id = id;
+        if self.outputs.len() == MAX_OUTPUTS {
+            self.outputs.pop_front();
+        }
+        self.outputs.push_back(output);
+        id
+    }
+    pub fn last(&self) -> Option<&CommandOutput> {
+        self.outputs.back()
+    }
+    pub fn get(
+        &self,
+        id: usize,
+    ) -> Option<&CommandOutput> {
+        self.outputs.iter().find(|o| o.id == id)
+    }
+    /// the position (starting at 1) of the output in the history
+    pub fn position(
+        &self,
+        id: usize,
+    ) -> Option<usize> {
+        self.outputs.iter().position(|o| o.id == id).map(|i| i + 1)
+    }
+    /// the output preceding the one with the given id, if any
+    pub fn previous(
+        &self,
+        id: usize,
+    ) -> Option<&CommandOutput> {
+        self.outputs.iter().rev().find(|o| o.id < id)
+    }
+    /// the output following the one with the given id, if any
+    pub fn next(
+        &self,
+        id: usize,
+    ) -> Option<&CommandOutput> {
+        self.outputs.iter().find(|o| o.id > id)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod output_history_tests {
+    use {
+        super::*,
+        std::process::Command,
+    };
+
+    #[test]
+    fn test_output_history() {
+        let mut history = OutputHistory::default();
+        for i in 0..MAX_OUTPUTS + 3 {
+            let output = Command::new("sh")
+                .args(["-c", &format!("echo out {i}; echo err {i} >&2; exit 3")])
+                .output()
+                .unwrap();
+            history.push(CommandOutput::new(format!("cmd {i}"), PathBuf::from("/"), output));
+        }
+        assert_eq!(history.len(), MAX_OUTPUTS);
+        let last = history.last().unwrap();
+        assert_eq!(last.text, format!("out {n}\nerr {n}\n", n = MAX_OUTPUTS + 2));
+        assert_eq!(last.status_description(), "exited with code 3");
+        assert_eq!(history.position(last.id), Some(MAX_OUTPUTS));
+        assert!(history.get(0).is_none());
+        let previous = history.previous(last.id).unwrap();
+        assert_eq!(previous.command, format!("cmd {}", MAX_OUTPUTS + 1));
+        assert_eq!(history.next(previous.id).unwrap().id, last.id);
+        assert!(history.next(last.id).is_none());
+    }
+}
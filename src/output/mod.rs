@@ -0,0 +1,7 @@
+mod command_output;
+mod output_state;
+
+pub use {
+    command_output::*,
+    output_state::*,
+};
@@ -0,0 +1,325 @@
+use {
+    super::*,
+    crate::{
+        app::*,
+        command::{
+            Command,
+            ScrollCommand,
+            TriggerType,
+        },
+        display::{
+            Screen,
+            W,
+        },
+        errors::ProgramError,
+        tree::TreeOptions,
+        tty::TtyView,
+        verb::*,
+    },
+    crokey::crossterm::{
+        QueueableCommand,
+        cursor,
+    },
+    std::path::{
+        Path,
+        PathBuf,
+    },
+    termimad::{
+        Area,
+        CropWriter,
+        SPACE_FILLING,
+    },
+};
+
+/// an application state displaying the captured output of the
+/// external commands executed without leaving broot.
+///
+/// The most recent output is shown unless the user navigates
+/// in the history (with the previous_match and next_match verbs).
+pub struct OutputState {
+    text_area: Area,
+    dirty: bool, // background must be cleared
+    /// id of the most recent output on last check, so that the
+    /// state can switch to a new output
+    last_id: Option<usize>,
+    /// id of the output which should be displayed
+    shown_id: Option<usize>,
+    /// the view of the displayed output, with the id of this output
+    view: Option<(usize, TtyView)>,
+    working_dir: PathBuf,
+    tree_options: TreeOptions,
+    mode: Mode,
+}
+
+impl OutputState {
+    pub fn new(
+        app_state: &AppState,
+        tree_options: TreeOptions,
+        con: &AppContext,
+    ) -> OutputState {
+        let mut state = OutputState {
+            text_area: Area::uninitialized(), // will be fixed at drawing time
+            dirty: true,
+            last_id: None,
+            shown_id: None,
+            view: None,
+            working_dir: app_state.root.clone(),
+            tree_options,
+            mode: con.initial_mode(),
+        };
+        state.sync(&app_state.outputs);
+        state
+    }
+    /// make sure the view is the one of the output which should be
+    /// displayed, switching to the most recent one when a new output
+    /// was added to the history
+    fn sync(
+        &mut self,
+        outputs: &OutputHistory,
+    ) {
+        let last_id = outputs.last().map(|o| o.id);
+        if last_id != self.last_id {
+            self.last_id = last_id;
+            self.shown_id = last_id;
+        }
+        if self.view.as_ref().map(|(id, _)| *id) == self.shown_id {
+            return;
+        }
+        self.dirty = true;
+        self.view = self.shown_id.and_then(|id| outputs.get(id)).map(|output| {
+            self.working_dir.clone_from(&output.working_dir);
+            let view = TtyView::from_text(&output.working_dir, &output.text);
+            (output.id, view)
+        });
+    }
+    fn no_opt_selection(&self) -> Selection<'_> {
+        Selection {
+            path: &self.working_dir,
+            stype: SelectionType::Directory,
+            is_exe: false,
+            line: 0,
+        }
+    }
+    fn mut_view(&mut self) -> Option<&mut TtyView> {
+        self.view.as_mut().map(|(_, view)| view)
+    }
+}
+
+impl PanelState for OutputState {
+    fn get_type(&self) -> PanelStateType {
+        PanelStateType::Output
+    }
+
+    fn set_mode(
+        &mut self,
+        mode: Mode,
+    ) {
+        self.mode = mode;
+    }
+
+    fn get_mode(&self) -> Mode {
+        self.mode
+    }
+
+    fn selected_path(&self) -> Option<&Path> {
+        Some(&self.working_dir)
+    }
+
+    fn selection(&self) -> Option<Selection<'_>> {
+        Some(self.no_opt_selection())
+    }
+
+    fn tree_options(&self) -> TreeOptions {
+        self.tree_options.clone()
+    }
+
+    fn with_new_options(
+        &mut self,
+        _screen: Screen,
+        change_options: &dyn Fn(&mut TreeOptions) -> &'static str,
+        _in_new_panel: bool,
+        _con: &AppContext,
+    ) -> CmdResult {
+        change_options(&mut self.tree_options);
+        CmdResult::Keep
+    }
+
+    fn refresh(
+        &mut self,
+        _screen: Screen,
+        _con: &AppContext,
+    ) -> Command {
+        self.dirty = true;
+        Command::empty()
+    }
+
+    fn on_click(
+        &mut self,
+        _x: u16,
+        y: u16,
+        _screen: Screen,
+        _con: &AppContext,
+    ) -> Result<CmdResult, ProgramError> {
+        let area = self.text_area.clone();
+        if y >= area.top && y < area.top + area.height {
+            if let Some(view) = self.mut_view() {
+                view.try_select_y(y - area.top);
+            }
+        }
+        Ok(CmdResult::Keep)
+    }
+
+    fn display(
+        &mut self,
+        w: &mut W,
+        disc: &DisplayContext,
+    ) -> Result<(), ProgramError> {
+        self.sync(&disc.app_state.outputs);
+        let state_area = &disc.state_area;
+        if state_area.height < 3 {
+            warn!("area too small for output");
+            return Ok(());
+        }
+        let mut text_area = state_area.clone();
+        text_area.height -= 1;
+        text_area.top += 1;
+        if text_area != self.text_area {
+            self.dirty = true;
+            self.text_area = text_area;
+        }
+        if self.dirty {
+            disc.panel_skin.styles.default.queue_bg(w)?;
+            disc.screen.clear_area_to_right(w, state_area)?;
+            self.dirty = false;
+        }
+        let styles = &disc.panel_skin.styles;
+        let outputs = &disc.app_state.outputs;
+        let output = self.shown_id.and_then(|id| outputs.get(id));
+        w.queue(cursor::MoveTo(state_area.left, state_area.top))?;
+        let mut cw = CropWriter::new(w, state_area.width as usize);
+        let Some(output) = output else {
+            cw.queue_str(&styles.preview_title, "no captured output")?;
+            cw.fill(&styles.preview_title, &SPACE_FILLING)?;
+            return Ok(());
+        };
+        let title = format!(
+            "{}/{} {} ",
+            outputs.position(output.id).unwrap_or(0),
+            outputs.len(),
+            output.status_description(),
+        );
+        cw.queue_str(&styles.preview_title, &title)?;
+        cw.queue_str(&styles.preview_title, &output.command)?;
+        cw.fill(&styles.preview_title, &SPACE_FILLING)?;
+        let text_area = self.text_area.clone();
+        match self.mut_view() {
+            Some(view) if !output.text.is_empty() => {
+                view.display(w, disc.screen, disc.panel_skin, &text_area)?;
+            }
+            _ => {
+                w.queue(cursor::MoveTo(text_area.left, text_area.top))?;
+                let mut cw = CropWriter::new(w, text_area.width as usize);
+                cw.queue_str(&styles.default, "the command wrote nothing")?;
+                cw.fill(&styles.default, &SPACE_FILLING)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn no_verb_status(
+        &self,
+        has_previous_state: bool,
+        con: &AppContext,
+        width: usize, // available width
+    ) -> Status {
+        let mut ssb = con
+            .standard_status
+            .builder(PanelStateType::Output, self.no_opt_selection(), width);
+        ssb.has_previous_state = has_previous_state;
+        ssb.status()
+    }
+
+    fn on_internal(
+        &mut self,
+        w: &mut W,
+        invocation_parser: Option<&InvocationParser>,
+        internal_exec: &InternalExecution,
+        input_invocation: Option<&VerbInvocation>,
+        trigger_type: TriggerType,
+        app_state: &mut AppState,
+        cc: &CmdContext,
+    ) -> Result<CmdResult, ProgramError> {
+        self.sync(&app_state.outputs);
+        let count = get_arg(input_invocation, internal_exec, 1);
+        let res = match internal_exec.internal {
+            Internal::line_down | Internal::line_down_no_cycle => {
+                let cycle = internal_exec.internal == Internal::line_down;
+                if let Some(view) = self.mut_view() {
+                    view.move_selection(count, cycle);
+                }
+                CmdResult::Keep
+            }
+            Internal::line_up | Internal::line_up_no_cycle => {
+                let cycle = internal_exec.internal == Internal::line_up;
+                if let Some(view) = self.mut_view() {
+                    view.move_selection(-count, cycle);
+                }
+                CmdResult::Keep
+            }
+            Internal::page_down => {
+                if let Some(view) = self.mut_view() {
+                    view.try_scroll(ScrollCommand::Pages(1));
+                }
+                CmdResult::Keep
+            }
+            Internal::page_up => {
+                if let Some(view) = self.mut_view() {
+                    view.try_scroll(ScrollCommand::Pages(-1));
+                }
+                CmdResult::Keep
+            }
+            Internal::select_first => {
+                if let Some(view) = self.mut_view() {
+                    view.select_first();
+                }
+                CmdResult::Keep
+            }
+            Internal::select_last => {
+                if let Some((id, view)) = &mut self.view {
+                    if app_state.outputs.get(*id).is_some_and(|o| !o.text.is_empty()) {
+                        view.select_last();
+                    }
+                }
+                CmdResult::Keep
+            }
+            Internal::previous_match | Internal::next_match => {
+                let outputs = &app_state.outputs;
+                let target = self.shown_id.and_then(|id| {
+                    if internal_exec.internal == Internal::previous_match {
+                        outputs.previous(id)
+                    } else {
+                        outputs.next(id)
+                    }
+                });
+                match target {
+                    Some(output) => {
+                        self.shown_id = Some(output.id);
+                        self.sync(outputs);
+                        CmdResult::Keep
+                    }
+                    None => CmdResult::Message("No other output in the history".to_string()),
+                }
+            }
+            _ => self.on_internal_generic(
+                w,
+                invocation_parser,
+                internal_exec,
+                input_invocation,
+                trigger_type,
+                app_state,
+                cc,
+            )?,
+        };
+        Ok(res)
+    }
+}
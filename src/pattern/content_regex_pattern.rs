@@ -4,12 +4,7 @@ use {
     lazy_regex::regex,
     std::{
         fmt,
-        fs::File,
-        io::{
-            self,
-            BufRead,
-            BufReader,
-        },
+        io,
         path::Path,
     },
 };
@@ -57,7 +52,7 @@ impl ContentRegexPattern {
         &self,
         path: &Path,
     ) -> io::Result<bool> {
-        for line in BufReader::new(File::open(path)?).lines() {
+        for line in LineReader::open(path)?.lines() {
             if self.rex.is_match(line?.as_str()) {
                 return Ok(true);
             }
@@ -87,7 +82,7 @@ impl ContentRegexPattern {
         path: &Path,
         desired_len: usize,
     ) -> io::Result<Option<ContentMatch>> {
-        for line in BufReader::new(File::open(path)?).lines() {
+        for line in LineReader::open(path)?.lines() {
             let line = line?;
             if let Some(regex_match) = self.rex.find(line.as_str()) {
                 return Ok(Some(ContentMatch::build(
@@ -107,7 +102,7 @@ impl ContentRegexPattern {
         path: &Path,
     ) -> io::Result<Option<usize>> {
         let mut line_count = 1;
-        for line in BufReader::new(File::open(path)?).lines() {
+        for line in LineReader::open(path)?.lines() {
             let line = line?;
             if self.rex.is_match(line.as_str()) {
                 return Ok(Some(line_count));
@@ -75,7 +75,7 @@ impl DirView {
             app_state: None,
             tree: &self.tree,
             skin: &disc.panel_skin.styles,
-            ext_colors: &disc.con.ext_colors,
+            file_colors: &disc.con.file_colors,
             area: area.clone(),
             in_app: true,
         };
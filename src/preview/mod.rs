@@ -27,4 +27,19 @@ pub enum PreviewMode {
 
     /// Show the content with ANSI escape codes
     Tty,
+
+    /// render the content as markdown
+    Markdown,
+
+    /// show a CSV or TSV file as a table
+    Table,
+
+    /// show a JSON, HJSON, TOML or YAML file as a tree of keys
+    Structured,
+
+    /// show the metadata of the file: inode, owner, times, mime type, etc.
+    Info,
+
+    /// show a summary of a known binary format: ELF, SQLite, PDF, audio or video
+    Format,
 }
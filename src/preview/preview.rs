@@ -5,11 +5,21 @@ use {
         command::ScrollCommand,
         display::*,
         errors::ProgramError,
+        file_format,
+        file_info::InfoView,
         hex::HexView,
         image::ImageView,
+        markdown::MarkdownView,
+        path::path_has_ext,
         pattern::InputPattern,
         skin::PanelSkin,
-        syntactic::TextView,
+        syntactic::{
+            TextView,
+            WINDOWED_MIN_FILE_SIZE,
+            WindowedTextView,
+        },
+        structured::StructuredView,
+        table::TableView,
         task_sync::Dam,
         tty::TtyView,
     },
@@ -33,8 +43,14 @@ pub enum Preview {
     Dir(DirView),
     Image(ImageView),
     Text(TextView),
+    Windowed(WindowedTextView),
     Hex(HexView),
     Tty(TtyView),
+    Markdown(MarkdownView),
+    Table(TableView),
+    Structured(StructuredView),
+    Info(InfoView),
+    Format(InfoView),
     ZeroLen(ZeroLenFileView),
     IoError(io::Error),
 }
@@ -47,14 +63,26 @@ impl Preview {
         preferred_mode: Option<PreviewMode>,
         con: &AppContext,
     ) -> Self {
-        if path.is_file() {
+        // directories have an info preview too
+        if path.is_file() || preferred_mode == Some(PreviewMode::Info) {
             match preferred_mode {
                 Some(PreviewMode::Hex) => Self::hex(path),
                 Some(PreviewMode::Image) => Self::image(path),
                 Some(PreviewMode::Text) => Self::unfiltered_text(path, con),
                 Some(PreviewMode::Tty) => Self::tty(path),
+                Some(PreviewMode::Markdown) => Self::markdown(path, con),
+                Some(PreviewMode::Table) => Self::table(path, con),
+                Some(PreviewMode::Structured) => Self::structured(path, con),
+                Some(PreviewMode::Info) => Self::info(path),
+                Some(PreviewMode::Format) => Self::binary(path),
                 None => {
-                    // automatic behavior: image, text, hex
+                    // automatic behavior: configured mode, image, text, known
+                    // binary format, hex
+                    if let Some(mode) = configured_mode(path, con) {
+                        if let Ok(preview) = Self::with_mode(path, mode, con) {
+                            return preview;
+                        }
+                    }
                     ImageView::new(path)
                         .map(Self::Image)
                         .unwrap_or_else(|_| Self::unfiltered_text(path, con))
@@ -71,13 +99,25 @@ impl Preview {
         mode: PreviewMode,
         con: &AppContext,
     ) -> Result<Self, ProgramError> {
-        if path.is_file() {
+        // directories have an info preview too
+        if path.is_file() || mode == PreviewMode::Info {
             match mode {
                 PreviewMode::Hex => Ok(HexView::new(path.to_path_buf()).map(Self::Hex)?),
                 PreviewMode::Image => ImageView::new(path).map(Self::Image),
                 PreviewMode::Tty => TtyView::new(path)
                     .map(Self::Tty)
                     .map_err(ProgramError::from),
+                PreviewMode::Markdown => MarkdownView::new(path).map(Self::Markdown),
+                PreviewMode::Table => TableView::new(path, con).map(Self::Table),
+                PreviewMode::Structured => StructuredView::new(path, con).map(Self::Structured),
+                PreviewMode::Info => InfoView::new(path).map(Self::Info),
+                PreviewMode::Format => file_format::read_format_sections(path)
+                    .map(|sections| Self::Format(InfoView::from_sections(sections)))
+                    .ok_or(ProgramError::UnknownFileFormat),
+                PreviewMode::Text if is_huge(path) => {
+                    WindowedTextView::new(path, InputPattern::none(), con.forced_encoding)
+                        .map(Self::Windowed)
+                }
                 PreviewMode::Text => Ok(TextView::new(
                     path,
                     InputPattern::none(),
@@ -129,14 +169,75 @@ impl Preview {
         }
     }
 
+    /// build a rendered markdown view, or a text one if the file
+    /// can't be read as UTF-8
+    pub fn markdown(
+        path: &Path,
+        con: &AppContext,
+    ) -> Self {
+        match MarkdownView::new(path) {
+            Ok(mv) => Self::Markdown(mv),
+            Err(_) => Self::unfiltered_text(path, con),
+        }
+    }
+
+    /// build a table view, or a text one if the file can't be
+    /// read as a table
+    pub fn table(
+        path: &Path,
+        con: &AppContext,
+    ) -> Self {
+        match TableView::new(path, con) {
+            Ok(tv) => Self::Table(tv),
+            Err(_) => Self::unfiltered_text(path, con),
+        }
+    }
+
+    /// build a structured view, or a text one if the file can't be
+    /// parsed
+    pub fn structured(
+        path: &Path,
+        con: &AppContext,
+    ) -> Self {
+        match StructuredView::new(path, con) {
+            Ok(sv) => Self::Structured(sv),
+            Err(_) => Self::unfiltered_text(path, con),
+        }
+    }
+
+    /// build a view of the metadata of a file or directory, or an
+    /// IOError if the file can't be read
+    pub fn info(path: &Path) -> Self {
+        match InfoView::new(path) {
+            Ok(iv) => Self::Info(iv),
+            Err(ProgramError::Io { source }) => Self::IoError(source),
+            Err(e) => Self::IoError(io::Error::other(e.to_string())),
+        }
+    }
+
+    /// build a summary of the file if its binary format is a known one
+    /// (executable, database, document, etc.), or a hex view
+    pub fn binary(path: &Path) -> Self {
+        if !file_format::is_supported(path) {
+            return Self::hex(path);
+        }
+        match file_format::read_format_sections(path) {
+            Some(sections) => Self::Format(InfoView::from_sections(sections)),
+            None => Self::hex(path),
+        }
+    }
+
     /// build a text preview (maybe with syntaxic coloring) if possible,
-    /// a hex (binary) view if content isnt't UTF8, a ZeroLen file if there's
+    /// a binary view if content isnt't UTF8, a ZeroLen file if there's
     /// no length (it's probably a linux pseudofile) or a IOError when
     /// there's a IO problem
     pub fn unfiltered_text(
         path: &Path,
         con: &AppContext,
     ) -> Self {
+        if is_huge(path) {
+            return Self::windowed(path, InputPattern::none(), con);
+        }
         match TextView::new(
             path,
             InputPattern::none(),
@@ -155,8 +256,8 @@ impl Preview {
             }
             // not previewable as UTF8 text
             // we'll try reading it as binary
-            Err(ProgramError::UnprintableFile) => Self::hex(path),
-            _ => Self::hex(path),
+            Err(ProgramError::UnprintableFile) => Self::binary(path),
+            _ => Self::binary(path),
         }
     }
     /// build a text preview with no syntax highlighting, if possible
@@ -164,6 +265,9 @@ impl Preview {
         path: &Path,
         con: &AppContext,
     ) -> Self {
+        if is_huge(path) {
+            return Self::windowed(path, InputPattern::none(), con);
+        }
         match TextView::new(path, InputPattern::none(), &mut Dam::unlimited(), con, true) {
             Ok(Some(sv)) => Self::Text(sv),
             Err(ProgramError::ZeroLenFile | ProgramError::UnmappableFile) => {
@@ -171,8 +275,24 @@ impl Preview {
                 Self::ZeroLen(ZeroLenFileView::new(path.to_path_buf()))
             }
             // not previewable as UTF8 text - we'll try reading it as binary
-            Err(ProgramError::UnprintableFile) => Self::hex(path),
-            _ => Self::hex(path),
+            Err(ProgramError::UnprintableFile) => Self::binary(path),
+            _ => Self::binary(path),
+        }
+    }
+    /// build a windowed text preview, for files too big to be loaded,
+    /// or a hex view if the file doesn't look like text
+    pub fn windowed(
+        path: &Path,
+        pattern: InputPattern,
+        con: &AppContext,
+    ) -> Self {
+        match WindowedTextView::new(path, pattern, con.forced_encoding) {
+            Ok(wv) => Self::Windowed(wv),
+            Err(ProgramError::ZeroLenFile) => {
+                Self::ZeroLen(ZeroLenFileView::new(path.to_path_buf()))
+            }
+            Err(ProgramError::Io { source }) => Self::IoError(source),
+            Err(_) => Self::binary(path),
         }
     }
     /// try to build a filtered view. Will return None if
@@ -186,7 +306,8 @@ impl Preview {
     ) -> Option<Self> {
         if path.is_file() {
             match self {
-                Self::Text(_) => {
+                // a markdown view is filtered as text
+                Self::Text(_) | Self::Markdown(_) => {
                     match TextView::new(path, pattern, dam, con, false) {
                         // normal finished loading
                         Ok(Some(sv)) => Some(Self::Text(sv)),
@@ -199,6 +320,10 @@ impl Preview {
                         Err(_) => Some(Self::hex(path)), // FIXME try as unstyled if syntect crashed
                     }
                 }
+                Self::Table(tv) => tv.filtered(pattern, dam).map(Self::Table),
+                Self::Structured(sv) => sv.filtered(pattern, dam).map(Self::Structured),
+                // the search is done later, as pending task
+                Self::Windowed(_) => Some(Self::windowed(path, pattern, con)),
                 _ => None, // not filterable
             }
         } else {
@@ -222,6 +347,7 @@ impl Preview {
     pub fn is_partial(&self) -> bool {
         match self {
             Self::Text(sv) => sv.is_partial(),
+            Self::Windowed(wv) => wv.is_partial(),
             _ => false,
         }
     }
@@ -232,17 +358,36 @@ impl Preview {
     ) -> Result<(), ProgramError> {
         match self {
             Self::Text(sv) => sv.complete_loading(con, dam),
+            Self::Windowed(wv) => wv.complete_loading(),
             _ => Ok(()),
         }
     }
+    /// read what was appended to a followed file. Return false
+    /// when the preview can't be updated and must be rebuilt
+    pub fn follow(
+        &mut self,
+        con: &AppContext,
+    ) -> Result<bool, ProgramError> {
+        match self {
+            Self::Text(sv) => sv.follow(con).map(|()| true),
+            Self::Windowed(wv) => wv.follow().map(|()| true),
+            _ => Ok(false),
+        }
+    }
     /// return the preview_mode, or None if we're on IOError or Directory
     pub fn get_mode(&self) -> Option<PreviewMode> {
         match self {
             Self::Image(_) => Some(PreviewMode::Image),
             Self::Text(_) => Some(PreviewMode::Text),
+            Self::Windowed(_) => Some(PreviewMode::Text),
             Self::ZeroLen(_) => Some(PreviewMode::Text),
             Self::Hex(_) => Some(PreviewMode::Hex),
             Self::Tty(_) => Some(PreviewMode::Tty),
+            Self::Markdown(_) => Some(PreviewMode::Markdown),
+            Self::Table(_) => Some(PreviewMode::Table),
+            Self::Structured(_) => Some(PreviewMode::Structured),
+            Self::Info(_) => Some(PreviewMode::Info),
+            Self::Format(_) => Some(PreviewMode::Format),
             Self::IoError(_) => None,
             Self::Dir(_) => None,
         }
@@ -251,6 +396,9 @@ impl Preview {
         match self {
             Self::Dir(dv) => dv.tree.options.pattern.clone(),
             Self::Text(sv) => sv.pattern.clone(),
+            Self::Windowed(wv) => wv.pattern.clone(),
+            Self::Table(tv) => tv.pattern.clone(),
+            Self::Structured(sv) => sv.pattern.clone(),
             _ => InputPattern::none(),
         }
     }
@@ -261,24 +409,99 @@ impl Preview {
         match self {
             Self::Dir(dv) => dv.try_scroll(cmd),
             Self::Text(sv) => sv.try_scroll(cmd),
+            Self::Windowed(wv) => wv.try_scroll(cmd),
             Self::Hex(hv) => hv.try_scroll(cmd),
             Self::Tty(v) => v.try_scroll(cmd),
+            Self::Markdown(mv) => mv.try_scroll(cmd),
+            Self::Table(tv) => tv.try_scroll(cmd),
+            Self::Structured(sv) => sv.try_scroll(cmd),
+            Self::Info(iv) | Self::Format(iv) => iv.try_scroll(cmd),
             _ => false,
         }
     }
+    /// Scroll horizontally, when lines aren't wrapped, or by
+    /// columns in a table. In a structured view, fold or unfold,
+    /// and in a hex view, move the cursor.
+    pub fn scroll_horizontally(
+        &mut self,
+        dir: i32,
+    ) -> bool {
+        match self {
+            Self::Text(sv) => sv.scroll_horizontally(dir),
+            Self::Table(tv) => tv.scroll_horizontally(dir),
+            Self::Structured(sv) => sv.fold(dir),
+            Self::Hex(hv) => hv.move_cursor(dir.into()),
+            _ => false,
+        }
+    }
+    pub fn set_wrap(
+        &mut self,
+        wrap: bool,
+    ) {
+        if let Self::Text(sv) = self {
+            sv.set_wrap(wrap);
+        }
+    }
+    /// Select the node at the given key path, in a structured view.
+    /// Return false if there's no such node.
+    pub fn jump_to_key(
+        &mut self,
+        path: &str,
+    ) -> bool {
+        match self {
+            Self::Structured(sv) => sv.jump_to_key(path),
+            _ => false,
+        }
+    }
+    /// Move the cursor of a hex view to an offset.
+    /// Return false if there's no such offset.
+    pub fn go_to_offset(
+        &mut self,
+        offset: &str,
+    ) -> bool {
+        match self {
+            Self::Hex(hv) => hv.go_to_offset(offset),
+            _ => false,
+        }
+    }
+    /// Search a byte pattern in a hex view and return the number of
+    /// occurrences, or None if the preview isn't a hex view.
+    pub fn search_bytes(
+        &mut self,
+        pattern: &str,
+    ) -> Result<Option<usize>, ProgramError> {
+        match self {
+            Self::Hex(hv) => hv.search(pattern).map(Some),
+            _ => Ok(None),
+        }
+    }
     pub fn is_filterable(&self) -> bool {
-        matches!(self, Self::Text(_) | Self::Dir(_))
+        matches!(
+            self,
+            Self::Text(_)
+                | Self::Windowed(_)
+                | Self::Markdown(_)
+                | Self::Table(_)
+                | Self::Structured(_)
+                | Self::Dir(_)
+        )
     }
 
     pub fn get_selected_line(&self) -> Option<String> {
         match self {
             Self::Text(sv) => sv.get_selected_line(),
+            Self::Windowed(wv) => wv.get_selected_line(),
+            Self::Table(tv) => tv.get_selected_line(),
+            Self::Structured(sv) => sv.get_selected_line(),
+            Self::Info(iv) | Self::Format(iv) => iv.get_selected_line(),
             _ => None,
         }
     }
     pub fn get_selected_line_number(&self) -> Option<LineNumber> {
         match self {
             Self::Text(sv) => sv.get_selected_line_number(),
+            Self::Windowed(wv) => wv.get_selected_line_number(),
+            Self::Table(tv) => tv.get_selected_line_number(),
             _ => None,
         }
     }
@@ -288,13 +511,20 @@ impl Preview {
     ) -> bool {
         match self {
             Self::Text(sv) => sv.try_select_line_number(number),
+            Self::Windowed(wv) => wv.try_select_line_number(number),
+            Self::Table(tv) => tv.try_select_line_number(number),
             _ => false,
         }
     }
     pub fn unselect(&mut self) {
         match self {
             Self::Text(sv) => sv.unselect(),
+            Self::Windowed(wv) => wv.unselect(),
             Self::Tty(tv) => tv.unselect(),
+            Self::Table(tv) => tv.unselect(),
+            Self::Structured(sv) => sv.unselect(),
+            Self::Hex(hv) => hv.unselect(),
+            Self::Info(iv) | Self::Format(iv) => iv.unselect(),
             _ => {}
         }
     }
@@ -305,7 +535,12 @@ impl Preview {
         match self {
             Self::Dir(dv) => dv.try_select_y(y),
             Self::Text(sv) => sv.try_select_y(y),
+            Self::Windowed(wv) => wv.try_select_y(y),
             Self::Tty(v) => v.try_select_y(y),
+            Self::Table(tv) => tv.try_select_y(y),
+            Self::Structured(sv) => sv.try_select_y(y),
+            Self::Hex(hv) => hv.try_select_y(y),
+            Self::Info(iv) | Self::Format(iv) => iv.try_select_y(y),
             _ => false,
         }
     }
@@ -317,43 +552,62 @@ impl Preview {
         match self {
             Self::Dir(dv) => dv.move_selection(dy, cycle),
             Self::Text(sv) => sv.move_selection(dy, cycle),
+            Self::Windowed(wv) => wv.move_selection(dy, cycle),
             Self::Tty(v) => v.move_selection(dy, cycle),
+            Self::Table(tv) => tv.move_selection(dy, cycle),
+            Self::Structured(sv) => sv.move_selection(dy, cycle),
+            Self::Info(iv) | Self::Format(iv) => iv.move_selection(dy, cycle),
             Self::Hex(hv) => {
-                hv.try_scroll(ScrollCommand::Lines(dy));
+                hv.move_cursor(16 * i64::from(dy));
+            }
+            Self::Markdown(mv) => {
+                mv.try_scroll(ScrollCommand::Lines(dy));
             }
             _ => {}
         }
     }
 
-    pub fn previous_match(&mut self) {
-        if let Self::Text(sv) = self {
-            sv.previous_match();
-        } else {
-            self.move_selection(-1, true);
+    pub fn previous_match(&mut self) -> Result<(), ProgramError> {
+        match self {
+            Self::Text(sv) => sv.previous_match(),
+            Self::Hex(hv) if hv.has_search() => hv.previous_match()?,
+            _ => self.move_selection(-1, true),
         }
+        Ok(())
     }
-    pub fn next_match(&mut self) {
-        if let Self::Text(sv) = self {
-            sv.next_match();
-        } else {
-            self.move_selection(1, true);
+    pub fn next_match(&mut self) -> Result<(), ProgramError> {
+        match self {
+            Self::Text(sv) => sv.next_match(),
+            Self::Hex(hv) if hv.has_search() => hv.next_match()?,
+            _ => self.move_selection(1, true),
         }
+        Ok(())
     }
 
     pub fn select_first(&mut self) {
         match self {
             Self::Dir(dv) => dv.select_first(),
             Self::Text(sv) => sv.select_first(),
+            Self::Windowed(wv) => wv.select_first(),
             Self::Hex(hv) => hv.select_first(),
             Self::Tty(v) => v.select_first(),
+            Self::Markdown(mv) => mv.select_first(),
+            Self::Table(tv) => tv.select_first(),
+            Self::Structured(sv) => sv.select_first(),
+            Self::Info(iv) | Self::Format(iv) => iv.select_first(),
             _ => {}
         }
     }
     pub fn select_last(&mut self) {
         match self {
             Self::Text(sv) => sv.select_last(),
+            Self::Windowed(wv) => wv.select_last(),
             Self::Hex(hv) => hv.select_last(),
             Self::Tty(v) => v.select_last(),
+            Self::Markdown(mv) => mv.select_last(),
+            Self::Table(tv) => tv.select_last(),
+            Self::Structured(sv) => sv.select_last(),
+            Self::Info(iv) | Self::Format(iv) => iv.select_last(),
             _ => {}
         }
     }
@@ -370,9 +624,14 @@ impl Preview {
             Self::Dir(dv) => dv.display(w, disc, area),
             Self::Image(iv) => time!(iv.display(w, disc, area)),
             Self::Text(sv) => sv.display(w, screen, panel_skin, area, con),
+            Self::Windowed(wv) => wv.display(w, screen, panel_skin, area, con),
             Self::ZeroLen(zlv) => zlv.display(w, screen, panel_skin, area),
             Self::Hex(hv) => hv.display(w, screen, panel_skin, area),
             Self::Tty(v) => v.display(w, screen, panel_skin, area),
+            Self::Markdown(mv) => mv.display(w, screen, panel_skin, area, con),
+            Self::Table(tv) => tv.display(w, screen, panel_skin, area, con),
+            Self::Structured(sv) => sv.display(w, screen, panel_skin, area, con),
+            Self::Info(iv) | Self::Format(iv) => iv.display(w, screen, panel_skin, area, con),
             Self::IoError(err) => {
                 let mut y = area.top;
                 w.queue(cursor::MoveTo(area.left, y))?;
@@ -406,8 +665,32 @@ impl Preview {
             Self::Dir(dv) => dv.display_info(w, screen, panel_skin, area),
             Self::Image(iv) => iv.display_info(w, screen, panel_skin, area),
             Self::Text(sv) => sv.display_info(w, screen, panel_skin, area),
+            Self::Windowed(wv) => wv.display_info(w, screen, panel_skin, area),
             Self::Hex(hv) => hv.display_info(w, screen, panel_skin, area),
+            Self::Table(tv) => tv.display_info(w, screen, panel_skin, area),
+            Self::Structured(sv) => sv.display_info(w, screen, panel_skin, area),
             _ => Ok(()),
         }
     }
 }
+
+/// return the preview mode configured for the extension of the file, if any.
+///
+/// When several extensions match (eg `gz` and `tar.gz`), the longest one wins.
+fn configured_mode(
+    path: &Path,
+    con: &AppContext,
+) -> Option<PreviewMode> {
+    con.preview_modes
+        .iter()
+        .filter(|(ext, _)| path_has_ext(path, ext))
+        .max_by_key(|(ext, _)| ext.len())
+        .map(|(_, mode)| *mode)
+}
+
+/// tell whether the file is too big to be previewed by a normal text view
+fn is_huge(path: &Path) -> bool {
+    path
+        .metadata()
+        .is_ok_and(|md| md.len() >= WINDOWED_MIN_FILE_SIZE)
+}
@@ -33,6 +33,20 @@ use {
     },
 };
 
+/// Return the path of the file to preview in the given mode: the output
+/// of the transformation, if any, except in Info mode, as the metadata
+/// to display are the ones of the source file
+fn path_to_preview<'p>(
+    source_path: &'p Path,
+    transform: Option<&'p PreviewTransform>,
+    mode: Option<PreviewMode>,
+) -> &'p Path {
+    match transform {
+        Some(transform) if mode != Some(PreviewMode::Info) => &transform.output_path,
+        _ => source_path,
+    }
+}
+
 /// an application state dedicated to previewing files.
 ///
 /// It's usually the only state in its panel and is kept when the
@@ -47,6 +61,10 @@ pub struct PreviewState {
     filtered_preview: Option<Preview>,
     removed_pattern: InputPattern,
     preferred_mode: Option<PreviewMode>,
+    /// whether the growth of the file is followed, like with `tail -f`
+    follow: bool,
+    /// whether long lines are wrapped instead of being cropped
+    wrap: bool,
     tree_options: TreeOptions,
     mode: Mode,
 }
@@ -63,10 +81,7 @@ impl PreviewState {
         let transform = con
             .preview_transformers
             .transform(&source_path, preferred_mode);
-        let preview_path = transform
-            .as_ref()
-            .map(|c| &c.output_path)
-            .unwrap_or(&source_path);
+        let preview_path = path_to_preview(&source_path, transform.as_ref(), preferred_mode);
         let preview = Preview::new(preview_path, preferred_mode, con);
         PreviewState {
             preview_area,
@@ -78,15 +93,18 @@ impl PreviewState {
             filtered_preview: None,
             removed_pattern: InputPattern::none(),
             preferred_mode,
+            follow: false,
+            wrap: false,
             tree_options,
             mode: con.initial_mode(),
         }
     }
     pub fn preview_path(&self) -> &Path {
-        self.transform
-            .as_ref()
-            .map(|c| &c.output_path)
-            .unwrap_or(&self.source_path)
+        path_to_preview(
+            &self.source_path,
+            self.transform.as_ref(),
+            self.preview.get_mode(),
+        )
     }
     fn vis_preview(&self) -> &Preview {
         self.filtered_preview.as_ref().unwrap_or(&self.preview)
@@ -102,7 +120,8 @@ impl PreviewState {
         if self.preview.get_mode() == Some(mode) {
             return Ok(CmdResult::Keep);
         }
-        Ok(match Preview::with_mode(self.preview_path(), mode, con) {
+        let path = path_to_preview(&self.source_path, self.transform.as_ref(), Some(mode));
+        Ok(match Preview::with_mode(path, mode, con) {
             Ok(preview) => {
                 self.preview = preview;
                 self.preferred_mode = Some(mode);
@@ -112,8 +131,33 @@ impl PreviewState {
         })
     }
 
+    /// update the previews with what was appended to the followed file,
+    /// or rebuild them when it's not possible
+    fn follow(
+        &mut self,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        let followed = self.transform.is_none()
+            && self.preview.follow(con)?
+            && match self.filtered_preview.as_mut() {
+                Some(filtered_preview) => filtered_preview.follow(con)?,
+                None => true,
+            };
+        if !followed {
+            // eg the file was empty and is now previewable as text
+            self.set_selected_path(self.source_path.clone(), con);
+            self.preview.select_last();
+            self.dirty = true;
+        }
+        Ok(())
+    }
+
     fn no_opt_selection(&self) -> Selection<'_> {
-        match self.transform.as_ref() {
+        let transform = self
+            .transform
+            .as_ref()
+            .filter(|_| self.preview.get_mode() != Some(PreviewMode::Info));
+        match transform {
             // When there's a transform, we can't assume the line number makes sense
             Some(transform) => Selection {
                 path: &transform.output_path,
@@ -176,7 +220,9 @@ impl PanelState for PreviewState {
     fn get_pending_task(&self) -> Option<&'static str> {
         if self.preview.is_partial() {
             Some("loading")
-        } else if self.pending_pattern.is_some() {
+        } else if self.pending_pattern.is_some()
+            || self.filtered_preview.as_ref().is_some_and(Preview::is_partial)
+        {
             Some("searching")
         } else {
             None
@@ -215,6 +261,9 @@ impl PanelState for PreviewState {
             self.preview.complete_loading(con, dam)?;
         } else if self.pending_pattern.is_some() {
             self.do_pending_search(con, dam)?;
+        } else if let Some(filtered_preview) = &mut self.filtered_preview {
+            // a windowed preview is searched progressively
+            filtered_preview.complete_loading(con, dam)?;
         }
         Ok(())
     }
@@ -223,6 +272,28 @@ impl PanelState for PreviewState {
         Some(&self.source_path)
     }
 
+    fn followed_path(&self) -> Option<&Path> {
+        self.follow.then_some(self.source_path.as_path())
+    }
+
+    fn refresh_followed(
+        &mut self,
+        con: &AppContext,
+    ) {
+        if !self.follow {
+            return;
+        }
+        if let Err(e) = self.follow(con) {
+            warn!("error while following {:?}: {e}", self.source_path);
+            self.preview = Preview::IoError(match e {
+                ProgramError::Io { source } => source,
+                e => std::io::Error::other(e.to_string()),
+            });
+            self.filtered_preview = None;
+            self.dirty = true;
+        }
+    }
+
     fn set_selected_path(
         &mut self,
         path: PathBuf,
@@ -239,7 +310,7 @@ impl PanelState for PreviewState {
         self.transform = con
             .preview_transformers
             .transform(&path, self.preferred_mode);
-        let preview_path = self.transform.as_ref().map_or(&path, |c| &c.output_path);
+        let preview_path = path_to_preview(&path, self.transform.as_ref(), self.preferred_mode);
         self.preview = Preview::new(preview_path, self.preferred_mode, con);
         if let Some(number) = selected_line_number {
             self.preview.try_select_line_number(number);
@@ -325,6 +396,9 @@ impl PanelState for PreviewState {
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_else(|| "???".to_string());
         cw.queue_str(&styles.preview_title, &file_name)?;
+        if self.follow {
+            cw.queue_str(&styles.preview_title, " (following)")?;
+        }
         let info_area = Area::new(
             state_area.left + state_area.width - cw.allowed as u16,
             state_area.top,
@@ -333,6 +407,7 @@ impl PanelState for PreviewState {
         );
         cw.fill(&styles.preview_title, &SPACE_FILLING)?;
         let preview = self.filtered_preview.as_mut().unwrap_or(&mut self.preview);
+        preview.set_wrap(self.wrap);
         preview.display_info(w, disc.screen, disc.panel_skin, &info_area)?;
         if let Err(err) = preview.display(w, disc, &self.preview_area) {
             warn!("error while displaying file: {:?}", err);
@@ -457,16 +532,81 @@ impl PanelState for PreviewState {
                 Ok(CmdResult::Keep)
             }
             Internal::previous_match => {
-                self.mut_preview().previous_match();
+                self.mut_preview().previous_match()?;
                 Ok(CmdResult::Keep)
             }
             Internal::next_match => {
-                self.mut_preview().next_match();
+                self.mut_preview().next_match()?;
+                Ok(CmdResult::Keep)
+            }
+            Internal::toggle_follow => {
+                self.follow = !self.follow;
+                if self.follow {
+                    self.follow(con)?;
+                    self.mut_preview().select_last();
+                }
+                Ok(CmdResult::Keep)
+            }
+            Internal::toggle_wrap => {
+                let wrap = !self.wrap;
+                self.wrap = wrap;
+                self.mut_preview().set_wrap(wrap);
+                Ok(CmdResult::Keep)
+            }
+            Internal::scroll_left => {
+                self.mut_preview().scroll_horizontally(-1);
+                Ok(CmdResult::Keep)
+            }
+            Internal::scroll_right => {
+                self.mut_preview().scroll_horizontally(1);
                 Ok(CmdResult::Keep)
             }
             Internal::preview_image => self.set_mode(PreviewMode::Image, con),
             Internal::preview_text => self.set_mode(PreviewMode::Text, con),
             Internal::preview_tty => self.set_mode(PreviewMode::Tty, con),
+            Internal::preview_markdown => self.set_mode(PreviewMode::Markdown, con),
+            Internal::preview_table => self.set_mode(PreviewMode::Table, con),
+            Internal::preview_info => self.set_mode(PreviewMode::Info, con),
+            Internal::preview_format => self.set_mode(PreviewMode::Format, con),
+            Internal::preview_structured => self.set_mode(PreviewMode::Structured, con),
+            Internal::jump_to_key => {
+                let path = input_invocation
+                    .and_then(|vi| vi.args.as_ref())
+                    .or(internal_exec.arg.as_ref())
+                    .map(|arg| arg.trim())
+                    .filter(|arg| !arg.is_empty());
+                Ok(match path {
+                    Some(path) if self.mut_preview().jump_to_key(path) => CmdResult::Keep,
+                    Some(path) => CmdResult::error(format!("No key at {path:?}")),
+                    None => CmdResult::error("No key path provided"),
+                })
+            }
+            Internal::goto_offset => {
+                let offset = input_invocation
+                    .and_then(|vi| vi.args.as_ref())
+                    .or(internal_exec.arg.as_ref())
+                    .map(|arg| arg.trim())
+                    .filter(|arg| !arg.is_empty());
+                Ok(match offset {
+                    Some(offset) if self.mut_preview().go_to_offset(offset) => CmdResult::Keep,
+                    Some(offset) => CmdResult::error(format!("Invalid offset: {offset:?}")),
+                    None => CmdResult::error("No offset provided"),
+                })
+            }
+            Internal::search_bytes => {
+                let pattern = input_invocation
+                    .and_then(|vi| vi.args.as_ref())
+                    .or(internal_exec.arg.as_ref())
+                    .map(|arg| arg.trim())
+                    .unwrap_or_default();
+                Ok(match self.mut_preview().search_bytes(pattern)? {
+                    Some(0) if !pattern.is_empty() => {
+                        CmdResult::error(format!("Bytes not found: {pattern:?}"))
+                    }
+                    Some(_) => CmdResult::Keep,
+                    None => CmdResult::error("Searching bytes needs a hex preview"),
+                })
+            }
             Internal::preview_binary => self.set_mode(PreviewMode::Hex, con),
             _ => self.on_internal_generic(
                 w,
@@ -7,6 +7,7 @@ use {
         errors::ProgramError,
         launchable::Launchable,
         skin::{
+            FileColorMap,
             PanelSkin,
             StyleMap,
         },
@@ -82,15 +83,15 @@ pub fn print_tree(
 ) -> Result<CmdResult, ProgramError> {
     // We write on stdout, but we must do it after app closing to have the normal terminal
     let show_color = con.launch_args.color.unwrap_or_else(|| stdout().is_tty());
-    let styles = if show_color {
-        panel_skin.styles.clone()
+    let (styles, file_colors) = if show_color {
+        (panel_skin.styles.clone(), con.file_colors.clone())
     } else {
-        StyleMap::no_term()
+        (StyleMap::no_term(), FileColorMap::default())
     };
     Ok(CmdResult::from(Launchable::tree_printer(
         tree,
         screen,
         styles,
-        con.ext_colors.clone(),
+        file_colors,
     )))
 }
@@ -0,0 +1,10 @@
+//! Sessions are the saved state of broot (tabs, panels, stage, layout),
+//! which can be restored in a next launch.
+
+mod session_file;
+mod session_tree_options;
+
+pub use {
+    session_file::*,
+    session_tree_options::SessionTreeOptions,
+};
@@ -0,0 +1,201 @@
+use {
+    super::SessionTreeOptions,
+    crate::{
+        conf,
+        display::LayoutInstruction,
+        errors::ProgramError,
+    },
+    lazy_regex::regex_is_match,
+    rustc_hash::FxHasher,
+    serde::{
+        Deserialize,
+        Serialize,
+    },
+    std::{
+        fs,
+        hash::{
+            Hash,
+            Hasher,
+        },
+        path::{
+            Path,
+            PathBuf,
+        },
+    },
+};
+
+/// What's saved of a tree panel
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PanelSession {
+    pub root: PathBuf,
+    /// the raw pattern, as typed in the input
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub pattern: String,
+    pub selection: Option<PathBuf>,
+    pub options: SessionTreeOptions,
+}
+
+/// What's saved of a tab
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TabSession {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub active_panel_idx: usize,
+    /// whether a preview panel was open (it's restored on the selection)
+    #[serde(default)]
+    pub preview: bool,
+    #[serde(default)]
+    pub stage: Vec<PathBuf>,
+    /// the tree panels, from left to right
+    pub panels: Vec<PanelSession>,
+}
+
+/// The saved state of the application
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Session {
+    /// the directory broot was launched in
+    pub launch_dir: PathBuf,
+    #[serde(default)]
+    pub active_tab_idx: usize,
+    #[serde(default)]
+    pub layout_instructions: Vec<LayoutInstruction>,
+    pub tabs: Vec<TabSession>,
+}
+
+impl Session {
+    /// return the path of the file of a session saved with a name
+    pub fn named_path(name: &str) -> Result<PathBuf, ProgramError> {
+        if !regex_is_match!(r"^[\w.-]+$", name) || name.starts_with('.') {
+            return Err(ProgramError::Session {
+                details: format!("invalid session name: {name:?}"),
+            });
+        }
+        Ok(conf::dir().join("sessions").join(format!("{name}.toml")))
+    }
+
+    /// return the path of the file of the last session launched
+    /// in `launch_dir`, which is saved on quit when `session_auto_restore`
+    /// is set
+    pub fn last_path(launch_dir: &Path) -> PathBuf {
+        let mut hasher = FxHasher::default();
+        launch_dir.hash(&mut hasher);
+        conf::dir()
+            .join("sessions")
+            .join("last")
+            .join(format!("{:016x}.toml", hasher.finish()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ProgramError> {
+        let content = fs::read_to_string(path).map_err(|e| ProgramError::Session {
+            details: format!("unable to read {path:?}: {e}"),
+        })?;
+        toml::from_str(&content).map_err(|e| ProgramError::Session {
+            details: format!("invalid session file {path:?}: {e}"),
+        })
+    }
+
+    pub fn save(
+        &self,
+        path: &Path,
+    ) -> Result<(), ProgramError> {
+        let content = toml::to_string(self).map_err(|e| ProgramError::Session {
+            details: format!("unable to serialize the session: {e}"),
+        })?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, content)?;
+        info!("session saved in {:?}", path);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod session_tests {
+    use {
+        super::*,
+        crate::tree::Sort,
+    };
+
+    #[test]
+    fn test_session_toml_round_trip() {
+        let options = SessionTreeOptions {
+            show_hidden: true,
+            only_folders: false,
+            show_counts: false,
+            show_dates: true,
+            show_sizes: true,
+            apparent_sizes: false,
+            max_depth: Some(3),
+            show_git_file_info: false,
+            show_device_id: false,
+            show_root_fs: true,
+            trim_root: false,
+            show_permissions: false,
+            respect_git_ignore: true,
+            filter_by_git_status: false,
+            show_tree: true,
+            sort: Sort::Size,
+        };
+        let session = Session {
+            launch_dir: PathBuf::from("/home/me/dev"),
+            active_tab_idx: 1,
+            layout_instructions: vec![
+                LayoutInstruction::MoveDivider {
+                    divider: 0,
+                    dx: -5,
+                },
+                LayoutInstruction::SetPanelWidth {
+                    panel: 1,
+                    width: 50,
+                },
+            ],
+            tabs: vec![
+                TabSession {
+                    name: None,
+                    active_panel_idx: 0,
+                    preview: true,
+                    stage: vec![PathBuf::from("/home/me/dev/a.txt")],
+                    panels: vec![PanelSession {
+                        root: PathBuf::from("/home/me/dev"),
+                        pattern: "/rs$".to_string(),
+                        selection: Some(PathBuf::from("/home/me/dev/main.rs")),
+                        options,
+                    }],
+                },
+                TabSession {
+                    name: Some("logs".to_string()),
+                    active_panel_idx: 1,
+                    preview: false,
+                    stage: Vec::new(),
+                    panels: vec![
+                        PanelSession {
+                            root: PathBuf::from("/var/log"),
+                            pattern: String::new(),
+                            selection: None,
+                            options,
+                        },
+                        PanelSession {
+                            root: PathBuf::from("/tmp"),
+                            pattern: String::new(),
+                            selection: None,
+                            options,
+                        },
+                    ],
+                },
+            ],
+        };
+        let toml = toml::to_string(&session).unwrap();
+        let read: Session = toml::from_str(&toml).unwrap();
+        assert_eq!(read, session);
+    }
+
+    #[test]
+    fn test_session_names() {
+        assert!(Session::named_path("logs").is_ok());
+        assert!(Session::named_path("my-work.2").is_ok());
+        assert!(Session::named_path("").is_err());
+        assert!(Session::named_path("../conf").is_err());
+        assert!(Session::named_path("a/b").is_err());
+    }
+}
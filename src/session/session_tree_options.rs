@@ -0,0 +1,81 @@
+use {
+    crate::tree::{
+        Sort,
+        TreeOptions,
+    },
+    serde::{
+        Deserialize,
+        Serialize,
+    },
+};
+
+/// The tree options which are saved in a session.
+///
+/// The pattern isn't here as it's saved as the raw input, and the
+/// options coming from the configuration aren't saved.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct SessionTreeOptions {
+    pub show_hidden: bool,
+    pub only_folders: bool,
+    pub show_counts: bool,
+    pub show_dates: bool,
+    pub show_sizes: bool,
+    pub apparent_sizes: bool,
+    pub max_depth: Option<u16>,
+    pub show_git_file_info: bool,
+    pub show_device_id: bool,
+    pub show_root_fs: bool,
+    pub trim_root: bool,
+    pub show_permissions: bool,
+    pub respect_git_ignore: bool,
+    pub filter_by_git_status: bool,
+    pub show_tree: bool,
+    pub sort: Sort,
+}
+
+impl From<&TreeOptions> for SessionTreeOptions {
+    fn from(o: &TreeOptions) -> Self {
+        Self {
+            show_hidden: o.show_hidden,
+            only_folders: o.only_folders,
+            show_counts: o.show_counts,
+            show_dates: o.show_dates,
+            show_sizes: o.show_sizes,
+            apparent_sizes: o.apparent_sizes,
+            max_depth: o.max_depth,
+            show_git_file_info: o.show_git_file_info,
+            show_device_id: o.show_device_id,
+            show_root_fs: o.show_root_fs,
+            trim_root: o.trim_root,
+            show_permissions: o.show_permissions,
+            respect_git_ignore: o.respect_git_ignore,
+            filter_by_git_status: o.filter_by_git_status,
+            show_tree: o.show_tree,
+            sort: o.sort,
+        }
+    }
+}
+
+impl SessionTreeOptions {
+    pub fn apply_to(
+        self,
+        o: &mut TreeOptions,
+    ) {
+        o.show_hidden = self.show_hidden;
+        o.only_folders = self.only_folders;
+        o.show_counts = self.show_counts;
+        o.show_dates = self.show_dates;
+        o.show_sizes = self.show_sizes;
+        o.apparent_sizes = self.apparent_sizes;
+        o.max_depth = self.max_depth;
+        o.show_git_file_info = self.show_git_file_info;
+        o.show_device_id = self.show_device_id;
+        o.show_root_fs = self.show_root_fs;
+        o.trim_root = self.trim_root;
+        o.show_permissions = self.show_permissions;
+        o.respect_git_ignore = self.respect_git_ignore;
+        o.filter_by_git_status = self.filter_by_git_status;
+        o.show_tree = self.show_tree;
+        o.sort = self.sort;
+    }
+}
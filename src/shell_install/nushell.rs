@@ -28,7 +28,7 @@ use {
 };
 
 const NAME: &str = "nushell";
-const VERSION: &str = "7";
+const VERSION: &str = "8";
 
 const NU_FUNC: &str = r#"
 # Launch broot
@@ -46,6 +46,7 @@ export def --env main [
     --conf: string                  # Semicolon separated paths to specific config files"),
     --dates(-d)                     # Show the last modified date of files and directories"
     --no-dates(-D)                  # Don't show the last modified date"
+    --dry-run                       # Display the external commands of verbs instead of executing them
     --only-folders(-f)              # Only show folders
     --no-only-folders(-F)           # Show folders and files alike
     --show-git-info(-g)             # Show git statuses on files and stats on repo
@@ -63,11 +64,15 @@ export def --env main [
     --no-sort                       # Don't sort
     --permissions(-p)               # Show permissions
     --no-permissions(-P)            # Don't show permissions
+    --one-filesystem                # Don't cross filesystem boundaries when computing sizes (like `du -x`)
     --print-shell-function: string  # Print to stdout the br function for a given shell
     --sizes(-s)                     # Show the size of files and directories
     --no-sizes(-S)                  # Don't show sizes
+    --apparent-sizes(-a)            # Show apparent sizes (as `ls -l`) instead of disk usage
+    --no-apparent-sizes(-A)         # Show disk usage instead of apparent sizes
     --set-install-state: path       # Where to write the produced cmd (if any) [possible values: undefined, refused, installed]
     --show-root-fs                  # Show filesystem info on top
+    --session: string               # Name of a session (saved with `:session_save`) to restore
     --max-depth: int                # Only show trees up to a certain depth
     --sort-by-count                 # Sort by count (only show one level of the tree)
     --sort-by-date                  # Sort by date (only show one level of the tree)
@@ -88,6 +93,7 @@ export def --env main [
     if $conf != null { $args = ($args | append $'--conf=($conf)') }
     if $dates { $args = ($args | append $'--dates') }
     if $no_dates { $args = ($args | append $'--no-dates') }
+    if $dry_run { $args = ($args | append $'--dry-run') }
     if $only_folders { $args = ($args | append $'--only-folders') }
     if $no_only_folders { $args = ($args | append $'--no-only-folders') }
     if $show_git_info { $args = ($args | append $'--show-git-info') }
@@ -105,11 +111,15 @@ export def --env main [
     if $no_sort { $args = ($args | append $'--no-sort') }
     if $permissions { $args = ($args | append $'--permissions') }
     if $no_permissions { $args = ($args | append $'--no-permissions') }
+    if $one_filesystem { $args = ($args | append $'--one-filesystem') }
     if $print_shell_function != null { $args = ($args | append $'--print-shell-function=($print_shell_function)') }
     if $sizes { $args = ($args | append $'--sizes') }
     if $no_sizes { $args = ($args | append $'--no-sizes') }
+    if $apparent_sizes { $args = ($args | append $'--apparent-sizes') }
+    if $no_apparent_sizes { $args = ($args | append $'--no-apparent-sizes') }
     if $set_install_state != null { $args = ($args | append $'--set-install-state=($set_install_state)') }
     if $show_root_fs { $args = ($args | append $'--show-root-fs') }
+    if $session != null { $args = ($args | append $'--session=($session)') }
     if $max_depth != null { $args = ($args | append $'--max-depth=($max_depth)') }
     if $sort_by_count { $args = ($args | append $'--sort-by-count') }
     if $sort_by_date { $args = ($args | append $'--sort-by-date') }
@@ -150,6 +160,7 @@ export extern broot [
     --conf: string                  # Semicolon separated paths to specific config files"),
     --dates(-d)                     # Show the last modified date of files and directories"
     --no-dates(-D)                  # Don't show the last modified date"
+    --dry-run                       # Display the external commands of verbs instead of executing them
     --only-folders(-f)              # Only show folders
     --no-only-folders(-F)           # Show folders and files alike
     --show-git-info(-g)             # Show git statuses on files and stats on repo
@@ -168,11 +179,15 @@ export extern broot [
     --outcmd: path                  # Write cd command in given path
     --permissions(-p)               # Show permissions
     --no-permissions(-P)            # Don't show permissions
+    --one-filesystem                # Don't cross filesystem boundaries when computing sizes (like `du -x`)
     --print-shell-function: string  # Print to stdout the br function for a given shell
     --sizes(-s)                     # Show the size of files and directories
     --no-sizes(-S)                  # Don't show sizes
+    --apparent-sizes(-a)            # Show apparent sizes (as `ls -l`) instead of disk usage
+    --no-apparent-sizes(-A)         # Show disk usage instead of apparent sizes
     --set-install-state: path       # Where to write the produced cmd (if any) [possible values: undefined, refused, installed]
     --show-root-fs                  # Show filesystem info on top
+    --session: string               # Name of a session (saved with `:session_save`) to restore
     --max-depth: int                # Only show trees up to a certain depth
     --sort-by-count                 # Sort by count (only show one level of the tree)
     --sort-by-date                  # Sort by date (only show one level of the tree)
@@ -0,0 +1,3 @@
+mod size_report_state;
+
+pub use size_report_state::SizeReportState;
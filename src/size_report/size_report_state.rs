@@ -0,0 +1,471 @@
+use {
+    crate::{
+        app::*,
+        command::*,
+        display::*,
+        errors::ProgramError,
+        file_sum::{
+            FileSum,
+            SizeReport,
+        },
+        pattern::*,
+        task_sync::Dam,
+        tree::TreeOptions,
+        verb::*,
+    },
+    crokey::crossterm::{
+        QueueableCommand,
+        cursor,
+        style::Color,
+    },
+    std::path::{
+        Path,
+        PathBuf,
+    },
+    termimad::{
+        minimad::Alignment,
+        *,
+    },
+};
+
+static CHILDREN_TITLE: &str = "by child";
+static EXTENSIONS_TITLE: &str = "by extension";
+static NO_EXTENSION: &str = "(no extension)";
+
+/// A line of the report, referencing the computed report
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReportLine {
+    Title(&'static str),
+    Child(usize),
+    Extension(usize),
+}
+
+impl SectionedLine for ReportLine {
+    fn is_item(self) -> bool {
+        !matches!(self, Self::Title(_))
+    }
+}
+
+/// an application state showing the breakdown of the size of
+/// a directory, per extension and per direct child
+pub struct SizeReportState {
+    root: PathBuf,
+    report: Option<SizeReport>, // None until computed
+    list: SectionedList<ReportLine>,
+    pattern: Pattern,
+    tree_options: TreeOptions,
+    mode: Mode,
+}
+
+impl SizeReportState {
+    /// create a state whose report will be computed as a pending task
+    pub fn new(
+        root: PathBuf,
+        tree_options: TreeOptions,
+        con: &AppContext,
+    ) -> Self {
+        Self {
+            root,
+            report: None,
+            list: SectionedList::default(),
+            pattern: Pattern::None,
+            tree_options,
+            mode: con.initial_mode(),
+        }
+    }
+
+    fn line_name(
+        &self,
+        line: ReportLine,
+    ) -> Option<String> {
+        let report = self.report.as_ref()?;
+        match line {
+            ReportLine::Title(_) => None,
+            ReportLine::Child(idx) => report.children[idx]
+                .0
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string()),
+            ReportLine::Extension(idx) => {
+                let ext = &report.extensions[idx].0;
+                Some(if ext.is_empty() {
+                    NO_EXTENSION.to_string()
+                } else {
+                    format!("*.{ext}")
+                })
+            }
+        }
+    }
+
+    fn line_sum(
+        &self,
+        line: ReportLine,
+    ) -> Option<FileSum> {
+        let report = self.report.as_ref()?;
+        match line {
+            ReportLine::Title(_) => None,
+            ReportLine::Child(idx) => Some(report.children[idx].1),
+            ReportLine::Extension(idx) => Some(report.extensions[idx].1),
+        }
+    }
+
+    /// rebuild the lines from the report, keeping only the items
+    /// matching the pattern
+    fn update_lines(&mut self) {
+        let mut lines = Vec::new();
+        if let Some(report) = &self.report {
+            let children = (0..report.children.len()).map(ReportLine::Child);
+            let extensions = (0..report.extensions.len()).map(ReportLine::Extension);
+            for (title, items) in [
+                (CHILDREN_TITLE, children.collect::<Vec<_>>()),
+                (EXTENSIONS_TITLE, extensions.collect::<Vec<_>>()),
+            ] {
+                let items: Vec<ReportLine> = items
+                    .into_iter()
+                    .filter(|&line| {
+                        self.line_name(line)
+                            .is_some_and(|name| self.pattern.score_of_string(&name).is_some())
+                    })
+                    .collect();
+                if !items.is_empty() {
+                    lines.push(ReportLine::Title(title));
+                    lines.extend(items);
+                }
+            }
+        }
+        self.list.set_lines(lines);
+    }
+
+    fn move_line(
+        &mut self,
+        internal_exec: &InternalExecution,
+        input_invocation: Option<&VerbInvocation>,
+        dir: i32, // -1 for up, 1 for down
+        cycle: bool,
+    ) -> CmdResult {
+        let count = get_arg(input_invocation, internal_exec, 1);
+        self.list.move_selection(count, dir, cycle);
+        CmdResult::Keep
+    }
+
+    /// the selected child, if the selection is a child and not an extension
+    fn selected_child(&self) -> Option<&Path> {
+        match self.list.selected_line()? {
+            ReportLine::Child(idx) => self
+                .report
+                .as_ref()
+                .map(|report| report.children[idx].0.as_path()),
+            _ => None,
+        }
+    }
+}
+
+impl PanelState for SizeReportState {
+    fn get_type(&self) -> PanelStateType {
+        PanelStateType::SizeReport
+    }
+
+    fn set_mode(
+        &mut self,
+        mode: Mode,
+    ) {
+        self.mode = mode;
+    }
+
+    fn get_mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// the selected child, or the root when the selection is an extension
+    fn selected_path(&self) -> Option<&Path> {
+        Some(self.selected_child().unwrap_or(&self.root))
+    }
+
+    fn selection(&self) -> Option<Selection<'_>> {
+        let path = self.selected_path()?;
+        Some(Selection {
+            path,
+            stype: SelectionType::from(path),
+            is_exe: false,
+            line: 0,
+        })
+    }
+
+    fn tree_options(&self) -> TreeOptions {
+        self.tree_options.clone()
+    }
+
+    fn with_new_options(
+        &mut self,
+        _screen: Screen,
+        change_options: &dyn Fn(&mut TreeOptions) -> &'static str,
+        _in_new_panel: bool,
+        _con: &AppContext,
+    ) -> CmdResult {
+        change_options(&mut self.tree_options);
+        if let Some(report) = &mut self.report {
+            report.sort_by_size(self.tree_options.apparent_sizes);
+            self.update_lines();
+        }
+        CmdResult::Keep
+    }
+
+    fn refresh(
+        &mut self,
+        _screen: Screen,
+        _con: &AppContext,
+    ) -> Command {
+        self.report = None;
+        self.list.clear();
+        Command::empty()
+    }
+
+    fn do_pending_task(
+        &mut self,
+        _app_state: &mut AppState,
+        _screen: Screen,
+        con: &AppContext,
+        dam: &mut Dam,
+    ) -> Result<(), ProgramError> {
+        // on interruption the report stays None, and the computation
+        // is restarted later
+        self.report = SizeReport::compute(&self.root, dam, con);
+        if let Some(report) = &mut self.report {
+            report.sort_by_size(self.tree_options.apparent_sizes);
+        }
+        self.update_lines();
+        Ok(())
+    }
+
+    fn get_pending_task(&self) -> Option<&'static str> {
+        if self.report.is_none() {
+            Some("computing size report")
+        } else {
+            None
+        }
+    }
+
+    fn on_pattern(
+        &mut self,
+        pattern: InputPattern,
+        _app_state: &AppState,
+        _con: &AppContext,
+    ) -> Result<CmdResult, ProgramError> {
+        self.pattern = pattern.pattern;
+        self.update_lines();
+        Ok(CmdResult::Keep)
+    }
+
+    fn display(
+        &mut self,
+        w: &mut W,
+        disc: &DisplayContext,
+    ) -> Result<(), ProgramError> {
+        let area = &disc.state_area;
+        let con = &disc.con;
+        self.list.page_height = area.height as usize - 1;
+        let width = area.width as usize;
+        let scrollbar = area.scrollbar(self.list.scroll, self.list.len());
+        //- style preparation
+        let styles = &disc.panel_skin.styles;
+        let selection_bg = styles
+            .selected_line
+            .get_bg()
+            .unwrap_or(Color::AnsiValue(240));
+        let mut selected_match_style = styles.char_match;
+        selected_match_style.set_bg(selection_bg);
+        let mut selected_count_style = styles.count;
+        selected_count_style.set_bg(selection_bg);
+        //- title line
+        w.queue(cursor::MoveTo(area.left, area.top))?;
+        let mut cw = CropWriter::new(w, width);
+        let title_style = &styles.staging_area_title;
+        cw.queue_g_string(title_style, self.root.to_string_lossy().to_string())?;
+        let apparent = self.tree_options.apparent_sizes;
+        if let Some(report) = &self.report {
+            let mut size = file_size::fit_4(report.total.size(apparent));
+            if report.total.is_partial() {
+                size.push(PARTIAL_SUM_MARK);
+            }
+            let total = format!(
+                "  {} in {} files",
+                size,
+                format_count(report.total.to_count()),
+            );
+            if total.len() < cw.allowed {
+                cw.repeat(title_style, &SPACE_FILLING, cw.allowed - total.len())?;
+                cw.queue_g_string(title_style, total)?;
+            }
+        }
+        cw.fill(title_style, &SPACE_FILLING)?;
+        //- content
+        let total = self
+            .report
+            .as_ref()
+            .map_or_else(FileSum::zero, |report| report.total);
+        let count_width = self
+            .list
+            .lines()
+            .iter()
+            .filter_map(|&line| self.line_sum(line))
+            .map(|sum| format_count(sum.to_count()).len())
+            .max()
+            .unwrap_or(0);
+        let bar_width = 10;
+        for y in 1..area.height {
+            w.queue(cursor::MoveTo(area.left, y + area.top))?;
+            let idx = self.list.scroll + y as usize - 1;
+            let selected = self.list.is_selected(idx);
+            let mut cw = CropWriter::new(w, width - 1); // -1 for scrollbar
+            let txt_style = if selected {
+                &styles.selected_line
+            } else {
+                &styles.default
+            };
+            match self.list.get(idx) {
+                Some(ReportLine::Title(title)) => {
+                    cw.queue_g_string(&styles.help_headers, format!("{title:^width$}"))?;
+                }
+                Some(line) => {
+                    if let (Some(name), Some(sum)) = (self.line_name(line), self.line_sum(line)) {
+                        if con.show_selection_mark {
+                            cw.queue_char(txt_style, if selected { '▶' } else { ' ' })?;
+                        }
+                        cw.queue_g_string(
+                            txt_style,
+                            format!("{:>4} ", file_size::fit_4(sum.size(apparent))),
+                        )?;
+                        let pb = ProgressBar::new(sum.part_of_size(total, apparent), bar_width);
+                        cw.queue_g_string(txt_style, format!("{pb:<bar_width$} "))?;
+                        let count_style = if selected {
+                            &selected_count_style
+                        } else {
+                            &styles.count
+                        };
+                        cw.queue_g_string(
+                            count_style,
+                            format!("{:>count_width$}", format_count(sum.to_count())),
+                        )?;
+                        cw.queue_char(txt_style, ' ')?;
+                        let name_style = match line {
+                            ReportLine::Child(idx)
+                                if self
+                                    .report
+                                    .as_ref()
+                                    .is_some_and(|r| r.children[idx].0.is_dir()) =>
+                            {
+                                &styles.directory
+                            }
+                            _ => &styles.file,
+                        };
+                        let mut selected_name_style;
+                        let name_style = if selected {
+                            selected_name_style = *name_style;
+                            selected_name_style.set_bg(selection_bg);
+                            &selected_name_style
+                        } else {
+                            name_style
+                        };
+                        let match_style = if selected {
+                            &selected_match_style
+                        } else {
+                            &styles.char_match
+                        };
+                        let mut matched_string = MatchedString::new(
+                            self.pattern.search_string(&name),
+                            &name,
+                            name_style,
+                            match_style,
+                        );
+                        matched_string.fill(cw.allowed, Alignment::Left);
+                        matched_string.queue_on(&mut cw)?;
+                    }
+                }
+                None => {}
+            }
+            cw.fill(txt_style, &SPACE_FILLING)?;
+            let scrollbar_style = if ScrollCommand::is_thumb(y + area.top, scrollbar) {
+                &styles.scrollbar_thumb
+            } else {
+                &styles.scrollbar_track
+            };
+            scrollbar_style.queue_str(w, "▐")?;
+        }
+        Ok(())
+    }
+
+    fn on_internal(
+        &mut self,
+        w: &mut W,
+        invocation_parser: Option<&InvocationParser>,
+        internal_exec: &InternalExecution,
+        input_invocation: Option<&VerbInvocation>,
+        trigger_type: TriggerType,
+        app_state: &mut AppState,
+        cc: &CmdContext,
+    ) -> Result<CmdResult, ProgramError> {
+        let con = &cc.app.con;
+        Ok(match internal_exec.internal {
+            Internal::back => {
+                if self.pattern.is_some() {
+                    self.pattern = Pattern::None;
+                    self.update_lines();
+                    CmdResult::Keep
+                } else {
+                    CmdResult::PopState
+                }
+            }
+            Internal::line_down => self.move_line(internal_exec, input_invocation, 1, true),
+            Internal::line_up => self.move_line(internal_exec, input_invocation, -1, true),
+            Internal::line_down_no_cycle => {
+                self.move_line(internal_exec, input_invocation, 1, false)
+            }
+            Internal::line_up_no_cycle => {
+                self.move_line(internal_exec, input_invocation, -1, false)
+            }
+            Internal::page_down => {
+                self.list.try_scroll(ScrollCommand::Pages(1));
+                CmdResult::Keep
+            }
+            Internal::page_up => {
+                self.list.try_scroll(ScrollCommand::Pages(-1));
+                CmdResult::Keep
+            }
+            Internal::open_stay => match self.selected_child() {
+                // drilling down into the selected directory
+                Some(path) if path.is_dir() => CmdResult::new_state(Box::new(
+                    SizeReportState::new(path.to_path_buf(), self.tree_options(), con),
+                )),
+                Some(_) => match self.selection() {
+                    Some(selection) => selection.to_opener(con)?,
+                    None => CmdResult::Keep,
+                },
+                None => CmdResult::error("select a child to open it"),
+            },
+            Internal::panel_left_no_open => CmdResult::HandleInApp(Internal::panel_left_no_open),
+            Internal::panel_right_no_open => CmdResult::HandleInApp(Internal::panel_right_no_open),
+            Internal::open_leave => CmdResult::PopStateAndReapply,
+            _ => self.on_internal_generic(
+                w,
+                invocation_parser,
+                internal_exec,
+                input_invocation,
+                trigger_type,
+                app_state,
+                cc,
+            )?,
+        })
+    }
+
+    fn on_click(
+        &mut self,
+        _x: u16,
+        y: u16,
+        _screen: Screen,
+        _con: &AppContext,
+    ) -> Result<CmdResult, ProgramError> {
+        if y >= 1 {
+            self.list.try_select_row(y as usize - 1);
+        }
+        Ok(CmdResult::Keep)
+    }
+}
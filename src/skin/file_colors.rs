@@ -0,0 +1,255 @@
+use {
+    super::ExtColorMap,
+    crate::{
+        errors::ConfError,
+        tree::{
+            TreeLine,
+            TreeLineType,
+        },
+    },
+    rustc_hash::FxHashMap,
+    serde::Deserialize,
+    termimad::CompoundStyle,
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::{
+    FileTypeExt,
+    PermissionsExt,
+};
+
+/// A kind of file which may be given a specific style
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    Directory,
+    Link,
+    OrphanLink,
+    File,
+    Executable,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+    Setuid,
+    Setgid,
+    /// a directory with the sticky bit
+    Sticky,
+    /// a directory writable by others
+    OtherWritable,
+    /// a directory with the sticky bit and writable by others
+    StickyOtherWritable,
+}
+
+/// The styles of file names in trees, depending on the kind of the
+/// file, on globs matching their names, or on their extensions.
+///
+/// The order of precedence is
+/// - the special kinds (everything but directory, link, and file)
+/// - the globs, the most specific ones first
+/// - the extension colors
+/// - the general kinds (directory, link, file)
+///
+/// When importing `LS_COLORS`, the extensions which already have a
+/// color in `ext_colors` are skipped, so that the user's colors win.
+#[derive(Debug, Clone, Default)]
+pub struct FileColorMap {
+    ext_colors: ExtColorMap,
+    kinds: FxHashMap<FileKind, CompoundStyle>,
+    /// styles of lowercase complete names (from globs without wildcard)
+    names: FxHashMap<String, CompoundStyle>,
+    /// styles of lowercase name ends (from globs like `*.tar.gz`)
+    suffixes: FxHashMap<String, CompoundStyle>,
+    /// the other globs, sorted with the longest ones first
+    globs: Vec<(glob::Pattern, CompoundStyle)>,
+}
+
+impl FileKind {
+    fn is_general(self) -> bool {
+        matches!(self, Self::Directory | Self::Link | Self::File)
+    }
+}
+
+impl FileColorMap {
+    pub fn new(ext_colors: ExtColorMap) -> Self {
+        Self {
+            ext_colors,
+            ..Default::default()
+        }
+    }
+    pub fn has_kind(
+        &self,
+        kind: FileKind,
+    ) -> bool {
+        self.kinds.contains_key(&kind)
+    }
+    pub fn set_kind(
+        &mut self,
+        kind: FileKind,
+        style: CompoundStyle,
+    ) {
+        self.kinds.insert(kind, style);
+    }
+    pub fn has_ext_color(
+        &self,
+        ext: &str,
+    ) -> bool {
+        self.ext_colors.get(ext).is_some()
+    }
+    pub fn has_glob(
+        &self,
+        glob: &str,
+    ) -> bool {
+        let lowercase = glob.to_lowercase();
+        self.names.contains_key(&lowercase)
+            || lowercase
+                .strip_prefix('*')
+                .is_some_and(|suffix| self.suffixes.contains_key(suffix))
+            || self
+                .globs
+                .iter()
+                .any(|(pattern, _)| pattern.as_str() == lowercase)
+    }
+    /// Set the style of the files whose name matches the glob
+    /// (case insensitively)
+    pub fn set_glob(
+        &mut self,
+        glob: &str,
+        style: CompoundStyle,
+    ) -> Result<(), ConfError> {
+        let lowercase = glob.to_lowercase();
+        let is_wildcard = |c| matches!(c, '*' | '?' | '[' | ']');
+        if !lowercase.contains(is_wildcard) {
+            self.names.insert(lowercase, style);
+        } else if let Some(suffix) = lowercase
+            .strip_prefix('*')
+            .filter(|suffix| !suffix.is_empty() && !suffix.contains(is_wildcard))
+        {
+            self.suffixes.insert(suffix.to_string(), style);
+        } else {
+            let pattern =
+                glob::Pattern::new(&lowercase).map_err(|_| ConfError::InvalidGlobPattern {
+                    pattern: glob.to_string(),
+                })?;
+            self.globs.retain(|(p, _)| p.as_str() != lowercase);
+            self.globs.push((pattern, style));
+            self.globs
+                .sort_by_key(|(p, _)| std::cmp::Reverse(p.as_str().len()));
+        }
+        Ok(())
+    }
+    /// Return the kinds of the line, the most specific first
+    fn kinds_of(line: &TreeLine) -> Vec<FileKind> {
+        let mut kinds = Vec::new();
+        match &line.line_type {
+            TreeLineType::Dir => {
+                #[cfg(unix)]
+                {
+                    let mode = line.metadata.permissions().mode();
+                    let sticky = mode & 0o1000 != 0;
+                    let other_writable = mode & 0o002 != 0;
+                    if sticky && other_writable {
+                        kinds.push(FileKind::StickyOtherWritable);
+                    }
+                    if other_writable {
+                        kinds.push(FileKind::OtherWritable);
+                    }
+                    if sticky {
+                        kinds.push(FileKind::Sticky);
+                    }
+                }
+                kinds.push(FileKind::Directory);
+            }
+            TreeLineType::BrokenSymLink(_) => {
+                kinds.push(FileKind::OrphanLink);
+                kinds.push(FileKind::Link);
+            }
+            TreeLineType::SymLink { .. } => {
+                kinds.push(FileKind::Link);
+            }
+            TreeLineType::File => {
+                #[cfg(unix)]
+                {
+                    let file_type = line.metadata.file_type();
+                    let mode = line.metadata.permissions().mode();
+                    if file_type.is_fifo() {
+                        kinds.push(FileKind::Fifo);
+                    } else if file_type.is_socket() {
+                        kinds.push(FileKind::Socket);
+                    } else if file_type.is_block_device() {
+                        kinds.push(FileKind::BlockDevice);
+                    } else if file_type.is_char_device() {
+                        kinds.push(FileKind::CharDevice);
+                    } else if mode & 0o4000 != 0 {
+                        kinds.push(FileKind::Setuid);
+                    } else if mode & 0o2000 != 0 {
+                        kinds.push(FileKind::Setgid);
+                    }
+                }
+                if line.is_exe() {
+                    kinds.push(FileKind::Executable);
+                }
+                kinds.push(FileKind::File);
+            }
+            TreeLineType::Pruning => {}
+        }
+        kinds
+    }
+    /// Return the style defined for the name, if any
+    pub fn name_style(
+        &self,
+        name: &str,
+    ) -> Option<&CompoundStyle> {
+        if self.names.is_empty() && self.suffixes.is_empty() && self.globs.is_empty() {
+            return None;
+        }
+        let lowercase = name.to_lowercase();
+        if let Some(style) = self.names.get(&lowercase) {
+            return Some(style);
+        }
+        // we look for the longest matching suffix
+        if !self.suffixes.is_empty() {
+            for (idx, _) in lowercase.char_indices() {
+                if let Some(style) = self.suffixes.get(&lowercase[idx..]) {
+                    return Some(style);
+                }
+            }
+        }
+        self.globs
+            .iter()
+            .find(|(pattern, _)| pattern.matches(&lowercase))
+            .map(|(_, style)| style)
+    }
+    /// Change the style of a tree line according to the kind of
+    /// the file and its name
+    pub fn apply(
+        &self,
+        line: &TreeLine,
+        style: &mut CompoundStyle,
+    ) {
+        let kinds = Self::kinds_of(line);
+        let special_style = kinds
+            .iter()
+            .filter(|kind| !kind.is_general())
+            .find_map(|kind| self.kinds.get(kind));
+        if let Some(special_style) = special_style {
+            style.overwrite_with(special_style);
+            return;
+        }
+        if let Some(name_style) = self.name_style(&line.name) {
+            style.overwrite_with(name_style);
+            return;
+        }
+        if let Some(ext_color) = line.extension().and_then(|ext| self.ext_colors.get(ext)) {
+            style.set_fg(ext_color);
+            return;
+        }
+        let general_style = kinds
+            .iter()
+            .filter(|kind| kind.is_general())
+            .find_map(|kind| self.kinds.get(kind));
+        if let Some(general_style) = general_style {
+            style.overwrite_with(general_style);
+        }
+    }
+}
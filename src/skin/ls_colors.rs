@@ -0,0 +1,190 @@
+//! Import of the `LS_COLORS` environment variable, as defined
+//! by `dircolors`, into a `FileColorMap`.
+//!
+//! See `man dircolors` and `man dir_colors` for the format.
+
+use {
+    super::*,
+    crokey::crossterm::style::{
+        Attribute,
+        Color,
+    },
+    termimad::CompoundStyle,
+};
+
+fn kind_of_code(code: &str) -> Option<FileKind> {
+    Some(match code {
+        "di" => FileKind::Directory,
+        "ln" => FileKind::Link,
+        "or" => FileKind::OrphanLink,
+        "fi" => FileKind::File,
+        "ex" => FileKind::Executable,
+        "pi" => FileKind::Fifo,
+        "so" => FileKind::Socket,
+        "bd" => FileKind::BlockDevice,
+        "cd" => FileKind::CharDevice,
+        "su" => FileKind::Setuid,
+        "sg" => FileKind::Setgid,
+        "st" => FileKind::Sticky,
+        "ow" => FileKind::OtherWritable,
+        "tw" => FileKind::StickyOtherWritable,
+        _ => {
+            return None;
+        }
+    })
+}
+
+/// Parse a color given as the remaining SGR parameters after
+/// a 38 or 48 code ("5;n" or "2;r;g;b")
+fn parse_extended_color<'a>(params: &mut impl Iterator<Item = &'a str>) -> Option<Color> {
+    match params.next()? {
+        "5" => params.next()?.parse().ok().map(Color::AnsiValue),
+        "2" => {
+            let r = params.next()?.parse().ok()?;
+            let g = params.next()?.parse().ok()?;
+            let b = params.next()?.parse().ok()?;
+            Some(Color::Rgb { r, g, b })
+        }
+        _ => None,
+    }
+}
+
+/// Parse a sequence of SGR parameters, like "01;38;5;208"
+///
+/// Return None when it's invalid or doesn't define anything
+pub fn parse_sgr(sgr: &str) -> Option<CompoundStyle> {
+    let mut style = CompoundStyle::default();
+    let mut defined = false;
+    let mut params = sgr.split(';');
+    while let Some(param) = params.next() {
+        let code: u8 = param.parse().ok()?;
+        match code {
+            0 => {}
+            1 => style.add_attr(Attribute::Bold),
+            2 => style.add_attr(Attribute::Dim),
+            3 => style.add_attr(Attribute::Italic),
+            4 => style.add_attr(Attribute::Underlined),
+            5 => style.add_attr(Attribute::SlowBlink),
+            7 => style.add_attr(Attribute::Reverse),
+            8 => style.add_attr(Attribute::Hidden),
+            9 => style.add_attr(Attribute::CrossedOut),
+            30..=37 => style.set_fg(Color::AnsiValue(code - 30)),
+            38 => style.set_fg(parse_extended_color(&mut params)?),
+            40..=47 => style.set_bg(Color::AnsiValue(code - 40)),
+            48 => style.set_bg(parse_extended_color(&mut params)?),
+            90..=97 => style.set_fg(Color::AnsiValue(code - 90 + 8)),
+            100..=107 => style.set_bg(Color::AnsiValue(code - 100 + 8)),
+            _ => {
+                continue;
+            }
+        }
+        defined |= code != 0;
+    }
+    defined.then_some(style)
+}
+
+impl FileColorMap {
+    /// Add the styles defined in a `LS_COLORS` value, except the ones
+    /// which are already defined in the map
+    pub fn import_ls_colors(
+        &mut self,
+        ls_colors: &str,
+    ) {
+        for entry in ls_colors.split(':') {
+            let Some((key, sgr)) = entry.split_once('=') else {
+                continue;
+            };
+            let Some(style) = parse_sgr(sgr) else {
+                continue; // e.g. "ln=target", or no style at all
+            };
+            if key.starts_with('*') {
+                // `*.rs` is skipped when the `rs` extension has a configured color
+                let ext_color_defined = key
+                    .strip_prefix("*.")
+                    .filter(|ext| !ext.contains(['.', '*', '?', '[']))
+                    .is_some_and(|ext| self.has_ext_color(ext));
+                if !ext_color_defined && !self.has_glob(key) {
+                    if let Err(e) = self.set_glob(key, style) {
+                        warn!("ignoring LS_COLORS entry {entry:?}: {e}");
+                    }
+                }
+            } else if let Some(kind) = kind_of_code(key) {
+                if !self.has_kind(kind) {
+                    self.set_kind(kind, style);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod ls_colors_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sgr() {
+        let style = parse_sgr("01;34").unwrap();
+        assert_eq!(style.get_fg(), Some(Color::AnsiValue(4)));
+        assert!(style.has_attr(Attribute::Bold));
+        let style = parse_sgr("38;5;208;48;2;10;20;30").unwrap();
+        assert_eq!(style.get_fg(), Some(Color::AnsiValue(208)));
+        assert_eq!(
+            style.get_bg(),
+            Some(Color::Rgb {
+                r: 10,
+                g: 20,
+                b: 30
+            })
+        );
+        let style = parse_sgr("40;93").unwrap();
+        assert_eq!(style.get_fg(), Some(Color::AnsiValue(11)));
+        assert_eq!(style.get_bg(), Some(Color::AnsiValue(0)));
+        assert!(parse_sgr("0").is_none());
+        assert!(parse_sgr("target").is_none());
+        assert!(parse_sgr("38;5").is_none());
+    }
+
+    #[test]
+    fn test_import_keeps_defined_entries() {
+        let mut map = FileColorMap::default();
+        map.set_glob("*.rs", CompoundStyle::with_fg(Color::Red))
+            .unwrap();
+        map.import_ls_colors("di=01;34:ln=target:*.rs=32:*.md=33:*.test.ts=35:so=01;35");
+        assert!(map.has_kind(FileKind::Directory));
+        assert!(map.has_kind(FileKind::Socket));
+        assert!(!map.has_kind(FileKind::Link));
+        assert_eq!(
+            map.name_style("main.rs").and_then(|s| s.get_fg()),
+            Some(Color::Red),
+        );
+        assert_eq!(
+            map.name_style("README.MD").and_then(|s| s.get_fg()),
+            Some(Color::AnsiValue(3)),
+        );
+        assert_eq!(
+            map.name_style("a.test.ts").and_then(|s| s.get_fg()),
+            Some(Color::AnsiValue(5)),
+        );
+        assert!(map.name_style("a.ts").is_none());
+    }
+
+    #[test]
+    fn test_import_keeps_ext_colors() {
+        let mut ext_colors = ExtColorMap::default();
+        ext_colors.set("rs".to_string(), "red").unwrap();
+        let mut map = FileColorMap::new(ext_colors);
+        map.import_ls_colors("*.rs=32:*.RS=32:*.md=33:*.tar.rs=35");
+        // the configured extension color isn't hidden by LS_COLORS
+        assert!(map.name_style("main.rs").is_none());
+        assert!(map.has_ext_color("rs"));
+        assert_eq!(
+            map.name_style("README.md").and_then(|s| s.get_fg()),
+            Some(Color::AnsiValue(3)),
+        );
+        // a longer suffix is still imported
+        assert_eq!(
+            map.name_style("a.tar.rs").and_then(|s| s.get_fg()),
+            Some(Color::AnsiValue(5)),
+        );
+    }
+}
@@ -1,7 +1,9 @@
 mod app_skin;
 mod cli_mad_skin;
 mod ext_colors;
+mod file_colors;
 mod help_mad_skin;
+mod ls_colors;
 mod panel_skin;
 mod purpose_mad_skin;
 mod skin_entry;
@@ -12,6 +14,7 @@ pub use {
     app_skin::AppSkin,
     cli_mad_skin::*,
     ext_colors::ExtColorMap,
+    file_colors::*,
     help_mad_skin::*,
     panel_skin::PanelSkin,
     purpose_mad_skin::*,
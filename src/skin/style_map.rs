@@ -225,8 +225,21 @@ StyleMap! {
     hex_ascii_whitespace: ansi(143), None, []
     hex_ascii_other: ansi(215), None, []
     hex_non_ascii: ansi(167), None, []
+    hex_cursor: None, ansi(24), []
+    structured_key: ansi(110), None, []
+    structured_string: ansi(107), None, []
+    structured_number: ansi(179), None, []
+    structured_literal: ansi(176), None, []
+    structured_count: gray(12), None, []
+    info_name: ansi(109), None, []
     staging_area_title: gray(22), gray(2), [] / gray(20), gray(3), []
     mode_command_mark: gray(5), ansi(204), [Bold]
+    input_prompt: ansi(178), None, [Bold]
+    job_running: ansi(220), None, []
+    job_success: ansi(28), None, []
+    job_failure: ansi(160), None, []
+    tab: gray(15), gray(2), []
+    tab_active: ansi(178), gray(4), [Bold]
     good_to_bad_0: ansi(28), None, []
     good_to_bad_1: ansi(29), None, []
     good_to_bad_2: ansi(29), None, []
@@ -99,7 +99,7 @@ impl StageState {
             show_count_label = true;
             if self.tree_options.show_sizes {
                 if let Some(sum) = self.stage_sum.computed() {
-                    let size = file_size::fit_4(sum.to_size());
+                    let size = file_size::fit_4(sum.size(self.tree_options.apparent_sizes));
                     let size_len = SIZE_LABEL.len() + size.len();
                     if size_len < rem {
                         rem -= size_len;
@@ -0,0 +1,4 @@
+mod structured_view;
+mod value;
+
+pub use structured_view::StructuredView;
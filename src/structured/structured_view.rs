@@ -0,0 +1,719 @@
+use {
+    super::value::{
+        DataFormat,
+        Value,
+        parse,
+    },
+    crate::{
+        app::AppContext,
+        command::{
+            ListScroll,
+            ScrollCommand,
+        },
+        content_type::encoding::Encoding,
+        display::{
+            Screen,
+            W,
+        },
+        errors::ProgramError,
+        pattern::InputPattern,
+        skin::PanelSkin,
+        syntactic::WINDOWED_MIN_FILE_SIZE,
+        task_sync::Dam,
+    },
+    crokey::crossterm::{
+        QueueableCommand,
+        cursor,
+        style::{
+            Color,
+            Print,
+            SetBackgroundColor,
+            SetForegroundColor,
+        },
+    },
+    std::{
+        fs,
+        path::{
+            Path,
+            PathBuf,
+        },
+    },
+    termimad::{
+        Area,
+        CropWriter,
+        SPACE_FILLING,
+    },
+};
+
+/// When the file is opened, the levels are unfolded as long as
+/// the number of lines doesn't exceed this
+const AUTO_UNFOLD_MAX_LINES: usize = 100;
+
+/// number of nodes between two checks of the dam when filtering
+const DAM_CHECK_PERIOD: usize = 1000;
+
+/// A preview of a JSON, HJSON, TOML or YAML file as a tree of
+/// foldable keys
+pub struct StructuredView {
+    pub path: PathBuf,
+    pub pattern: InputPattern,
+    format: DataFormat,
+    /// all the nodes, in document order
+    nodes: Vec<Node>,
+    /// when there's a pattern, the nodes which match or have a
+    /// matching descendant
+    kept: Option<Vec<bool>>,
+    matching_count: usize,
+    /// indexes in nodes of the displayed lines
+    lines: Vec<usize>,
+    list_scroll: ListScroll, // the selection is an index in lines
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Key {
+    Name(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone)]
+enum Content {
+    /// any value but an array or an object
+    Scalar(Value),
+    Array(usize),
+    Object(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    depth: usize,
+    parent: Option<usize>,
+    /// none for a root which isn't an array or an object
+    key: Option<Key>,
+    content: Content,
+    /// index of the first node after the descendants of this one
+    end: usize,
+    folded: bool,
+}
+
+impl Node {
+    /// tell whether the node is an array or an object with children
+    fn is_foldable(&self) -> bool {
+        matches!(self.content, Content::Array(n) | Content::Object(n) if n > 0)
+    }
+    /// the text of the line, with the number of chars of the key part
+    fn text(&self) -> (String, usize) {
+        let mut text = match &self.key {
+            Some(Key::Name(name)) => format!("{name}: "),
+            Some(Key::Index(idx)) => format!("{idx}: "),
+            None => String::new(),
+        };
+        let key_len = text.chars().count();
+        match &self.content {
+            Content::Scalar(Value::Null) => text.push_str("null"),
+            Content::Scalar(Value::Bool(b)) => text.push_str(if *b { "true" } else { "false" }),
+            Content::Scalar(Value::String(s)) => text.push_str(&format!("{s:?}")),
+            Content::Scalar(Value::Number(s) | Value::Date(s)) => text.push_str(s),
+            Content::Array(1) => text.push_str("[1 item]"),
+            Content::Array(n) => text.push_str(&format!("[{n} items]")),
+            Content::Object(1) => text.push_str("{1 key}"),
+            Content::Object(n) => text.push_str(&format!("{{{n} keys}}")),
+            Content::Scalar(_) => {} // arrays and objects aren't scalars
+        }
+        (text, key_len)
+    }
+}
+
+/// add the node of the value, and the nodes of its descendants
+fn flatten(
+    value: Value,
+    key: Option<Key>,
+    depth: usize,
+    parent: Option<usize>,
+    nodes: &mut Vec<Node>,
+) {
+    let first = nodes.len();
+    // the values still to add, the next one last
+    let mut stack = vec![(value, key, depth, parent)];
+    while let Some((value, key, depth, parent)) = stack.pop() {
+        let idx = nodes.len();
+        let content = match value {
+            Value::Array(values) => {
+                let len = values.len();
+                stack.extend(values.into_iter().enumerate().rev().map(|(i, value)| {
+                    (value, Some(Key::Index(i)), depth + 1, Some(idx))
+                }));
+                Content::Array(len)
+            }
+            Value::Object(entries) => {
+                let len = entries.len();
+                stack.extend(entries.into_iter().rev().map(|(name, value)| {
+                    (value, Some(Key::Name(name)), depth + 1, Some(idx))
+                }));
+                Content::Object(len)
+            }
+            scalar => Content::Scalar(scalar),
+        };
+        nodes.push(Node {
+            depth,
+            parent,
+            key,
+            content,
+            end: idx + 1,
+            folded: false,
+        });
+    }
+    // descendants come after their ancestors, so a reverse pass is
+    // enough to give each node the end of its last descendant
+    for idx in (first..nodes.len()).rev() {
+        if let Some(parent) = nodes[idx].parent {
+            nodes[parent].end = nodes[parent].end.max(nodes[idx].end);
+        }
+    }
+}
+
+/// Build the nodes of a document. The root, when it's an array
+/// or an object, isn't a node: its children are at depth 0.
+fn make_nodes(root: Value) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    match root {
+        Value::Array(values) => {
+            for (i, value) in values.into_iter().enumerate() {
+                flatten(value, Some(Key::Index(i)), 0, None, &mut nodes);
+            }
+        }
+        Value::Object(entries) => {
+            for (name, value) in entries {
+                flatten(value, Some(Key::Name(name)), 0, None, &mut nodes);
+            }
+        }
+        scalar => flatten(scalar, None, 0, None, &mut nodes),
+    }
+    // we unfold as many levels as possible without having too many lines
+    let max_depth = nodes.iter().map(|node| node.depth).max().unwrap_or(0);
+    let mut counts = vec![0; max_depth + 1];
+    for node in &nodes {
+        counts[node.depth] += 1;
+    }
+    let mut unfolded_depth = 0;
+    let mut lines_count = counts[0];
+    while unfolded_depth < max_depth
+        && lines_count + counts[unfolded_depth + 1] <= AUTO_UNFOLD_MAX_LINES
+    {
+        unfolded_depth += 1;
+        lines_count += counts[unfolded_depth];
+    }
+    for node in &mut nodes {
+        node.folded = node.is_foldable() && node.depth >= unfolded_depth;
+    }
+    nodes
+}
+
+/// Split a key path like `a.b[2].c` (or `a.b.2.c`) into its segments
+fn parse_key_path(path: &str) -> Vec<&str> {
+    path.trim()
+        .trim_start_matches('$')
+        .split(['.', '[', ']'])
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+impl StructuredView {
+    pub fn new(
+        path: &Path,
+        con: &AppContext,
+    ) -> Result<Self, ProgramError> {
+        let len = fs::metadata(path)?.len();
+        if len == 0 {
+            return Err(ProgramError::ZeroLenFile);
+        }
+        if len >= WINDOWED_MIN_FILE_SIZE {
+            return Err(ProgramError::TooBigFile);
+        }
+        let bytes = fs::read(path)?;
+        let encoding = match con.forced_encoding {
+            Some(encoding) => encoding,
+            None => Encoding::detect(&bytes).ok_or(ProgramError::UnprintableFile)?,
+        };
+        let text = encoding.decode(&bytes[encoding.bom_len(&bytes)..]);
+        let (root, format) = parse(path, &text)?;
+        let mut view = Self {
+            path: path.to_path_buf(),
+            pattern: InputPattern::none(),
+            format,
+            nodes: make_nodes(root),
+            kept: None,
+            matching_count: 0,
+            lines: Vec::new(),
+            list_scroll: ListScroll::default(),
+        };
+        view.update_lines();
+        Ok(view)
+    }
+
+    /// Build a view of the nodes matching the pattern, with their ancestors,
+    /// or None if the dam gets an event before it's done
+    pub fn filtered(
+        &self,
+        pattern: InputPattern,
+        dam: &Dam,
+    ) -> Option<Self> {
+        let mut nodes = self.nodes.clone();
+        let mut kept = None;
+        let mut matching_count = 0;
+        if pattern.is_some() {
+            let mut mask = vec![false; nodes.len()];
+            // children come after their parent, so a backward pass
+            // propagates the matches to the ancestors
+            for idx in (0..nodes.len()).rev() {
+                if idx % DAM_CHECK_PERIOD == 0 && dam.has_event() {
+                    return None;
+                }
+                if !mask[idx] && pattern.pattern.search_string(&nodes[idx].text().0).is_some() {
+                    mask[idx] = true;
+                    matching_count += 1;
+                }
+                if mask[idx] {
+                    if let Some(parent) = nodes[idx].parent {
+                        mask[parent] = true;
+                    }
+                }
+            }
+            for node in &mut nodes {
+                node.folded = false;
+            }
+            kept = Some(mask);
+        }
+        let mut view = Self {
+            path: self.path.clone(),
+            pattern,
+            format: self.format,
+            nodes,
+            kept,
+            matching_count,
+            lines: Vec::new(),
+            list_scroll: ListScroll {
+                page_height: self.list_scroll.page_height,
+                ..Default::default()
+            },
+        };
+        view.update_lines();
+        Some(view)
+    }
+
+    fn is_kept(
+        &self,
+        idx: usize,
+    ) -> bool {
+        self.kept.as_ref().is_none_or(|mask| mask[idx])
+    }
+
+    /// compute the displayed lines, according to the foldings
+    /// and the pattern, keeping the selected node if possible
+    fn update_lines(&mut self) {
+        let selected_node = self.selected_node();
+        self.lines.clear();
+        let mut idx = 0;
+        while idx < self.nodes.len() {
+            let node = &self.nodes[idx];
+            if !self.is_kept(idx) {
+                idx = node.end;
+                continue;
+            }
+            self.lines.push(idx);
+            idx = if node.folded { node.end } else { idx + 1 };
+        }
+        self.list_scroll.selection_idx =
+            selected_node.and_then(|node| self.lines.iter().position(|&idx| idx == node));
+        self.ensure_selection_is_visible();
+    }
+
+    fn selected_node(&self) -> Option<usize> {
+        self.list_scroll.selection_idx.and_then(|idx| self.lines.get(idx).copied())
+    }
+
+    /// the path of the node, as accepted by `jump_to_key`
+    fn key_path(
+        &self,
+        mut idx: usize,
+    ) -> String {
+        let mut segments = Vec::new();
+        loop {
+            let node = &self.nodes[idx];
+            match &node.key {
+                Some(Key::Name(name)) => segments.push(format!(".{name}")),
+                Some(Key::Index(i)) => segments.push(format!("[{i}]")),
+                None => {}
+            }
+            match node.parent {
+                Some(parent) => idx = parent,
+                None => break,
+            }
+        }
+        segments.reverse();
+        let path = segments.concat();
+        path.strip_prefix('.').map(str::to_string).unwrap_or(path)
+    }
+
+    /// Select the node at the given key path (eg `package.authors[0]`),
+    /// unfolding its ancestors. Return false if there's no such node.
+    pub fn jump_to_key(
+        &mut self,
+        path: &str,
+    ) -> bool {
+        let mut found = None;
+        let (mut start, mut end) = (0, self.nodes.len());
+        for segment in parse_key_path(path) {
+            let index = segment.parse::<usize>().ok();
+            let mut idx = start;
+            found = None;
+            while idx < end {
+                let node = &self.nodes[idx];
+                let matching = match &node.key {
+                    Some(Key::Name(name)) => name == segment,
+                    Some(Key::Index(i)) => Some(*i) == index,
+                    None => false,
+                };
+                if matching {
+                    found = Some(idx);
+                    break;
+                }
+                idx = node.end;
+            }
+            let Some(idx) = found else {
+                return false;
+            };
+            (start, end) = (idx + 1, self.nodes[idx].end);
+        }
+        let Some(idx) = found else {
+            return false;
+        };
+        if !self.is_kept(idx) {
+            return false;
+        }
+        let mut ancestor = self.nodes[idx].parent;
+        while let Some(parent) = ancestor {
+            self.nodes[parent].folded = false;
+            ancestor = self.nodes[parent].parent;
+        }
+        self.update_lines();
+        self.list_scroll.selection_idx = self.lines.iter().position(|&line| line == idx);
+        self.ensure_selection_is_visible();
+        true
+    }
+
+    /// Fold (dir < 0) or unfold (dir > 0) the selected node, or, when it's
+    /// already done, select its parent or first child. Return true if the
+    /// view changed.
+    pub fn fold(
+        &mut self,
+        dir: i32,
+    ) -> bool {
+        let Some(idx) = self.selected_node() else {
+            self.select_first();
+            return self.list_scroll.selection_idx.is_some();
+        };
+        let node = &self.nodes[idx];
+        if dir < 0 {
+            let parent_line = node
+                .parent
+                .and_then(|parent| self.lines.iter().position(|&line| line == parent));
+            if node.is_foldable() && !node.folded {
+                self.nodes[idx].folded = true;
+                self.update_lines();
+                true
+            } else if let Some(parent_line) = parent_line {
+                self.list_scroll.selection_idx = Some(parent_line);
+                self.ensure_selection_is_visible();
+                true
+            } else {
+                false
+            }
+        } else if node.is_foldable() && node.folded {
+            self.nodes[idx].folded = false;
+            self.update_lines();
+            true
+        } else if node.is_foldable() {
+            let selection_idx = self.list_scroll.selection_idx.map(|sel| sel + 1);
+            // the first child isn't necessarily displayed, when filtered
+            if selection_idx
+                .and_then(|sel| self.lines.get(sel))
+                .is_some_and(|&line| self.nodes[line].parent == Some(idx))
+            {
+                self.list_scroll.selection_idx = selection_idx;
+                self.ensure_selection_is_visible();
+                true
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn get_selected_line(&self) -> Option<String> {
+        self.selected_node().map(|idx| self.nodes[idx].text().0)
+    }
+
+    fn ensure_selection_is_visible(&mut self) {
+        self.list_scroll.ensure_selection_is_visible(self.lines.len());
+    }
+
+    pub fn unselect(&mut self) {
+        self.list_scroll.selection_idx = None;
+    }
+    pub fn try_select_y(
+        &mut self,
+        y: u16,
+    ) -> bool {
+        self.list_scroll.try_select_row(y as usize, self.lines.len())
+    }
+
+    pub fn select_first(&mut self) {
+        self.list_scroll.select_first(self.lines.len());
+    }
+    pub fn select_last(&mut self) {
+        self.list_scroll.select_last(self.lines.len());
+    }
+
+    pub fn move_selection(
+        &mut self,
+        dy: i32,
+        cycle: bool,
+    ) {
+        self.list_scroll.move_selection(dy, cycle, self.lines.len());
+        self.ensure_selection_is_visible();
+    }
+
+    pub fn try_scroll(
+        &mut self,
+        cmd: ScrollCommand,
+    ) -> bool {
+        self.list_scroll.try_scroll(cmd, self.lines.len())
+    }
+
+    pub fn display(
+        &mut self,
+        w: &mut W,
+        _screen: Screen,
+        panel_skin: &PanelSkin,
+        area: &Area,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        if area.height as usize != self.list_scroll.page_height {
+            self.list_scroll.page_height = area.height as usize;
+            self.ensure_selection_is_visible();
+        }
+        let styles = &panel_skin.styles;
+        let normal_bg = styles
+            .preview
+            .get_bg()
+            .or_else(|| styles.default.get_bg())
+            .unwrap_or(Color::Reset);
+        let selection_bg = styles
+            .selected_line
+            .get_bg()
+            .unwrap_or(Color::AnsiValue(240));
+        let match_bg = styles
+            .preview_match
+            .get_bg()
+            .unwrap_or(Color::AnsiValue(28));
+        let content_width = area.width as usize - 1; // 1 char left for scrollbar
+        let scrollbar = area.scrollbar(self.list_scroll.scroll, self.lines.len());
+        let scrollbar_fg = styles
+            .scrollbar_thumb
+            .get_fg()
+            .or_else(|| styles.preview.get_fg())
+            .unwrap_or(Color::White);
+        for y in 0..area.height as usize {
+            w.queue(cursor::MoveTo(area.left, area.top + y as u16))?;
+            let mut cw = CropWriter::new(w, content_width);
+            let line_idx = self.list_scroll.scroll + y;
+            let selected = self.list_scroll.is_selected(line_idx);
+            let bg = if selected { selection_bg } else { normal_bg };
+            let mut style = styles.preview;
+            style.set_bg(bg);
+            if let Some(&idx) = self.lines.get(line_idx) {
+                let node = &self.nodes[idx];
+                let mut count_style = styles.structured_count;
+                count_style.set_bg(bg);
+                let mut key_style = match node.key {
+                    Some(Key::Index(_)) => count_style,
+                    _ => styles.structured_key,
+                };
+                key_style.set_bg(bg);
+                let mut value_style = match &node.content {
+                    Content::Scalar(Value::String(_)) => styles.structured_string,
+                    Content::Scalar(Value::Number(_)) => styles.structured_number,
+                    Content::Scalar(_) => styles.structured_literal,
+                    _ => styles.structured_count,
+                };
+                value_style.set_bg(bg);
+                cw.queue_char(
+                    &style,
+                    if selected && con.show_selection_mark {
+                        '▶'
+                    } else {
+                        ' '
+                    },
+                )?;
+                cw.repeat(&style, &SPACE_FILLING, 2 * node.depth)?;
+                let marker = match (node.is_foldable(), node.folded) {
+                    (false, _) => ' ',
+                    (true, false) => '▾',
+                    (true, true) => '▸',
+                };
+                cw.queue_char(&count_style, marker)?;
+                cw.queue_char(&style, ' ')?;
+                let (text, key_len) = node.text();
+                let name_match = if self.pattern.is_some() {
+                    self.pattern.pattern.search_string(&text)
+                } else {
+                    None
+                };
+                for (i, c) in text.chars().enumerate() {
+                    let mut char_style = if i + 2 < key_len {
+                        key_style
+                    } else if i < key_len {
+                        style
+                    } else {
+                        value_style
+                    };
+                    if name_match.as_ref().is_some_and(|nm| nm.pos.contains(&i)) {
+                        char_style.set_bg(match_bg);
+                    }
+                    cw.queue_char(&char_style, c)?;
+                }
+            }
+            cw.fill(&style, &SPACE_FILLING)?;
+            w.queue(SetBackgroundColor(bg))?;
+            if ScrollCommand::is_thumb(area.top + y as u16, scrollbar) {
+                w.queue(SetForegroundColor(scrollbar_fg))?;
+                w.queue(Print('▐'))?;
+            } else {
+                w.queue(Print(' '))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn info(
+        &self,
+        width: usize,
+    ) -> String {
+        let mut s = if self.pattern.is_some() {
+            format!("matches: {}", self.matching_count)
+        } else {
+            self.format.name().to_string()
+        };
+        if let Some(idx) = self.selected_node() {
+            let path = self.key_path(idx);
+            if s.len() + path.chars().count() + 1 < width {
+                s = format!("{path} {s}");
+            }
+        }
+        if s.chars().count() > width {
+            return "".to_string();
+        }
+        s
+    }
+
+    pub fn display_info(
+        &mut self,
+        w: &mut W,
+        _screen: Screen,
+        panel_skin: &PanelSkin,
+        area: &Area,
+    ) -> Result<(), ProgramError> {
+        let width = area.width as usize;
+        let s = self.info(width);
+        w.queue(cursor::MoveTo(
+            area.left + area.width - s.chars().count() as u16,
+            area.top,
+        ))?;
+        panel_skin.styles.default.queue(w, s)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod structured_view_tests {
+    use {
+        super::*,
+        crate::pattern::{
+            ExactPattern,
+            Pattern,
+        },
+    };
+
+    fn view(json: &str) -> StructuredView {
+        let mut view = StructuredView {
+            path: PathBuf::from("test.json"),
+            pattern: InputPattern::none(),
+            format: DataFormat::Json,
+            nodes: make_nodes(DataFormat::Json.parse(json).unwrap()),
+            kept: None,
+            matching_count: 0,
+            lines: Vec::new(),
+            list_scroll: ListScroll {
+                page_height: 10,
+                ..Default::default()
+            },
+        };
+        view.update_lines();
+        view
+    }
+
+    fn displayed(view: &StructuredView) -> Vec<String> {
+        view.lines
+            .iter()
+            .map(|&idx| view.nodes[idx].text().0)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_key_path() {
+        assert_eq!(parse_key_path("a.b[2].c"), vec!["a", "b", "2", "c"]);
+        assert_eq!(parse_key_path(" $.a.0 "), vec!["a", "0"]);
+        assert!(parse_key_path("").is_empty());
+    }
+
+    #[test]
+    fn test_fold_and_jump() {
+        let mut view = view(r#"{"a": {"b": [1, {"c": true}]}, "d": "x"}"#);
+        // small enough to be fully unfolded
+        assert_eq!(
+            displayed(&view),
+            vec![
+                "a: {1 key}",
+                "b: [2 items]",
+                "0: 1",
+                "1: {1 key}",
+                "c: true",
+                "d: \"x\"",
+            ]
+        );
+        assert!(view.jump_to_key("a.b[1]"));
+        assert_eq!(view.key_path(view.selected_node().unwrap()), "a.b[1]");
+        assert!(view.fold(-1)); // folds the selected node
+        assert_eq!(displayed(&view).len(), 5);
+        assert!(view.fold(-1)); // selects the parent
+        assert_eq!(view.key_path(view.selected_node().unwrap()), "a.b");
+        assert!(view.fold(-1));
+        assert_eq!(displayed(&view), vec!["a: {1 key}", "b: [2 items]", "d: \"x\""]);
+        // jumping unfolds the ancestors
+        assert!(view.jump_to_key("a.b.1.c"));
+        assert_eq!(displayed(&view).len(), 6);
+        assert!(!view.jump_to_key("a.z"));
+    }
+
+    #[test]
+    fn test_filter() {
+        let view = view(r#"{"a": {"b": 1, "c": 2}, "d": {"e": 3}}"#);
+        let pattern = InputPattern {
+            raw: "c".to_string(),
+            pattern: Pattern::NameExact(ExactPattern::from("c")),
+        };
+        let filtered = view.filtered(pattern, &Dam::unlimited()).unwrap();
+        assert_eq!(displayed(&filtered), vec!["a: {2 keys}", "c: 2"]);
+    }
+}
@@ -0,0 +1,537 @@
+use {
+    crate::{
+        errors::ProgramError,
+        path::path_has_ext,
+    },
+    rustc_hash::FxHashMap,
+    serde::de::{
+        self,
+        Deserialize,
+        DeserializeSeed,
+        Deserializer,
+        MapAccess,
+        SeqAccess,
+        Visitor,
+    },
+    std::{
+        fmt,
+        panic,
+        path::Path,
+    },
+    yaml_rust::{
+        Yaml,
+        YamlLoader,
+        parser::{
+            Event,
+            MarkedEventReceiver,
+            Parser,
+        },
+        scanner::Marker,
+    },
+};
+
+/// the key under which the toml crate gives its dates to a deserializer
+const TOML_DATETIME_FIELD: &str = "$__toml_private_datetime";
+
+/// Max nesting depth of the parsed documents, as the parsers, and the
+/// building and dropping of values, are recursive and would overflow
+/// the stack on deeply nested input
+const MAX_DEPTH: usize = 128;
+
+/// Max number of nodes the aliases of a YAML document may expand to, as
+/// the YAML loader copies the anchored node at every alias, so that a
+/// tiny file could take hours and all the memory to load
+const MAX_ALIASED_NODES: usize = 100_000;
+
+/// A value read from a JSON, HJSON, TOML or YAML file, the order of
+/// the keys in objects being kept
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    /// a number, as written for integers and in YAML, else as
+    /// formatted from its float value (eg `1e3` becomes `1000`)
+    Number(String),
+    String(String),
+    Date(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+/// The data formats which can be parsed into a [Value]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DataFormat {
+    Json,
+    Hjson,
+    Toml,
+    Yaml,
+}
+
+impl DataFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Hjson => "hjson",
+            Self::Toml => "toml",
+            Self::Yaml => "yaml",
+        }
+    }
+    /// the format of a file, according to its extension
+    pub fn of_path(path: &Path) -> Option<Self> {
+        if path_has_ext(path, "json") {
+            Some(Self::Json)
+        } else if path_has_ext(path, "hjson") {
+            Some(Self::Hjson)
+        } else if path_has_ext(path, "toml") {
+            Some(Self::Toml)
+        } else if path_has_ext(path, "yaml") || path_has_ext(path, "yml") {
+            Some(Self::Yaml)
+        } else {
+            None
+        }
+    }
+    pub fn parse(
+        self,
+        text: &str,
+    ) -> Result<Value, ProgramError> {
+        let unparsable = |details: String| ProgramError::UnparsableFile { details };
+        // a cheap check before parsing, the real limit being enforced
+        // while building the value
+        if max_depth(text, self) > MAX_DEPTH {
+            return Err(unparsable(too_deep()));
+        }
+        match self {
+            Self::Json => serde_json::from_str(text).or_else(|e| {
+                // JSON files may have comments or trailing commas, which
+                // Hjson, a superset of JSON, accepts
+                parse_hjson(text).map_err(|_| unparsable(e.to_string()))
+            }),
+            Self::Hjson => parse_hjson(text).map_err(unparsable),
+            Self::Toml => toml::from_str(text).map_err(|e| {
+                let mut details = e.message().to_string();
+                if let Some(span) = e.span() {
+                    let line = text[..span.start].matches('\n').count() + 1;
+                    details = format!("{details} (line {line})");
+                }
+                unparsable(details)
+            }),
+            Self::Yaml => {
+                let mut counter = AliasCounter::default();
+                Parser::new(text.chars())
+                    .load(&mut counter, true)
+                    .map_err(|e| unparsable(e.to_string()))?;
+                if counter.aliased > MAX_ALIASED_NODES {
+                    return Err(unparsable(format!(
+                        "aliases expanding to more than {MAX_ALIASED_NODES} nodes"
+                    )));
+                }
+                let mut docs =
+                    YamlLoader::load_from_str(text).map_err(|e| unparsable(e.to_string()))?;
+                match docs.len() {
+                    0 => Ok(Value::Null),
+                    1 => Value::from_yaml(docs.remove(0), 0),
+                    _ => docs
+                        .into_iter()
+                        .map(|doc| Value::from_yaml(doc, 1))
+                        .collect::<Result<_, _>>()
+                        .map(Value::Array),
+                }
+                .map_err(unparsable)
+            }
+        }
+    }
+}
+
+/// Parse a Hjson (or JSON) text.
+///
+/// deser_hjson panics on some errors (eg when locating an error just
+/// before a multi-byte char), so the panics are caught.
+fn parse_hjson(text: &str) -> Result<Value, String> {
+    let from_str = |text: &str| match panic::catch_unwind(|| deser_hjson::from_str(text)) {
+        Ok(res) => res.map_err(|e| e.to_string()),
+        Err(_) => Err("invalid Hjson".to_string()),
+    };
+    from_str(text).or_else(|e| {
+        // the braces around the root object may be omitted in Hjson
+        from_str(&format!("{{\n{text}\n}}")).map_err(|_| e)
+    })
+}
+
+/// Computes, from the events of the YAML parser, the number of nodes
+/// the aliases of a document expand to
+#[derive(Default)]
+struct AliasCounter {
+    /// sizes of the anchored nodes, by anchor id
+    anchored: FxHashMap<usize, usize>,
+    /// anchor ids and sizes of the collections being parsed
+    open: Vec<(usize, usize)>,
+    aliased: usize,
+}
+
+impl MarkedEventReceiver for AliasCounter {
+    fn on_event(
+        &mut self,
+        event: Event,
+        _mark: Marker,
+    ) {
+        let (anchor, size) = match event {
+            Event::SequenceStart(anchor) | Event::MappingStart(anchor) => {
+                self.open.push((anchor, 1));
+                return;
+            }
+            Event::SequenceEnd | Event::MappingEnd => match self.open.pop() {
+                Some(collection) => collection,
+                None => return,
+            },
+            Event::Scalar(_, _, anchor, _) => (anchor, 1),
+            Event::Alias(id) => {
+                let size = self.anchored.get(&id).copied().unwrap_or(1);
+                self.aliased = self.aliased.saturating_add(size);
+                (0, size)
+            }
+            _ => return,
+        };
+        // 0 is the anchor id of nodes without anchor
+        if anchor > 0 {
+            self.anchored.insert(anchor, size);
+        }
+        if let Some((_, parent_size)) = self.open.last_mut() {
+            *parent_size = parent_size.saturating_add(size);
+        }
+    }
+}
+
+/// Return an estimate of the nesting depth of a document, from the
+/// brackets and braces out of quoted strings and, in YAML, from the
+/// indentation
+fn max_depth(
+    text: &str,
+    format: DataFormat,
+) -> usize {
+    let mut max = 0;
+    let mut depth: usize = 0;
+    for line in text.lines() {
+        // strings spanning several lines are ignored, so that an
+        // unbalanced quote can't hide brackets
+        let mut quote: Option<char> = None;
+        let mut escaped = false;
+        // single quoted strings have no escapes in TOML and YAML
+        let single_quote_escapes = matches!(format, DataFormat::Json | DataFormat::Hjson);
+        let mut previous = ' ';
+        let indent = if format == DataFormat::Yaml {
+            // a nested sequence may start on the line of its parent's dash
+            line.len() - line.trim_start_matches([' ', '-']).len()
+        } else {
+            0
+        };
+        for c in line.chars() {
+            if let Some(q) = quote {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' && (q == '"' || single_quote_escapes) {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+                continue;
+            }
+            match c {
+                '"' => quote = Some(c),
+                // an apostrophe in a word doesn't start a string
+                '\'' if !previous.is_alphanumeric() => quote = Some(c),
+                '[' | '{' => {
+                    depth += 1;
+                    max = max.max(indent + depth);
+                }
+                ']' | '}' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            previous = c;
+        }
+        max = max.max(indent + depth);
+    }
+    max
+}
+
+/// Parse a text with the format of the file extension or, when it's
+/// unknown, with the first format giving an array or an object.
+pub fn parse(
+    path: &Path,
+    text: &str,
+) -> Result<(Value, DataFormat), ProgramError> {
+    if let Some(format) = DataFormat::of_path(path) {
+        return format.parse(text).map(|value| (value, format));
+    }
+    for format in [DataFormat::Json, DataFormat::Toml, DataFormat::Yaml] {
+        if let Ok(value @ (Value::Array(_) | Value::Object(_))) = format.parse(text) {
+            return Ok((value, format));
+        }
+    }
+    Err(ProgramError::UnparsableFile {
+        details: "not JSON, TOML or YAML".to_string(),
+    })
+}
+
+impl Value {
+    /// Build a value from a YAML node found at the given depth
+    fn from_yaml(
+        yaml: Yaml,
+        depth: usize,
+    ) -> Result<Self, String> {
+        if depth > MAX_DEPTH {
+            return Err(too_deep());
+        }
+        Ok(match yaml {
+            Yaml::Real(s) => Self::Number(s),
+            Yaml::Integer(i) => Self::Number(i.to_string()),
+            Yaml::String(s) => Self::String(s),
+            Yaml::Boolean(b) => Self::Bool(b),
+            Yaml::Array(a) => Self::Array(
+                a.into_iter()
+                    .map(|v| Self::from_yaml(v, depth + 1))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Yaml::Hash(h) => Self::Object(
+                h.into_iter()
+                    .map(|(k, v)| Ok((yaml_key(k), Self::from_yaml(v, depth + 1)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => Self::Null,
+        })
+    }
+}
+
+fn too_deep() -> String {
+    format!("more than {MAX_DEPTH} levels of nesting")
+}
+
+fn yaml_key(key: Yaml) -> String {
+    match key {
+        Yaml::Real(s) | Yaml::String(s) => s,
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Boolean(b) => b.to_string(),
+        _ => "~".to_string(),
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        ValueSeed { depth: 0 }.deserialize(deserializer)
+    }
+}
+
+/// Deserializes a value found at a given depth, failing past
+/// [MAX_DEPTH] before the parser recurses any deeper
+#[derive(Clone, Copy)]
+struct ValueSeed {
+    depth: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed {
+    type Value = Value;
+
+    fn deserialize<D>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if self.depth > MAX_DEPTH {
+            return Err(de::Error::custom(too_deep()));
+        }
+        deserializer.deserialize_any(ValueVisitor { depth: self.depth })
+    }
+}
+
+struct ValueVisitor {
+    depth: usize,
+}
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(
+        &self,
+        formatter: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+    fn visit_bool<E: de::Error>(
+        self,
+        v: bool,
+    ) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+    fn visit_i64<E: de::Error>(
+        self,
+        v: i64,
+    ) -> Result<Value, E> {
+        Ok(Value::Number(v.to_string()))
+    }
+    fn visit_u64<E: de::Error>(
+        self,
+        v: u64,
+    ) -> Result<Value, E> {
+        Ok(Value::Number(v.to_string()))
+    }
+    fn visit_f64<E: de::Error>(
+        self,
+        v: f64,
+    ) -> Result<Value, E> {
+        Ok(Value::Number(v.to_string()))
+    }
+    fn visit_str<E: de::Error>(
+        self,
+        v: &str,
+    ) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+    fn visit_string<E: de::Error>(
+        self,
+        v: String,
+    ) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+    fn visit_some<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        ValueSeed { depth: self.depth }.deserialize(deserializer)
+    }
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> Result<Value, A::Error> {
+        let seed = ValueSeed { depth: self.depth + 1 };
+        let mut values = Vec::new();
+        while let Some(value) = seq.next_element_seed(seed)? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> Result<Value, A::Error> {
+        let seed = ValueSeed { depth: self.depth + 1 };
+        let mut entries = Vec::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(seed)?;
+            if key == TOML_DATETIME_FIELD {
+                if let Value::String(date) = value {
+                    return Ok(Value::Date(date));
+                }
+            }
+            entries.push((key, value));
+        }
+        Ok(Value::Object(entries))
+    }
+}
+
+#[cfg(test)]
+mod value_tests {
+    use super::*;
+
+    fn obj(entries: &[(&str, Value)]) -> Value {
+        Value::Object(
+            entries
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+        )
+    }
+    fn num(s: &str) -> Value {
+        Value::Number(s.to_string())
+    }
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let expected = obj(&[
+            ("b", num("1")),
+            ("a", Value::Array(vec![Value::Bool(true), string("x")])),
+        ]);
+        assert_eq!(
+            DataFormat::Json.parse(r#"{"b": 1, "a": [true, "x"]}"#).unwrap(),
+            expected
+        );
+        assert_eq!(
+            DataFormat::Hjson.parse("b: 1\na: [\n  true\n  x\n]\n").unwrap(),
+            expected
+        );
+        assert_eq!(
+            DataFormat::Toml.parse("b = 1\na = [true, \"x\"]\n").unwrap(),
+            expected
+        );
+        assert_eq!(
+            DataFormat::Yaml.parse("b: 1\na:\n  - true\n  - x\n").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_toml_date() {
+        assert_eq!(
+            DataFormat::Toml.parse("d = 1979-05-27\n").unwrap(),
+            obj(&[("d", Value::Date("1979-05-27".to_string()))])
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(DataFormat::Json.parse("{\"a\": ").is_err());
+        assert!(parse(Path::new("notes"), "just some words").is_err());
+        let nested = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(DataFormat::Json.parse(&nested).is_err());
+        assert!(parse(Path::new("data"), &nested).is_err());
+        let nested: String = (0..1000).map(|i| format!("{}a:\n", " ".repeat(i))).collect();
+        assert!(DataFormat::Yaml.parse(&nested).is_err());
+        assert!(DataFormat::Json.parse(r#"{"a": "[[[[{{{{"}"#).is_ok());
+        // a double quote in a single quoted string mustn't hide brackets
+        let nested = format!("{{a: ['\"', {}{}]}}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(DataFormat::Hjson.parse(&nested).is_err());
+        let nested = format!("a = ['\"', {}{}]", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(DataFormat::Toml.parse(&nested).is_err());
+        let nested = format!("a: ['\"', {}{}]", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(DataFormat::Yaml.parse(&nested).is_err());
+        assert!(DataFormat::Yaml.parse("a: ['\"[', \"it's\", b]").is_ok());
+        // deser_hjson panics when locating an error before a multi-byte char
+        assert!(parse(Path::new("x.json"), "{\"a\"é}").is_err());
+        assert!(DataFormat::Hjson.parse("{\"a\"é}").is_err());
+        assert!(DataFormat::Json.parse("{\"a\": 1, // comment\n}").is_ok());
+    }
+
+    #[test]
+    fn test_yaml_aliases() {
+        assert_eq!(
+            DataFormat::Yaml.parse("a: &x [1, 2]\nb: *x\n").unwrap(),
+            obj(&[
+                ("a", Value::Array(vec![num("1"), num("2")])),
+                ("b", Value::Array(vec![num("1"), num("2")])),
+            ])
+        );
+        // a "billion laughs" document, with 10^6 nodes once expanded
+        let mut yaml = "l0: &l0 [lol]\n".to_string();
+        for i in 1..=6 {
+            let aliases = vec![format!("*l{}", i - 1); 10].join(", ");
+            yaml.push_str(&format!("l{i}: &l{i} [{aliases}]\n"));
+        }
+        assert!(DataFormat::Yaml.parse(&yaml).is_err());
+        assert_eq!(
+            parse(Path::new("data"), "[1, null]").unwrap(),
+            (Value::Array(vec![num("1"), Value::Null]), DataFormat::Json)
+        );
+    }
+}
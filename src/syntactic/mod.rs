@@ -1,12 +1,20 @@
 mod text_view;
 mod syntax_theme;
 mod syntaxer;
+mod windowed_text_view;
 
 pub use {
-    text_view::TextView,
+    text_view::{
+        Region,
+        TextView,
+    },
     syntax_theme::*,
     syntaxer::{
         SYNTAXER,
         Syntaxer,
     },
+    windowed_text_view::{
+        WINDOWED_MIN_FILE_SIZE,
+        WindowedTextView,
+    },
 };
@@ -11,7 +11,10 @@ use {
             Theme,
             ThemeSet,
         },
-        parsing::SyntaxSet,
+        parsing::{
+            SyntaxReference,
+            SyntaxSet,
+        },
     },
 };
 
@@ -49,17 +52,35 @@ impl Syntaxer {
         path.extension()
             .and_then(|e| e.to_str())
             .and_then(|ext| self.syntax_set.find_syntax_by_extension(ext))
-            .map(|syntax| {
-                let theme = con.syntax_theme.unwrap_or_default();
-                let theme = self
-                    .theme_set
-                    .themes
-                    .get(theme.syntect_name())
-                    .unwrap_or_else(|| self.theme_set.themes.iter().next().unwrap().1);
-                let options = HighlightOptions {
-                    ignore_errors: true,
-                };
-                HighlightLines::new(syntax, theme, options)
-            })
+            .map(|syntax| self.highlighter(syntax, con))
+    }
+
+    /// Return a highlighter for a language given by its name or
+    /// extension, as in the info string of a markdown code block
+    pub fn highlighter_for_token(
+        &self,
+        token: &str,
+        con: &AppContext,
+    ) -> Option<HighlightLines<'_>> {
+        self.syntax_set
+            .find_syntax_by_token(token)
+            .map(|syntax| self.highlighter(syntax, con))
+    }
+
+    fn highlighter(
+        &self,
+        syntax: &SyntaxReference,
+        con: &AppContext,
+    ) -> HighlightLines<'_> {
+        let theme = con.syntax_theme.unwrap_or_default();
+        let theme = self
+            .theme_set
+            .themes
+            .get(theme.syntect_name())
+            .unwrap_or_else(|| self.theme_set.themes.iter().next().unwrap().1);
+        let options = HighlightOptions {
+            ignore_errors: true,
+        };
+        HighlightLines::new(syntax, theme, options)
     }
 }
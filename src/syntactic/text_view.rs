@@ -6,8 +6,12 @@ use {
             LineNumber,
         },
         command::{
+            ListScroll,
             ScrollCommand,
-            move_sel,
+        },
+        content_type::encoding::{
+            Encoding,
+            LineReader,
         },
         display::{
             Screen,
@@ -35,24 +39,36 @@ use {
     once_cell::sync::Lazy,
     std::{
         borrow::Cow,
-        fs::File,
+        collections::VecDeque,
+        fs::{
+            File,
+            Metadata,
+        },
         io::{
             BufRead,
             BufReader,
+            Seek,
+            SeekFrom,
         },
+        ops::Range,
         path::{
             Path,
             PathBuf,
         },
         str,
     },
-    syntect::highlighting::Style,
+    syntect::{
+        easy::HighlightLines,
+        highlighting::Style,
+    },
     termimad::{
         Area,
         CropWriter,
+        DEFAULT_TAB_REPLACEMENT,
         Filling,
         SPACE_FILLING,
     },
+    unicode_width::UnicodeWidthChar,
 };
 
 pub static SEPARATOR_FILLING: Lazy<Filling> = Lazy::new(|| Filling::from_char('─'));
@@ -95,24 +111,56 @@ pub struct Line {
     pub number: LineNumber,   // starting at 1
     pub start: usize,         // offset in the file, in bytes
     pub len: usize,           // len in bytes
+    pub width: usize,         // in columns, without the end of line
     pub regions: Vec<Region>, // not always computed
     pub name_match: Option<NameMatch>,
 }
 
+/// What's needed to apply the pattern (with its context lines) to the
+/// lines appended to a followed file
+#[derive(Default)]
+struct FollowFilter {
+    /// the last lines which were filtered out, which may be displayed
+    /// before a new match
+    recent: VecDeque<Line>,
+    /// how many lines must still be kept after the last match
+    remaining_after: usize,
+}
+
 /// A text viewer, which can display a text file with syntax coloring if it's not too big.
 ///
 /// In some cases, only the beginning of the file is read at first, and the rest is read
 /// in background.
+///
+/// When the file is followed, the lines appended to the file are read and
+/// filtered as they come.
 pub struct TextView {
     pub path: PathBuf,
     pub pattern: InputPattern,
     lines: Vec<DisplayLine>,
-    scroll: usize,
-    page_height: usize,
-    selection_idx: Option<usize>, // index in lines of the selection, if any
+    list_scroll: ListScroll, // the selection is an index in lines
     content_lines_count: usize,   // number of lines excluding separators
     total_lines_count: usize,     // including lines not filtered out
     partial: bool,
+    no_style: bool,
+    /// the highlighter in the state of the end of the file, to style
+    /// the lines appended to a followed file
+    highlighter: Option<HighlightLines<'static>>,
+    /// offset of the end of the last complete line read
+    read_offset: usize,
+    /// whether the last line read wasn't terminated (it's read again
+    /// when the file grows)
+    last_line_incomplete: bool,
+    file_id: u64,
+    follow_filter: FollowFilter,
+    /// the encoding of the file, detected or forced
+    pub encoding: Encoding,
+    /// whether long lines are wrapped instead of being cropped
+    wrap: bool,
+    /// number of columns hidden at left, when lines aren't wrapped
+    hscroll: usize,
+    /// width available for the text of the lines, at last display
+    text_width: usize,
 }
 
 impl DisplayLine {
@@ -146,12 +194,20 @@ impl TextView {
             path: path.to_path_buf(),
             pattern,
             lines: Vec::new(),
-            scroll: 0,
-            page_height: 0,
-            selection_idx: None,
+            list_scroll: ListScroll::default(),
             content_lines_count: 0,
             total_lines_count: 0,
             partial: false,
+            no_style,
+            highlighter: None,
+            read_offset: 0,
+            last_line_incomplete: false,
+            file_id: 0,
+            follow_filter: FollowFilter::default(),
+            encoding: Encoding::Utf8,
+            wrap: false,
+            hscroll: 0,
+            text_width: 0,
         };
         if sv.read_lines(dam, con, no_style, allow_partial)? {
             sv.select_first();
@@ -198,50 +254,42 @@ impl TextView {
         if md.len() == 0 {
             return Err(ProgramError::ZeroLenFile);
         }
+        self.file_id = file_id(&md);
         let with_style = !no_style && md.len() < MAX_SIZE_FOR_STYLING;
         let mut reader = BufReader::new(f);
+        let start_bytes = reader.fill_buf()?;
+        self.encoding = match con.forced_encoding {
+            Some(encoding) => encoding,
+            None => Encoding::detect(start_bytes).ok_or(ProgramError::UnprintableFile)?,
+        };
+        let mut offset = self.encoding.bom_len(start_bytes);
+        reader.consume(offset);
+        let mut reader = LineReader::new_after_start(reader, self.encoding);
         let mut content_lines = Vec::new();
         let mut line = String::new();
         self.total_lines_count = 0;
-        let mut offset = 0;
         let mut number = 0;
-        static SYNTAXER: Lazy<Syntaxer> = Lazy::new(Syntaxer::default);
         let mut highlighter = if with_style {
             SYNTAXER.highlighter_for(&self.path, con)
         } else {
             None
         };
-        let pattern = &self.pattern.pattern;
-        while reader.read_line(&mut line)? > 0 {
+        self.last_line_incomplete = false;
+        loop {
+            let len = reader.read_line(&mut line)?;
+            if len == 0 {
+                break;
+            }
             number += 1;
             self.total_lines_count += 1;
             let start = offset;
-            offset += line.len();
-            // We clean the line to prevent TTY rendering from being broken.
-            // We don't remove '\n' or '\r' at this point because some syntax sets
-            // need them for correct detection of comments. See #477
-            // Those chars are removed on printing, later on.
-            let clean_line = printable_line(&line);
-            let name_match = pattern.search_string(&clean_line);
-            let regions = if let Some(highlighter) = highlighter.as_mut() {
-                highlighter
-                    .highlight_line(&clean_line, &SYNTAXER.syntax_set)
-                    .map_err(|e| ProgramError::SyntectCrashed {
-                        details: e.to_string(),
-                    })?
-                    .iter()
-                    .map(Region::from_syntect)
-                    .collect()
+            offset += len;
+            if line.ends_with('\n') {
+                self.read_offset = offset;
             } else {
-                Vec::new()
-            };
-            content_lines.push(Line {
-                regions,
-                start,
-                len: clean_line.len(),
-                name_match,
-                number,
-            });
+                self.last_line_incomplete = true;
+            }
+            content_lines.push(self.make_line(&line, number, start, len, highlighter.as_mut())?);
             line.clear();
             if dam.has_event() {
                 info!("event interrupted preview filtering");
@@ -254,6 +302,9 @@ impl TextView {
                 break;
             }
         }
+        self.highlighter = highlighter;
+        self.follow_filter = FollowFilter::default();
+        let pattern = &self.pattern.pattern;
         let mut must_add_separators = false;
         if !pattern.is_empty() {
             let lines_before = con.lines_before_match_in_preview;
@@ -274,6 +325,10 @@ impl TextView {
                         kept[i] = true;
                     }
                 }
+                if let Some(last_match) = content_lines.iter().rposition(|l| l.name_match.is_some()) {
+                    self.follow_filter.remaining_after =
+                        (last_match + lines_after + 1).saturating_sub(content_lines.len());
+                }
                 content_lines.retain(|line| kept[line.number - 1]);
                 must_add_separators = true;
             } else {
@@ -295,6 +350,174 @@ impl TextView {
         Ok(true)
     }
 
+    /// Build a line, applying the pattern and the highlighter
+    fn make_line(
+        &self,
+        line: &str,
+        number: LineNumber,
+        start: usize,
+        len: usize, // in bytes, in the file
+        highlighter: Option<&mut HighlightLines<'static>>,
+    ) -> Result<Line, ProgramError> {
+        // We clean the line to prevent TTY rendering from being broken.
+        // We don't remove '\n' or '\r' at this point because some syntax sets
+        // need them for correct detection of comments. See #477
+        // Those chars are removed on printing, later on.
+        let clean_line = printable_line(line, self.encoding);
+        let name_match = self.pattern.pattern.search_string(&clean_line);
+        let width = clean_line
+            .trim_end_matches(is_char_end_of_line)
+            .chars()
+            .map(char_width)
+            .sum();
+        let regions = if let Some(highlighter) = highlighter {
+            highlighter
+                .highlight_line(&clean_line, &SYNTAXER.syntax_set)
+                .map_err(|e| ProgramError::SyntectCrashed {
+                    details: e.to_string(),
+                })?
+                .iter()
+                .map(Region::from_syntect)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok(Line {
+            regions,
+            start,
+            len,
+            width,
+            name_match,
+            number,
+        })
+    }
+
+    /// Read the lines appended to the file since the last read, keeping
+    /// only the ones accepted by the pattern (with their context).
+    ///
+    /// When the file was truncated or replaced (eg by a log rotation),
+    /// it's read again from the start.
+    ///
+    /// When the selection was on the last line, it's moved to the new
+    /// last line.
+    pub fn follow(
+        &mut self,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        if self.partial {
+            return Ok(());
+        }
+        let mut file = File::open(&self.path)?;
+        let md = file.metadata()?;
+        let len = md.len() as usize;
+        let was_at_end = self
+            .list_scroll
+            .selection_idx
+            .is_none_or(|idx| idx + 1 >= self.lines.len());
+        if len < self.read_offset || file_id(&md) != self.file_id {
+            info!("followed file truncated or replaced");
+            self.lines.clear();
+            self.list_scroll.selection_idx = None;
+            self.read_offset = 0;
+            self.total_lines_count = 0;
+            self.content_lines_count = 0;
+            if len > 0 {
+                self.read_lines(&mut Dam::unlimited(), con, self.no_style, false)?;
+            }
+            self.select_last();
+            return Ok(());
+        }
+        if len == self.read_offset {
+            return Ok(());
+        }
+        if self.last_line_incomplete {
+            // this line will be read again, with what's been appended
+            self.last_line_incomplete = false;
+            self.total_lines_count -= 1;
+            if self.max_line_number() == Some(self.total_lines_count + 1) {
+                self.lines.pop();
+                self.content_lines_count -= 1;
+            }
+            if matches!(self.lines.last(), Some(DisplayLine::Separator)) {
+                self.lines.pop();
+            }
+        }
+        file.seek(SeekFrom::Start(self.read_offset as u64))?;
+        let mut reader = LineReader::new_after_start(BufReader::new(file), self.encoding);
+        let mut highlighter = self.highlighter.take();
+        let mut line = String::new();
+        loop {
+            let len = reader.read_line(&mut line)?;
+            if len == 0 {
+                break;
+            }
+            if !line.ends_with('\n') {
+                // the writer may not have finished this line, we'll
+                // read it at next change
+                break;
+            }
+            self.total_lines_count += 1;
+            let number = self.total_lines_count;
+            let line_struct =
+                self.make_line(&line, number, self.read_offset, len, highlighter.as_mut())?;
+            self.read_offset += len;
+            self.push_followed_line(line_struct, con);
+            line.clear();
+        }
+        self.highlighter = highlighter;
+        if was_at_end {
+            self.select_last();
+        }
+        Ok(())
+    }
+
+    /// Add a line read from a followed file, if the pattern accepts it
+    fn push_followed_line(
+        &mut self,
+        line: Line,
+        con: &AppContext,
+    ) {
+        let lines_before = con.lines_before_match_in_preview;
+        let lines_after = con.lines_after_match_in_preview;
+        let with_context = lines_before + lines_after > 0;
+        if self.pattern.is_some() {
+            if line.name_match.is_some() {
+                let recent: Vec<Line> = self.follow_filter.recent.drain(..).collect();
+                for recent_line in recent {
+                    self.push_content_line(recent_line, with_context);
+                }
+                self.follow_filter.remaining_after = lines_after;
+            } else if self.follow_filter.remaining_after > 0 {
+                self.follow_filter.remaining_after -= 1;
+            } else {
+                if lines_before > 0 {
+                    if self.follow_filter.recent.len() == lines_before {
+                        self.follow_filter.recent.pop_front();
+                    }
+                    self.follow_filter.recent.push_back(line);
+                }
+                return;
+            }
+        }
+        self.push_content_line(line, with_context);
+    }
+
+    fn push_content_line(
+        &mut self,
+        line: Line,
+        with_separators: bool,
+    ) {
+        if with_separators {
+            if let Some(last_number) = self.lines.last().and_then(DisplayLine::line_number) {
+                if line.number > last_number + 1 {
+                    self.lines.push(DisplayLine::Separator);
+                }
+            }
+        }
+        self.content_lines_count += 1;
+        self.lines.push(DisplayLine::Content(line));
+    }
+
     /// Give the count of lines which can be seen when scrolling,
     /// total count including filtered ones
     pub fn line_counts(&self) -> (usize, usize) {
@@ -302,30 +525,129 @@ impl TextView {
     }
 
     fn ensure_selection_is_visible(&mut self) {
-        if self.page_height >= self.lines.len() {
-            self.scroll = 0;
-        } else if let Some(idx) = self.selection_idx {
-            let padding = self.padding();
-            if idx < self.scroll + padding || idx + padding > self.scroll + self.page_height {
-                if idx <= padding {
-                    self.scroll = 0;
-                } else if idx + padding > self.lines.len() {
-                    self.scroll = self.lines.len() - self.page_height;
-                } else if idx < self.scroll + self.page_height / 2 {
-                    self.scroll = idx - padding;
-                } else {
-                    self.scroll = idx + padding - self.page_height;
-                }
+        if self.wrap {
+            self.ensure_selection_is_visible_in_rows();
+        } else {
+            self.list_scroll.ensure_selection_is_visible(self.lines.len());
+        }
+    }
+
+    /// When lines are wrapped, the scroll is still an index in lines,
+    /// but a line may take several rows on screen
+    fn ensure_selection_is_visible_in_rows(&mut self) {
+        let Some(idx) = self.list_scroll.selection_idx else {
+            return;
+        };
+        let padding = self.list_scroll.padding();
+        if idx < self.list_scroll.scroll + padding {
+            self.list_scroll.scroll = idx.saturating_sub(padding);
+            return;
+        }
+        let end = (idx + padding + 1).min(self.lines.len());
+        let mut rows: usize = (self.list_scroll.scroll..end).map(|i| self.line_height(i)).sum();
+        while rows > self.list_scroll.page_height && self.list_scroll.scroll < idx {
+            rows -= self.line_height(self.list_scroll.scroll);
+            self.list_scroll.scroll += 1;
+        }
+    }
+
+    /// Return the number of rows the line takes on screen
+    fn line_height(
+        &self,
+        idx: usize,
+    ) -> usize {
+        match self.lines.get(idx) {
+            Some(DisplayLine::Content(line)) if self.wrap && self.text_width > 0 => {
+                line.width.div_ceil(self.text_width).max(1)
             }
+            _ => 1,
         }
     }
 
-    fn padding(&self) -> usize {
-        (self.page_height / 4).min(4)
+    /// Return the number of lines fully displayed when scrolling a
+    /// page in the given direction
+    fn lines_per_page(
+        &self,
+        up: bool,
+    ) -> usize {
+        if !self.wrap {
+            return self.list_scroll.page_height;
+        }
+        let mut count = 0;
+        let mut rows = 0;
+        loop {
+            let idx = if up {
+                self.list_scroll.scroll.checked_sub(count + 1)
+            } else {
+                Some(self.list_scroll.scroll + count)
+            };
+            let Some(idx) = idx.filter(|&idx| idx < self.lines.len()) else {
+                break;
+            };
+            rows += self.line_height(idx);
+            if rows > self.list_scroll.page_height {
+                break;
+            }
+            count += 1;
+        }
+        count.max(1)
+    }
+
+    pub fn set_wrap(
+        &mut self,
+        wrap: bool,
+    ) {
+        if wrap != self.wrap {
+            self.wrap = wrap;
+            self.hscroll = 0;
+            self.ensure_selection_is_visible();
+        }
+    }
+
+    /// Scroll by half the width of the view, to the right when `dir`
+    /// is positive, to the left when it's negative.
+    ///
+    /// Do nothing when lines are wrapped. Return true when the view moved.
+    pub fn scroll_horizontally(
+        &mut self,
+        dir: i32,
+    ) -> bool {
+        if self.wrap {
+            return false;
+        }
+        let old_hscroll = self.hscroll;
+        let step = (self.text_width / 2).max(1);
+        if dir < 0 {
+            self.hscroll = self.hscroll.saturating_sub(step);
+        } else if dir > 0 {
+            let max_width = self
+                .lines
+                .iter()
+                .filter_map(|line| match line {
+                    DisplayLine::Content(line) => Some(line.width),
+                    DisplayLine::Separator => None,
+                })
+                .max()
+                .unwrap_or(0);
+            let max_hscroll = max_width.saturating_sub(self.text_width);
+            self.hscroll = (self.hscroll + step).min(max_hscroll.max(self.hscroll));
+        }
+        self.hscroll != old_hscroll
+    }
+
+    /// decode bytes of the file, failing on invalid UTF-8
+    fn decode(
+        &self,
+        bytes: &[u8],
+    ) -> Option<String> {
+        match self.encoding {
+            Encoding::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+            encoding => Some(encoding.decode(bytes).into_owned()),
+        }
     }
 
     pub fn get_selected_line(&self) -> Option<String> {
-        self.selection_idx
+        self.list_scroll.selection_idx
             .and_then(|idx| self.lines.get(idx))
             .and_then(|line| match line {
                 DisplayLine::Content(line) => Some(line),
@@ -336,42 +658,40 @@ impl TextView {
                     .and_then(|file| unsafe { Mmap::map(&file) })
                     .ok()
                     .filter(|mmap| mmap.len() >= line.start + line.len)
-                    .and_then(|mmap| {
-                        String::from_utf8((mmap[line.start..line.start + line.len]).to_vec()).ok()
-                    })
+                    .and_then(|mmap| self.decode(&mmap[line.start..line.start + line.len]))
             })
     }
 
     pub fn get_selected_line_number(&self) -> Option<LineNumber> {
-        self.selection_idx
+        self.list_scroll.selection_idx
             .and_then(|idx| self.lines[idx].line_number())
     }
     pub fn unselect(&mut self) {
-        self.selection_idx = None;
+        self.list_scroll.selection_idx = None;
     }
     pub fn try_select_y(
         &mut self,
         y: u16,
     ) -> bool {
-        let idx = y as usize + self.scroll;
-        if idx < self.lines.len() {
-            self.selection_idx = Some(idx);
-            true
-        } else {
-            false
+        let mut y = y as usize;
+        for idx in self.list_scroll.scroll..self.lines.len() {
+            let height = self.line_height(idx);
+            if y < height {
+                self.list_scroll.selection_idx = Some(idx);
+                return true;
+            }
+            y -= height;
         }
+        false
     }
 
     pub fn select_first(&mut self) {
-        if !self.lines.is_empty() {
-            self.selection_idx = Some(0);
-            self.scroll = 0;
-        }
+        self.list_scroll.select_first(self.lines.len());
     }
     pub fn select_last(&mut self) {
-        self.selection_idx = Some(self.lines.len() - 1);
-        if self.page_height < self.lines.len() {
-            self.scroll = self.lines.len() - self.page_height;
+        self.list_scroll.select_last(self.lines.len());
+        if self.wrap {
+            self.ensure_selection_is_visible();
         }
     }
 
@@ -382,7 +702,7 @@ impl TextView {
         // this could obviously be optimized
         for (idx, line) in self.lines.iter().enumerate() {
             if line.line_number() == Some(number) {
-                self.selection_idx = Some(idx);
+                self.list_scroll.selection_idx = Some(idx);
                 self.ensure_selection_is_visible();
                 return true;
             }
@@ -395,31 +715,27 @@ impl TextView {
         dy: i32,
         cycle: bool,
     ) {
-        if let Some(idx) = self.selection_idx {
-            self.selection_idx = Some(move_sel(idx, self.lines.len(), dy, cycle));
-        } else if !self.lines.is_empty() {
-            self.selection_idx = Some(0)
-        }
+        self.list_scroll.move_selection(dy, cycle, self.lines.len());
         self.ensure_selection_is_visible();
     }
 
     pub fn previous_match(&mut self) {
-        let s = self.selection_idx.unwrap_or(0);
+        let s = self.list_scroll.selection_idx.unwrap_or(0);
         for d in 1..self.lines.len() {
             let idx = (self.lines.len() + s - d) % self.lines.len();
             if self.lines[idx].is_match() {
-                self.selection_idx = Some(idx);
+                self.list_scroll.selection_idx = Some(idx);
                 self.ensure_selection_is_visible();
                 return;
             }
         }
     }
     pub fn next_match(&mut self) {
-        let s = self.selection_idx.unwrap_or(0);
+        let s = self.list_scroll.selection_idx.unwrap_or(0);
         for d in 1..self.lines.len() {
             let idx = (s + d) % self.lines.len();
             if self.lines[idx].is_match() {
-                self.selection_idx = Some(idx);
+                self.list_scroll.selection_idx = Some(idx);
                 self.ensure_selection_is_visible();
                 return;
             }
@@ -430,28 +746,9 @@ impl TextView {
         &mut self,
         cmd: ScrollCommand,
     ) -> bool {
-        let old_scroll = self.scroll;
-        self.scroll = cmd.apply(self.scroll, self.lines.len(), self.page_height);
-        if let Some(idx) = self.selection_idx {
-            if self.scroll == old_scroll {
-                let old_selection = self.selection_idx;
-                if cmd.is_up() {
-                    self.selection_idx = Some(0);
-                } else {
-                    self.selection_idx = Some(self.lines.len() - 1);
-                }
-                return self.selection_idx == old_selection;
-            } else if idx >= old_scroll && idx < old_scroll + self.page_height {
-                if idx + self.scroll < old_scroll {
-                    self.selection_idx = Some(0);
-                } else if idx + self.scroll - old_scroll >= self.lines.len() {
-                    self.selection_idx = Some(self.lines.len() - 1);
-                } else {
-                    self.selection_idx = Some(idx + self.scroll - old_scroll);
-                }
-            }
-        }
-        self.scroll != old_scroll
+        let page_height = self.lines_per_page(cmd.is_up());
+        self.list_scroll
+            .try_scroll_pages_of(cmd, self.lines.len(), page_height)
     }
 
     pub fn max_line_number(&self) -> Option<LineNumber> {
@@ -481,8 +778,8 @@ impl TextView {
         area: &Area,
         con: &AppContext,
     ) -> Result<(), ProgramError> {
-        if area.height as usize != self.page_height {
-            self.page_height = area.height as usize;
+        if area.height as usize != self.list_scroll.page_height {
+            self.list_scroll.page_height = area.height as usize;
             self.ensure_selection_is_visible();
         }
         let max_number_len = self.max_line_number().unwrap_or(0).to_string().len();
@@ -508,52 +805,88 @@ impl TextView {
             .get_bg()
             .unwrap_or(Color::AnsiValue(28));
         let code_width = area.width as usize - 1; // 1 char left for scrollbar
-        let scrollbar = area.scrollbar(self.scroll, self.lines.len());
+        let gutter_width = if show_line_number {
+            max_number_len + 2
+        } else {
+            1
+        } + usize::from(con.show_selection_mark);
+        let text_width = code_width.saturating_sub(gutter_width);
+        if text_width != self.text_width {
+            self.text_width = text_width;
+            if self.wrap {
+                self.ensure_selection_is_visible();
+            }
+        }
+        let scrollbar = area.scrollbar(self.list_scroll.scroll, self.lines.len());
         let scrollbar_fg = styles
             .scrollbar_thumb
             .get_fg()
             .or_else(|| styles.preview.get_fg())
             .unwrap_or(Color::White);
+        let mut op_mmap: Option<Mmap> = None;
+        // a line may be displayed on several rows when wrapped
+        let mut line_idx = self.list_scroll.scroll;
+        let mut row_idx = 0;
+        let mut cells = Vec::new();
+        let mut rows = Vec::new();
         for y in 0..line_count {
             w.queue(cursor::MoveTo(area.left, y as u16 + area.top))?;
             let mut cw = CropWriter::new(w, code_width);
-            let line_idx = self.scroll + y;
-            let selected = self.selection_idx == Some(line_idx);
+            let selected = self.list_scroll.selection_idx == Some(line_idx);
             let bg = if selected { selection_bg } else { normal_bg };
-            let mut op_mmap: Option<Mmap> = None;
             match self.lines.get(line_idx) {
                 Some(DisplayLine::Separator) => {
                     cw.w.queue(SetBackgroundColor(bg))?;
                     cw.queue_unstyled_str(" ")?;
                     cw.fill(&styles.preview_separator, &SEPARATOR_FILLING)?;
+                    line_idx += 1;
                 }
                 Some(DisplayLine::Content(line)) => {
-                    let mut regions = &line.regions;
-                    let regions_ur;
-                    if regions.is_empty() && line.len > 0 {
-                        if op_mmap.is_none() {
-                            let file = File::open(&self.path)?;
-                            let mmap = unsafe { Mmap::map(&file)? };
-                            op_mmap = Some(mmap);
+                    if row_idx == 0 {
+                        let mut regions = &line.regions;
+                        let regions_ur;
+                        if regions.is_empty() && line.len > 0 {
+                            if op_mmap.is_none() {
+                                let file = File::open(&self.path)?;
+                                let mmap = unsafe { Mmap::map(&file)? };
+                                op_mmap = Some(mmap);
+                            }
+                            let mmap = op_mmap.as_ref().unwrap();
+                            if mmap.len() < line.start + line.len {
+                                warn!("file truncated since parsing");
+                            } else {
+                                // an UTF8 error can only happen if file modified during display
+                                let string = self
+                                    .decode(&mmap[line.start..line.start + line.len])
+                                    .map(|s| printable_line(&s, self.encoding).into_owned())
+                                    .unwrap_or_else(|| "Bad UTF8".to_string());
+                                regions_ur = vec![Region {
+                                    fg: normal_fg,
+                                    string,
+                                }];
+                                regions = &regions_ur;
+                            }
                         }
-                        if op_mmap.as_ref().unwrap().len() < line.start + line.len {
-                            warn!("file truncated since parsing");
+                        cells = line_cells(regions, line.name_match.as_ref());
+                        rows = if self.wrap {
+                            wrap_rows(&cells, text_width)
                         } else {
-                            // an UTF8 error can only happen if file modified during display
-                            let string = String::from_utf8(
-                                // we copy the memmap slice, as it's not immutable
-                                (op_mmap.unwrap()[line.start..line.start + line.len]).to_vec(),
-                            )
-                            .unwrap_or_else(|_| "Bad UTF8".to_string());
-                            regions_ur = vec![Region {
-                                fg: normal_fg,
-                                string,
-                            }];
-                            regions = &regions_ur;
-                        }
+                            vec![Range {
+                                start: skip_columns(&cells, self.hscroll),
+                                end: cells.len(),
+                            }]
+                        };
                     }
                     cw.w.queue(SetBackgroundColor(bg))?;
-                    if show_line_number {
+                    if row_idx > 0 {
+                        // continuation of a wrapped line
+                        let marker = if show_line_number {
+                            format!(" {:>w$} ", '↪', w = max_number_len)
+                        } else {
+                            "↪".to_string()
+                        };
+                        cw.queue_g_string(&styles.preview_line_number, marker)?;
+                    } else if show_line_number {
                         cw.queue_g_string(
                             &styles.preview_line_number,
                             format!(" {:w$} ", line.number, w = max_number_len),
@@ -563,38 +896,13 @@ impl TextView {
                     }
                     cw.w.queue(SetBackgroundColor(bg))?;
                     if con.show_selection_mark {
-                        cw.queue_unstyled_char(if selected { '▶' } else { ' ' })?;
+                        cw.queue_unstyled_char(if selected && row_idx == 0 { '▶' } else { ' ' })?;
                     }
-                    if let Some(nm) = &line.name_match {
-                        let mut dec = 0;
-                        let pos = &nm.pos;
-                        let mut pos_idx: usize = 0;
-                        for content in regions {
-                            let s = content.string.trim_end_matches(is_char_end_of_line);
-                            cw.w.queue(SetForegroundColor(content.fg))?;
-                            if pos_idx < pos.len() {
-                                for (cand_idx, cand_char) in s.chars().enumerate() {
-                                    if pos_idx < pos.len() && pos[pos_idx] == cand_idx + dec {
-                                        cw.w.queue(SetBackgroundColor(match_bg))?;
-                                        cw.queue_unstyled_char(cand_char)?;
-                                        cw.w.queue(SetBackgroundColor(bg))?;
-                                        pos_idx += 1;
-                                    } else {
-                                        cw.queue_unstyled_char(cand_char)?;
-                                    }
-                                }
-                                dec += s.chars().count();
-                            } else {
-                                cw.queue_unstyled_str(s)?;
-                            }
-                        }
-                    } else {
-                        for content in regions {
-                            cw.w.queue(SetForegroundColor(content.fg))?;
-                            cw.queue_unstyled_str(
-                                content.string.trim_end_matches(is_char_end_of_line),
-                            )?;
-                        }
+                    queue_cells(&mut cw, &cells[rows[row_idx].clone()], bg, match_bg)?;
+                    row_idx += 1;
+                    if row_idx == rows.len() {
+                        row_idx = 0;
+                        line_idx += 1;
                     }
                 }
                 None => {}
@@ -642,6 +950,9 @@ impl TextView {
         if s.len() + "lines: ".len() < width {
             s = format!("lines: {s}");
         }
+        if self.encoding != Encoding::Utf8 && s.len() + self.encoding.name().len() < width {
+            s = format!("{} {s}", self.encoding);
+        }
         s
     }
 
@@ -663,16 +974,135 @@ impl TextView {
     }
 }
 
+/// an id of the file, to detect it was replaced
+pub(super) fn file_id(md: &Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        md.ino()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = md;
+        0
+    }
+}
+
 fn is_thumb(
     y: usize,
     scrollbar: Option<(u16, u16)>,
 ) -> bool {
-    scrollbar.map_or(false, |(sctop, scbottom)| {
+    scrollbar.is_some_and(|(sctop, scbottom)| {
         let y = y as u16;
         sctop <= y && y <= scbottom
     })
 }
 
+/// A char of a line, with what's needed to display it
+struct Cell {
+    c: char,
+    fg: Color,
+    matched: bool,
+}
+
+/// Return the displayable chars of a line, flagging the ones matching the pattern
+fn line_cells(
+    regions: &[Region],
+    name_match: Option<&NameMatch>,
+) -> Vec<Cell> {
+    let pos = name_match.map_or(&[][..], |nm| &nm.pos[..]);
+    let mut pos_idx = 0;
+    let mut cells = Vec::new();
+    for region in regions {
+        for c in region.string.trim_end_matches(is_char_end_of_line).chars() {
+            let matched = pos.get(pos_idx) == Some(&cells.len());
+            if matched {
+                pos_idx += 1;
+            }
+            cells.push(Cell {
+                c,
+                fg: region.fg,
+                matched,
+            });
+        }
+    }
+    cells
+}
+
+/// Split the cells of a line into rows of at most `width` columns.
+/// There's always at least one row, even for an empty line.
+fn wrap_rows(
+    cells: &[Cell],
+    width: usize,
+) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    let mut start = 0;
+    let mut row_width = 0;
+    for (idx, cell) in cells.iter().enumerate() {
+        let cw = char_width(cell.c);
+        if row_width + cw > width && idx > start {
+            rows.push(start..idx);
+            start = idx;
+            row_width = 0;
+        }
+        row_width += cw;
+    }
+    rows.push(start..cells.len());
+    rows
+}
+
+/// Return the index of the first cell starting after `cols` columns
+fn skip_columns(
+    cells: &[Cell],
+    cols: usize,
+) -> usize {
+    let mut col = 0;
+    for (idx, cell) in cells.iter().enumerate() {
+        if col >= cols {
+            return idx;
+        }
+        col += char_width(cell.c);
+    }
+    cells.len()
+}
+
+/// Write the cells, grouped in runs of the same style
+fn queue_cells(
+    cw: &mut CropWriter<'_, W>,
+    cells: &[Cell],
+    bg: Color,
+    match_bg: Color,
+) -> Result<(), ProgramError> {
+    let mut run = String::new();
+    for (idx, cell) in cells.iter().enumerate() {
+        run.push(cell.c);
+        let run_ends = cells
+            .get(idx + 1)
+            .is_none_or(|next| next.fg != cell.fg || next.matched != cell.matched);
+        if run_ends {
+            cw.w.queue(SetForegroundColor(cell.fg))?;
+            cw.w.queue(SetBackgroundColor(if cell.matched { match_bg } else { bg }))?;
+            cw.queue_unstyled_str(&run)?;
+            run.clear();
+            if cw.is_full() {
+                break;
+            }
+        }
+    }
+    cw.w.queue(SetBackgroundColor(bg))?;
+    Ok(())
+}
+
+/// Return the number of columns taken by the char, with tabs
+/// replaced as by the `CropWriter`
+fn char_width(c: char) -> usize {
+    if c == '\t' {
+        DEFAULT_TAB_REPLACEMENT.len()
+    } else {
+        c.width().unwrap_or(0)
+    }
+}
+
 /// Tell whether the character must be replaced to prevent rendering from being broken
 fn is_char_unprintable(c: char) -> bool {
     match c {
@@ -685,15 +1115,66 @@ fn is_char_unprintable(c: char) -> bool {
     }
 }
 
-fn printable_line(line: &str) -> Cow<'_, str> {
-    if line.chars().any(is_char_unprintable) {
-        let replacement = line.replace(is_char_unprintable, "�");
+/// Return the line with the unprintable chars replaced.
+///
+/// In a text decoded from Latin-1, all the chars from 0x80 to 0x9F are
+/// replaced, as they come from bytes which aren't text in this encoding.
+pub(super) fn printable_line(
+    line: &str,
+    encoding: Encoding,
+) -> Cow<'_, str> {
+    let is_unprintable = |c: char| {
+        is_char_unprintable(c)
+            || (encoding == Encoding::Latin1 && ('\u{80}'..='\u{9f}').contains(&c))
+    };
+    if line.chars().any(is_unprintable) {
+        let replacement = line.replace(is_unprintable, "�");
         Cow::Owned(replacement)
     } else {
         Cow::Borrowed(line)
     }
 }
 
-fn is_char_end_of_line(c: char) -> bool {
+pub(super) fn is_char_end_of_line(c: char) -> bool {
     c == '\n' || c == '\r'
 }
+
+#[cfg(test)]
+mod text_view_tests {
+    use super::*;
+
+    fn cells(s: &str) -> Vec<Cell> {
+        let regions = [Region {
+            fg: Color::Reset,
+            string: s.to_string(),
+        }];
+        line_cells(&regions, None)
+    }
+
+    #[test]
+    fn test_wrap_rows() {
+        assert_eq!(wrap_rows(&cells(""), 4), vec![0..0]);
+        assert_eq!(wrap_rows(&cells("abcd\n"), 4), vec![0..4]);
+        assert_eq!(wrap_rows(&cells("abcdefghij"), 4), vec![0..4, 4..8, 8..10]);
+        // a wide char doesn't fit at the end of the first row
+        assert_eq!(wrap_rows(&cells("abc界d"), 4), vec![0..3, 3..5]);
+        // a tab takes 2 columns
+        assert_eq!(wrap_rows(&cells("a\tbc"), 4), vec![0..3, 3..4]);
+    }
+
+    #[test]
+    fn test_skip_columns() {
+        assert_eq!(skip_columns(&cells("abcdef"), 0), 0);
+        assert_eq!(skip_columns(&cells("abcdef"), 4), 4);
+        assert_eq!(skip_columns(&cells("abcdef"), 10), 6);
+        assert_eq!(skip_columns(&cells("界界ab"), 3), 2);
+    }
+
+    #[test]
+    fn test_printable_line() {
+        assert_eq!(printable_line("a\u{8}b", Encoding::Utf8), "a�b");
+        assert_eq!(printable_line("a\u{80}b", Encoding::Utf8), "a\u{80}b");
+        assert_eq!(printable_line("a\u{80}b", Encoding::Latin1), "a�b");
+        assert_eq!(printable_line("déjà", Encoding::Latin1), "déjà");
+    }
+}
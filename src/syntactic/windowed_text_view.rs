@@ -0,0 +1,912 @@
+use {
+    super::text_view::{
+        file_id,
+        is_char_end_of_line,
+        printable_line,
+    },
+    crate::{
+        app::{
+            AppContext,
+            LineNumber,
+        },
+        command::{
+            ListScroll,
+            ScrollCommand,
+        },
+        content_type::encoding::{
+            DETECTION_LEN,
+            Encoding,
+        },
+        display::{
+            Screen,
+            W,
+        },
+        errors::*,
+        pattern::InputPattern,
+        skin::PanelSkin,
+    },
+    crokey::crossterm::{
+        QueueableCommand,
+        cursor,
+        style::{
+            Color,
+            Print,
+            SetBackgroundColor,
+            SetForegroundColor,
+        },
+    },
+    std::{
+        borrow::Cow,
+        fs::File,
+        io::{
+            self,
+            Read,
+            Seek,
+            SeekFrom,
+        },
+        path::{
+            Path,
+            PathBuf,
+        },
+    },
+    termimad::{
+        Area,
+        CropWriter,
+        SPACE_FILLING,
+    },
+};
+
+/// Files at least this big are previewed with a windowed text view
+/// instead of being loaded in memory
+pub const WINDOWED_MIN_FILE_SIZE: u64 = 10_000_000;
+
+/// number of lines between two checkpoints of the line index
+const INDEX_STEP: usize = 256;
+
+/// max number of bytes indexed or searched in one step of the
+/// background loading, so that the progress can be displayed
+const LOADING_CHUNK: usize = 4_000_000;
+
+/// number of bytes read at once when looking for the lines to display
+const READ_LEN: usize = 64 * 1024;
+
+/// Lines longer than this are cut, so that a line never needs more than
+/// a loading chunk to be read
+const MAX_LINE_LEN: usize = 1 << 20;
+
+/// A part of the file, read at an offset.
+///
+/// The file is read instead of being mapped, as a mapped file which is
+/// truncated (eg a log being rotated while followed) would crash broot
+/// on access to the removed part.
+#[derive(Debug)]
+struct Window {
+    start: usize,
+    bytes: Vec<u8>,
+    /// whether the end of the file was reached
+    eof: bool,
+}
+
+impl Window {
+    fn read(
+        file: &File,
+        start: usize,
+        len: usize,
+    ) -> io::Result<Self> {
+        let mut file = file;
+        file.seek(SeekFrom::Start(start as u64))?;
+        let mut bytes = Vec::with_capacity(len);
+        file.take(len as u64).read_to_end(&mut bytes)?;
+        let eof = bytes.len() < len;
+        Ok(Self { start, bytes, eof })
+    }
+    fn end(&self) -> usize {
+        self.start + self.bytes.len()
+    }
+    /// the bytes between two offsets of the file, if they were read
+    fn get(
+        &self,
+        start: usize,
+        end: usize,
+    ) -> Option<&[u8]> {
+        self.bytes
+            .get(start.checked_sub(self.start)?..end.checked_sub(self.start)?)
+    }
+}
+
+/// A sparse index of the starts of the lines of a file, only built as
+/// far as needed
+#[derive(Debug)]
+struct LineIndex {
+    encoding: Encoding,
+    /// offset of the first line (after the BOM, if any)
+    start: usize,
+    /// starts of the lines 0, INDEX_STEP, 2*INDEX_STEP, etc.
+    checkpoints: Vec<usize>,
+    /// start of the last indexed line
+    last_start: usize,
+    /// number of indexed lines
+    count: usize,
+    /// offset of the end of the indexed part
+    end: usize,
+    /// whether the end of the file was reached
+    complete: bool,
+}
+
+impl LineIndex {
+    fn new(
+        encoding: Encoding,
+        start: usize,
+    ) -> Self {
+        Self {
+            encoding,
+            start,
+            checkpoints: Vec::new(),
+            last_start: start,
+            count: 0,
+            end: start,
+            complete: false,
+        }
+    }
+    /// Return the end (excluding the newline) of the line starting at
+    /// `start` and the start of the next one, or `None` when the window
+    /// doesn't hold the whole line
+    fn line_bounds(
+        &self,
+        window: &Window,
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        let bytes = window.get(start, window.end()).filter(|b| !b.is_empty())?;
+        let newline_len = self.encoding.newline_len();
+        match self.encoding.find_newline(bytes) {
+            Some(p) if p < MAX_LINE_LEN => Some((start + p, start + p + newline_len)),
+            _ if bytes.len() >= MAX_LINE_LEN => Some((start + MAX_LINE_LEN, start + MAX_LINE_LEN)),
+            None if window.eof => Some((window.end(), window.end())),
+            _ => None,
+        }
+    }
+    /// Index the lines of the window until there are `max_count` of them,
+    /// calling `on_line` with the index, start and end (excluding the
+    /// newline) of each new line
+    fn extend(
+        &mut self,
+        window: &Window,
+        max_count: usize,
+        mut on_line: impl FnMut(usize, usize, usize),
+    ) {
+        while !self.complete && self.count < max_count {
+            let start = self.end;
+            if start >= window.end() {
+                self.complete = window.eof;
+                break;
+            }
+            let Some((end, next_start)) = self.line_bounds(window, start) else {
+                break;
+            };
+            if self.count % INDEX_STEP == 0 {
+                self.checkpoints.push(start);
+            }
+            on_line(self.count, start, end);
+            self.last_start = start;
+            self.count += 1;
+            self.end = next_start.min(window.end());
+            if self.end == window.end() && window.eof {
+                self.complete = true;
+            }
+        }
+    }
+    /// the start of the indexed line at the checkpoint before `idx`,
+    /// and the index of that line
+    fn checkpoint(
+        &self,
+        idx: usize,
+    ) -> (usize, usize) {
+        let cp = idx / INDEX_STEP;
+        (self.checkpoints[cp], cp * INDEX_STEP)
+    }
+    /// Prepare the index for the indexing of appended content. If the
+    /// last indexed line wasn't terminated, it's removed and its index
+    /// is returned.
+    ///
+    /// The window must hold the bytes of the last newline.
+    fn reopen(
+        &mut self,
+        window: &Window,
+    ) -> Option<usize> {
+        self.complete = false;
+        if self.count == 0 {
+            return None;
+        }
+        let newline_start = self.end.saturating_sub(self.encoding.newline_len());
+        let newline = window.get(newline_start, self.end)?;
+        if self.encoding.find_newline(newline) == Some(0) {
+            return None;
+        }
+        let idx = self.count - 1;
+        self.count = idx;
+        self.end = self.last_start;
+        if idx % INDEX_STEP == 0 {
+            self.checkpoints.pop();
+        }
+        Some(idx)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct MatchingLine {
+    idx: usize,
+    start: usize,
+    end: usize,
+}
+
+/// the displayable string of a line
+fn line_str(
+    bytes: &[u8],
+    encoding: Encoding,
+) -> Cow<'_, str> {
+    match encoding.decode(bytes) {
+        Cow::Borrowed(s) => printable_line(s.trim_end_matches(is_char_end_of_line), encoding),
+        Cow::Owned(s) => Cow::Owned(
+            printable_line(s.trim_end_matches(is_char_end_of_line), encoding).into_owned(),
+        ),
+    }
+}
+
+/// A text viewer for files too big to be loaded in memory.
+///
+/// Lines aren't styled. Only the offsets of some lines are kept, and only
+/// as far as the user scrolled. When there's a pattern, the matching lines
+/// are searched progressively, as a pending task, and no context line is
+/// displayed around them.
+///
+/// Going to the end of a big file also indexes it as a pending task.
+pub struct WindowedTextView {
+    pub path: PathBuf,
+    pub pattern: InputPattern,
+    file: File,
+    file_id: u64,
+    /// length of the file when it was last checked
+    file_len: usize,
+    index: LineIndex,
+    /// the lines found matching the pattern, if any
+    matches: Vec<MatchingLine>,
+    list_scroll: ListScroll, // the selection is an index in the visible lines
+    /// whether the last line must be selected once the file is indexed
+    pending_last: bool,
+}
+
+impl WindowedTextView {
+    /// Return a windowed view, with the first lines indexed, or an
+    /// `UnprintableFile` error when the start of the file isn't text.
+    ///
+    /// The encoding is detected, unless it's given.
+    ///
+    /// The search of the pattern, if any, is done by `complete_loading`.
+    pub fn new(
+        path: &Path,
+        pattern: InputPattern,
+        encoding: Option<Encoding>,
+    ) -> Result<Self, ProgramError> {
+        let mut file = File::open(path)?;
+        let md = file.metadata()?;
+        if md.len() == 0 {
+            return Err(ProgramError::ZeroLenFile);
+        }
+        let mut start = Vec::with_capacity(DETECTION_LEN);
+        (&mut file).take(DETECTION_LEN as u64).read_to_end(&mut start)?;
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None => Encoding::detect(&start).ok_or(ProgramError::UnprintableFile)?,
+        };
+        let mut wv = Self {
+            path: path.to_path_buf(),
+            pattern,
+            file,
+            file_id: file_id(&md),
+            file_len: md.len() as usize,
+            index: LineIndex::new(encoding, encoding.bom_len(&start)),
+            matches: Vec::new(),
+            list_scroll: ListScroll::default(),
+            pending_last: false,
+        };
+        wv.ensure_indexed(INDEX_STEP);
+        wv.select_first();
+        Ok(wv)
+    }
+
+    fn is_filtered(&self) -> bool {
+        self.pattern.is_some()
+    }
+
+    /// the number of lines which can be displayed: the indexed lines,
+    /// or the matching lines found until now
+    fn visible_count(&self) -> usize {
+        if self.is_filtered() {
+            self.matches.len()
+        } else {
+            self.index.count
+        }
+    }
+
+    /// Make sure, when there's no pattern, that the first `count` lines
+    /// are indexed
+    fn ensure_indexed(
+        &mut self,
+        count: usize,
+    ) {
+        let mut len = READ_LEN;
+        while !self.is_filtered() && !self.index.complete && self.index.count < count {
+            let window = match Window::read(&self.file, self.index.end, len) {
+                Ok(window) => window,
+                Err(e) => {
+                    warn!("error while reading {:?}: {e}", self.path);
+                    return;
+                }
+            };
+            let indexed = self.index.end;
+            self.index.extend(&window, count, |_, _, _| {});
+            if self.index.end == indexed {
+                // the window was too small for the next line
+                len = LOADING_CHUNK;
+            }
+        }
+    }
+
+    /// Return true when the search for the pattern, or the indexing
+    /// needed to go to the last line, isn't finished
+    pub fn is_partial(&self) -> bool {
+        (self.is_filtered() || self.pending_last) && !self.index.complete
+    }
+
+    /// Search the next chunk of the file for lines matching the pattern
+    /// or, when there's no pattern, index it
+    pub fn complete_loading(&mut self) -> Result<(), ProgramError> {
+        if !self.is_partial() {
+            return Ok(());
+        }
+        let window = Window::read(&self.file, self.index.end, LOADING_CHUNK)?;
+        if !self.is_filtered() {
+            self.index.extend(&window, usize::MAX, |_, _, _| {});
+            if self.index.complete {
+                self.select_last();
+            }
+            return Ok(());
+        }
+        let pattern = &self.pattern.pattern;
+        let encoding = self.index.encoding;
+        let matches = &mut self.matches;
+        self.index.extend(&window, usize::MAX, |idx, start, end| {
+            let line = window.get(start, end).unwrap_or_default();
+            if pattern.search_string(&line_str(line, encoding)).is_some() {
+                matches.push(MatchingLine { idx, start, end });
+            }
+        });
+        if self.list_scroll.selection_idx.is_none() && !self.matches.is_empty() {
+            self.list_scroll.selection_idx = Some(0);
+        }
+        Ok(())
+    }
+
+    /// Take into account the changes of a followed file: appended lines
+    /// will be indexed and searched, and the view is reset if the file
+    /// was truncated or replaced.
+    ///
+    /// When the selection was on the last line, it's moved to the new
+    /// last line.
+    pub fn follow(&mut self) -> Result<(), ProgramError> {
+        let md = self.path.metadata()?;
+        let len = md.len() as usize;
+        let was_at_end = self.pending_last
+            || self
+                .list_scroll
+                .selection_idx
+                .is_none_or(|idx| idx + 1 >= self.visible_count());
+        let mut reset = len < self.index.end || file_id(&md) != self.file_id;
+        if !reset && len != self.file_len && self.index.complete {
+            let newline_start = self.index.end.saturating_sub(self.index.encoding.newline_len());
+            let window = Window::read(&self.file, newline_start, self.index.end - newline_start)?;
+            if window.end() < self.index.end {
+                // the file was truncated since its length was read
+                reset = true;
+            } else if let Some(idx) = self.index.reopen(&window) {
+                if self.matches.last().is_some_and(|m| m.idx == idx) {
+                    self.matches.pop();
+                }
+            }
+        }
+        if reset {
+            info!("followed file truncated or replaced");
+            self.file = File::open(&self.path)?;
+            let md = self.file.metadata()?;
+            self.file_id = file_id(&md);
+            self.file_len = md.len() as usize;
+            self.index = LineIndex::new(self.index.encoding, self.index.start);
+            self.matches.clear();
+            self.list_scroll.selection_idx = None;
+            self.list_scroll.scroll = 0;
+        } else {
+            self.file_len = len;
+        }
+        if was_at_end {
+            self.select_last();
+        }
+        Ok(())
+    }
+
+    /// Read the visible lines from the one at index `first`, returning at
+    /// most `count` of them with their numbers.
+    ///
+    /// Fewer lines are returned when the file was truncated.
+    fn read_lines(
+        &self,
+        first: usize,
+        count: usize,
+    ) -> io::Result<Vec<(LineNumber, String)>> {
+        let encoding = self.index.encoding;
+        let mut lines = Vec::new();
+        if self.is_filtered() {
+            for m in self.matches.iter().skip(first).take(count) {
+                let window = Window::read(&self.file, m.start, m.end - m.start)?;
+                if window.end() < m.end {
+                    break;
+                }
+                lines.push((m.idx + 1, line_str(&window.bytes, encoding).into_owned()));
+            }
+            return Ok(lines);
+        }
+        let count = count.min(self.index.count.saturating_sub(first));
+        if count == 0 {
+            return Ok(lines);
+        }
+        let (checkpoint_start, checkpoint_idx) = self.index.checkpoint(first);
+        let mut len = READ_LEN;
+        loop {
+            let window = Window::read(&self.file, checkpoint_start, len)?;
+            let mut start = checkpoint_start;
+            for idx in checkpoint_idx..first + count {
+                let Some((end, next_start)) = self.index.line_bounds(&window, start) else {
+                    break;
+                };
+                if idx >= first {
+                    let line = window.get(start, end).unwrap_or_default();
+                    lines.push((idx + 1, line_str(line, encoding).into_owned()));
+                }
+                start = next_start;
+            }
+            if lines.len() == count || window.eof {
+                return Ok(lines);
+            }
+            // some lines didn't fit in the window
+            lines.clear();
+            len *= 4;
+        }
+    }
+
+    pub fn get_selected_line(&self) -> Option<String> {
+        let idx = self.list_scroll.selection_idx?;
+        let mut lines = self.read_lines(idx, 1).ok()?;
+        lines.pop().map(|(_, line)| line)
+    }
+
+    pub fn get_selected_line_number(&self) -> Option<LineNumber> {
+        let idx = self.list_scroll.selection_idx?;
+        if self.is_filtered() {
+            self.matches.get(idx).map(|m| m.idx + 1)
+        } else {
+            Some(idx + 1)
+        }
+    }
+
+    pub fn try_select_line_number(
+        &mut self,
+        number: LineNumber,
+    ) -> bool {
+        let Some(line_idx) = number.checked_sub(1) else {
+            return false;
+        };
+        let idx = if self.is_filtered() {
+            self.matches.binary_search_by_key(&line_idx, |m| m.idx).ok()
+        } else {
+            self.ensure_indexed(number);
+            Some(line_idx).filter(|&idx| idx < self.index.count)
+        };
+        if let Some(idx) = idx {
+            self.pending_last = false;
+            self.list_scroll.selection_idx = Some(idx);
+            self.ensure_selection_is_visible();
+        }
+        idx.is_some()
+    }
+
+    fn ensure_selection_is_visible(&mut self) {
+        self.list_scroll.ensure_selection_is_visible(self.visible_count());
+    }
+
+    pub fn unselect(&mut self) {
+        self.pending_last = false;
+        self.list_scroll.selection_idx = None;
+    }
+
+    pub fn try_select_y(
+        &mut self,
+        y: u16,
+    ) -> bool {
+        self.pending_last = false;
+        self.list_scroll.try_select_row(y as usize, self.visible_count())
+    }
+
+    pub fn select_first(&mut self) {
+        self.pending_last = false;
+        self.list_scroll.select_first(self.visible_count());
+    }
+
+    /// Select the last line (or the last matching line found until now).
+    ///
+    /// When the file isn't indexed up to a chunk from its end, the
+    /// indexing is left to `complete_loading`, which selects the last
+    /// line when it's done.
+    pub fn select_last(&mut self) {
+        if self.file_len.saturating_sub(self.index.end) <= LOADING_CHUNK {
+            self.ensure_indexed(usize::MAX);
+        }
+        self.pending_last = !self.is_filtered() && !self.index.complete;
+        if !self.pending_last {
+            self.list_scroll.select_last(self.visible_count());
+        }
+    }
+
+    pub fn move_selection(
+        &mut self,
+        dy: i32,
+        cycle: bool,
+    ) {
+        if self.list_scroll.selection_idx == Some(0) && dy < 0 && cycle {
+            self.select_last();
+            return;
+        }
+        self.pending_last = false;
+        if let Some(idx) = self.list_scroll.selection_idx {
+            let target = idx as i32 + dy + self.list_scroll.page_height as i32;
+            self.ensure_indexed(target.max(0) as usize + 1);
+        }
+        self.list_scroll.move_selection(dy, cycle, self.visible_count());
+        self.ensure_selection_is_visible();
+    }
+
+    pub fn try_scroll(
+        &mut self,
+        cmd: ScrollCommand,
+    ) -> bool {
+        self.pending_last = false;
+        let page_height = self.list_scroll.page_height;
+        let dy = cmd.to_lines(page_height);
+        if dy > 0 {
+            self.ensure_indexed(self.list_scroll.scroll + 2 * page_height + dy as usize);
+        }
+        self.list_scroll.try_scroll(cmd, self.visible_count())
+    }
+
+    /// the number of lines to consider for the scrollbar: the visible
+    /// ones or, when the file isn't completely indexed, an estimation
+    /// of the total number of lines
+    fn scrollbar_count(&self) -> usize {
+        if self.is_filtered() || self.index.complete || self.index.end == 0 {
+            self.visible_count()
+        } else {
+            (self.index.count as f64 * self.file_len as f64 / self.index.end as f64) as usize
+        }
+    }
+
+    pub fn display(
+        &mut self,
+        w: &mut W,
+        _screen: Screen,
+        panel_skin: &PanelSkin,
+        area: &Area,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        if area.height as usize != self.list_scroll.page_height {
+            self.list_scroll.page_height = area.height as usize;
+            self.ensure_selection_is_visible();
+        }
+        let scroll = self.list_scroll.scroll;
+        self.ensure_indexed(scroll + self.list_scroll.page_height);
+        let page = self.read_lines(scroll, self.list_scroll.page_height)?;
+        let max_number_len = page
+            .iter()
+            .map(|(number, _)| *number)
+            .max()
+            .unwrap_or(0)
+            .to_string()
+            .len();
+        let show_line_number = area.width > 55 || (self.pattern.is_some() && area.width > 8);
+        let styles = &panel_skin.styles;
+        let normal_fg = styles
+            .preview
+            .get_fg()
+            .or_else(|| styles.default.get_fg())
+            .unwrap_or(Color::Reset);
+        let normal_bg = styles
+            .preview
+            .get_bg()
+            .or_else(|| styles.default.get_bg())
+            .unwrap_or(Color::Reset);
+        let selection_bg = styles
+            .selected_line
+            .get_bg()
+            .unwrap_or(Color::AnsiValue(240));
+        let match_bg = styles
+            .preview_match
+            .get_bg()
+            .unwrap_or(Color::AnsiValue(28));
+        let code_width = area.width as usize - 1; // 1 char left for scrollbar
+        let scrollbar = area.scrollbar(scroll, self.scrollbar_count());
+        let scrollbar_fg = styles
+            .scrollbar_thumb
+            .get_fg()
+            .or_else(|| styles.preview.get_fg())
+            .unwrap_or(Color::White);
+        for y in 0..self.list_scroll.page_height {
+            w.queue(cursor::MoveTo(area.left, y as u16 + area.top))?;
+            let mut cw = CropWriter::new(w, code_width);
+            let selected = self.list_scroll.is_selected(scroll + y);
+            let bg = if selected { selection_bg } else { normal_bg };
+            if let Some((number, s)) = page.get(y) {
+                cw.w.queue(SetBackgroundColor(bg))?;
+                if show_line_number {
+                    cw.queue_g_string(
+                        &styles.preview_line_number,
+                        format!(" {:w$} ", number, w = max_number_len),
+                    )?;
+                } else {
+                    cw.queue_unstyled_str(" ")?;
+                }
+                cw.w.queue(SetBackgroundColor(bg))?;
+                if con.show_selection_mark {
+                    cw.queue_unstyled_char(if selected { '▶' } else { ' ' })?;
+                }
+                cw.w.queue(SetForegroundColor(normal_fg))?;
+                match self.pattern.pattern.search_string(s) {
+                    Some(nm) => {
+                        let pos = &nm.pos;
+                        let mut pos_idx = 0;
+                        for (char_idx, c) in s.chars().enumerate() {
+                            if pos_idx < pos.len() && pos[pos_idx] == char_idx {
+                                cw.w.queue(SetBackgroundColor(match_bg))?;
+                                cw.queue_unstyled_char(c)?;
+                                cw.w.queue(SetBackgroundColor(bg))?;
+                                pos_idx += 1;
+                            } else {
+                                cw.queue_unstyled_char(c)?;
+                            }
+                        }
+                    }
+                    None => {
+                        cw.queue_unstyled_str(s)?;
+                    }
+                }
+            }
+            cw.fill(
+                if selected {
+                    &styles.selected_line
+                } else {
+                    &styles.preview
+                },
+                &SPACE_FILLING,
+            )?;
+            w.queue(SetBackgroundColor(bg))?;
+            if ScrollCommand::is_thumb(y as u16 + area.top, scrollbar) {
+                w.queue(SetForegroundColor(scrollbar_fg))?;
+                w.queue(Print('▐'))?;
+            } else {
+                w.queue(Print(' '))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn info(
+        &self,
+        width: usize,
+    ) -> String {
+        let percent = (self.index.end * 100)
+            .checked_div(self.file_len)
+            .unwrap_or(100);
+        let s = if self.is_partial() && self.is_filtered() {
+            format!("{} matches, searching {}%", self.matches.len(), percent)
+        } else if self.is_partial() {
+            format!("lines: {}+, indexing {}%", self.index.count, percent)
+        } else if self.is_filtered() {
+            format!("lines: {}/{}", self.matches.len(), self.index.count)
+        } else if self.index.complete {
+            format!("lines: {}", self.index.count)
+        } else {
+            format!("lines: {}+", self.index.count)
+        };
+        let encoding = self.index.encoding;
+        let s = if encoding != Encoding::Utf8 && s.len() + encoding.name().len() < width {
+            format!("{encoding} {s}")
+        } else {
+            s
+        };
+        if s.len() > width { "".to_string() } else { s }
+    }
+
+    pub fn display_info(
+        &mut self,
+        w: &mut W,
+        _screen: Screen,
+        panel_skin: &PanelSkin,
+        area: &Area,
+    ) -> Result<(), ProgramError> {
+        let width = area.width as usize;
+        let s = self.info(width);
+        w.queue(cursor::MoveTo(
+            area.left + area.width - s.len() as u16,
+            area.top,
+        ))?;
+        panel_skin.styles.default.queue(w, s)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod windowed_text_view_tests {
+    use {
+        super::*,
+        std::io::{
+            Seek,
+            Write,
+        },
+    };
+
+    fn window(bytes: &[u8]) -> Window {
+        Window {
+            start: 0,
+            bytes: bytes.to_vec(),
+            eof: true,
+        }
+    }
+
+    /// the start and end of all the lines, read with a fresh index
+    fn line_ranges(
+        encoding: Encoding,
+        window: &Window,
+        start: usize,
+    ) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+        LineIndex::new(encoding, start).extend(window, usize::MAX, |_, start, end| {
+            ranges.push((start, end));
+        });
+        ranges
+    }
+
+    #[test]
+    fn test_line_index() {
+        let text: String = (0..1000).map(|i| format!("line {i}\n")).collect();
+        let bytes = text.as_bytes();
+        let mut index = LineIndex::new(Encoding::Utf8, 0);
+        index.extend(&window(bytes), 10, |_, _, _| {});
+        assert_eq!(index.count, 10);
+        assert!(!index.complete);
+        let mut long_lines = 0;
+        index.extend(&window(bytes), usize::MAX, |idx, start, end| {
+            assert_eq!(&text[start..end], format!("line {idx}"));
+            if end - start > 7 {
+                long_lines += 1;
+            }
+        });
+        assert_eq!(long_lines, 900);
+        assert!(index.complete);
+        assert_eq!(index.count, 1000);
+        assert_eq!(index.checkpoint(600), (text.find("line 512").unwrap(), 512));
+        // an index extended window by window
+        let mut index = LineIndex::new(Encoding::Utf8, 0);
+        let mut count = 0;
+        while !index.complete {
+            let start = index.end;
+            let part = Window {
+                start,
+                bytes: bytes[start..].iter().take(100).copied().collect(),
+                eof: start + 100 >= bytes.len(),
+            };
+            index.extend(&part, usize::MAX, |idx, start, end| {
+                assert_eq!(&text[start..end], format!("line {idx}"));
+                count += 1;
+            });
+        }
+        assert_eq!(count, 1000);
+    }
+
+    #[test]
+    fn test_line_index_reopen() {
+        let mut index = LineIndex::new(Encoding::Utf8, 0);
+        index.extend(&window(b"a\nb"), usize::MAX, |_, _, _| {});
+        assert!(index.complete);
+        assert_eq!(index.count, 2);
+        let bytes = window(b"a\nbc\nd\n");
+        assert_eq!(index.reopen(&bytes), Some(1));
+        let mut lines = Vec::new();
+        index.extend(&bytes, usize::MAX, |idx, start, end| lines.push((idx, start, end)));
+        assert_eq!(lines, vec![(1, 2, 4), (2, 5, 6)]);
+        assert_eq!(index.count, 3);
+        assert_eq!(index.reopen(&window(b"a\nbc\nd\ne")), None);
+    }
+
+    #[test]
+    fn test_long_line_is_cut() {
+        let mut bytes = vec![b'a'; MAX_LINE_LEN + 10];
+        bytes.extend(b"\nb");
+        assert_eq!(
+            line_ranges(Encoding::Utf8, &window(&bytes), 0),
+            vec![
+                (0, MAX_LINE_LEN),
+                (MAX_LINE_LEN, MAX_LINE_LEN + 10),
+                (MAX_LINE_LEN + 11, MAX_LINE_LEN + 12),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_utf16_line_index() {
+        let encoding = Encoding::Utf16Be;
+        let mut bytes = encoding.bom().to_vec();
+        bytes.extend(encoding.encode("ab\n\u{a0a}\nc").unwrap());
+        let ranges = line_ranges(encoding, &window(&bytes), 2);
+        let lines: Vec<_> = ranges
+            .iter()
+            .map(|&(start, end)| line_str(&bytes[start..end], encoding).into_owned())
+            .collect();
+        assert_eq!(lines, vec!["ab", "\u{a0a}", "c"]);
+        assert_eq!(ranges[1], (8, 10));
+    }
+
+    #[test]
+    fn test_followed_file_truncated() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for i in 0..1000 {
+            writeln!(file, "line {i}").unwrap();
+        }
+        file.flush().unwrap();
+        let mut wv = WindowedTextView::new(file.path(), InputPattern::none(), None).unwrap();
+        assert!(wv.try_select_line_number(200));
+        assert_eq!(wv.get_selected_line().as_deref(), Some("line 199"));
+        assert_eq!(wv.read_lines(998, 10).unwrap().len(), 0); // not indexed yet
+        // truncated in place, as by a copytruncate rotation
+        file.as_file().set_len(0).unwrap();
+        file.rewind().unwrap();
+        write!(file, "new\nlines").unwrap();
+        assert_eq!(wv.get_selected_line(), None);
+        wv.follow().unwrap();
+        assert_eq!(wv.get_selected_line(), None);
+        wv.select_last();
+        assert_eq!(wv.get_selected_line().as_deref(), Some("lines"));
+        // appended content
+        writeln!(file, " and more\nlast").unwrap();
+        wv.follow().unwrap();
+        assert_eq!(wv.get_selected_line().as_deref(), Some("last"));
+        assert_eq!(wv.get_selected_line_number(), Some(3));
+    }
+
+    #[test]
+    fn test_select_last_in_background() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        let count = 2 * LOADING_CHUNK / 10;
+        for i in 0..count {
+            writeln!(file, "line {i:>4}").unwrap();
+        }
+        file.flush().unwrap();
+        let mut wv = WindowedTextView::new(file.path(), InputPattern::none(), None).unwrap();
+        wv.select_last();
+        assert!(wv.is_partial());
+        assert_eq!(wv.get_selected_line_number(), Some(1));
+        while wv.is_partial() {
+            wv.complete_loading().unwrap();
+        }
+        assert_eq!(wv.get_selected_line_number(), Some(count));
+        wv.select_first();
+        wv.select_last();
+        assert!(!wv.is_partial());
+        assert_eq!(wv.get_selected_line_number(), Some(count));
+    }
+}
@@ -0,0 +1,195 @@
+//! A small parser for the CSV/TSV format, lenient with malformed files
+
+/// the delimiters we look for, in order of preference
+const DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+
+/// number of lines looked at to guess the delimiter
+const DETECTION_LINES: usize = 20;
+
+/// A record of a CSV file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    /// the line number (starting at 1) where the record starts
+    pub number: usize,
+    pub cells: Vec<String>,
+}
+
+/// Guess the delimiter of a CSV file from its first lines: it's the one
+/// found the same number of times in most lines.
+///
+/// Return `None` when no candidate delimiter is found.
+pub fn detect_delimiter(text: &str) -> Option<char> {
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(DETECTION_LINES)
+        .collect();
+    let mut best: Option<(char, usize, usize)> = None; // (delimiter, consistent lines, count)
+    for delimiter in DELIMITERS {
+        let counts: Vec<usize> = lines
+            .iter()
+            .map(|line| count_outside_quotes(line, delimiter))
+            .collect();
+        let Some(&count) = counts.first() else {
+            break;
+        };
+        if count == 0 {
+            continue;
+        }
+        let consistent = counts.iter().filter(|&&c| c == count).count();
+        if best.is_none_or(|(_, bc, bn)| (consistent, count) > (bc, bn)) {
+            best = Some((delimiter, consistent, count));
+        }
+    }
+    best.map(|(delimiter, _, _)| delimiter)
+}
+
+/// count the occurrences of the delimiter which aren't in a quoted field
+fn count_outside_quotes(
+    line: &str,
+    delimiter: char,
+) -> usize {
+    let mut in_quotes = false;
+    let mut count = 0;
+    for c in line.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == delimiter && !in_quotes {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Parse the records of a CSV text.
+///
+/// Quoted fields may contain delimiters, escaped quotes (`""`) and
+/// newlines. As the cells are meant to be displayed on one line, newlines
+/// in cells are replaced with `↵` and other control chars with spaces.
+/// Empty lines are skipped.
+pub fn parse_records(
+    text: &str,
+    delimiter: char,
+) -> Vec<Record> {
+    let mut records = Vec::new();
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut line_number = 1;
+    let mut record_number = 1;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    cell.push('"');
+                }
+                '"' => {
+                    in_quotes = false;
+                }
+                '\n' => {
+                    line_number += 1;
+                    cell.push('↵');
+                }
+                '\r' => {}
+                c if c.is_control() => cell.push(' '),
+                c => cell.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if cell.is_empty() => {
+                in_quotes = true;
+            }
+            '\n' => {
+                if !cells.is_empty() || !cell.trim().is_empty() {
+                    cells.push(std::mem::take(&mut cell));
+                    records.push(Record {
+                        number: record_number,
+                        cells: std::mem::take(&mut cells),
+                    });
+                }
+                cell.clear();
+                line_number += 1;
+                record_number = line_number;
+            }
+            '\r' => {}
+            c if c == delimiter => {
+                cells.push(std::mem::take(&mut cell));
+            }
+            c if c.is_control() => cell.push(' '),
+            c => cell.push(c),
+        }
+    }
+    if !cells.is_empty() || !cell.trim().is_empty() {
+        cells.push(cell);
+        records.push(Record {
+            number: record_number,
+            cells,
+        });
+    }
+    records
+}
+
+#[cfg(test)]
+mod csv_tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_delimiter() {
+        assert_eq!(detect_delimiter("a,b,c\n1,2,3\n"), Some(','));
+        assert_eq!(detect_delimiter("a\tb\n1\t2\n"), Some('\t'));
+        // the commas are decimal separators, the semicolons are consistent
+        assert_eq!(detect_delimiter("x;y\n1,5;2\n3;4,25\n"), Some(';'));
+        // delimiters in quotes don't count
+        assert_eq!(detect_delimiter("name|desc\nbob|\"a, b, c\"\n"), Some('|'));
+        assert_eq!(detect_delimiter("just\nwords\n"), None);
+        assert_eq!(detect_delimiter(""), None);
+    }
+
+    #[test]
+    fn test_parse_records() {
+        let text = concat!(
+            "name,comment\r\n",
+            "\"Smith, J\",\"said \"\"hi\"\"\"\n",
+            "\n",
+            "bob,\"two\nlines\"\n",
+            "alice\n",
+        );
+        assert_eq!(
+            parse_records(text, ','),
+            vec![
+                Record {
+                    number: 1,
+                    cells: vec!["name".to_string(), "comment".to_string()],
+                },
+                Record {
+                    number: 2,
+                    cells: vec!["Smith, J".to_string(), "said \"hi\"".to_string()],
+                },
+                Record {
+                    number: 4,
+                    cells: vec!["bob".to_string(), "two↵lines".to_string()],
+                },
+                Record {
+                    number: 6,
+                    cells: vec!["alice".to_string()],
+                },
+            ]
+        );
+        // empty cells are kept, even at the end of a line without newline
+        assert_eq!(
+            parse_records("a\t\tb\t", '\t'),
+            vec![Record {
+                number: 1,
+                cells: vec![
+                    "a".to_string(),
+                    String::new(),
+                    "b".to_string(),
+                    String::new()
+                ],
+            }]
+        );
+    }
+}
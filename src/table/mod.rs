@@ -0,0 +1,4 @@
+mod csv;
+mod table_view;
+
+pub use table_view::TableView;
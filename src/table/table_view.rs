@@ -0,0 +1,670 @@
+use {
+    super::csv::{
+        Record,
+        detect_delimiter,
+        parse_records,
+    },
+    crate::{
+        app::{
+            AppContext,
+            LineNumber,
+        },
+        command::{
+            ListScroll,
+            ScrollCommand,
+        },
+        content_type::encoding::Encoding,
+        display::{
+            Screen,
+            W,
+        },
+        errors::ProgramError,
+        path::path_has_ext,
+        pattern::{
+            InputPattern,
+            NameMatch,
+        },
+        skin::PanelSkin,
+        syntactic::WINDOWED_MIN_FILE_SIZE,
+        task_sync::Dam,
+    },
+    crokey::crossterm::{
+        QueueableCommand,
+        cursor,
+        style::{
+            Color,
+            Print,
+            SetBackgroundColor,
+            SetForegroundColor,
+        },
+    },
+    once_cell::sync::Lazy,
+    std::{
+        fs,
+        path::{
+            Path,
+            PathBuf,
+        },
+    },
+    termimad::{
+        Area,
+        CompoundStyle,
+        CropWriter,
+        Filling,
+        SPACE_FILLING,
+    },
+    unicode_width::{
+        UnicodeWidthChar,
+        UnicodeWidthStr,
+    },
+};
+
+static RULE_FILLING: Lazy<Filling> = Lazy::new(|| Filling::from_char('─'));
+
+/// what's drawn between two columns
+const COLUMN_SEPARATOR: &str = " │ ";
+const RULE_SEPARATOR: &str = "─┼─";
+
+/// the width under which a column is never cropped
+const MIN_CROPPED_WIDTH: usize = 8;
+
+/// number of rows between two checks of the dam when filtering
+const DAM_CHECK_PERIOD: usize = 1000;
+
+/// A preview of a CSV or TSV file as a table, with the first record
+/// used as a header which stays on top.
+///
+/// When there are too many columns for the width of the panel, the
+/// table scrolls horizontally by whole columns.
+pub struct TableView {
+    pub path: PathBuf,
+    pub pattern: InputPattern,
+    encoding: Encoding,
+    delimiter: char,
+    header: Vec<String>,
+    /// the records after the header, only the ones matching the pattern
+    rows: Vec<Record>,
+    total_rows_count: usize,
+    /// the width needed by each column to display all its cells
+    widths: Vec<usize>,
+    /// whether all the cells of the column are numbers, which are right aligned
+    numeric: Vec<bool>,
+    first_column: usize,
+    /// number of columns, starting at first_column, shown at last display
+    visible_columns: usize,
+    list_scroll: ListScroll, // the selection is an index in rows
+}
+
+impl TableView {
+    pub fn new(
+        path: &Path,
+        con: &AppContext,
+    ) -> Result<Self, ProgramError> {
+        let len = fs::metadata(path)?.len();
+        if len == 0 {
+            return Err(ProgramError::ZeroLenFile);
+        }
+        if len >= WINDOWED_MIN_FILE_SIZE {
+            return Err(ProgramError::TooBigFile);
+        }
+        let bytes = fs::read(path)?;
+        let encoding = match con.forced_encoding {
+            Some(encoding) => encoding,
+            None => Encoding::detect(&bytes).ok_or(ProgramError::UnprintableFile)?,
+        };
+        let text = encoding.decode(&bytes[encoding.bom_len(&bytes)..]);
+        let delimiter = if path_has_ext(path, "tsv") {
+            '\t'
+        } else {
+            detect_delimiter(&text).unwrap_or(',')
+        };
+        let mut records = parse_records(&text, delimiter).into_iter();
+        let header = records.next().map(|r| r.cells).unwrap_or_default();
+        let rows: Vec<Record> = records.collect();
+        let columns_count = rows
+            .iter()
+            .map(|r| r.cells.len())
+            .chain(std::iter::once(header.len()))
+            .max()
+            .unwrap_or(0);
+        let mut widths = vec![0; columns_count];
+        let mut numeric = vec![false; columns_count];
+        let mut non_numeric = vec![false; columns_count];
+        for (idx, cell) in header.iter().enumerate() {
+            widths[idx] = cell.width();
+        }
+        for row in &rows {
+            for (idx, cell) in row.cells.iter().enumerate() {
+                widths[idx] = widths[idx].max(cell.width());
+                let cell = cell.trim();
+                if !cell.is_empty() {
+                    if cell.parse::<f64>().is_ok() {
+                        numeric[idx] = true;
+                    } else {
+                        non_numeric[idx] = true;
+                    }
+                }
+            }
+        }
+        for (numeric, non_numeric) in numeric.iter_mut().zip(non_numeric) {
+            *numeric &= !non_numeric;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            pattern: InputPattern::none(),
+            encoding,
+            delimiter,
+            header,
+            total_rows_count: rows.len(),
+            rows,
+            widths,
+            numeric,
+            first_column: 0,
+            visible_columns: 0,
+            list_scroll: ListScroll::default(),
+        })
+    }
+
+    /// Build a view with only the rows matching the pattern, or None if
+    /// the dam gets an event before it's done
+    pub fn filtered(
+        &self,
+        pattern: InputPattern,
+        dam: &Dam,
+    ) -> Option<Self> {
+        // the pattern is searched in each cell, as matches are highlighted in cells
+        let matches = |cell: &String| pattern.pattern.search_string(cell).is_some();
+        let mut rows = Vec::new();
+        for (idx, row) in self.rows.iter().enumerate() {
+            if idx % DAM_CHECK_PERIOD == 0 && dam.has_event() {
+                return None;
+            }
+            if pattern.is_none() || row.cells.iter().any(matches) {
+                rows.push(row.clone());
+            }
+        }
+        Some(Self {
+            path: self.path.clone(),
+            pattern,
+            encoding: self.encoding,
+            delimiter: self.delimiter,
+            header: self.header.clone(),
+            rows,
+            total_rows_count: self.total_rows_count,
+            widths: self.widths.clone(),
+            numeric: self.numeric.clone(),
+            first_column: self.first_column,
+            visible_columns: self.visible_columns,
+            list_scroll: ListScroll {
+                page_height: self.list_scroll.page_height,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// the row as a line of the file (without the quotes)
+    fn row_line(
+        &self,
+        row: &Record,
+    ) -> String {
+        row.cells.join(self.delimiter.encode_utf8(&mut [0; 4]))
+    }
+
+    pub fn get_selected_line(&self) -> Option<String> {
+        self.list_scroll
+            .selection_idx
+            .and_then(|idx| self.rows.get(idx))
+            .map(|row| self.row_line(row))
+    }
+    pub fn get_selected_line_number(&self) -> Option<LineNumber> {
+        self.list_scroll
+            .selection_idx
+            .and_then(|idx| self.rows.get(idx))
+            .map(|row| row.number)
+    }
+    /// select the row starting at the given line, or the first
+    /// one after it
+    pub fn try_select_line_number(
+        &mut self,
+        number: LineNumber,
+    ) -> bool {
+        match self.rows.iter().position(|row| row.number >= number) {
+            Some(idx) => {
+                self.list_scroll.selection_idx = Some(idx);
+                self.ensure_selection_is_visible();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn ensure_selection_is_visible(&mut self) {
+        self.list_scroll.ensure_selection_is_visible(self.rows.len());
+    }
+
+    pub fn unselect(&mut self) {
+        self.list_scroll.selection_idx = None;
+    }
+    /// select the row at the given y, counted from the top of the
+    /// view, which includes the header and the rule
+    pub fn try_select_y(
+        &mut self,
+        y: u16,
+    ) -> bool {
+        let Some(row) = (y as usize).checked_sub(2) else {
+            return false;
+        };
+        self.list_scroll.try_select_row(row, self.rows.len())
+    }
+
+    pub fn select_first(&mut self) {
+        self.list_scroll.select_first(self.rows.len());
+    }
+    pub fn select_last(&mut self) {
+        self.list_scroll.select_last(self.rows.len());
+    }
+
+    pub fn move_selection(
+        &mut self,
+        dy: i32,
+        cycle: bool,
+    ) {
+        self.list_scroll.move_selection(dy, cycle, self.rows.len());
+        self.ensure_selection_is_visible();
+    }
+
+    pub fn try_scroll(
+        &mut self,
+        cmd: ScrollCommand,
+    ) -> bool {
+        self.list_scroll.try_scroll(cmd, self.rows.len())
+    }
+
+    /// Scroll horizontally by one column. Return true if the
+    /// view changed
+    pub fn scroll_horizontally(
+        &mut self,
+        dir: i32,
+    ) -> bool {
+        let old_first_column = self.first_column;
+        if dir < 0 {
+            self.first_column = self.first_column.saturating_sub(1);
+        } else if self.first_column + self.visible_columns < self.widths.len() {
+            self.first_column += 1;
+        }
+        self.first_column != old_first_column
+    }
+
+    pub fn display(
+        &mut self,
+        w: &mut W,
+        _screen: Screen,
+        panel_skin: &PanelSkin,
+        area: &Area,
+        con: &AppContext,
+    ) -> Result<(), ProgramError> {
+        // the first two lines are taken by the header and the rule
+        let page_height = (area.height as usize).saturating_sub(2);
+        if page_height != self.list_scroll.page_height {
+            self.list_scroll.page_height = page_height;
+            self.ensure_selection_is_visible();
+        }
+        let styles = &panel_skin.styles;
+        let normal_bg = styles
+            .preview
+            .get_bg()
+            .or_else(|| styles.default.get_bg())
+            .unwrap_or(Color::Reset);
+        let selection_bg = styles
+            .selected_line
+            .get_bg()
+            .unwrap_or(Color::AnsiValue(240));
+        let match_bg = styles
+            .preview_match
+            .get_bg()
+            .unwrap_or(Color::AnsiValue(28));
+        let content_width = area.width as usize - 1; // 1 char left for scrollbar
+        let max_number_len = self
+            .rows
+            .last()
+            .map_or(0, |row| row.number)
+            .to_string()
+            .len();
+        let show_line_number = area.width > 55 || (self.pattern.is_some() && area.width > 8);
+        let gutter_width = if show_line_number {
+            max_number_len + 2
+        } else {
+            1
+        } + usize::from(con.show_selection_mark);
+        let columns = layout(
+            &self.widths,
+            self.first_column,
+            content_width.saturating_sub(gutter_width),
+        );
+        self.visible_columns = fully_visible(&columns, content_width.saturating_sub(gutter_width));
+        let rows_area = Area::new(
+            area.left,
+            area.top + 2,
+            area.width,
+            self.list_scroll.page_height as u16,
+        );
+        let scrollbar = rows_area.scrollbar(self.list_scroll.scroll, self.rows.len());
+        let scrollbar_fg = styles
+            .scrollbar_thumb
+            .get_fg()
+            .or_else(|| styles.preview.get_fg())
+            .unwrap_or(Color::White);
+        let mut rule_style = styles.preview_separator;
+        rule_style.set_bg(normal_bg);
+        for y in 0..area.height {
+            w.queue(cursor::MoveTo(area.left, area.top + y))?;
+            let mut cw = CropWriter::new(w, content_width);
+            let mut selected = false;
+            match y {
+                0 => {
+                    let title_style = &styles.preview_title;
+                    let mut separator_style = styles.preview_separator;
+                    if let Some(bg) = title_style.get_bg() {
+                        separator_style.set_bg(bg);
+                    }
+                    cw.repeat(title_style, &SPACE_FILLING, gutter_width)?;
+                    for (i, &(idx, width)) in columns.iter().enumerate() {
+                        if i > 0 {
+                            cw.queue_str(&separator_style, COLUMN_SEPARATOR)?;
+                        }
+                        let cell = self.header.get(idx).map_or("", String::as_str);
+                        queue_cell(
+                            &mut cw,
+                            cell,
+                            width,
+                            self.numeric[idx],
+                            title_style,
+                            title_style,
+                            None,
+                        )?;
+                    }
+                    cw.fill(title_style, &SPACE_FILLING)?;
+                }
+                1 => {
+                    cw.repeat(&rule_style, &RULE_FILLING, gutter_width)?;
+                    for (i, &(_, width)) in columns.iter().enumerate() {
+                        if i > 0 {
+                            cw.queue_str(&rule_style, RULE_SEPARATOR)?;
+                        }
+                        cw.repeat(&rule_style, &RULE_FILLING, width)?;
+                    }
+                    cw.fill(&rule_style, &SPACE_FILLING)?;
+                }
+                _ => {
+                    let row_idx = self.list_scroll.scroll + y as usize - 2;
+                    selected = self.list_scroll.is_selected(row_idx);
+                    let bg = if selected { selection_bg } else { normal_bg };
+                    let mut style = styles.preview;
+                    style.set_bg(bg);
+                    let mut match_style = style;
+                    match_style.set_bg(match_bg);
+                    let mut separator_style = styles.preview_separator;
+                    separator_style.set_bg(bg);
+                    if let Some(row) = self.rows.get(row_idx) {
+                        let mut line_number_style = styles.preview_line_number;
+                        if selected {
+                            line_number_style.set_bg(bg);
+                        }
+                        if show_line_number {
+                            cw.queue_g_string(
+                                &line_number_style,
+                                format!(" {:w$} ", row.number, w = max_number_len),
+                            )?;
+                        } else {
+                            cw.queue_str(&style, " ")?;
+                        }
+                        if con.show_selection_mark {
+                            cw.queue_char(&style, if selected { '▶' } else { ' ' })?;
+                        }
+                        for (i, &(idx, width)) in columns.iter().enumerate() {
+                            if i > 0 {
+                                cw.queue_str(&separator_style, COLUMN_SEPARATOR)?;
+                            }
+                            let cell = row.cells.get(idx).map_or("", String::as_str);
+                            let name_match = if self.pattern.is_some() {
+                                self.pattern.pattern.search_string(cell)
+                            } else {
+                                None
+                            };
+                            queue_cell(
+                                &mut cw,
+                                cell,
+                                width,
+                                self.numeric[idx],
+                                &style,
+                                &match_style,
+                                name_match.as_ref(),
+                            )?;
+                        }
+                    }
+                    cw.fill(&style, &SPACE_FILLING)?;
+                }
+            }
+            w.queue(SetBackgroundColor(if selected {
+                selection_bg
+            } else {
+                normal_bg
+            }))?;
+            if ScrollCommand::is_thumb(area.top + y, scrollbar) {
+                w.queue(SetForegroundColor(scrollbar_fg))?;
+                w.queue(Print('▐'))?;
+            } else {
+                w.queue(Print(' '))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn info(
+        &self,
+        width: usize,
+    ) -> String {
+        let mut s = if self.pattern.is_some() {
+            format!("{}/{}", self.rows.len(), self.total_rows_count)
+        } else {
+            format!("{}", self.total_rows_count)
+        };
+        if s.len() > width {
+            return "".to_string();
+        }
+        if s.len() + "rows: ".len() < width {
+            s = format!("rows: {s}");
+        }
+        let columns_count = self.widths.len();
+        if self.first_column > 0 || self.visible_columns < columns_count {
+            let columns = format!(
+                "cols: {}-{}/{}",
+                self.first_column + 1,
+                self.first_column + self.visible_columns.max(1),
+                columns_count,
+            );
+            if s.len() + columns.len() < width {
+                s = format!("{columns} {s}");
+            }
+        }
+        if self.encoding != Encoding::Utf8 && s.len() + self.encoding.name().len() < width {
+            s = format!("{} {s}", self.encoding);
+        }
+        s
+    }
+
+    pub fn display_info(
+        &mut self,
+        w: &mut W,
+        _screen: Screen,
+        panel_skin: &PanelSkin,
+        area: &Area,
+    ) -> Result<(), ProgramError> {
+        let width = area.width as usize;
+        let s = self.info(width);
+        w.queue(cursor::MoveTo(
+            area.left + area.width - s.len() as u16,
+            area.top,
+        ))?;
+        panel_skin.styles.default.queue(w, s)?;
+        Ok(())
+    }
+}
+
+/// Compute the columns to display, starting at `first_column`, with their
+/// widths, so that they fill the available width.
+///
+/// Columns wider than half the available width are cropped, unless there's
+/// enough space left for them. The last column may not fully fit.
+fn layout(
+    widths: &[usize],
+    first_column: usize,
+    available: usize,
+) -> Vec<(usize, usize)> {
+    let max_width = (available / 2).max(MIN_CROPPED_WIDTH);
+    let mut columns = Vec::new();
+    let mut used = 0;
+    for (idx, &natural_width) in widths.iter().enumerate().skip(first_column) {
+        if !columns.is_empty() {
+            used += COLUMN_SEPARATOR.width();
+        }
+        if used >= available {
+            break;
+        }
+        let width = natural_width.clamp(1, max_width);
+        columns.push((idx, width));
+        used += width;
+    }
+    // the remaining space is given to the cropped columns
+    let mut left = available.saturating_sub(used);
+    for (idx, width) in &mut columns {
+        let added = widths[*idx].saturating_sub(*width).min(left);
+        *width += added;
+        left -= added;
+    }
+    columns
+}
+
+/// Return the number of columns which fit entirely in the available width
+fn fully_visible(
+    columns: &[(usize, usize)],
+    available: usize,
+) -> usize {
+    let mut end = 0;
+    columns
+        .iter()
+        .take_while(|&&(_, width)| {
+            end += width;
+            let fits = end <= available;
+            end += COLUMN_SEPARATOR.width();
+            fits
+        })
+        .count()
+}
+
+/// Write a cell, padded or cropped to the given width
+fn queue_cell(
+    cw: &mut CropWriter<'_, W>,
+    cell: &str,
+    width: usize,
+    right_aligned: bool,
+    style: &CompoundStyle,
+    match_style: &CompoundStyle,
+    name_match: Option<&NameMatch>,
+) -> Result<(), ProgramError> {
+    let cell_width = cell.width();
+    let cropped = cell_width > width;
+    let padding = width.saturating_sub(cell_width);
+    if right_aligned {
+        cw.repeat(style, &SPACE_FILLING, padding)?;
+    }
+    let mut used = 0;
+    for (idx, c) in cell.chars().enumerate() {
+        let char_width = c.width().unwrap_or(0);
+        if cropped && used + char_width >= width {
+            break;
+        }
+        let matched = name_match.is_some_and(|nm| nm.pos.contains(&idx));
+        cw.queue_char(if matched { match_style } else { style }, c)?;
+        used += char_width;
+    }
+    if cropped {
+        cw.queue_char(style, '…')?;
+        cw.repeat(style, &SPACE_FILLING, width.saturating_sub(used + 1))?;
+    } else if !right_aligned {
+        cw.repeat(style, &SPACE_FILLING, padding)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod table_view_tests {
+    use {
+        super::*,
+        crate::pattern::{
+            ExactPattern,
+            Pattern,
+        },
+    };
+
+    #[test]
+    fn test_layout() {
+        // everything fits
+        assert_eq!(layout(&[3, 5], 0, 40), vec![(0, 3), (1, 5)]);
+        // the wide column is cropped to leave room for the others
+        // then enlarged with the remaining space
+        assert_eq!(layout(&[5, 100, 4], 0, 80), vec![(0, 5), (1, 65), (2, 4)]);
+        // the last column doesn't fully fit
+        assert_eq!(layout(&[20, 20, 20], 0, 50), vec![(0, 20), (1, 20), (2, 20)]);
+        assert_eq!(layout(&[20, 20, 20, 20], 1, 30), vec![(1, 15), (2, 15)]);
+        // empty columns still take a column
+        assert_eq!(layout(&[0, 2], 0, 40), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_fully_visible() {
+        assert_eq!(fully_visible(&[(0, 20), (1, 20), (2, 20)], 50), 2);
+        assert_eq!(fully_visible(&[(0, 20), (1, 20), (2, 4)], 50), 3);
+        assert_eq!(fully_visible(&[(3, 60)], 50), 0);
+    }
+
+    #[test]
+    fn test_filter_in_cells() {
+        let rows: Vec<Record> = [["ab", "cd"], ["a", "bc"]]
+            .iter()
+            .enumerate()
+            .map(|(idx, cells)| Record {
+                number: idx + 2,
+                cells: cells.iter().map(|c| c.to_string()).collect(),
+            })
+            .collect();
+        let view = TableView {
+            path: PathBuf::from("test.csv"),
+            pattern: InputPattern::none(),
+            encoding: Encoding::Utf8,
+            delimiter: ',',
+            header: vec!["x".to_string(), "y".to_string()],
+            total_rows_count: rows.len(),
+            rows,
+            widths: vec![2, 2],
+            numeric: vec![false, false],
+            first_column: 0,
+            visible_columns: 2,
+            list_scroll: ListScroll::default(),
+        };
+        // "b,c" is in the second line, but not in a cell
+        let pattern = InputPattern {
+            raw: "b,c".to_string(),
+            pattern: Pattern::NameExact(ExactPattern::from("b,c")),
+        };
+        let filtered = view.filtered(pattern, &Dam::unlimited()).unwrap();
+        assert!(filtered.rows.is_empty());
+        let pattern = InputPattern {
+            raw: "bc".to_string(),
+            pattern: Pattern::NameExact(ExactPattern::from("bc")),
+        };
+        let filtered = view.filtered(pattern, &Dam::unlimited()).unwrap();
+        assert_eq!(filtered.rows.len(), 1);
+        assert_eq!(filtered.rows[0].number, 3);
+    }
+}
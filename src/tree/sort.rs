@@ -1,8 +1,14 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
 /// A sort key.
 /// A non None sort mode implies only one level of the tree
 /// is displayed.
 /// When in None mode, paths are alpha sorted
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Sort {
     None,
     Count,
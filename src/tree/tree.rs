@@ -14,9 +14,13 @@ use {
             TreeBuilder,
         },
     },
-    rustc_hash::FxHashMap,
+    rustc_hash::{
+        FxHashMap,
+        FxHashSet,
+    },
     std::{
         cmp::Ord,
+        fs,
         mem,
         path::{
             Path,
@@ -68,6 +72,114 @@ impl Tree {
         Ok(())
     }
 
+    /// Update the tree after changes of the given paths (usually reported
+    /// by the watcher).
+    ///
+    /// When possible (no pattern, no git file info, no new file to show), the
+    /// changed lines are patched in place. Otherwise the tree is rebuilt. In both
+    /// cases, the lines which aren't affected by the changes keep their sums.
+    pub fn apply_changes(
+        &mut self,
+        changed: &[PathBuf],
+        page_height: usize,
+        con: &AppContext,
+    ) -> Result<(), TreeBuildError> {
+        let is_affected =
+            |path: &Path| changed.iter().any(|c| c.starts_with(path) || path.starts_with(c));
+        let changed_paths: FxHashSet<&Path> = changed.iter().map(PathBuf::as_path).collect();
+        let line_paths: FxHashSet<&Path> =
+            self.lines.iter().map(|line| line.path.as_path()).collect();
+        // a new file needs a rebuild only when it appears in a listed
+        // directory: changes in collapsed or hidden subtrees are ignored
+        let is_new_in_listed_dir = |c: &PathBuf| {
+            !line_paths.contains(c.as_path())
+                && c.parent().is_some_and(|parent| self.is_listed_dir(parent))
+                && c.exists()
+        };
+        let needs_rebuild = self.options.pattern.is_some()
+            || self.options.filter_by_git_status
+            || self.options.show_git_file_info
+            || changed.iter().any(is_new_in_listed_dir);
+        if needs_rebuild {
+            let old_sums: FxHashMap<PathBuf, FileSum> = self
+                .lines
+                .iter()
+                .filter(|line| !is_affected(&line.path))
+                .filter_map(|line| line.sum.map(|sum| (line.path.clone(), sum)))
+                .collect();
+            self.refresh(page_height, con)?;
+            for line in &mut self.lines {
+                if line.sum.is_none() {
+                    line.sum = old_sums.get(&line.path).copied();
+                }
+            }
+            self.sort_siblings();
+            return Ok(());
+        }
+        let selected_path = self.selected_line().path.to_path_buf();
+        let mut removed_ids: FxHashSet<TreeLineId> = FxHashSet::default();
+        for line in &mut self.lines[1..] {
+            if !changed_paths.contains(line.path.as_path()) {
+                continue;
+            }
+            match fs::symlink_metadata(&line.path) {
+                Ok(metadata) => {
+                    line.line_type = TreeLineType::new(&line.path, metadata.file_type());
+                    line.metadata = metadata;
+                }
+                Err(_) => {
+                    removed_ids.insert(line.id);
+                }
+            }
+        }
+        if !removed_ids.is_empty() {
+            // the descendants of removed lines are removed too (parents
+            // come before their children in the lines)
+            let mut removed_children: FxHashMap<TreeLineId, usize> = FxHashMap::default();
+            for line in &self.lines[1..] {
+                let Some(parent_id) = line.parent_id else {
+                    continue;
+                };
+                if removed_ids.contains(&parent_id) {
+                    removed_ids.insert(line.id);
+                } else if removed_ids.contains(&line.id) {
+                    *removed_children.entry(parent_id).or_default() += 1;
+                }
+            }
+            self.lines.retain(|line| !removed_ids.contains(&line.id));
+            for line in &mut self.lines {
+                if let Some(count) = removed_children.get(&line.id) {
+                    line.nb_kept_children = line.nb_kept_children.saturating_sub(*count);
+                }
+            }
+        }
+        for line in &mut self.lines {
+            if is_affected(&line.path) {
+                line.sum = None;
+            }
+        }
+        // this also computes the sums of the regular files
+        self.after_lines_changed();
+        if !self.try_select_path(&selected_path) && self.selection >= self.lines.len() {
+            self.selection = self.lines.len() - 1;
+        }
+        self.make_selection_visible(page_height);
+        Ok(())
+    }
+
+    /// tell whether the path is the one of a displayed directory whose
+    /// children are listed (the root, or a directory with child lines)
+    fn is_listed_dir(
+        &self,
+        path: &Path,
+    ) -> bool {
+        let Some(idx) = self.lines.iter().position(|line| line.path == path) else {
+            return false;
+        };
+        let dir = &self.lines[idx];
+        dir.is_dir() && (idx == 0 || self.lines.iter().any(|line| line.parent_id == Some(dir.id)))
+    }
+
     /// do what must be done after line additions or removals:
     /// - sort the lines
     /// - compute left branches
@@ -484,10 +596,9 @@ impl Tree {
 
     pub fn has_dir_missing_sum(&self) -> bool {
         self.options.needs_sum()
-            && self
-                .lines
-                .iter()
-                .any(|line| line.line_type == TreeLineType::Dir && line.sum.is_none())
+            && self.lines.iter().any(|line| {
+                line.line_type == TreeLineType::Dir && line.sum.is_none_or(FileSum::is_stale)
+            })
     }
 
     pub fn is_missing_git_status_computation(&self) -> bool {
@@ -517,11 +628,31 @@ impl Tree {
         dam: &Dam,
         con: &AppContext,
     ) {
+        // sums persisted in a previous session are displayed while
+        // the current ones are computed
+        if con.file_sum_cache {
+            let mut found = false;
+            for line in &mut self.lines {
+                if line.sum.is_none() && line.line_type == TreeLineType::Dir {
+                    line.sum = FileSum::from_persisted_cache(&line.path, con);
+                    found |= line.sum.is_some();
+                }
+            }
+            if found {
+                self.sort_siblings();
+                return;
+            }
+        }
         // we prefer to compute the root directory last: its computation
         // is faster when its first level children are already computed
         for i in (0..self.lines.len()).rev() {
-            if self.lines[i].sum.is_none() && self.lines[i].line_type == TreeLineType::Dir {
-                self.lines[i].sum = FileSum::from_dir(&self.lines[i].path, dam, con);
+            if self.lines[i].sum.is_none_or(FileSum::is_stale)
+                && self.lines[i].line_type == TreeLineType::Dir
+            {
+                // on interruption, a stale sum is kept until the next try
+                if let Some(sum) = FileSum::from_dir(&self.lines[i].path, dam, con) {
+                    self.lines[i].sum = Some(sum);
+                }
                 self.sort_siblings();
                 return;
             }
@@ -554,9 +685,10 @@ impl Tree {
             }
             Sort::Size => {
                 let selected_path = self.selected_line().path.to_path_buf();
+                let apparent = self.options.apparent_sizes;
                 self.lines[1..].sort_by(|a, b| {
-                    let asize = a.sum.map_or(0, |s| s.to_size());
-                    let bsize = b.sum.map_or(0, |s| s.to_size());
+                    let asize = a.sum.map_or(0, |s| s.size(apparent));
+                    let bsize = b.sum.map_or(0, |s| s.size(apparent));
                     bsize.cmp(&asize)
                 });
                 self.try_select_path(&selected_path);
@@ -683,3 +815,60 @@ impl Tree {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tree_tests {
+    use super::*;
+
+    fn line_names(tree: &Tree) -> Vec<&str> {
+        tree.lines[1..].iter().map(|line| line.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("a"), "a").unwrap();
+        fs::write(root.join("b"), "b").unwrap();
+        fs::write(root.join("sub/c"), "c").unwrap();
+        let con = AppContext::default();
+        let mut tree = TreeBuilder::from(root.to_path_buf(), TreeOptions::default(), 100, &con)
+            .unwrap()
+            .build_tree(false, &Dam::unlimited())
+            .unwrap();
+        assert_eq!(line_names(&tree), vec!["a", "b", "sub", "c"]);
+        // removals are patched in place
+        fs::remove_dir_all(root.join("sub")).unwrap();
+        tree.apply_changes(&[root.join("sub")], 100, &con).unwrap();
+        assert_eq!(line_names(&tree), vec!["a", "b"]);
+        // new files are added
+        fs::write(root.join("d"), "d").unwrap();
+        tree.apply_changes(&[root.join("d")], 100, &con).unwrap();
+        assert_eq!(line_names(&tree), vec!["a", "b", "d"]);
+    }
+
+    #[test]
+    fn test_change_of_undisplayed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join(".cache")).unwrap();
+        fs::write(root.join("a"), "a").unwrap();
+        let con = AppContext::default();
+        let mut tree = TreeBuilder::from(root.to_path_buf(), TreeOptions::default(), 100, &con)
+            .unwrap()
+            .build_tree(false, &Dam::unlimited())
+            .unwrap();
+        assert_eq!(line_names(&tree), vec!["a"]);
+        // a rebuild would restore the real name of the line
+        tree.lines[1].name = "not rebuilt".to_string();
+        // a file written in a hidden directory doesn't trigger a rebuild
+        fs::write(root.join(".cache/app.log"), "log").unwrap();
+        tree.apply_changes(&[root.join(".cache/app.log")], 100, &con).unwrap();
+        assert_eq!(line_names(&tree), vec!["not rebuilt"]);
+        // while a new file in the root does
+        fs::write(root.join("b"), "b").unwrap();
+        tree.apply_changes(&[root.join("b")], 100, &con).unwrap();
+        assert_eq!(line_names(&tree), vec!["a", "b"]);
+    }
+}
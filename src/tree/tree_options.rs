@@ -24,6 +24,7 @@ pub struct TreeOptions {
     pub show_counts: bool,         // whether to show the number of files (> 1 only for dirs)
     pub show_dates: bool,          // whether to show the last modified date
     pub show_sizes: bool,          // whether to show sizes of files and dirs
+    pub apparent_sizes: bool,      // whether sizes are apparent ones rather than disk usage
     pub max_depth: Option<u16>,    // the maximum directory depth to recurse to
     pub show_git_file_info: bool,
     pub show_device_id: bool,
@@ -51,6 +52,7 @@ impl TreeOptions {
             show_counts: self.show_counts,
             show_dates: self.show_dates,
             show_sizes: self.show_sizes,
+            apparent_sizes: self.apparent_sizes,
             show_permissions: self.show_permissions,
             respect_git_ignore: self.respect_git_ignore,
             filter_by_git_status: self.filter_by_git_status,
@@ -125,7 +127,7 @@ impl TreeOptions {
         let tokens = vec!["broot", &prefixed];
         let args = Args::try_parse_from(tokens).map_err(|_| {
             warn!("invalid flags: {:?}", flags);
-            "invalid flag (valid flags are -dDfFgGhHiIpPsSwWtT)"
+            "invalid flag (valid flags are -aAdDfFgGhHiIpPsSwWtT)"
         })?;
         self.apply_launch_args(&args);
         Ok(())
@@ -141,6 +143,11 @@ impl TreeOptions {
         } else if cli_args.no_sizes {
             self.show_sizes = false;
         }
+        if cli_args.apparent_sizes {
+            self.apparent_sizes = true;
+        } else if cli_args.no_apparent_sizes {
+            self.apparent_sizes = false;
+        }
         if cli_args.whale_spotting {
             self.show_hidden = true;
             self.respect_git_ignore = false;
@@ -239,6 +246,7 @@ impl Default for TreeOptions {
             show_counts: false,
             show_dates: false,
             show_sizes: false,
+            apparent_sizes: false,
             show_git_file_info: false,
             show_device_id: false,
             show_root_fs: false,
@@ -62,6 +62,25 @@ impl TtyView {
         Ok(sv)
     }
 
+    /// Build a view of a text which isn't read from a file, like the
+    /// captured output of a command. The text may contain ANSI escape codes.
+    pub fn from_text(
+        path: &Path,
+        text: &str,
+    ) -> Self {
+        let lines: Vec<TLine> = text.lines().map(TLine::from_tty).collect();
+        let mut sv = Self {
+            path: path.to_path_buf(),
+            total_lines_count: lines.len(),
+            lines,
+            scroll: 0,
+            page_height: 0,
+            selection_idx: None,
+        };
+        sv.select_first();
+        sv
+    }
+
     fn read_lines(&mut self) -> Result<(), io::Error> {
         let f = File::open(&self.path)?;
         {
@@ -353,7 +353,8 @@ impl<'b> ExecutionBuilder<'b> {
         }
     }
 
-    fn string(
+    /// replace the groups of a pattern, without escaping
+    pub fn string(
         &self,
         pattern: &str,
         con: &AppContext,
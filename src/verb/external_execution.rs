@@ -4,7 +4,13 @@ use {
         app::*,
         display::W,
         errors::ProgramError,
+        job::{
+            CAPTURE_TIMEOUT,
+            JobRequest,
+            RunningProcess,
+        },
         launchable::Launchable,
+        output::CommandOutput,
     },
     std::{
         fs::OpenOptions,
@@ -46,6 +52,14 @@ pub struct ExternalExecution {
 
     /// whether the tree must be refreshed after the verb is executed
     pub refresh_after: bool,
+
+    /// whether the output of the process must be captured and displayed
+    /// in the output panel (only when staying in broot)
+    pub capture_output: bool,
+
+    /// whether the process must run as a background job, its output
+    /// being collected (only when staying in broot)
+    pub background: bool,
 }
 
 impl ExternalExecution {
@@ -59,6 +73,8 @@ impl ExternalExecution {
             working_dir: None,
             switch_terminal: true, // by default we switch
             refresh_after: true,   // by default we refresh
+            capture_output: false,
+            background: false,
         }
     }
 
@@ -84,6 +100,11 @@ impl ExternalExecution {
         through_shell: bool,
         con: &AppContext,
     ) -> Result<CmdResult, ProgramError> {
+        if con.dry_run {
+            return Ok(CmdResult::Message(
+                self.dry_run_message(builder, through_shell, con),
+            ));
+        }
         match self.exec_mode {
             ExternalExecutionMode::FromParentShell => {
                 self.cmd_result_exec_from_parent_shell(builder, con)
@@ -97,6 +118,35 @@ impl ExternalExecution {
         }
     }
 
+    /// describe the command lines which would be executed
+    fn dry_run_message(
+        &self,
+        mut builder: ExecutionBuilder<'_>,
+        through_shell: bool,
+        con: &AppContext,
+    ) -> String {
+        let per_selection = self.exec_pattern.coarity() == CommandCoarity::PerSelection
+            && builder.sel_info.count_paths() > 1;
+        let lines = match builder.sel_info {
+            SelInfo::More(stage) if per_selection => stage
+                .paths()
+                .iter()
+                .map(|path| {
+                    let sel = Selection {
+                        path,
+                        line: 0,
+                        stype: SelectionType::from(path),
+                        is_exe: false,
+                    };
+                    builder.sel_shell_exec_string(&self.exec_pattern, Some(sel), con)
+                })
+                .collect(),
+            _ => vec![builder.shell_exec_string(&self.exec_pattern, con)],
+        };
+        let shell = if through_shell { " (through shell)" } else { "" };
+        format!("Dry run{shell}, not executed: `{}`", lines.join("`, `"))
+    }
+
     /// build a launchable for the whole (possibly merged) selection
     fn merged_launchable(
         &self,
@@ -212,6 +262,76 @@ impl ExternalExecution {
         Ok(CmdResult::from(launchable))
     }
 
+    /// whether the command line must be kept, to be displayed with
+    /// the output of the process
+    fn keeps_command(&self) -> bool {
+        self.capture_output || self.background
+    }
+
+    /// execute a launchable without leaving broot, either letting it
+    /// write on the terminal, capturing its output, or preparing its
+    /// start as a background job.
+    ///
+    /// A captured command which doesn't end soon enough goes on as
+    /// a background job.
+    fn execute_staying(
+        &self,
+        w: &mut W,
+        launchable: Launchable,
+        command: Option<String>,
+        outputs: &mut Vec<CommandOutput>,
+        jobs: &mut Vec<JobRequest>,
+    ) -> Result<(), ProgramError> {
+        match command {
+            Some(command) if self.background => {
+                jobs.push(JobRequest {
+                    command,
+                    launchable,
+                    refresh_after: self.refresh_after,
+                    process: None,
+                });
+                Ok(())
+            }
+            Some(command) => {
+                let mut process = RunningProcess::start(&launchable)?;
+                match process.wait_timeout(CAPTURE_TIMEOUT)? {
+                    Some(status) => outputs.push(process.command_output(command, status)),
+                    None => jobs.push(JobRequest {
+                        command,
+                        launchable,
+                        refresh_after: self.refresh_after,
+                        process: Some(process),
+                    }),
+                }
+                Ok(())
+            }
+            None => launchable.execute(Some(w)),
+        }
+    }
+
+    /// build the cmd result of an execution stopped by an error, keeping
+    /// the outputs captured before and the captured commands still running
+    fn interrupted_execution(
+        &self,
+        error: ProgramError,
+        outputs: Vec<CommandOutput>,
+        jobs: Vec<JobRequest>,
+    ) -> CmdResult {
+        warn!("launchable failed : {:#?}", error);
+        // the jobs of a background verb aren't started yet
+        let jobs = if self.background { Vec::new() } else { jobs };
+        if outputs.is_empty() && jobs.is_empty() {
+            CmdResult::error(error.to_string())
+        } else {
+            CmdResult::CapturedOutputs {
+                outputs,
+                jobs,
+                refresh: self.refresh_after,
+                error: Some(error.to_string()),
+            }
+        }
+    }
+
     /// build the cmd result as an executable which will be called in a process
     /// launched by broot
     fn cmd_result_exec_stay_in_broot(
@@ -222,15 +342,21 @@ impl ExternalExecution {
         con: &AppContext,
     ) -> Result<CmdResult, ProgramError> {
         let working_dir_path = self.working_dir_path(&builder, con);
+        let mut outputs = Vec::new();
+        let mut jobs = Vec::new();
         match &builder.sel_info {
             SelInfo::None | SelInfo::One(_) => {
                 // zero or one selection -> only one execution
+                let command = self
+                    .keeps_command()
+                    .then(|| builder.shell_exec_string(&self.exec_pattern, con));
                 let launchable =
                     self.merged_launchable(&mut builder, working_dir_path, through_shell, con)?;
                 info!("Executing not leaving, launchable {:#?}", launchable);
-                if let Err(e) = launchable.execute(Some(w)) {
-                    warn!("launchable failed : {:#?}", e);
-                    return Ok(CmdResult::error(e.to_string()));
+                if let Err(e) =
+                    self.execute_staying(w, launchable, command, &mut outputs, &mut jobs)
+                {
+                    return Ok(self.interrupted_execution(e, outputs, jobs));
                 }
             }
             SelInfo::More(stage) => {
@@ -248,23 +374,37 @@ impl ExternalExecution {
                         });
                         let n = sels.len();
                         for (i, sel) in sels.enumerate() {
-                            let launchable = self.sel_launchable(
+                            let command = self.keeps_command().then(|| {
+                                builder.sel_shell_exec_string(&self.exec_pattern, Some(sel), con)
+                            });
+                            let launchable = match self.sel_launchable(
                                 &mut builder,
                                 Some(sel),
                                 working_dir_path.clone(),
                                 through_shell,
                                 con,
-                            )?;
+                            ) {
+                                Ok(launchable) => launchable,
+                                Err(e) => return Ok(self.interrupted_execution(e, outputs, jobs)),
+                            };
                             let i = i + 1;
                             info!("Executing not leaving launchable {i}/{n}: {launchable:#?}");
-                            if let Err(e) = launchable.execute(Some(w)) {
-                                warn!("launchable failed : {:#?}", e);
-                                return Ok(CmdResult::error(e.to_string()));
+                            if let Err(e) = self.execute_staying(
+                                w,
+                                launchable,
+                                command,
+                                &mut outputs,
+                                &mut jobs,
+                            ) {
+                                return Ok(self.interrupted_execution(e, outputs, jobs));
                             }
                         }
                     }
                     CommandCoarity::Merged => {
                         // we execute once as the arguments are merging the selection
+                        let command = self
+                            .keeps_command()
+                            .then(|| builder.shell_exec_string(&self.exec_pattern, con));
                         let launchable = self.merged_launchable(
                             &mut builder,
                             working_dir_path.clone(),
@@ -272,15 +412,29 @@ impl ExternalExecution {
                             con,
                         )?;
                         info!("Executing not leaving, merged launchable {:#?}", launchable);
-                        if let Err(e) = launchable.execute(Some(w)) {
-                            warn!("launchable failed : {:?}", e);
-                            return Ok(CmdResult::error(e.to_string()));
+                        if let Err(e) = self.execute_staying(
+                            w,
+                            launchable,
+                            command,
+                            &mut outputs,
+                            &mut jobs,
+                        ) {
+                            return Ok(self.interrupted_execution(e, outputs, jobs));
                         }
                     }
                 }
             }
         }
-        if self.refresh_after {
+        if self.background {
+            Ok(CmdResult::StartJobs(jobs))
+        } else if self.capture_output {
+            Ok(CmdResult::CapturedOutputs {
+                outputs,
+                jobs,
+                refresh: self.refresh_after,
+                error: None,
+            })
+        } else if self.refresh_after {
             Ok(CmdResult::RefreshState { clear_cache: true })
         } else {
             Ok(CmdResult::Keep)
@@ -0,0 +1,294 @@
+use {
+    crate::{
+        app::SelInfo,
+        conf::{
+            FileConditionConf,
+            file_size,
+        },
+        content_type::magic_numbers::{
+            self,
+            MagicType,
+        },
+        errors::ConfError,
+        git,
+    },
+    once_cell::sync::Lazy,
+    rustc_hash::FxHashMap,
+    std::{
+        path::{
+            Path,
+            PathBuf,
+        },
+        sync::Mutex,
+    },
+};
+
+const MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+/// Conditions on the selected file(s) restricting the applicability
+/// of a verb, all of them having to be met.
+///
+/// This completes the `FileTypeCondition` and the extensions filtering
+/// so that several verbs may share a key and apply to different kinds
+/// of files.
+#[derive(Debug, Clone, Default)]
+pub struct FileCondition {
+    name: Option<glob::Pattern>,
+    /// matched against both the name and the mime type of the magic type
+    content_type: Option<glob::Pattern>,
+    in_git_repo: Option<bool>,
+    executable: Option<bool>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+}
+
+fn parse_glob(pattern: &str) -> Result<glob::Pattern, ConfError> {
+    glob::Pattern::new(pattern).map_err(|_| ConfError::InvalidGlobPattern {
+        pattern: pattern.to_string(),
+    })
+}
+
+fn parse_size(size: &str) -> Result<u64, ConfError> {
+    file_size::parse_file_size(size).map_err(|details| ConfError::InvalidVerbConf { details })
+}
+
+impl FileCondition {
+    pub fn from_conf(conf: &FileConditionConf) -> Result<Self, ConfError> {
+        Ok(Self {
+            name: conf.name.as_deref().map(parse_glob).transpose()?,
+            content_type: conf.content_type.as_deref().map(parse_glob).transpose()?,
+            in_git_repo: conf.in_git_repo,
+            executable: conf.executable,
+            min_size: conf.min_size.as_deref().map(parse_size).transpose()?,
+            max_size: conf.max_size.as_deref().map(parse_size).transpose()?,
+        })
+    }
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.content_type.is_none()
+            && self.in_git_repo.is_none()
+            && self.executable.is_none()
+            && self.min_size.is_none()
+            && self.max_size.is_none()
+    }
+    pub fn accepts_path(
+        &self,
+        path: &Path,
+    ) -> bool {
+        self.accepts(path, &mut PathFacts::default())
+    }
+    fn accepts(
+        &self,
+        path: &Path,
+        facts: &mut PathFacts,
+    ) -> bool {
+        if let Some(pattern) = &self.name {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if !pattern.matches_with(name, MATCH_OPTIONS) {
+                return false;
+            }
+        }
+        if let Some(in_git_repo) = self.in_git_repo {
+            if facts.in_git_repo(path) != in_git_repo {
+                return false;
+            }
+        }
+        if self.executable.is_some() || self.min_size.is_some() || self.max_size.is_some() {
+            let Some(file_info) = facts.file_info(path) else {
+                return false;
+            };
+            if let Some(executable) = self.executable {
+                if (file_info.is_file && file_info.is_exe) != executable {
+                    return false;
+                }
+            }
+            if self.min_size.is_some() || self.max_size.is_some() {
+                // directories have no meaningful size here
+                if !file_info.is_file {
+                    return false;
+                }
+                let size = file_info.size;
+                if self.min_size.is_some_and(|min| size < min) {
+                    return false;
+                }
+                if self.max_size.is_some_and(|max| size > max) {
+                    return false;
+                }
+            }
+        }
+        if let Some(pattern) = &self.content_type {
+            let Some(magic_type) = facts.magic_type(path) else {
+                return false;
+            };
+            if !pattern.matches_with(magic_type.name, MATCH_OPTIONS)
+                && !pattern.matches_with(magic_type.mime, MATCH_OPTIONS)
+            {
+                return false;
+            }
+        }
+        true
+    }
+    /// tell whether all the selected paths meet the conditions.
+    ///
+    /// As this is called on every keystroke and redraw, what's read
+    /// on disk is kept until the selection changes
+    #[allow(clippy::missing_panics_doc)] // panics on mutex poisoning (good)
+    pub fn accepts_sel_info(
+        &self,
+        sel_info: SelInfo<'_>,
+    ) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let paths = sel_info.paths();
+        let mut cache = FACTS_CACHE.lock().unwrap();
+        if cache.len() != paths.len() || !paths.iter().all(|&path| cache.contains_key(path)) {
+            cache.clear();
+        }
+        paths.iter().all(|&path| {
+            let facts = cache.entry(path.to_path_buf()).or_default();
+            self.accepts(path, facts)
+        })
+    }
+}
+
+/// The facts about the paths of the last checked selection
+static FACTS_CACHE: Lazy<Mutex<FxHashMap<PathBuf, PathFacts>>> =
+    Lazy::new(|| Mutex::new(FxHashMap::default()));
+
+/// Forget what was read of the selected paths, eg because an
+/// external command may have changed them
+#[allow(clippy::missing_panics_doc)] // panics on mutex poisoning (good)
+pub fn clear_file_condition_cache() {
+    FACTS_CACHE.lock().unwrap().clear();
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FileInfo {
+    is_file: bool,
+    is_exe: bool,
+    size: u64,
+}
+
+/// What's known of a path, each fact being read only when
+/// a condition needs it
+#[derive(Debug, Default)]
+struct PathFacts {
+    in_git_repo: Option<bool>,
+    file_info: Option<Option<FileInfo>>,
+    magic_type: Option<Option<MagicType>>,
+}
+
+impl PathFacts {
+    fn in_git_repo(
+        &mut self,
+        path: &Path,
+    ) -> bool {
+        *self
+            .in_git_repo
+            .get_or_insert_with(|| git::closest_repo_dir(path).is_some())
+    }
+    fn file_info(
+        &mut self,
+        path: &Path,
+    ) -> Option<FileInfo> {
+        *self.file_info.get_or_insert_with(|| {
+            let metadata = path.metadata().ok()?;
+            Some(FileInfo {
+                is_file: metadata.is_file(),
+                is_exe: metadata.is_file() && is_exe(path, &metadata),
+                size: metadata.len(),
+            })
+        })
+    }
+    fn magic_type(
+        &mut self,
+        path: &Path,
+    ) -> Option<MagicType> {
+        if !self.file_info(path)?.is_file {
+            return None;
+        }
+        *self
+            .magic_type
+            .get_or_insert_with(|| magic_numbers::file_magic_type(path).ok().flatten())
+    }
+}
+
+#[cfg(unix)]
+fn is_exe(
+    _path: &Path,
+    metadata: &std::fs::Metadata,
+) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(windows)]
+fn is_exe(
+    path: &Path,
+    _metadata: &std::fs::Metadata,
+) -> bool {
+    use is_executable::IsExecutable;
+    path.is_executable()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn is_exe(
+    _path: &Path,
+    _metadata: &std::fs::Metadata,
+) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod file_condition_tests {
+    use {
+        super::*,
+        std::fs,
+    };
+
+    fn condition(hjson: &str) -> FileCondition {
+        let conf: FileConditionConf = deser_hjson::from_str(hjson).unwrap();
+        FileCondition::from_conf(&conf).unwrap()
+    }
+
+    #[test]
+    fn test_file_condition() {
+        let dir = tempfile::tempdir().unwrap();
+        let pdf = dir.path().join("Report.PDF");
+        fs::write(&pdf, b"%PDF-1.7\n...").unwrap();
+        let txt = dir.path().join("notes.txt");
+        fs::write(&txt, "some notes, a little longer than the pdf").unwrap();
+        assert!(condition("{}").is_empty());
+        assert!(condition("{}").accepts_path(&txt));
+        let c = condition(r#"{ name: "*.pdf" }"#);
+        assert!(c.accepts_path(&pdf));
+        assert!(!c.accepts_path(&txt));
+        let c = condition(r#"{ content_type: "application/*" }"#);
+        assert!(c.accepts_path(&pdf));
+        assert!(!c.accepts_path(&txt));
+        assert!(!c.accepts_path(dir.path()));
+        let c = condition(r#"{ content_type: "pdf", max_size: "20" }"#);
+        assert!(c.accepts_path(&pdf));
+        let c = condition(r#"{ min_size: "20" }"#);
+        assert!(!c.accepts_path(&pdf));
+        assert!(c.accepts_path(&txt));
+        assert!(!c.accepts_path(dir.path()));
+        assert!(!condition("{ executable: true }").accepts_path(&txt));
+        assert!(condition("{ executable: false }").accepts_path(&txt));
+        assert!(!condition("{ executable: true }").accepts_path(dir.path()));
+    }
+
+    #[test]
+    fn test_invalid_file_condition() {
+        let conf = FileConditionConf {
+            min_size: Some("big".to_string()),
+            ..Default::default()
+        };
+        assert!(FileCondition::from_conf(&conf).is_err());
+    }
+}
@@ -63,8 +63,10 @@ Internals! {
     copy_path: "copy path to system clipboard" true,
     escape: "escape from edition, completion, page, etc." false,
     filesystems: "list mounted filesystems" false,
+    find_duplicates: "search identical files below the current root" false,
     focus: "display the directory (mapped to *enter*)" true,
     focus_staging_area_no_open: "focus the staging area if already open" false,
+    goto_offset: "move the cursor of a hex preview to an offset" false,
     help: "display broot's help" false,
     input_clear: "empty the input" false,
     input_del_char_below: "delete the char left at the cursor's position" false,
@@ -80,10 +82,17 @@ Internals! {
     input_paste: "paste the clipboard content into the input" false,
     input_selection_copy: "copy the selected part of the input into the selection" false,
     input_selection_cut: "cut the selected part of the input into the selection" false,
+    job_kill: "kill a background job" false,
+    jump_to_key: "select a key path (eg `a.b[2]`) in a structured preview" false,
     line_down: "move one line down" false,
     line_down_no_cycle: "move one line down" false,
     line_up: "move one line up" false,
     line_up_no_cycle: "move one line up" false,
+    macro_play: "replay the last recorded macro, optionally several times" false,
+    macro_play_staged: "replay the last recorded macro once per staged file" false,
+    macro_record: "start recording the executed commands as a macro" false,
+    macro_save: "save the last recorded macro as a verb" false,
+    macro_stop: "stop recording the macro" false,
     mode_command: "enter the command mode" false,
     mode_input: "enter the input mode" false,
     move_panel_divider: "move a panel divider" false,
@@ -92,7 +101,9 @@ Internals! {
     next_same_depth: "select the next file at the same depth" false,
     no_action: "do nothing (can be used to disable a key)" false,
     no_sort: "don't sort" false,
+    open_jobs: "list the background jobs" false,
     open_leave: "open file or directory according to OS (quit broot)" true,
+    open_output: "show the captured output of the last commands" false,
     open_preview: "open the preview panel" true,
     open_staging_area: "open the staging area" false,
     open_stay: "open file or directory according to OS (stay in broot)" true,
@@ -108,7 +119,12 @@ Internals! {
     panel_right_no_open: "either focus panel on right or close left one" false,
     parent: "move to the parent directory" false,
     preview_binary: "preview the selection as binary" true,
+    preview_format: "preview a summary of the selection's binary format" true,
     preview_image: "preview the selection as image" true,
+    preview_info: "preview the metadata of the selection" true,
+    preview_markdown: "preview the selection as rendered markdown" true,
+    preview_structured: "preview the selection as a tree of keys" true,
+    preview_table: "preview the selection as a table" true,
     preview_text: "preview the selection as text" true,
     preview_tty: "preview the selection as tty" true,
     previous_dir: "select the previous directory" false,
@@ -119,16 +135,24 @@ Internals! {
     print_tree: "print tree and leaves broot" true,
     quit: "quit Broot" false,
     refresh: "refresh tree and clear size cache" false,
+    refresh_changes: "update the lines of the paths changed since last refresh" false,
+    refresh_followed: "update the previews following their file" false,
+    refresh_jobs: "notify the end of background jobs" false,
     delete_trashed_file: "irreversibly delete a file which is in the trash" false,
     restore_trashed_file: "restore a file which is in the trash" false,
     purge_trash: "irreversibly delete the trash's content" false,
     root_down: "move tree root down" true,
     root_up: "move tree root up" true,
+    scroll_left: "scroll the preview to the left" false,
+    scroll_right: "scroll the preview to the right" false,
+    search_bytes: "search a byte sequence or a string in a hex preview" false,
     select: "select a file by path" true,
+    session_save: "save the tabs, panels and stage under a name" false,
     show: "reveal and select a file by path" true,
     select_first: "select the first item" false,
     select_last: "select the last item" false,
     set_panel_width: "set the width of a panel" false,
+    set_encoding: "force the encoding of text previews (or `auto`)" false,
     set_syntax_theme: "set the theme of code preview" false,
     sort_by_count: "sort by count" false,
     sort_by_date: "sort by date" false,
@@ -140,10 +164,17 @@ Internals! {
     stage_all_directories: "stage all matching directories" true,
     stage_all_files: "stage all matching files" true,
     start_end_panel: "either open or close an additional panel" true,
+    tab_close: "close the current tab" false,
+    tab_new: "open a new tab, optionally named" false,
+    tab_next: "activate the tab to the right" false,
+    tab_previous: "activate the tab to the left" false,
+    toggle_apparent_sizes: "toggle between apparent sizes and disk usage" false,
     toggle_counts: "toggle showing number of files in directories" false,
     toggle_dates: "toggle showing last modified dates" false,
     toggle_device_id: "toggle showing device id" false,
+    toggle_dry_run: "toggle displaying external commands instead of executing them" false,
     toggle_files: "toggle showing files (or just folders)" false,
+    toggle_follow: "toggle following the growth of the previewed file" false,
     toggle_git_file_info: "toggle display of git file information" false,
     toggle_git_ignore: "toggle use of .gitignore and .ignore" false,
     toggle_git_status: "toggle showing only files relevant for git status" false,
@@ -152,6 +183,7 @@ Internals! {
     toggle_perm: "toggle showing file permissions" false,
     toggle_preview: "open/close the preview panel" false,
     toggle_root_fs: "toggle showing filesystem info on top" false,
+    size_report: "show the sizes per extension and per child of the directory" true,
     set_max_depth: "set the maximum directory depth shown" false,
     unset_max_depth: "clear the max_depth" false,
     toggle_second_tree: "toggle display of a second tree panel" true,
@@ -160,6 +192,7 @@ Internals! {
     toggle_staging_area: "open/close the staging area panel" false,
     toggle_tree: "toggle showing more than one level of the tree" true,
     toggle_trim_root: "toggle removing nodes at first level too" false,
+    toggle_wrap: "toggle wrapping long lines in the preview" false,
     total_search: "search again but on all children" false,
     search_again: "either put back last search, or search deeper" false,
     trash: "move file to system trash" true,
@@ -178,14 +211,23 @@ impl Internal {
             Self::focus => r"focus (?P<path>.*)?",
             Self::select => r"select (?P<path>.*)?",
             Self::show => r"show (?P<path>.*)?",
+            Self::goto_offset => r"goto_offset (?P<offset>.*)?",
+            Self::job_kill => r"job_kill (?P<id>\d*)?",
+            Self::jump_to_key => r"jump_to_key (?P<path>.*)?",
+            Self::search_bytes => r"search_bytes (?P<pattern>.*)?",
             Self::line_down => r"line_down (?P<count>\d*)?",
             Self::line_up => r"line_up (?P<count>\d*)?",
             Self::line_down_no_cycle => r"line_down_no_cycle (?P<count>\d*)?",
             Self::line_up_no_cycle => r"line_up_no_cycle (?P<count>\d*)?",
+            Self::macro_play => r"macro_play (?P<count>\d*)?",
+            Self::macro_save => r"macro_save (?P<name>.*)?",
             Self::move_panel_divider => r"move_panel_divider (?P<idx>\d+) (?P-?<dx>\d+)",
             Self::set_panel_width => r"set_panel_width (?P<idx>\d+) (?P<width>\d+)",
             Self::set_max_depth => r"set_max_depth (?P<depth>\d+)",
+            Self::set_encoding => r"set_encoding (?P<encoding>.*)?",
             Self::set_syntax_theme => r"set_syntax_theme {theme:theme}",
+            Self::session_save => r"session_save (?P<name>.*)?",
+            Self::tab_new => r"tab_new (?P<name>.*)?",
             Self::write_output => r"write_output (?P<line>.*)",
             _ => self.name(),
         }
@@ -4,6 +4,7 @@ mod exec_pattern;
 mod execution_builder;
 mod external_execution;
 mod external_execution_mode;
+mod file_condition;
 mod file_type_condition;
 mod internal;
 mod internal_execution;
@@ -17,6 +18,7 @@ mod verb_arg_def;
 mod verb_description;
 mod verb_execution;
 mod verb_invocation;
+mod verb_prompt;
 mod verb_store;
 mod write;
 
@@ -28,6 +30,10 @@ pub use {
     execution_builder::*,
     external_execution::*,
     external_execution_mode::ExternalExecutionMode,
+    file_condition::{
+        FileCondition,
+        clear_file_condition_cache,
+    },
     file_type_condition::*,
     internal::Internal,
     internal_execution::InternalExecution,
@@ -39,6 +45,7 @@ pub use {
     verb_description::VerbDescription,
     verb_execution::VerbExecution,
     verb_invocation::*,
+    verb_prompt::VerbPrompt,
     verb_store::{
         PrefixSearchResult,
         VerbStore,
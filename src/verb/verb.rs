@@ -59,6 +59,9 @@ pub struct Verb {
     /// extension filtering. If empty, all extensions apply
     pub file_extensions: Vec<String>,
 
+    /// finer conditions on the selected file(s)
+    pub file_condition: FileCondition,
+
     /// whether the verb needs a selection
     pub needs_selection: bool,
 
@@ -78,6 +81,10 @@ pub struct Verb {
 
     /// The panel on which the verb applies (even if triggered from another panel)
     pub impacted_panel: PanelReference,
+
+    /// questions asked before execution when the verb is called
+    /// without arguments
+    pub prompts: Vec<VerbPrompt>,
 }
 
 impl PartialEq for Verb {
@@ -123,12 +130,14 @@ impl Verb {
             description,
             selection_condition: FileTypeCondition::Any,
             file_extensions: Vec::new(),
+            file_condition: FileCondition::default(),
             needs_selection,
             needs_another_panel,
             auto_exec: true,
             show_in_doc: true,
             panels: Vec::new(),
             impacted_panel: PanelReference::default(),
+            prompts: Vec::new(),
         })
     }
     pub fn with_key(
@@ -179,6 +188,20 @@ impl Verb {
         self.selection_condition = selection_condition;
         self
     }
+    pub fn with_impacted_panel(
+        &mut self,
+        impacted_panel: PanelReference,
+    ) -> &mut Self {
+        self.impacted_panel = impacted_panel;
+        self
+    }
+    pub fn with_panels(
+        &mut self,
+        panels: &[PanelStateType],
+    ) -> &mut Self {
+        self.panels = panels.to_vec();
+        self
+    }
     pub fn needing_another_panel(&mut self) -> &mut Self {
         self.needs_another_panel = true;
         self
@@ -191,6 +214,26 @@ impl Verb {
         self
     }
 
+    /// tell whether some prompts must be asked before execution: all of
+    /// them when the invocation has no argument, only the confirmations
+    /// otherwise
+    pub fn needs_prompting(
+        &self,
+        invocation: Option<&VerbInvocation>,
+    ) -> bool {
+        if has_args(invocation) {
+            self.needs_confirmation()
+        } else {
+            !self.prompts.is_empty()
+        }
+    }
+
+    /// tell whether the verb must be confirmed before execution, whatever
+    /// the way it's called
+    pub fn needs_confirmation(&self) -> bool {
+        self.prompts.iter().any(VerbPrompt::is_confirmation)
+    }
+
     pub fn has_name(
         &self,
         searched_name: &str,
@@ -362,3 +405,10 @@ pub fn check_verb_name(name: &str) -> Result<(), ConfError> {
         })
     }
 }
+
+/// tell whether the invocation comes with (non blank) arguments
+fn has_args(invocation: Option<&VerbInvocation>) -> bool {
+    invocation
+        .and_then(|invocation| invocation.args.as_ref())
+        .is_some_and(|args| !args.trim().is_empty())
+}
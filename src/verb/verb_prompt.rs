@@ -0,0 +1,89 @@
+use {
+    super::*,
+    crate::{
+        conf::VerbPromptConf,
+        errors::ConfError,
+    },
+};
+
+/// A question asked to the user before the execution of a verb
+/// called without arguments.
+///
+/// Labels and defaults may contain the same `{file}` like groups
+/// than executions, and the arguments already answered.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VerbPrompt {
+    /// ask the value of an argument of the invocation pattern
+    Arg {
+        name: String,
+        label: Option<String>,
+        default: Option<String>,
+    },
+    /// ask for a yes/no confirmation
+    Confirm { question: String },
+}
+
+impl VerbPrompt {
+    pub fn from_conf(
+        conf: &VerbPromptConf,
+        invocation_parser: Option<&InvocationParser>,
+    ) -> Result<Self, ConfError> {
+        let Some(name) = &conf.arg else {
+            let question = conf.label.clone().ok_or_else(|| ConfError::InvalidVerbConf {
+                details: "a prompt needs either an arg or a label".to_string(),
+            })?;
+            return Ok(Self::Confirm { question });
+        };
+        let known = invocation_parser
+            .is_some_and(|parser| parser.arg_defs.iter().any(|def| &def.name == name));
+        if !known {
+            return Err(ConfError::InvalidVerbConf {
+                details: format!("prompted arg {name:?} isn't in the invocation"),
+            });
+        }
+        Ok(Self::Arg {
+            name: name.clone(),
+            label: conf.label.clone(),
+            default: conf.default.clone(),
+        })
+    }
+    pub fn is_confirmation(&self) -> bool {
+        matches!(self, Self::Confirm { .. })
+    }
+    /// the label, before group replacement
+    pub fn label_pattern(&self) -> &str {
+        match self {
+            Self::Arg { name, label, .. } => label.as_deref().unwrap_or(name),
+            Self::Confirm { question } => question,
+        }
+    }
+}
+
+#[cfg(test)]
+mod verb_prompt_tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_from_conf() {
+        let parser = InvocationParser::new("rename {new_name}").unwrap();
+        let conf = VerbPromptConf {
+            arg: Some("new_name".to_string()),
+            label: None,
+            default: Some("{file-name}".to_string()),
+        };
+        let prompt = VerbPrompt::from_conf(&conf, Some(&parser)).unwrap();
+        assert_eq!(prompt.label_pattern(), "new_name");
+        assert!(!prompt.is_confirmation());
+        let conf = VerbPromptConf {
+            arg: Some("target".to_string()),
+            ..Default::default()
+        };
+        assert!(VerbPrompt::from_conf(&conf, Some(&parser)).is_err());
+        let conf = VerbPromptConf {
+            label: Some("Really?".to_string()),
+            ..Default::default()
+        };
+        assert!(VerbPrompt::from_conf(&conf, None).unwrap().is_confirmation());
+        assert!(VerbPrompt::from_conf(&VerbPromptConf::default(), None).is_err());
+    }
+}
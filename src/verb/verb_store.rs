@@ -105,6 +105,7 @@ impl VerbStore {
 
         // changing display
         self.add_internal(set_syntax_theme);
+        self.add_internal(set_encoding);
         self.add_internal(apply_flags).with_name("apply_flags")?;
         self.add_internal(set_panel_width);
         self.add_internal(default_layout);
@@ -134,6 +135,32 @@ impl VerbStore {
         self.add_internal(preview_text).with_shortcut("txt");
         self.add_internal(preview_binary).with_shortcut("hex");
         self.add_internal(preview_tty).with_shortcut("tty");
+        self.add_internal(preview_markdown).with_shortcut("md");
+        self.add_internal(preview_table).with_shortcut("table");
+        self.add_internal(preview_structured).with_shortcut("struct");
+        self.add_internal(preview_info).with_shortcut("info");
+        self.add_internal(preview_format).with_shortcut("format");
+        self.add_internal(jump_to_key)
+            .with_shortcut("jump")
+            .with_impacted_panel(PanelReference::Preview);
+        self.add_internal(goto_offset)
+            .with_shortcut("goto")
+            .with_impacted_panel(PanelReference::Preview);
+        self.add_internal(search_bytes)
+            .with_shortcut("bytes")
+            .with_impacted_panel(PanelReference::Preview);
+        self.add_internal(toggle_follow)
+            .with_shortcut("follow")
+            .with_impacted_panel(PanelReference::Preview);
+        self.add_internal(toggle_wrap)
+            .with_shortcut("wrap")
+            .with_impacted_panel(PanelReference::Preview);
+        self.add_internal(scroll_left)
+            .with_key(key!(left))
+            .with_panels(&[PanelStateType::Preview]);
+        self.add_internal(scroll_right)
+            .with_key(key!(right))
+            .with_panels(&[PanelStateType::Preview]);
         self.add_internal(close_panel_ok);
         self.add_internal(close_panel_cancel)
             .with_key(key!(ctrl - w));
@@ -182,6 +209,7 @@ impl VerbStore {
         }
         #[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
         self.add_internal(filesystems).with_shortcut("fs");
+        self.add_internal(find_duplicates).with_shortcut("dup");
         self.add_internal(focus_staging_area_no_open);
         // :focus is also hardcoded on Enter on directories
         // but ctrl-f is useful for focusing on a file's parent
@@ -278,8 +306,18 @@ impl VerbStore {
         self.add_internal(page_up)
             .with_key(key!(ctrl - u))
             .with_key(key!(pageup));
+        self.add_internal(toggle_dry_run);
+        self.add_internal(macro_record);
+        self.add_internal(macro_stop);
+        self.add_internal(macro_play).with_key(key!(alt - m));
+        self.add_internal(macro_play_staged);
+        self.add_internal(macro_save);
         self.add_internal(focus_panel_left);
         self.add_internal(focus_panel_right);
+        self.add_internal(tab_new).with_key(key!(alt - t));
+        self.add_internal(tab_close);
+        self.add_internal(tab_next).with_key(key!(ctrl - pagedown));
+        self.add_internal(tab_previous).with_key(key!(ctrl - pageup));
         self.add_internal(panel_left_no_open)
             .with_key(key!(ctrl - left));
         self.add_internal(panel_right).with_key(key!(ctrl - right));
@@ -291,11 +329,14 @@ impl VerbStore {
             .with_key(key!(ctrl - q))
             .with_shortcut("q");
         self.add_internal(refresh).with_key(key!(f5));
+        self.add_internal(refresh_changes);
+        self.add_internal(refresh_followed);
         self.add_internal(root_up).with_key(key!(ctrl - up));
         self.add_internal(root_down).with_key(key!(ctrl - down));
         self.add_internal(select_first);
         self.add_internal(select_last);
         self.add_internal(select);
+        self.add_internal(session_save);
         self.add_internal(show);
         self.add_internal(clear_stage).with_shortcut("cls");
         self.add_internal(stage).with_key(key!('+'));
@@ -304,12 +345,17 @@ impl VerbStore {
         self.add_internal(stage_all_files).with_key(key!(ctrl - a));
         self.add_internal(toggle_stage).with_key(key!(ctrl - g));
         self.add_internal(open_staging_area).with_shortcut("osa");
+        self.add_internal(open_output).with_shortcut("oo");
+        self.add_internal(open_jobs).with_shortcut("jobs");
+        self.add_internal(job_kill);
+        self.add_internal(refresh_jobs);
         self.add_internal(close_staging_area).with_shortcut("csa");
         self.add_internal(toggle_staging_area).with_shortcut("tsa");
         self.add_internal(toggle_tree).with_shortcut("tree");
         self.add_internal(toggle_watch)
             .with_shortcut("watch")
             .with_key(key!(alt - w));
+        self.add_internal(size_report).with_shortcut("sr");
         self.add_internal(sort_by_count).with_shortcut("sc");
         self.add_internal(sort_by_date).with_shortcut("sd");
         self.add_internal(sort_by_size).with_shortcut("ss");
@@ -340,6 +386,7 @@ impl VerbStore {
         #[cfg(unix)]
         self.add_internal(toggle_perm).with_shortcut("perm");
         self.add_internal(toggle_sizes).with_shortcut("sizes");
+        self.add_internal(toggle_apparent_sizes).with_shortcut("apparent");
         self.add_internal(toggle_trim_root);
         self.add_internal(total_search);
         self.add_internal(search_again).with_key(key!(ctrl - s));
@@ -478,6 +525,20 @@ impl VerbStore {
             if let Some(b) = vc.switch_terminal {
                 external_execution.switch_terminal = b;
             }
+            if vc.capture_output == Some(true) {
+                if external_execution.exec_mode == ExternalExecutionMode::StayInBroot {
+                    external_execution.capture_output = true;
+                } else {
+                    warn!("capture_output is only relevant for verbs not leaving broot");
+                }
+            }
+            if vc.background == Some(true) {
+                if external_execution.exec_mode == ExternalExecutionMode::StayInBroot {
+                    external_execution.background = true;
+                } else {
+                    warn!("background is only relevant for verbs not leaving broot");
+                }
+            }
             external_execution
         };
         let mut execution = match (execution, internal, external, shell_command, cmd) {
@@ -557,6 +618,18 @@ impl VerbStore {
         }
         verb.impacted_panel = vc.impacted_panel;
         verb.selection_condition = vc.apply_to;
+        if let Some(apply_if) = &vc.apply_if {
+            verb.file_condition = FileCondition::from_conf(apply_if)?;
+        }
+        for prompt in &vc.prompts {
+            let prompt = VerbPrompt::from_conf(prompt, verb.invocation_parser.as_ref())?;
+            verb.prompts.push(prompt);
+        }
+        if let Some(question) = &vc.confirm {
+            verb.prompts.push(VerbPrompt::Confirm {
+                question: question.clone(),
+            });
+        }
         Ok(())
     }
 
@@ -628,6 +701,9 @@ impl VerbStore {
                 if !sel_info.is_accepted_by(verb.selection_condition) {
                     continue;
                 }
+                if !verb.file_condition.accepts_sel_info(sel_info) {
+                    continue;
+                }
             }
             if let Some(panel_state_type) = panel_state_type {
                 if !verb.can_be_called_in_panel(panel_state_type) {
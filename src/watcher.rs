@@ -16,7 +16,12 @@ use {
         },
     },
     std::{
+        mem,
         path::PathBuf,
+        sync::{
+            Arc,
+            Mutex,
+        },
         thread,
     },
     termimad::crossbeam::channel,
@@ -24,12 +29,25 @@ use {
 
 const DEBOUNCE_MAX_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
 
-/// Watch for notify events on a path, and send a :refresh sequence when a change is detected
+/// The changes notified since they were last taken
+#[derive(Debug, Default)]
+pub struct Changes {
+    /// the changed paths
+    pub paths: Vec<PathBuf>,
+    /// whether there was an event without precise paths (eg a rescan
+    /// after an overflow of the event queue), in which case everything
+    /// must be refreshed
+    pub all: bool,
+}
+
+/// Watch for notify events on a path, and send a :refresh_changes sequence when a change
+/// is detected. The changed paths are kept until they're taken by the application, so
+/// that only the affected lines and caches are updated.
 ///
 /// inotify events are debounced:
 /// - an isolated event sends a refresh immediately
 /// - successive events after the first one will have to wait a little
-/// - there's at most one refrest sent every DEBOUNCE_MAX_DELAY
+/// - there's at most one refresh sent every DEBOUNCE_MAX_DELAY
 /// - if there's a long sequence of events, it's guaranteed that there's one
 ///   refresh sent every DEBOUNCE_MAX_DELAY
 /// - the last event of the sequence is always sent (with a delay of
@@ -38,6 +56,8 @@ pub struct Watcher {
     notify_sender: channel::Sender<()>,
     notify_watcher: Option<RecommendedWatcher>,
     watched: Vec<PathBuf>,
+    /// the changes since the last call to take_changes
+    changes: Arc<Mutex<Changes>>,
 }
 
 impl Watcher {
@@ -74,8 +94,17 @@ impl Watcher {
             notify_sender,
             notify_watcher: None,
             watched: Default::default(),
+            changes: Default::default(),
         }
     }
+    /// Return the changes since the last call, with sorted and deduplicated paths
+    #[allow(clippy::missing_panics_doc)] // panics on mutex poisoning (good)
+    pub fn take_changes(&self) -> Changes {
+        let mut changes = mem::take(&mut *self.changes.lock().unwrap());
+        changes.paths.sort();
+        changes.paths.dedup();
+        changes
+    }
     fn send_refresh(
         tx_seqs: &channel::Sender<Sequence>,
     ) {
@@ -84,7 +113,7 @@ impl Watcher {
             debug!("skipping refresh, channel full");
             return;
         }
-        let sequence = Sequence::new_single(":refresh");
+        let sequence = Sequence::new_single(":refresh_changes");
         if let Err(e) = tx_seqs.send(sequence) {
             warn!("error when sending sequence from watcher: {}", e);
         }
@@ -109,7 +138,10 @@ impl Watcher {
             }
             None => self
                 .notify_watcher
-                .insert(Self::make_notify_watcher(self.notify_sender.clone())?),
+                .insert(Self::make_notify_watcher(
+                    self.notify_sender.clone(),
+                    Arc::clone(&self.changes),
+                )?),
         };
         let mut err = None;
         for path in &paths {
@@ -134,7 +166,10 @@ impl Watcher {
             Ok(())
         }
     }
-    fn make_notify_watcher(sender: channel::Sender<()>) -> Result<RecommendedWatcher, ProgramError> {
+    fn make_notify_watcher(
+        sender: channel::Sender<()>,
+        changes: Arc<Mutex<Changes>>,
+    ) -> Result<RecommendedWatcher, ProgramError> {
         let mut notify_watcher =
             notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
                 Ok(we) => {
@@ -162,6 +197,12 @@ impl Watcher {
                             debug!("notify event: {we:?}");
                         }
                     }
+                    let mut changes = changes.lock().unwrap();
+                    if we.paths.is_empty() || we.need_rescan() {
+                        changes.all = true;
+                    }
+                    changes.paths.extend(we.paths);
+                    drop(changes);
                     if let Err(e) = sender.send(()) {
                         info!("error when notifying on notify event: {}", e);
                     }